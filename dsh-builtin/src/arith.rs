@@ -0,0 +1,28 @@
+use super::ShellProxy;
+use dsh_types::{Context, ExitStatus};
+
+/// Built-in let command description
+pub fn let_description() -> &'static str {
+    "Evaluate arithmetic expressions"
+}
+
+/// Built-in let command implementation
+/// Evaluates each argument as an arithmetic expression; `(( expr ))` runs through here too
+/// Exits with 0 when the last value is non-zero and 1 when it is zero
+pub fn let_command(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    if argv.len() < 2 {
+        ctx.write_stderr("let: expression expected\n").ok();
+        return ExitStatus::ExitedWith(1);
+    }
+    let mut last = 0;
+    for expr in &argv[1..] {
+        match proxy.eval_arithmetic(expr) {
+            Ok(value) => last = value,
+            Err(e) => {
+                ctx.write_stderr(&format!("let: {e}\n")).ok();
+                return ExitStatus::ExitedWith(1);
+            }
+        }
+    }
+    ExitStatus::ExitedWith(if last != 0 { 0 } else { 1 })
+}
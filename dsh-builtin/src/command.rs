@@ -0,0 +1,64 @@
+use super::ShellProxy;
+use dsh_types::{Context, ExitStatus};
+
+/// Built-in type command description
+pub fn type_description() -> &'static str {
+    "Show how a command name resolves (use -a for every match)"
+}
+
+/// Built-in which command description
+pub fn which_description() -> &'static str {
+    "Show the alias, function, builtin or executable a name runs"
+}
+
+/// Built-in command command description
+pub fn command_description() -> &'static str {
+    "Run a command bypassing aliases and functions, or describe it with -v/-V"
+}
+
+/// Built-in builtin command description
+pub fn builtin_description() -> &'static str {
+    "Run a builtin command, bypassing functions and executables"
+}
+
+/// Describes commands through the shell, which knows aliases and functions
+fn describe(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    let name = argv[0].clone();
+    match proxy.describe_commands(ctx, argv) {
+        Ok(status) => ExitStatus::ExitedWith(status),
+        Err(e) => {
+            ctx.write_stderr(&format!("{name}: {e}")).ok();
+            ExitStatus::ExitedWith(2)
+        }
+    }
+}
+
+/// Built-in type command implementation
+/// `type NAME` tells whether NAME is an alias, abbreviation, function, builtin, Lisp
+/// function, executable or directory; `-a`, `-t`, `-p` and `-P` work as in bash
+pub fn type_command(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    describe(ctx, argv, proxy)
+}
+
+/// Built-in which command implementation
+/// `which NAME` prints what NAME runs and `which -a NAME` every match
+pub fn which_command(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    describe(ctx, argv, proxy)
+}
+
+/// Built-in command command implementation
+/// `command NAME ARGS...` is run by the shell without functions; this handles
+/// `command -v NAME` and `command -V NAME`
+pub fn command_command(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    describe(ctx, argv, proxy)
+}
+
+/// Built-in builtin command implementation
+/// `builtin NAME ARGS...` is run by the shell; `builtin` alone does nothing
+pub fn builtin_command(
+    _ctx: &Context,
+    _argv: Vec<String>,
+    _proxy: &mut dyn ShellProxy,
+) -> ExitStatus {
+    ExitStatus::ExitedWith(0)
+}
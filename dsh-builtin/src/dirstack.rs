@@ -0,0 +1,46 @@
+use super::ShellProxy;
+use dsh_types::{Context, ExitStatus};
+
+/// Built-in pushd command description
+pub fn pushd_description() -> &'static str {
+    "Save the current directory on the directory stack and change directory"
+}
+
+/// Built-in popd command description
+pub fn popd_description() -> &'static str {
+    "Remove a directory from the directory stack and change to the new top"
+}
+
+/// Built-in dirs command description
+pub fn dirs_description() -> &'static str {
+    "Display the directory stack (use -v for numbered entries)"
+}
+
+/// Runs a directory stack command through the shell, which owns the stack
+fn run(ctx: &Context, name: &str, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    match proxy.dispatch(ctx, name, argv) {
+        Ok(()) => ExitStatus::ExitedWith(0),
+        Err(e) => {
+            ctx.write_stderr(&format!("{name}: {e}")).ok();
+            ExitStatus::ExitedWith(1)
+        }
+    }
+}
+
+/// Built-in pushd command implementation
+/// `pushd DIR` changes to DIR, `pushd +N`/`-N` rotates the stack and `pushd` swaps the top two entries
+pub fn pushd_command(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    run(ctx, "pushd", argv, proxy)
+}
+
+/// Built-in popd command implementation
+/// `popd` returns to the previous directory, `popd +N`/`-N` drops entry N
+pub fn popd_command(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    run(ctx, "popd", argv, proxy)
+}
+
+/// Built-in dirs command implementation
+/// Prints the stack, with `-v`, `-p`, `-l`, `-c` and `+N`/`-N` as in bash
+pub fn dirs_command(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    run(ctx, "dirs", argv, proxy)
+}
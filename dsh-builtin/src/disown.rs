@@ -0,0 +1,20 @@
+use super::ShellProxy;
+use dsh_types::{Context, ExitStatus};
+
+/// Built-in disown command description
+pub fn description() -> &'static str {
+    "Remove jobs from the job table (-h keeps them running after exit)"
+}
+
+/// Built-in disown command implementation
+/// Disowned jobs are no longer listed or killed when the shell exits; with `-h` they
+/// stay in the job table but are still left running at exit
+pub fn command(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    match proxy.dispatch(ctx, "disown", argv) {
+        Ok(_) => ExitStatus::ExitedWith(0),
+        Err(e) => {
+            ctx.write_stderr(&format!("disown: {e}")).ok();
+            ExitStatus::ExitedWith(1)
+        }
+    }
+}
@@ -31,6 +31,10 @@ pub fn command(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) ->
         print_header(ctx, "config");
         check_config(ctx);
     }
+    if show_section(section, "options") {
+        print_header(ctx, "options");
+        check_options(ctx, proxy);
+    }
     if show_section(section, "ai") {
         print_header(ctx, "ai");
         check_ai(ctx, proxy);
@@ -70,12 +74,13 @@ fn print_help(ctx: &Context) -> ExitStatus {
 
 fn help_text() -> &'static str {
     concat!(
-        "Usage: doctor [config|ai|mcp|project|runtime|performance|skills|dev|validate] [OPTIONS]\n",
+        "Usage: doctor [config|options|ai|mcp|project|runtime|performance|skills|dev|validate] [OPTIONS]\n",
         "\n",
         "Run diagnostics for the current shell setup. Without a section, all checks run.\n",
         "\n",
         "Sections:\n",
         "  config   Check config.lisp and runtime skills directory\n",
+        "  options  Show the shell options and their values\n",
         "  ai       Check AI-related environment and defaults\n",
         "  mcp      Check configured MCP servers and connection counters\n",
         "  project  Detect project marker files in the current directory\n",
@@ -88,6 +93,7 @@ fn help_text() -> &'static str {
         "Examples:\n",
         "  doctor\n",
         "  doctor ai\n",
+        "  doctor options\n",
         "  doctor project\n",
         "  doctor performance --latency --latency-iters 1000\n",
         "  doctor skills\n",
@@ -100,6 +106,7 @@ fn is_known_section(value: &str) -> bool {
     matches!(
         value,
         "config"
+            | "options"
             | "ai"
             | "mcp"
             | "project"
@@ -154,6 +161,12 @@ fn check_config(ctx: &Context) {
     }
 }
 
+fn check_options(ctx: &Context, proxy: &mut dyn ShellProxy) {
+    for option in proxy.shell_options() {
+        let _ = ctx.write_stdout(&format!("ok {} {}", option.name, option.value));
+    }
+}
+
 fn check_ai(ctx: &Context, proxy: &mut dyn ShellProxy) {
     let api_key = proxy
         .get_var("AI_CHAT_API_KEY")
@@ -884,6 +897,7 @@ mod tests {
         assert!(help.contains("Usage: doctor"));
         assert!(help.contains("config"));
         assert!(help.contains("ai"));
+        assert!(help.contains("options"));
         assert!(help.contains("mcp"));
         assert!(help.contains("project"));
         assert!(help.contains("runtime"));
@@ -911,6 +925,7 @@ mod tests {
     fn show_section_matches_new_aliases() {
         assert!(show_section(Some("validate"), "dev"));
         assert!(is_known_section("skills"));
+        assert!(is_known_section("options"));
         assert!(!is_known_section("unknown"));
     }
 
@@ -0,0 +1,14 @@
+use super::ShellProxy;
+use dsh_types::{Context, ExitStatus};
+
+/// Built-in exec command description
+pub fn description() -> &'static str {
+    "Replace the shell with a command, or keep redirections (exec 3>log) for the shell"
+}
+
+/// Built-in exec command implementation
+/// `exec CMD ARGS...` is run by the shell, which replaces itself with CMD; `exec` with
+/// only redirections is left here once the shell has made them permanent
+pub fn command(_ctx: &Context, _argv: Vec<String>, _proxy: &mut dyn ShellProxy) -> ExitStatus {
+    ExitStatus::ExitedWith(0)
+}
@@ -0,0 +1,57 @@
+use super::ShellProxy;
+use dsh_types::{Context, ExitStatus};
+
+/// Built-in break command description
+pub fn break_description() -> &'static str {
+    "Exit from a for, while, or until loop"
+}
+
+/// Built-in continue command description
+pub fn continue_description() -> &'static str {
+    "Resume the next iteration of a for, while, or until loop"
+}
+
+/// Built-in break command implementation
+/// Leaves the innermost loop, or the Nth enclosing loop with `break N`
+pub fn break_command(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    match proxy.dispatch(ctx, "break", argv) {
+        Ok(_) => ExitStatus::Break,
+        Err(e) => {
+            ctx.write_stderr(&format!("break: {e}\n")).ok();
+            ExitStatus::ExitedWith(1)
+        }
+    }
+}
+
+/// Built-in continue command implementation
+/// Skips to the next iteration of the innermost loop, or the Nth enclosing loop
+pub fn continue_command(
+    ctx: &Context,
+    argv: Vec<String>,
+    proxy: &mut dyn ShellProxy,
+) -> ExitStatus {
+    match proxy.dispatch(ctx, "continue", argv) {
+        Ok(_) => ExitStatus::Continue,
+        Err(e) => {
+            ctx.write_stderr(&format!("continue: {e}\n")).ok();
+            ExitStatus::ExitedWith(1)
+        }
+    }
+}
+
+/// Built-in return command description
+pub fn return_description() -> &'static str {
+    "Return from a shell function"
+}
+
+/// Built-in return command implementation
+/// Leaves the current shell function with the given status (or the last status)
+pub fn return_command(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    match proxy.dispatch(ctx, "return", argv) {
+        Ok(_) => ExitStatus::Return,
+        Err(e) => {
+            ctx.write_stderr(&format!("return: {e}\n")).ok();
+            ExitStatus::ExitedWith(1)
+        }
+    }
+}
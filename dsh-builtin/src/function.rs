@@ -0,0 +1,36 @@
+use super::ShellProxy;
+use dsh_types::{Context, ExitStatus};
+
+/// Built-in local command description
+pub fn local_description() -> &'static str {
+    "Declare variables local to a shell function"
+}
+
+/// Built-in shift command description
+pub fn shift_description() -> &'static str {
+    "Shift positional parameters"
+}
+
+/// Built-in local command implementation
+/// Declares `NAME` or `NAME=VALUE` variables that are restored when the function returns
+pub fn local_command(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    match proxy.dispatch(ctx, "local", argv) {
+        Ok(_) => ExitStatus::ExitedWith(0),
+        Err(e) => {
+            ctx.write_stderr(&format!("local: {e}\n")).ok();
+            ExitStatus::ExitedWith(1)
+        }
+    }
+}
+
+/// Built-in shift command implementation
+/// Drops the first N (default 1) positional parameters
+pub fn shift_command(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    match proxy.dispatch(ctx, "shift", argv) {
+        Ok(_) => ExitStatus::ExitedWith(0),
+        Err(e) => {
+            ctx.write_stderr(&format!("shift: {e}\n")).ok();
+            ExitStatus::ExitedWith(1)
+        }
+    }
+}
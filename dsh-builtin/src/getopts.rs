@@ -0,0 +1,189 @@
+use super::ShellProxy;
+use dsh_types::{Context, ExitStatus};
+use std::sync::Mutex;
+
+/// Position inside a group of options such as `-abc`, kept between calls like in other
+/// shells. It only applies while `OPTIND` still has the value getopts left in it.
+static NEXT_CHAR: Mutex<Option<Cursor>> = Mutex::new(None);
+
+/// Built-in getopts command description
+pub fn description() -> &'static str {
+    "Parse options from the positional parameters"
+}
+
+/// Where getopts continues: the argument `OPTIND` (from 1) and the character in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cursor {
+    optind: usize,
+    charind: usize,
+}
+
+impl Cursor {
+    fn next_arg(optind: usize) -> Self {
+        Self { optind, charind: 1 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Next {
+    Option(char, Option<String>),
+    Invalid(char),
+    MissingArgument(char),
+    End,
+}
+
+/// Parses the option at `cursor` according to `optstring`.
+fn next_option(optstring: &str, args: &[String], cursor: Cursor) -> (Next, Cursor) {
+    let Cursor { optind, charind } = cursor;
+    let Some(arg) = args.get(optind - 1) else {
+        return (Next::End, cursor);
+    };
+    if charind == 1 {
+        if arg == "--" {
+            return (Next::End, Cursor::next_arg(optind + 1));
+        }
+        if arg == "-" || !arg.starts_with('-') {
+            return (Next::End, cursor);
+        }
+    }
+
+    let chars: Vec<char> = arg.chars().collect();
+    let Some(&opt) = chars.get(charind) else {
+        return (Next::End, Cursor::next_arg(optind + 1));
+    };
+    let rest: String = chars[charind + 1..].iter().collect();
+    let advanced = if rest.is_empty() {
+        Cursor::next_arg(optind + 1)
+    } else {
+        Cursor {
+            optind,
+            charind: charind + 1,
+        }
+    };
+
+    let spec: Vec<char> = optstring.trim_start_matches(':').chars().collect();
+    let Some(pos) = spec.iter().position(|&c| c == opt && c != ':') else {
+        return (Next::Invalid(opt), advanced);
+    };
+    if spec.get(pos + 1) != Some(&':') {
+        return (Next::Option(opt, None), advanced);
+    }
+    if !rest.is_empty() {
+        return (Next::Option(opt, Some(rest)), Cursor::next_arg(optind + 1));
+    }
+    match args.get(optind) {
+        Some(value) => (
+            Next::Option(opt, Some(value.clone())),
+            Cursor::next_arg(optind + 2),
+        ),
+        None => (Next::MissingArgument(opt), Cursor::next_arg(optind + 1)),
+    }
+}
+
+/// Built-in getopts command implementation
+/// `getopts OPTSTRING NAME [ARG...]` stores the next option in NAME, its argument in
+/// `OPTARG` and the index of the next argument in `OPTIND`, as specified by POSIX.
+/// A leading `:` in OPTSTRING reports errors through NAME instead of stderr.
+pub fn command(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    if argv.len() < 3 {
+        ctx.write_stderr("getopts: usage: getopts optstring name [arg ...]")
+            .ok();
+        return ExitStatus::ExitedWith(2);
+    }
+    let optstring = &argv[1];
+    let name = argv[2].clone();
+    let args = if argv.len() > 3 {
+        argv[3..].to_vec()
+    } else {
+        proxy.positional_params()
+    };
+
+    let optind = proxy
+        .get_var("OPTIND")
+        .and_then(|value| value.parse::<usize>().ok())
+        .filter(|&optind| optind > 0)
+        .unwrap_or(1);
+    let mut next_char = NEXT_CHAR.lock().unwrap_or_else(|e| e.into_inner());
+    let cursor = match *next_char {
+        Some(cursor) if cursor.optind == optind => cursor,
+        _ => Cursor::next_arg(optind),
+    };
+
+    let (next, cursor) = next_option(optstring, &args, cursor);
+    *next_char = (cursor.charind > 1).then_some(cursor);
+    proxy.set_var("OPTIND".to_string(), cursor.optind.to_string());
+
+    let silent = optstring.starts_with(':');
+    let (value, optarg) = match next {
+        Next::Option(opt, arg) => (opt.to_string(), arg.unwrap_or_default()),
+        Next::Invalid(opt) if silent => ("?".to_string(), opt.to_string()),
+        Next::Invalid(opt) => {
+            ctx.write_stderr(&format!("getopts: illegal option -- {opt}"))
+                .ok();
+            ("?".to_string(), String::new())
+        }
+        Next::MissingArgument(opt) if silent => (":".to_string(), opt.to_string()),
+        Next::MissingArgument(opt) => {
+            ctx.write_stderr(&format!("getopts: option requires an argument -- {opt}"))
+                .ok();
+            ("?".to_string(), String::new())
+        }
+        Next::End => {
+            proxy.set_var(name, "?".to_string());
+            proxy.set_var("OPTARG".to_string(), String::new());
+            return ExitStatus::ExitedWith(1);
+        }
+    };
+    proxy.set_var(name, value);
+    proxy.set_var("OPTARG".to_string(), optarg);
+    ExitStatus::ExitedWith(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_all(optstring: &str, args: &[&str]) -> (Vec<Next>, usize) {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        let mut cursor = Cursor::next_arg(1);
+        let mut found = Vec::new();
+        loop {
+            let (next, next_cursor) = next_option(optstring, &args, cursor);
+            cursor = next_cursor;
+            if next == Next::End {
+                return (found, cursor.optind);
+            }
+            found.push(next);
+        }
+    }
+
+    #[test]
+    fn test_next_option() {
+        let (found, optind) = parse_all("ab:c", &["-ac", "-bvalue", "-b", "x y", "file", "-a"]);
+        assert_eq!(
+            found,
+            vec![
+                Next::Option('a', None),
+                Next::Option('c', None),
+                Next::Option('b', Some("value".to_string())),
+                Next::Option('b', Some("x y".to_string())),
+            ]
+        );
+        assert_eq!(optind, 5);
+
+        let (found, optind) = parse_all(":a", &["-x", "--", "-a"]);
+        assert_eq!(found, vec![Next::Invalid('x')]);
+        assert_eq!(optind, 3);
+
+        let (found, optind) = parse_all("ab:", &["-a", "-b"]);
+        assert_eq!(
+            found,
+            vec![Next::Option('a', None), Next::MissingArgument('b')]
+        );
+        assert_eq!(optind, 3);
+
+        let (found, optind) = parse_all("a", &["-", "-a"]);
+        assert!(found.is_empty());
+        assert_eq!(optind, 1);
+    }
+}
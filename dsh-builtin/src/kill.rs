@@ -0,0 +1,20 @@
+use super::ShellProxy;
+use dsh_types::{Context, ExitStatus};
+
+/// Built-in kill command description
+pub fn description() -> &'static str {
+    "Send a signal to jobs (%N) or processes"
+}
+
+/// Built-in kill command implementation
+/// Accepts `%job` specs as well as PIDs, so a whole pipeline is signalled through its
+/// process group; `kill -l` lists the signal names
+pub fn command(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    match proxy.dispatch(ctx, "kill", argv) {
+        Ok(_) => ExitStatus::ExitedWith(0),
+        Err(e) => {
+            ctx.write_stderr(&format!("kill: {e}")).ok();
+            ExitStatus::ExitedWith(1)
+        }
+    }
+}
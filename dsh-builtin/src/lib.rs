@@ -1,7 +1,10 @@
 use anyhow::Result;
-use dsh_types::{Context, ExitStatus, mcp::McpServerConfig, output_history::OutputEntry};
+use dsh_types::{
+    Context, ExitStatus, mcp::McpServerConfig, options::OptionEntry, output_history::OutputEntry,
+};
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
+use std::os::fd::RawFd;
 use std::sync::Mutex;
 use tracing::debug;
 
@@ -10,13 +13,18 @@ mod abbr;
 mod add_path;
 
 mod alias;
+mod arith;
 mod bg;
 pub mod cd;
 mod chatgpt;
+mod command;
 mod dashboard;
+mod dirstack;
+mod disown;
 mod doctor;
 mod eproject;
 mod eview;
+mod exec;
 mod export;
 mod include;
 mod magit;
@@ -30,8 +38,11 @@ mod commit_ai;
 pub mod comp_gen;
 mod dmv;
 mod fg;
+mod flow;
+pub mod function;
 pub mod ga;
 mod gco;
+mod getopts;
 pub mod gh_notify;
 mod github_client;
 mod glog;
@@ -40,6 +51,7 @@ mod gwt;
 mod help;
 mod history;
 mod jobs;
+mod kill;
 pub mod lisp;
 mod mcp;
 mod notebook_play;
@@ -50,15 +62,19 @@ pub mod project_context;
 mod read;
 
 mod reload;
+mod resource;
 pub mod serve;
 mod set;
 mod skim_runner;
 mod snippet;
+mod source;
 pub mod task;
 pub mod tm;
+mod trap;
 mod trigger;
 mod uuid;
 mod var;
+mod wait;
 mod z;
 
 /// Trait that provides an interface for builtin commands to interact with the shell
@@ -80,6 +96,38 @@ pub trait ShellProxy {
     /// Used for commands that need to be handled by the main shell logic
     fn dispatch(&mut self, ctx: &Context, cmd: &str, argv: Vec<String>) -> Result<()>;
 
+    /// Runs `type`, `which` or `command -v` (named by `argv[0]`) and returns its exit status
+    fn describe_commands(&mut self, _ctx: &Context, _argv: Vec<String>) -> Result<i32> {
+        Err(anyhow::anyhow!("describe_commands not implemented"))
+    }
+
+    /// Evaluates an arithmetic expression, assigning shell variables as it goes
+    fn eval_arithmetic(&mut self, _expr: &str) -> Result<i64> {
+        Err(anyhow::anyhow!("eval_arithmetic not implemented"))
+    }
+
+    /// Returns the positional parameters of the running function or script
+    fn positional_params(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Descriptor the shell uses for `fd` as scripts see it, which differs for
+    /// descriptors opened with `exec 3<file` and the pipes of coprocesses
+    fn resolve_fd(&self, fd: RawFd) -> RawFd {
+        fd
+    }
+
+    /// Lists the registered shell options with their values
+    fn shell_options(&self) -> Vec<OptionEntry> {
+        Vec::new()
+    }
+
+    /// Changes a registered shell option; `value` is converted to the option's kind, so
+    /// `on`/`off` work for switches
+    fn set_shell_option(&mut self, name: &str, _value: &str) -> Result<()> {
+        Err(anyhow::anyhow!("{name}: invalid option name"))
+    }
+
     /// Saves a command output entry to the shell's history
     fn save_output_history(&mut self, _entry: OutputEntry) {}
 
@@ -379,6 +427,27 @@ pub static BUILTIN_COMMAND: Lazy<Mutex<HashMap<&str, Box<dyn BuiltinCommandTrait
             Box::new(BuiltinCommandFn::new(z::command, z::description()))
                 as Box<dyn BuiltinCommandTrait>,
         );
+        builtin.insert(
+            "pushd",
+            Box::new(BuiltinCommandFn::new(
+                dirstack::pushd_command,
+                dirstack::pushd_description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
+        builtin.insert(
+            "popd",
+            Box::new(BuiltinCommandFn::new(
+                dirstack::popd_command,
+                dirstack::popd_description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
+        builtin.insert(
+            "dirs",
+            Box::new(BuiltinCommandFn::new(
+                dirstack::dirs_command,
+                dirstack::dirs_description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
 
         // Job control commands
         builtin.insert(
@@ -396,6 +465,78 @@ pub static BUILTIN_COMMAND: Lazy<Mutex<HashMap<&str, Box<dyn BuiltinCommandTrait
             Box::new(BuiltinCommandFn::new(bg::command, bg::description()))
                 as Box<dyn BuiltinCommandTrait>,
         );
+        builtin.insert(
+            "kill",
+            Box::new(BuiltinCommandFn::new(kill::command, kill::description()))
+                as Box<dyn BuiltinCommandTrait>,
+        );
+        builtin.insert(
+            "wait",
+            Box::new(BuiltinCommandFn::new(wait::command, wait::description()))
+                as Box<dyn BuiltinCommandTrait>,
+        );
+        builtin.insert(
+            "disown",
+            Box::new(BuiltinCommandFn::new(
+                disown::command,
+                disown::description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
+
+        // Loop control commands
+        builtin.insert(
+            "break",
+            Box::new(BuiltinCommandFn::new(
+                flow::break_command,
+                flow::break_description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
+        builtin.insert(
+            "continue",
+            Box::new(BuiltinCommandFn::new(
+                flow::continue_command,
+                flow::continue_description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
+
+        // Shell function commands
+        builtin.insert(
+            "return",
+            Box::new(BuiltinCommandFn::new(
+                flow::return_command,
+                flow::return_description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
+        builtin.insert(
+            "local",
+            Box::new(BuiltinCommandFn::new(
+                function::local_command,
+                function::local_description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
+        builtin.insert(
+            "shift",
+            Box::new(BuiltinCommandFn::new(
+                function::shift_command,
+                function::shift_description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
+        builtin.insert(
+            "getopts",
+            Box::new(BuiltinCommandFn::new(
+                getopts::command,
+                getopts::description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
+
+        // Arithmetic
+        builtin.insert(
+            "let",
+            Box::new(BuiltinCommandFn::new(
+                arith::let_command,
+                arith::let_description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
 
         // Include command
         builtin.insert(
@@ -405,6 +546,72 @@ pub static BUILTIN_COMMAND: Lazy<Mutex<HashMap<&str, Box<dyn BuiltinCommandTrait
                 include::description(),
             )) as Box<dyn BuiltinCommandTrait>,
         );
+        builtin.insert(
+            "source",
+            Box::new(BuiltinCommandFn::new(
+                source::command,
+                source::description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
+        builtin.insert(
+            ".",
+            Box::new(BuiltinCommandFn::new(
+                source::command,
+                source::description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
+        builtin.insert(
+            "trap",
+            Box::new(BuiltinCommandFn::new(trap::command, trap::description()))
+                as Box<dyn BuiltinCommandTrait>,
+        );
+        builtin.insert(
+            "type",
+            Box::new(BuiltinCommandFn::new(
+                command::type_command,
+                command::type_description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
+        builtin.insert(
+            "which",
+            Box::new(BuiltinCommandFn::new(
+                command::which_command,
+                command::which_description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
+        builtin.insert(
+            "command",
+            Box::new(BuiltinCommandFn::new(
+                command::command_command,
+                command::command_description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
+        builtin.insert(
+            "builtin",
+            Box::new(BuiltinCommandFn::new(
+                command::builtin_command,
+                command::builtin_description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
+        builtin.insert(
+            "exec",
+            Box::new(BuiltinCommandFn::new(exec::command, exec::description()))
+                as Box<dyn BuiltinCommandTrait>,
+        );
+        builtin.insert(
+            "ulimit",
+            Box::new(BuiltinCommandFn::new(
+                resource::ulimit_command,
+                resource::ulimit_description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
+        builtin.insert(
+            "umask",
+            Box::new(BuiltinCommandFn::new(
+                resource::umask_command,
+                resource::umask_description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
         // Scripting and configuration
         builtin.insert(
             "lisp",
@@ -416,6 +623,13 @@ pub static BUILTIN_COMMAND: Lazy<Mutex<HashMap<&str, Box<dyn BuiltinCommandTrait
             Box::new(BuiltinCommandFn::new(set::command, set::description()))
                 as Box<dyn BuiltinCommandTrait>,
         );
+        builtin.insert(
+            "shopt",
+            Box::new(BuiltinCommandFn::new(
+                set::shopt_command,
+                set::shopt_description(),
+            )) as Box<dyn BuiltinCommandTrait>,
+        );
         builtin.insert(
             "var",
             Box::new(BuiltinCommandFn::new(var::command, var::description()))
@@ -745,9 +959,18 @@ pub fn exit_description() -> &'static str {
 }
 
 /// Built-in exit command implementation
-/// Initiates graceful shell termination
-pub fn exit(_ctx: &Context, _argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+/// Initiates graceful shell termination, with an optional exit status
+pub fn exit(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
     debug!("Exit command called - initiating normal shell exit");
+    let code = match argv.get(1).map(|arg| arg.parse::<i32>()) {
+        Some(Ok(code)) => code & 0xff,
+        Some(Err(_)) => {
+            ctx.write_stderr(&format!("exit: {}: numeric argument required\n", argv[1]))
+                .ok();
+            2
+        }
+        None => 0,
+    };
     proxy.exit_shell();
-    ExitStatus::ExitedWith(0)
+    ExitStatus::ExitedWith(code)
 }
@@ -1,5 +1,9 @@
 use super::ShellProxy;
 use dsh_types::{Context, ExitStatus};
+use std::fs::File;
+use std::io::Read;
+use std::mem::ManuallyDrop;
+use std::os::fd::{FromRawFd, RawFd};
 
 /// Built-in read command description
 pub fn description() -> &'static str {
@@ -7,15 +11,70 @@ pub fn description() -> &'static str {
 }
 
 /// Built-in read command implementation
-/// Reads input from stdin and stores it in shell variables
-/// Commonly used in shell scripts for interactive input collection
+/// `read [NAME]` reads a single line from stdin into NAME or `REPLY`, so that
+/// `while read line; do ...; done < file` sees the lines one by one.
+/// `read -u FD [NAME]` reads from descriptor FD instead, such as the output of a
+/// coprocess. The status is 1 at end of input.
 pub fn command(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
-    // Delegate input reading to the shell's input handling system
-    match proxy.dispatch(ctx, "read", argv) {
-        Ok(_) => ExitStatus::ExitedWith(0),
-        Err(e) => {
-            let _ = ctx.write_stderr(&format!("read: {}\n", e));
+    if argv.get(1).is_some_and(|arg| arg.starts_with("-u")) {
+        return read_fd_line(ctx, &argv, proxy);
+    }
+    read_into(ctx, ctx.infile, argv.get(1..).unwrap_or(&[]), proxy)
+}
+
+fn read_fd_line(ctx: &Context, argv: &[String], proxy: &mut dyn ShellProxy) -> ExitStatus {
+    // Both `-u 3` and `-u3`
+    let (fd, rest) = match argv[1].strip_prefix("-u").filter(|fd| !fd.is_empty()) {
+        Some(fd) => (Some(fd), &argv[2..]),
+        None => (
+            argv.get(2).map(String::as_str),
+            argv.get(3..).unwrap_or(&[]),
+        ),
+    };
+    let Some(fd) = fd.and_then(|fd| fd.parse::<RawFd>().ok()) else {
+        ctx.write_stderr("read: usage: read -u fd [name]").ok();
+        return ExitStatus::ExitedWith(2);
+    };
+    let fd = proxy.resolve_fd(fd);
+    read_into(ctx, fd, rest, proxy)
+}
+
+/// Reads a line from `fd` into the variable named first in `names`, or `REPLY`.
+fn read_into(ctx: &Context, fd: RawFd, names: &[String], proxy: &mut dyn ShellProxy) -> ExitStatus {
+    let name = names
+        .first()
+        .cloned()
+        .unwrap_or_else(|| "REPLY".to_string());
+
+    match read_line(fd) {
+        Ok(Some(line)) => {
+            proxy.set_var(name, line);
+            ExitStatus::ExitedWith(0)
+        }
+        Ok(None) => ExitStatus::ExitedWith(1),
+        Err(err) => {
+            ctx.write_stderr(&format!("read: {fd}: {err}")).ok();
             ExitStatus::ExitedWith(1)
         }
     }
 }
+
+/// Reads one line a byte at a time, so the rest stays in the descriptor for the next
+/// read. Returns `None` at end of input.
+fn read_line(fd: RawFd) -> std::io::Result<Option<String>> {
+    // The descriptor belongs to the shell, don't close it
+    let mut input = ManuallyDrop::new(unsafe { File::from_raw_fd(fd) });
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    loop {
+        match input.read(&mut byte) {
+            Ok(0) if line.is_empty() => return Ok(None),
+            Ok(0) => break,
+            Ok(_) if byte[0] == b'\n' => break,
+            Ok(_) => line.push(byte[0]),
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(Some(String::from_utf8_lossy(&line).into_owned()))
+}
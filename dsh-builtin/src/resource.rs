@@ -0,0 +1,354 @@
+use super::ShellProxy;
+use dsh_types::{Context, ExitStatus};
+use nix::sys::resource::{RLIM_INFINITY, Resource, getrlimit, rlim_t, setrlimit};
+use nix::sys::stat::{Mode, mode_t, umask};
+
+/// Built-in ulimit command description
+pub fn ulimit_description() -> &'static str {
+    "Show or set resource limits of the shell and the commands it starts"
+}
+
+/// Built-in umask command description
+pub fn umask_description() -> &'static str {
+    "Show or set the file creation mask"
+}
+
+/// A resource `ulimit` knows, with the flag selecting it and the unit of its values
+struct Limit {
+    flag: char,
+    name: &'static str,
+    resource: Resource,
+    /// Bytes per unit shown to the user (1 for counts and seconds)
+    unit: rlim_t,
+}
+
+const LIMITS: &[Limit] = &[
+    Limit {
+        flag: 'c',
+        name: "core file size (blocks)",
+        resource: Resource::RLIMIT_CORE,
+        unit: 1024,
+    },
+    Limit {
+        flag: 'd',
+        name: "data seg size (kbytes)",
+        resource: Resource::RLIMIT_DATA,
+        unit: 1024,
+    },
+    Limit {
+        flag: 'f',
+        name: "file size (blocks)",
+        resource: Resource::RLIMIT_FSIZE,
+        unit: 1024,
+    },
+    #[cfg(target_os = "linux")]
+    Limit {
+        flag: 'l',
+        name: "max locked memory (kbytes)",
+        resource: Resource::RLIMIT_MEMLOCK,
+        unit: 1024,
+    },
+    #[cfg(target_os = "linux")]
+    Limit {
+        flag: 'm',
+        name: "max memory size (kbytes)",
+        resource: Resource::RLIMIT_RSS,
+        unit: 1024,
+    },
+    Limit {
+        flag: 'n',
+        name: "open files",
+        resource: Resource::RLIMIT_NOFILE,
+        unit: 1,
+    },
+    Limit {
+        flag: 's',
+        name: "stack size (kbytes)",
+        resource: Resource::RLIMIT_STACK,
+        unit: 1024,
+    },
+    Limit {
+        flag: 't',
+        name: "cpu time (seconds)",
+        resource: Resource::RLIMIT_CPU,
+        unit: 1,
+    },
+    #[cfg(target_os = "linux")]
+    Limit {
+        flag: 'u',
+        name: "max user processes",
+        resource: Resource::RLIMIT_NPROC,
+        unit: 1,
+    },
+    Limit {
+        flag: 'v',
+        name: "virtual memory (kbytes)",
+        resource: Resource::RLIMIT_AS,
+        unit: 1024,
+    },
+];
+
+/// Which of the soft and hard limits `ulimit` reads or changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Which {
+    Soft,
+    Hard,
+    /// Setting without `-S`/`-H` changes both, showing reads the soft limit
+    Both,
+}
+
+/// Formats a raw limit in the unit of its resource
+fn format_limit(value: rlim_t, unit: rlim_t) -> String {
+    if value == RLIM_INFINITY {
+        "unlimited".to_string()
+    } else {
+        (value / unit).to_string()
+    }
+}
+
+/// Parses a new limit: a number in the unit of the resource, `unlimited`, or `soft` and
+/// `hard` for the current limits
+fn parse_limit(value: &str, unit: rlim_t, soft: rlim_t, hard: rlim_t) -> Result<rlim_t, String> {
+    match value {
+        "unlimited" => Ok(RLIM_INFINITY),
+        "soft" => Ok(soft),
+        "hard" => Ok(hard),
+        number => number
+            .parse::<rlim_t>()
+            .ok()
+            .and_then(|number| number.checked_mul(unit))
+            .ok_or_else(|| format!("{number}: invalid number")),
+    }
+}
+
+fn show_limit(ctx: &Context, limit: &Limit, which: Which, label: bool) -> Result<(), String> {
+    let (soft, hard) = getrlimit(limit.resource).map_err(|e| format!("{}: {e}", limit.name))?;
+    let value = format_limit(if which == Which::Hard { hard } else { soft }, limit.unit);
+    let line = if label {
+        format!("{:<28} (-{}) {value}", limit.name, limit.flag)
+    } else {
+        value
+    };
+    ctx.write_stdout(&line).map_err(|e| e.to_string())
+}
+
+fn set_limit(limit: &Limit, which: Which, value: &str) -> Result<(), String> {
+    let (soft, hard) = getrlimit(limit.resource).map_err(|e| format!("{}: {e}", limit.name))?;
+    let value = parse_limit(value, limit.unit, soft, hard)?;
+    let (soft, hard) = match which {
+        Which::Soft => (value, hard),
+        Which::Hard => (soft.min(value), value),
+        Which::Both => (value, value),
+    };
+    setrlimit(limit.resource, soft, hard)
+        .map_err(|e| format!("{}: cannot modify limit: {e}", limit.name))
+}
+
+fn run_ulimit(ctx: &Context, argv: &[String]) -> Result<(), String> {
+    let mut which = Which::Both;
+    let mut all = false;
+    let mut selected: Vec<&Limit> = Vec::new();
+    let mut args = argv.iter().skip(1).peekable();
+    while let Some(flags) = args.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+        for flag in flags[1..].chars() {
+            match flag {
+                'S' => which = Which::Soft,
+                'H' => which = Which::Hard,
+                'a' => all = true,
+                _ => match LIMITS.iter().find(|limit| limit.flag == flag) {
+                    Some(limit) => selected.push(limit),
+                    None => return Err(format!("-{flag}: invalid option")),
+                },
+            }
+        }
+    }
+    let value = args.next();
+    if args.next().is_some() {
+        return Err("too many arguments".to_string());
+    }
+
+    if all {
+        for limit in LIMITS {
+            show_limit(ctx, limit, which, true)?;
+        }
+        return Ok(());
+    }
+    if selected.is_empty() {
+        // Like in other shells, the file size is the default resource
+        selected.extend(LIMITS.iter().find(|limit| limit.flag == 'f'));
+    }
+    match value {
+        Some(value) => selected
+            .iter()
+            .try_for_each(|limit| set_limit(limit, which, value)),
+        None => {
+            let label = selected.len() > 1;
+            selected
+                .iter()
+                .try_for_each(|limit| show_limit(ctx, limit, which, label))
+        }
+    }
+}
+
+/// Built-in ulimit command implementation
+/// `ulimit [-SH] [-a] [-cdflmnstuv] [LIMIT]` shows or sets limits of the shell, which
+/// every command it starts inherits; LIMIT may be a number, `unlimited`, `soft` or `hard`
+pub fn ulimit_command(ctx: &Context, argv: Vec<String>, _proxy: &mut dyn ShellProxy) -> ExitStatus {
+    match run_ulimit(ctx, &argv) {
+        Ok(()) => ExitStatus::ExitedWith(0),
+        Err(e) => {
+            ctx.write_stderr(&format!("ulimit: {e}")).ok();
+            ExitStatus::ExitedWith(1)
+        }
+    }
+}
+
+/// Reads the file creation mask, which can only be done by setting it
+fn current_umask() -> mode_t {
+    let mask = umask(Mode::empty());
+    umask(mask);
+    mask.bits()
+}
+
+/// Formats a mask as `u=rwx,g=rx,o=rx`, the permissions it lets through
+fn symbolic_umask(mask: mode_t) -> String {
+    let allowed = !mask & 0o777;
+    ['u', 'g', 'o']
+        .iter()
+        .enumerate()
+        .map(|(i, who)| {
+            let bits = (allowed >> (6 - 3 * i)) & 0o7;
+            let perms: String = [(4, 'r'), (2, 'w'), (1, 'x')]
+                .iter()
+                .filter(|(bit, _)| bits & bit != 0)
+                .map(|(_, c)| *c)
+                .collect();
+            format!("{who}={perms}")
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Parses a new mask, either octal (`022`) or symbolic (`u=rwx,go-w`) applied to `mask`
+fn parse_umask(spec: &str, mask: mode_t) -> Result<mode_t, String> {
+    if spec.chars().all(|c| c.is_ascii_digit()) {
+        return mode_t::from_str_radix(spec, 8)
+            .ok()
+            .filter(|mask| *mask <= 0o777)
+            .ok_or_else(|| format!("{spec}: octal number out of range"));
+    }
+
+    let invalid = || format!("{spec}: invalid symbolic mode");
+    let mut allowed = !mask & 0o777;
+    for clause in spec.split(',') {
+        let op_at = clause.find(['=', '+', '-']).ok_or_else(invalid)?;
+        let (who, rest) = clause.split_at(op_at);
+        let mut who_bits = 0;
+        for c in who.chars() {
+            who_bits |= match c {
+                'u' => 0o700,
+                'g' => 0o070,
+                'o' => 0o007,
+                'a' => 0o777,
+                _ => return Err(invalid()),
+            };
+        }
+        if who_bits == 0 {
+            who_bits = 0o777;
+        }
+        let mut perm_bits = 0;
+        for c in rest[1..].chars() {
+            perm_bits |= match c {
+                'r' => 0o444,
+                'w' => 0o222,
+                'x' => 0o111,
+                _ => return Err(invalid()),
+            };
+        }
+        let bits = who_bits & perm_bits;
+        match &rest[..1] {
+            "=" => allowed = (allowed & !who_bits) | bits,
+            "+" => allowed |= bits,
+            _ => allowed &= !bits,
+        }
+    }
+    Ok(!allowed & 0o777)
+}
+
+fn run_umask(ctx: &Context, argv: &[String]) -> Result<(), String> {
+    let mut symbolic = false;
+    let mut reusable = false;
+    let mut args = argv.iter().skip(1).peekable();
+    while let Some(flags) = args.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+        for flag in flags[1..].chars() {
+            match flag {
+                'S' => symbolic = true,
+                'p' => reusable = true,
+                _ => return Err(format!("-{flag}: invalid option")),
+            }
+        }
+    }
+
+    let mask = current_umask();
+    match args.next() {
+        Some(spec) => {
+            let mask = parse_umask(spec, mask)?;
+            umask(Mode::from_bits_truncate(mask));
+            Ok(())
+        }
+        None => {
+            let value = if symbolic {
+                symbolic_umask(mask)
+            } else {
+                format!("{mask:04o}")
+            };
+            let line = if reusable {
+                let flag = if symbolic { "-S " } else { "" };
+                format!("umask {flag}{value}")
+            } else {
+                value
+            };
+            ctx.write_stdout(&line).map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Built-in umask command implementation
+/// `umask [-p] [-S] [MODE]` shows the mask in octal (`-S`: symbolic) or sets it from an
+/// octal or symbolic MODE; commands started afterwards inherit it
+pub fn umask_command(ctx: &Context, argv: Vec<String>, _proxy: &mut dyn ShellProxy) -> ExitStatus {
+    match run_umask(ctx, &argv) {
+        Ok(()) => ExitStatus::ExitedWith(0),
+        Err(e) => {
+            ctx.write_stderr(&format!("umask: {e}")).ok();
+            ExitStatus::ExitedWith(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_limit() {
+        assert_eq!(parse_limit("8", 1024, 0, 0), Ok(8192));
+        assert_eq!(parse_limit("unlimited", 1, 0, 0), Ok(RLIM_INFINITY));
+        assert_eq!(parse_limit("hard", 1, 10, 20), Ok(20));
+        assert!(parse_limit("lots", 1, 0, 0).is_err());
+        assert_eq!(format_limit(8192, 1024), "8");
+        assert_eq!(format_limit(RLIM_INFINITY, 1024), "unlimited");
+    }
+
+    #[test]
+    fn test_parse_umask() {
+        assert_eq!(parse_umask("077", 0o022), Ok(0o077));
+        assert!(parse_umask("1777", 0o022).is_err());
+        assert_eq!(parse_umask("u=rwx,g=rx,o=", 0o022), Ok(0o027));
+        assert_eq!(parse_umask("go-w", 0o002), Ok(0o022));
+        assert_eq!(parse_umask("a+r", 0o077), Ok(0o033));
+        assert!(parse_umask("u=rwz", 0o022).is_err());
+        assert_eq!(symbolic_umask(0o022), "u=rwx,g=rx,o=rx");
+        assert_eq!(symbolic_umask(0o077), "u=rwx,g=,o=");
+    }
+}
@@ -1,5 +1,5 @@
 use super::ShellProxy;
-use dsh_types::{Context, ExitStatus};
+use dsh_types::{Context, ExitStatus, options::OptionEntry};
 use getopts::Options;
 
 /// Built-in set command description
@@ -7,18 +7,136 @@ pub fn description() -> &'static str {
     "Set shell options"
 }
 
+/// Built-in shopt command description
+pub fn shopt_description() -> &'static str {
+    "Show or change shell options such as globstar, dotglob and auto-pair"
+}
+
 /// Prints usage information for the set command
 /// Displays command syntax and available options
 fn print_usage(ctx: &Context, cmd_name: &str, opts: Options) {
-    let brief = format!("Usage: {cmd_name} [OPTIONS] KEY VALUE");
+    let brief = format!(
+        "Usage: {cmd_name} [OPTIONS] KEY VALUE\n       {cmd_name} [-euxC] [+euxC] [-o NAME] [+o NAME]"
+    );
     ctx.write_stdout(&opts.usage(&brief)).ok();
 }
 
+/// Single-letter flags and their `-o` names
+const OPTION_FLAGS: [(char, &str); 4] = [
+    ('e', "errexit"),
+    ('u', "nounset"),
+    ('x', "xtrace"),
+    ('C', "noclobber"),
+];
+
+/// One change requested by `set -e`, `set +o pipefail`, ...
+#[derive(Debug, PartialEq, Eq)]
+enum OptionArg {
+    Set(String, bool),
+    /// A bare `-o` (true) or `+o` (false)
+    List(bool),
+}
+
+/// Parses arguments that only change shell options, returning None when they assign a
+/// variable instead. `o` may end a group of flags, as in `set -eo pipefail`.
+fn parse_option_args(args: &[String]) -> Option<Vec<OptionArg>> {
+    let mut parsed = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let enable = arg.starts_with('-');
+        let flags = arg
+            .strip_prefix(['-', '+'])
+            .filter(|flags| !flags.is_empty())?;
+        let mut chars = flags.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == 'o' && chars.peek().is_none() {
+                parsed.push(match iter.next() {
+                    Some(name) => OptionArg::Set(name.clone(), enable),
+                    None => OptionArg::List(enable),
+                });
+            } else {
+                let (_, name) = OPTION_FLAGS.iter().find(|(flag, _)| *flag == c)?;
+                parsed.push(OptionArg::Set(name.to_string(), enable));
+            }
+        }
+    }
+    (!parsed.is_empty()).then_some(parsed)
+}
+
+/// Line that restores an option, as printed by `set +o` and `shopt -p`
+fn restore_command(option: &OptionEntry, shopt: bool) -> String {
+    match (option.is_switch(), shopt) {
+        (true, true) => format!(
+            "shopt -{} {}",
+            if option.is_on() { 's' } else { 'u' },
+            option.name
+        ),
+        (true, false) => format!(
+            "set {}o {}",
+            if option.is_on() { '-' } else { '+' },
+            option.name
+        ),
+        (false, _) => format!(
+            "set -o {}={}",
+            option.name,
+            quote(&option.value.to_string())
+        ),
+    }
+}
+
+/// Quotes a value for the shell unless it is a plain word
+fn quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:,+@%".contains(c));
+    if plain {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// Applies the parsed options; a bare `-o` lists them and a bare `+o` prints them as
+/// commands that restore the current settings. `-o NAME=VALUE` sets options that are not
+/// switches, such as `safety-level`
+fn set_options(ctx: &Context, options: Vec<OptionArg>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    for option in options {
+        match option {
+            OptionArg::Set(name, enable) => {
+                let (name, value) = match name.split_once('=') {
+                    Some((name, value)) => (name, value),
+                    None => (name.as_str(), if enable { "on" } else { "off" }),
+                };
+                if let Err(err) = proxy.set_shell_option(name, value) {
+                    ctx.write_stderr(&format!("set: {err}")).ok();
+                    return ExitStatus::ExitedWith(2);
+                }
+            }
+            OptionArg::List(as_table) => {
+                for option in proxy.shell_options() {
+                    let line = if as_table {
+                        format!("{:<15} {}", option.name, option.value)
+                    } else {
+                        restore_command(&option, false)
+                    };
+                    ctx.write_stdout(&line).ok();
+                }
+            }
+        }
+    }
+    ExitStatus::ExitedWith(0)
+}
+
 /// Built-in set command implementation
 /// Sets shell variables or environment variables with optional export functionality
 /// Supports both local shell variables and exported environment variables
+/// Without KEY VALUE, `-e`, `-u`, `-x` and `-o NAME` turn shell options on and `+` turns them off
 pub fn command(ctx: &Context, args: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
     let cmd_name = args[0].clone();
+    if let Some(options) = parse_option_args(&args[1..]) {
+        return set_options(ctx, options, proxy);
+    }
     let mut opts = Options::new();
 
     // Define command-line options
@@ -52,3 +170,138 @@ pub fn command(ctx: &Context, args: Vec<String>, proxy: &mut dyn ShellProxy) ->
     }
     ExitStatus::ExitedWith(0)
 }
+
+/// Built-in shopt command implementation
+/// `shopt -s NAME...` turns options on and `shopt -u NAME...` turns them off; otherwise the
+/// named options (or all switches) are shown, with `-p` as commands that restore them and
+/// with `-q` only through the status, which is 0 when all shown options are on
+pub fn shopt_command(ctx: &Context, args: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    let mut change = None;
+    let mut quiet = false;
+    let mut reusable = false;
+    let mut names = args.iter().skip(1).peekable();
+    while let Some(flags) = names.next_if(|arg| arg.starts_with('-') && arg.len() > 1) {
+        for flag in flags[1..].chars() {
+            match flag {
+                's' => change = Some("on"),
+                'u' => change = Some("off"),
+                'q' => quiet = true,
+                'p' => reusable = true,
+                _ => {
+                    ctx.write_stderr(&format!("shopt: -{flag}: invalid option"))
+                        .ok();
+                    return ExitStatus::ExitedWith(2);
+                }
+            }
+        }
+    }
+    let names: Vec<&String> = names.collect();
+
+    if let Some(value) = change {
+        for name in &names {
+            if let Err(err) = proxy.set_shell_option(name, value) {
+                ctx.write_stderr(&format!("shopt: {err}")).ok();
+                return ExitStatus::ExitedWith(1);
+            }
+        }
+        return ExitStatus::ExitedWith(0);
+    }
+
+    let options = proxy.shell_options();
+    let mut shown = Vec::new();
+    if names.is_empty() {
+        shown.extend(options.iter().filter(|option| option.is_switch()));
+    }
+    for name in names {
+        match options.iter().find(|option| option.name == *name) {
+            Some(option) => shown.push(option),
+            None => {
+                ctx.write_stderr(&format!("shopt: {name}: invalid shell option name"))
+                    .ok();
+                return ExitStatus::ExitedWith(1);
+            }
+        }
+    }
+    if !quiet {
+        for option in &shown {
+            let line = if reusable {
+                restore_command(option, true)
+            } else {
+                format!("{:<15} {}", option.name, option.value)
+            };
+            ctx.write_stdout(&line).ok();
+        }
+    }
+    ExitStatus::ExitedWith(if shown.iter().all(|option| option.is_on()) {
+        0
+    } else {
+        1
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_option_args() {
+        let set = |name: &str, enable| OptionArg::Set(name.to_string(), enable);
+        assert_eq!(
+            parse_option_args(&args(&["-e"])),
+            Some(vec![set("errexit", true)])
+        );
+        assert_eq!(
+            parse_option_args(&args(&["-eu", "+x"])),
+            Some(vec![
+                set("errexit", true),
+                set("nounset", true),
+                set("xtrace", false)
+            ])
+        );
+        assert_eq!(
+            parse_option_args(&args(&["-eo", "pipefail"])),
+            Some(vec![set("errexit", true), set("pipefail", true)])
+        );
+        assert_eq!(
+            parse_option_args(&args(&["+o"])),
+            Some(vec![OptionArg::List(false)])
+        );
+        assert_eq!(parse_option_args(&args(&[])), None);
+        assert_eq!(parse_option_args(&args(&["-x", "KEY", "VALUE"])), None);
+        assert_eq!(parse_option_args(&args(&["KEY", "VALUE"])), None);
+        assert_eq!(parse_option_args(&args(&["-h"])), None);
+    }
+
+    #[test]
+    fn test_restore_command() {
+        use dsh_types::options::OptionValue;
+
+        let option = |name: &str, value| OptionEntry {
+            name: name.to_string(),
+            value,
+            description: String::new(),
+        };
+        let dotglob = option("dotglob", OptionValue::Bool(false));
+        assert_eq!(restore_command(&dotglob, false), "set +o dotglob");
+        assert_eq!(restore_command(&dotglob, true), "shopt -u dotglob");
+        let level = option("safety-level", OptionValue::Text("strict".to_string()));
+        assert_eq!(restore_command(&level, true), "set -o safety-level=strict");
+        let completer = option(
+            "external-completer",
+            OptionValue::Text("carapace _carapace 'x'".to_string()),
+        );
+        assert_eq!(
+            restore_command(&completer, false),
+            "set -o external-completer='carapace _carapace '\\''x'\\'''"
+        );
+        let unset = option("external-completer", OptionValue::Text(String::new()));
+        assert_eq!(
+            restore_command(&unset, false),
+            "set -o external-completer=''"
+        );
+    }
+}
@@ -0,0 +1,17 @@
+use super::ShellProxy;
+use dsh_types::{Context, ExitStatus};
+
+/// Built-in source command description
+pub fn description() -> &'static str {
+    "Run a dsh script in the current shell"
+}
+
+/// Built-in source command implementation
+/// `source FILE [ARGS...]` (or `. FILE`) is run by the shell, which evaluates FILE
+/// statement by statement so that variables, aliases, functions and the working
+/// directory it changes are kept; ARGS become `$1`, `$2`, ...
+pub fn command(ctx: &Context, argv: Vec<String>, _proxy: &mut dyn ShellProxy) -> ExitStatus {
+    ctx.write_stderr(&format!("{}: can only be run by the shell\n", argv[0]))
+        .ok();
+    ExitStatus::ExitedWith(1)
+}
@@ -0,0 +1,19 @@
+use super::ShellProxy;
+use dsh_types::{Context, ExitStatus};
+
+/// Built-in trap command description
+pub fn description() -> &'static str {
+    "Run commands when the shell receives a signal or exits (EXIT, ERR, DEBUG)"
+}
+
+/// Built-in trap command implementation
+/// Sets, resets (`trap - SIG`) and prints (`trap -p`) signal handlers
+pub fn command(ctx: &Context, argv: Vec<String>, proxy: &mut dyn ShellProxy) -> ExitStatus {
+    match proxy.dispatch(ctx, "trap", argv) {
+        Ok(()) => ExitStatus::ExitedWith(0),
+        Err(e) => {
+            ctx.write_stderr(&format!("trap: {e}")).ok();
+            ExitStatus::ExitedWith(1)
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use super::ShellProxy;
+use dsh_types::{Context, ExitStatus};
+
+/// Built-in wait command description
+pub fn description() -> &'static str {
+    "Wait for jobs to finish and return their exit status"
+}
+
+/// Built-in wait command implementation
+/// `wait` waits for all jobs, `wait %N PID...` for the given ones and `wait -n` for the
+/// next one to finish; the exit status is that of the job waited for. It is run by the
+/// shell, which awaits the jobs
+pub fn command(ctx: &Context, argv: Vec<String>, _proxy: &mut dyn ShellProxy) -> ExitStatus {
+    ctx.write_stderr(&format!("{}: can only be run by the shell\n", argv[0]))
+        .ok();
+    ExitStatus::ExitedWith(1)
+}
@@ -16,6 +16,7 @@ pub mod terminal;
 pub use terminal::{ShellMode, TerminalState};
 pub mod mcp;
 pub mod notebook;
+pub mod options;
 pub mod output_history;
 pub mod project;
 pub mod snippet;
@@ -0,0 +1,47 @@
+//! Shell options as seen by builtins
+//!
+//! The options themselves are registered by the shell; builtins such as `set -o`,
+//! `shopt` and `doctor` only list them and change them by name.
+
+use std::fmt;
+
+/// Value of a shell option
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionValue {
+    /// A switch, shown as `on` or `off`
+    Bool(bool),
+    Number(u64),
+    /// A word, such as the safety level
+    Text(String),
+}
+
+impl fmt::Display for OptionValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bool(true) => f.write_str("on"),
+            Self::Bool(false) => f.write_str("off"),
+            Self::Number(n) => write!(f, "{n}"),
+            Self::Text(text) => f.write_str(text),
+        }
+    }
+}
+
+/// A registered option and its current value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptionEntry {
+    pub name: String,
+    pub value: OptionValue,
+    pub description: String,
+}
+
+impl OptionEntry {
+    /// Whether the option is turned on and off rather than given a value
+    pub fn is_switch(&self) -> bool {
+        matches!(self.value, OptionValue::Bool(_))
+    }
+
+    /// Whether the option is a switch that is on
+    pub fn is_on(&self) -> bool {
+        self.value == OptionValue::Bool(true)
+    }
+}
@@ -17,6 +17,7 @@ pub mod port_check;
 pub mod reload_config;
 pub mod run_script;
 pub mod search_history;
+pub mod shell_options;
 pub mod show_env;
 pub mod ssh_connect;
 
@@ -54,6 +55,7 @@ pub fn register_all(registry: &mut ActionRegistry) {
     registry.register(Arc::new(ssh_connect::SshConnectAction));
     // Environment
     registry.register(Arc::new(show_env::ShowEnvAction));
+    registry.register(Arc::new(shell_options::ShellOptionsAction));
 
     // AI
     registry.register(Arc::new(ai::explain::ExplainCommandAction));
@@ -0,0 +1,73 @@
+use super::super::Action;
+use crate::environment::find_option;
+use crate::shell::Shell;
+use anyhow::Result;
+use async_trait::async_trait;
+use skim::prelude::*;
+
+pub struct ShellOptionsAction;
+
+#[async_trait(?Send)]
+impl Action for ShellOptionsAction {
+    fn name(&self) -> &str {
+        "Shell Options"
+    }
+    fn description(&self) -> &str {
+        "Show and toggle shell options"
+    }
+    fn icon(&self) -> &str {
+        "🔧"
+    }
+
+    async fn execute(&self, shell: &mut Shell, _input: &str) -> Result<()> {
+        let options = shell.environment.read().options();
+
+        use crate::command_palette::StringItem;
+
+        let options_ui = SkimOptionsBuilder::default()
+            .prompt("Option> ".to_string())
+            .bind(vec!["Enter:accept".to_string(), "Esc:abort".to_string()])
+            .build()
+            .map_err(|e| anyhow::anyhow!("Failed to build skim options: {}", e))?;
+
+        let (tx, rx): (SkimItemSender, SkimItemReceiver) = unbounded();
+        for option in &options {
+            let line = format!(
+                "{:<22} {:<8} {}",
+                option.name, option.value, option.description
+            );
+            let _ = tx.send(vec![Arc::new(StringItem(line))]);
+        }
+        drop(tx);
+
+        let selected = crate::utils::skim::run_skim_with(options_ui, Some(rx))
+            .map(|out| out.selected_items)
+            .unwrap_or_default();
+
+        let Some(item) = selected.first() else {
+            return Ok(());
+        };
+        let line = item.output().to_string();
+        let Some(spec) = line.split_whitespace().next().and_then(find_option) else {
+            return Ok(());
+        };
+
+        // Switches flip and choices move to the next one; numbers need a value
+        let current = spec.get(&shell.environment.read());
+        match spec.next_value(&current) {
+            Some(value) => {
+                shell
+                    .environment
+                    .write()
+                    .set_option(spec.name, value.clone())?;
+                println!("{}: {} -> {}", spec.name, current, value);
+            }
+            None => println!(
+                "{} is {}; change it with `set -o {}=VALUE`",
+                spec.name, current, spec.name
+            ),
+        }
+
+        Ok(())
+    }
+}
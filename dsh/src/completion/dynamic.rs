@@ -335,12 +335,7 @@ impl DynamicCompletionProvider {
         cursor_pos: usize,
         parsed_command_line: &ParsedCommandLine,
     ) -> Vec<EnhancedCandidate> {
-        let Some(command_template) = self
-            .environment
-            .read()
-            .get_var("DSH_EXTERNAL_COMPLETER")
-            .filter(|value| !value.trim().is_empty())
-        else {
+        let Some(command_template) = self.environment.read().external_completer() else {
             return Vec::new();
         };
 
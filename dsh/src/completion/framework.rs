@@ -43,6 +43,26 @@ pub enum CompletionFrameworkKind {
     Skim,
 }
 
+impl CompletionFrameworkKind {
+    /// The framework named `inline` or `skim`, in any case.
+    pub fn parse(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case("inline") {
+            Some(Self::Inline)
+        } else if name.eq_ignore_ascii_case("skim") {
+            Some(Self::Skim)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Inline => "inline",
+            Self::Skim => "skim",
+        }
+    }
+}
+
 /// Trait implemented by completion presentation backends.
 /// Result of a completion selection attempt.
 #[derive(Debug, PartialEq)]
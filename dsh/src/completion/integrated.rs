@@ -12,6 +12,7 @@ use super::json_loader::JsonCompletionLoader;
 use super::parser::{self, CommandLineParser, ParsedCommandLine};
 use crate::completion::display::Candidate;
 use crate::environment::Environment;
+use crate::parser::pathname;
 use anyhow::Result;
 use dsh_builtin::project;
 use dsh_types::mcp::McpTransport;
@@ -165,7 +166,7 @@ impl<'a> CandidateAggregator<'a> {
             CompletionFrameworkKind::Inline
         } else {
             self.framework
-                .unwrap_or_else(super::default_completion_framework)
+                .unwrap_or_else(|| self.engine.environment.read().completion_framework())
         };
 
         CompletionResult {
@@ -290,6 +291,19 @@ impl IntegratedCompletionEngine {
             completion_replacement_range(input, cursor_pos, &parsed_command_line);
         let uses_dynamic_completion = is_dynamic_completion_command(&parsed_command_line.command);
 
+        // A pattern completes to the paths it matches; these are not cached as files come
+        // and go
+        let mut glob_candidates =
+            self.collect_glob_candidates(&parsed_command_line.current_token, current_dir);
+        if !glob_candidates.is_empty() {
+            glob_candidates.truncate(request.max_results);
+            return CompletionResult {
+                candidates: glob_candidates,
+                framework: CompletionFrameworkKind::Skim,
+                replacement_range,
+            };
+        }
+
         if !uses_dynamic_completion
             && !request.input.is_empty()
             && let Some(hit) = self.cache.lookup(request.input)
@@ -303,7 +317,7 @@ impl IntegratedCompletionEngine {
                 self.cache.extend_ttl(&hit.key);
                 let framework = self
                     .lookup_cached_framework(&hit.key)
-                    .unwrap_or_else(super::default_completion_framework);
+                    .unwrap_or_else(|| self.environment.read().completion_framework());
 
                 return CompletionResult {
                     candidates: hit.candidates,
@@ -606,6 +620,31 @@ impl IntegratedCompletionEngine {
         }
     }
 
+    /// Paths matched by a pattern such as `**/*.rs`, expanded with the shell's glob
+    /// options (`globstar`, `dotglob`, `extglob`).
+    fn collect_glob_candidates(
+        &self,
+        current_token: &str,
+        current_dir: &Path,
+    ) -> Vec<EnhancedCandidate> {
+        let options = self.environment.read().shell_options;
+        let pattern = shellexpand::tilde(current_token);
+        let Some(paths) = pathname::expand(&pattern, current_dir, &options) else {
+            return Vec::new();
+        };
+        paths
+            .into_iter()
+            .map(|path| {
+                let candidate = if current_dir.join(&path).is_dir() {
+                    CompletionCandidate::directory(path)
+                } else {
+                    CompletionCandidate::file(path)
+                };
+                self.convert_to_enhanced_candidate(candidate)
+            })
+            .collect()
+    }
+
     fn collect_mcp_candidates(
         &self,
         parsed_command_line: &ParsedCommandLine,
@@ -1272,6 +1311,39 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn glob_completion_follows_shell_glob_options() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        fs::write(dir.path().join(".hidden.rs"), "").unwrap();
+        fs::write(dir.path().join("lib.rs"), "").unwrap();
+
+        let environment = Environment::new();
+        let mut engine = IntegratedCompletionEngine::new(Arc::clone(&environment));
+        engine.initialize_command_completion().unwrap();
+        let texts = |result: CompletionResult| {
+            result
+                .candidates
+                .into_iter()
+                .map(|candidate| candidate.text)
+                .collect::<Vec<_>>()
+        };
+
+        let input = "cat **/*.rs";
+        let result = engine
+            .complete(input, input.len(), dir.path(), 50, None)
+            .await;
+        assert_eq!(texts(result), vec!["lib.rs", "src/main.rs"]);
+
+        environment.write().shell_options.dotglob = true;
+        let input = "cat *.rs";
+        let result = engine
+            .complete(input, input.len(), dir.path(), 50, None)
+            .await;
+        assert_eq!(texts(result), vec![".hidden.rs", "lib.rs"]);
+    }
+
     #[tokio::test]
     async fn escaped_path_completion_generates_and_formats_candidate() {
         let dir = tempdir().unwrap();
@@ -6,6 +6,7 @@ use crate::completion::framework::{
 };
 use crate::completion::fuzzy::fuzzy_match_score;
 use crate::completion::skim_adapter::replace_space;
+use crate::environment::COMPLETION_FRAMEWORK_ENV;
 use crate::input::Input;
 use crate::lisp::Value;
 use crate::repl::Repl;
@@ -31,12 +32,13 @@ pub fn get_prompt_and_input_for_completion() -> (String, String) {
     ("$ ".to_string(), "".to_string())
 }
 
+/// The framework named by `DSH_COMPLETION_FRAMEWORK` in the process environment, for
+/// callers without a shell; the shell reads the `completion-framework` option.
 pub fn default_completion_framework() -> CompletionFrameworkKind {
-    match std::env::var("DSH_COMPLETION_FRAMEWORK") {
-        Ok(value) if value.eq_ignore_ascii_case("skim") => CompletionFrameworkKind::Skim,
-        Ok(value) if value.eq_ignore_ascii_case("inline") => CompletionFrameworkKind::Inline,
-        _ => CompletionFrameworkKind::Inline,
-    }
+    std::env::var(COMPLETION_FRAMEWORK_ENV)
+        .ok()
+        .and_then(|name| CompletionFrameworkKind::parse(&name))
+        .unwrap_or(CompletionFrameworkKind::Inline)
 }
 
 pub fn select_completion_items(
@@ -151,7 +153,7 @@ pub async fn input_completion(
     prompt_text: &str,
     input_text: &str,
 ) -> CompletionSelection {
-    let framework = default_completion_framework();
+    let framework = repl.shell.environment.read().completion_framework();
     // Main fallback completion function that tries multiple completion sources in sequence:
     // 1. Lisp-based completion (custom completion definitions)
     // 2. Current context completion (path completion, command completion from PATH)
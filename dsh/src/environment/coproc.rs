@@ -0,0 +1,77 @@
+//! Coprocesses started with `coproc`.
+//!
+//! The shell's ends of the two pipes of a coprocess are kept in
+//! [`Environment::exec_fds`] like descriptors opened with `exec 3>file`, so every command
+//! can use them by number. `${NAME[0]}` is the descriptor to read the output of the
+//! coprocess from and `${NAME[1]}` the one to write its input to; `$NAME_PID` is its pid.
+
+use super::Environment;
+use std::os::fd::{OwnedFd, RawFd};
+
+/// Lowest descriptor number given to the pipes of a coprocess.
+const COPROC_FD_BASE: RawFd = 60;
+
+/// A running coprocess, with the descriptor numbers scripts use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Coproc {
+    pub pid: i32,
+    /// Reads the output of the coprocess (`${NAME[0]}`)
+    pub read_fd: RawFd,
+    /// Writes to the input of the coprocess (`${NAME[1]}`)
+    pub write_fd: RawFd,
+}
+
+impl Environment {
+    /// Registers a coprocess and the shell's ends of its pipes.
+    ///
+    /// A previous coprocess with the same name loses its descriptors, which closes its
+    /// input.
+    pub fn add_coproc(&mut self, name: &str, pid: i32, output: OwnedFd, input: OwnedFd) -> Coproc {
+        self.remove_coproc(name);
+        let read_fd = self.free_coproc_fd();
+        self.exec_fds.insert(read_fd, output);
+        let write_fd = self.free_coproc_fd();
+        self.exec_fds.insert(write_fd, input);
+        let coproc = Coproc {
+            pid,
+            read_fd,
+            write_fd,
+        };
+        self.coprocs.insert(name.to_string(), coproc);
+        coproc
+    }
+
+    /// Forgets a coprocess and closes the shell's ends of its pipes.
+    pub fn remove_coproc(&mut self, name: &str) -> Option<Coproc> {
+        let coproc = self.coprocs.remove(name)?;
+        self.exec_fds.remove(&coproc.read_fd);
+        self.exec_fds.remove(&coproc.write_fd);
+        Some(coproc)
+    }
+
+    /// Value of `NAME`, `NAME[0]`, `NAME[1]` or `NAME_PID` for a coprocess.
+    pub(crate) fn coproc_var(&self, key: &str) -> Option<String> {
+        if let Some(name) = key.strip_suffix("_PID")
+            && let Some(coproc) = self.coprocs.get(name)
+        {
+            return Some(coproc.pid.to_string());
+        }
+        let (name, index) = match key.split_once('[') {
+            Some((name, rest)) => (name, rest.strip_suffix(']')?),
+            None => (key, "0"),
+        };
+        let coproc = self.coprocs.get(name)?;
+        match index {
+            "0" => Some(coproc.read_fd.to_string()),
+            "1" => Some(coproc.write_fd.to_string()),
+            "@" | "*" => Some(format!("{} {}", coproc.read_fd, coproc.write_fd)),
+            _ => None,
+        }
+    }
+
+    fn free_coproc_fd(&self) -> RawFd {
+        (COPROC_FD_BASE..)
+            .find(|fd| !self.exec_fds.contains_key(fd))
+            .unwrap_or(COPROC_FD_BASE)
+    }
+}
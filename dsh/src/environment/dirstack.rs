@@ -0,0 +1,56 @@
+//! Directory stack for `pushd`, `popd` and `dirs`.
+//!
+//! Entry 0 of the stack is always the current directory and the directories saved in
+//! [`Environment::dir_stack`] follow it. `+N` counts entries from the left starting at
+//! 0, `-N` counts from the right.
+
+use super::Environment;
+
+impl Environment {
+    /// Returns the current directory followed by the saved directories.
+    pub fn dir_stack_entries(&self) -> Vec<String> {
+        let cwd = std::env::current_dir()
+            .map(|dir| dir.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        std::iter::once(cwd)
+            .chain(self.dir_stack.iter().cloned())
+            .collect()
+    }
+
+    /// Resolves `~+`, `~-`, `~N`, `~+N` and `~-N` at the start of a word.
+    ///
+    /// Returns the directory and the rest of the word, or `None` when the word does not
+    /// start with one of these prefixes or the entry does not exist.
+    pub fn expand_dir_stack_tilde<'a>(&self, word: &'a str) -> Option<(String, &'a str)> {
+        let rest = word.strip_prefix('~')?;
+        let (prefix, suffix) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let dir = match prefix {
+            "+" => self.dir_stack_entries().swap_remove(0),
+            "-" => self.variables.get("OLDPWD")?.clone(),
+            spec if spec.starts_with(|c: char| c.is_ascii_digit() || c == '+' || c == '-') => {
+                let mut entries = self.dir_stack_entries();
+                let index = dir_stack_index(spec, entries.len())?;
+                entries.swap_remove(index)
+            }
+            _ => return None,
+        };
+        Some((dir, suffix))
+    }
+}
+
+/// Resolves `+N`, `-N` or a bare `N` to an index into a stack of `len` entries.
+pub fn dir_stack_index(spec: &str, len: usize) -> Option<usize> {
+    let (from_right, digits) = match spec.as_bytes().first()? {
+        b'+' => (false, &spec[1..]),
+        b'-' => (true, &spec[1..]),
+        _ => (false, spec),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let n: usize = digits.parse().ok()?;
+    if n >= len {
+        return None;
+    }
+    Some(if from_right { len - 1 - n } else { n })
+}
@@ -3,30 +3,43 @@
 //! This module provides the core `Environment` struct that holds:
 //! - PATH and command lookup
 //! - Variables and exports
-//! - Aliases and abbreviations
+//! - Aliases, abbreviations and shell functions
 //! - MCP server configuration
 //! - Input preferences
 //!
 //! # Module Structure
 //!
+//! - [`coproc`] - Coprocesses started with `coproc`
+//! - [`dirstack`] - Directory stack for `pushd`, `popd` and `dirs`
 //! - [`paths`] - PATH lookup and command caching
 //! - [`variables`] - Variable and alias resolution  
 //! - [`mcp`] - MCP server management
 //! - [`preferences`] - Input preferences and settings
 
+mod coproc;
+mod dirstack;
 mod mcp;
 mod paths;
 mod preferences;
 mod variables;
 
+pub use coproc::Coproc;
+pub use dirstack::dir_stack_index;
+pub use preferences::{
+    COMPLETION_FRAMEWORK_ENV, EXTERNAL_COMPLETER_VAR, NO_PTY_ENV, OPTIONS, OptionKind, OptionSpec,
+    ShellOptions, find_option,
+};
+
 #[cfg(test)]
 mod tests;
 
 use crate::ai_features::AiService;
 use crate::completion::AutoComplete;
 use crate::direnv::DirEnvironment;
+use crate::parser::compound::Command;
 use crate::secrets::SecretManager;
 use crate::shell::APP_NAME;
+use crate::shell::trap::{TrapAction, TrapSignal};
 use crate::suggestion::InputPreferences;
 use anyhow::Context as _;
 use anyhow::Result;
@@ -35,8 +48,9 @@ use dsh_types::mcp::McpServerConfig;
 use dsh_types::output_history::OutputHistory;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
+use std::os::fd::{OwnedFd, RawFd};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::UNIX_EPOCH;
@@ -74,6 +88,10 @@ pub trait ChangePwdHook: Send + Sync {
 /// Shell environment configuration and state.
 pub struct Environment {
     pub alias: HashMap<String, String>,
+    /// Shell functions defined with `name() { ... }`
+    pub functions: HashMap<String, Arc<Command>>,
+    /// Positional parameters (`$1`, `$@`, `$#`) of the current function or script
+    pub positional_params: Vec<String>,
     pub abbreviations: HashMap<String, String>,
     pub autocompletion: UnsafeSend<Vec<AutoComplete>>,
     pub paths: Vec<String>,
@@ -86,6 +104,21 @@ pub struct Environment {
     pub execute_allowlist: Arc<RwLock<Vec<String>>>,
     pub system_env_vars: HashMap<String, String>,
     pub input_preferences: InputPreferences,
+    /// Options set with `set -e`, `set -o pipefail`, ...
+    pub shell_options: ShellOptions,
+    /// Exit status of the last command (`$?`)
+    pub last_status: i32,
+    /// Exit status of each stage of the last pipeline (`$PIPESTATUS`)
+    pub pipe_status: Vec<i32>,
+    /// Directories saved by `pushd`, most recent first (the current directory is not included)
+    pub dir_stack: Vec<String>,
+    /// Handlers set with `trap` or the Lisp `trap` function
+    pub traps: BTreeMap<TrapSignal, TrapAction>,
+    /// Descriptors opened with `exec 3>file`, by the number scripts use. The shell keeps
+    /// them close-on-exec elsewhere and each command gets them at that number.
+    pub exec_fds: BTreeMap<RawFd, OwnedFd>,
+    /// Coprocesses by name; their descriptors are in `exec_fds`
+    pub coprocs: BTreeMap<String, Coproc>,
     pub safety_level: Arc<RwLock<crate::safety::SafetyLevel>>,
     /// Cache for PATH command lookups to avoid repeated filesystem access
     pub(crate) command_cache: RwLock<HashMap<String, Option<String>>>,
@@ -145,6 +178,8 @@ impl Environment {
         #[allow(clippy::arc_with_non_send_sync)]
         let env_arc = Arc::new(RwLock::new(Environment {
             alias: HashMap::new(),
+            functions: HashMap::new(),
+            positional_params: Vec::new(),
             abbreviations: HashMap::new(),
             autocompletion: UnsafeSend(Vec::new()),
             variables: HashMap::new(),
@@ -157,6 +192,13 @@ impl Environment {
             execute_allowlist: Arc::new(RwLock::new(Vec::new())),
             system_env_vars,
             input_preferences: default_input_preferences(),
+            shell_options: ShellOptions::default(),
+            last_status: 0,
+            pipe_status: Vec::new(),
+            dir_stack: Vec::new(),
+            traps: BTreeMap::new(),
+            exec_fds: BTreeMap::new(),
+            coprocs: BTreeMap::new(),
             safety_level: Arc::new(RwLock::new(crate::safety::SafetyLevel::Normal)),
 
             command_cache: RwLock::new(HashMap::new()),
@@ -180,6 +222,8 @@ impl Environment {
     /// Create a child environment that inherits from the parent.
     pub fn extend(parent: Arc<RwLock<Environment>>) -> Arc<RwLock<Self>> {
         let alias = parent.read().alias.clone();
+        let functions = parent.read().functions.clone();
+        let positional_params = parent.read().positional_params.clone();
         let abbreviations = parent.read().abbreviations.clone();
         let paths = parent.read().paths.clone();
         let autocompletion = parent.read().autocompletion.clone();
@@ -190,12 +234,23 @@ impl Environment {
         let mcp_manager = parent.read().mcp_manager.clone();
         let execute_allowlist = parent.read().execute_allowlist.clone();
         let input_preferences = parent.read().input_preferences;
+        let shell_options = parent.read().shell_options;
+        let dir_stack = parent.read().dir_stack.clone();
         let system_env_vars = parent.read().system_env_vars.clone();
         let safety_level = parent.read().safety_level.clone();
+        let coprocs = parent.read().coprocs.clone();
+        let exec_fds = parent
+            .read()
+            .exec_fds
+            .iter()
+            .filter_map(|(fd, file)| file.try_clone().ok().map(|file| (*fd, file)))
+            .collect();
 
         #[allow(clippy::arc_with_non_send_sync)]
         Arc::new(RwLock::new(Environment {
             alias,
+            functions,
+            positional_params,
             abbreviations,
             autocompletion,
             variables,
@@ -208,6 +263,13 @@ impl Environment {
             execute_allowlist,
             system_env_vars,
             input_preferences,
+            shell_options,
+            last_status: 0,
+            pipe_status: Vec::new(),
+            dir_stack,
+            traps: BTreeMap::new(),
+            exec_fds,
+            coprocs,
             safety_level,
             command_cache: RwLock::new(HashMap::new()),
             executable_names: Arc::new(RwLock::new(Vec::new())),
@@ -225,6 +287,7 @@ impl std::fmt::Debug for Environment {
         let execute_allowlist_len = self.execute_allowlist.read().len();
         f.debug_struct("Environment")
             .field("alias", &self.alias)
+            .field("functions", &self.functions.keys().collect::<Vec<_>>())
             .field("abbreviations", &self.abbreviations)
             .field("autocompletion", &self.autocompletion)
             .field("direnv_paths", &self.direnv_roots)
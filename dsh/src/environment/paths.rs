@@ -58,6 +58,24 @@ impl Environment {
         self.lookup(cmd)
     }
 
+    /// Every match of a command in PATH, in PATH order, for `type -a`.
+    pub fn lookup_all(&self, cmd: &str) -> Vec<String> {
+        if is_absolute_command_path(cmd) || is_relative_command_path(cmd) {
+            return self.lookup(cmd).into_iter().collect();
+        }
+        let mut found: Vec<String> = Vec::new();
+        for path in &self.paths {
+            let cmd_path = Path::new(path).join(cmd);
+            if cmd_path.is_file()
+                && let Some(cmd_path) = cmd_path.to_str()
+                && !found.iter().any(|seen| seen == cmd_path)
+            {
+                found.push(cmd_path.to_string());
+            }
+        }
+        found
+    }
+
     fn lookup_path_uncached(&self, cmd: &str) -> Option<String> {
         for path in &self.paths {
             let cmd_path = Path::new(path).join(cmd);
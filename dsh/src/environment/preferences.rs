@@ -1,9 +1,401 @@
-//! Input preferences and settings.
+//! Input preferences, shell options and the registry that lists them all.
 
 use super::Environment;
+use crate::completion::framework::CompletionFrameworkKind;
+use crate::safety::SafetyLevel;
 use crate::suggestion::{InputPreferences, SuggestionMode};
+use anyhow::{Result, bail};
+use dsh_types::options::{OptionEntry, OptionValue};
+
+/// Shell options changed with `set -e`, `set +u`, `set -o pipefail` or `shopt -s dotglob`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShellOptions {
+    /// `-e`: exit when a command fails outside of a condition or `&&`/`||` list.
+    pub errexit: bool,
+    /// `-u`: treat expanding an unset variable as an error.
+    pub nounset: bool,
+    /// `-x`: print each command to stderr before running it.
+    pub xtrace: bool,
+    /// The status of a pipeline is that of its last failing stage.
+    pub pipefail: bool,
+    /// `-C`: `>` refuses to overwrite an existing regular file; `>|` still does.
+    pub noclobber: bool,
+    /// `**` matches any number of directories; on by default.
+    pub globstar: bool,
+    /// Wildcards also match names starting with a dot.
+    pub dotglob: bool,
+    /// A pattern matching nothing expands to nothing instead of itself.
+    pub nullglob: bool,
+    /// A pattern matching nothing is an error and the command does not run.
+    pub failglob: bool,
+    /// `?(a|b)`, `*(...)`, `+(...)`, `@(...)` and `!(...)` patterns.
+    pub extglob: bool,
+}
+
+impl Default for ShellOptions {
+    fn default() -> Self {
+        Self {
+            errexit: false,
+            nounset: false,
+            xtrace: false,
+            pipefail: false,
+            noclobber: false,
+            globstar: true,
+            dotglob: false,
+            nullglob: false,
+            failglob: false,
+            extglob: false,
+        }
+    }
+}
+
+/// Environment variable that runs foreground jobs without a PTY when set.
+pub const NO_PTY_ENV: &str = "DSH_NO_PTY";
+
+/// Environment variable naming how completion candidates are shown: `inline` or `skim`.
+pub const COMPLETION_FRAMEWORK_ENV: &str = "DSH_COMPLETION_FRAMEWORK";
+
+/// Variable holding a command that completes what no other completion source does.
+pub const EXTERNAL_COMPLETER_VAR: &str = "DSH_EXTERNAL_COMPLETER";
+
+/// Kind of value an option holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionKind {
+    /// Turned on and off.
+    Switch,
+    Number,
+    /// One of a fixed set of words.
+    Choice(&'static [&'static str]),
+    /// Any text, such as a command line.
+    Text,
+}
+
+/// An option of the registry. `set -o`, `shopt`, `(option 'name)`, `doctor options` and
+/// the command palette all read and change options through [`OPTIONS`].
+#[derive(Debug)]
+pub struct OptionSpec {
+    pub name: &'static str,
+    pub kind: OptionKind,
+    pub description: &'static str,
+    get: fn(&Environment) -> OptionValue,
+    /// Receives a value already converted by [`OptionSpec::coerce`].
+    set: fn(&mut Environment, OptionValue),
+}
+
+impl OptionSpec {
+    /// Converts a value to the kind of this option, so that `"on"` turns a switch on and
+    /// `"12"` sets a number.
+    pub fn coerce(&self, value: OptionValue) -> Result<OptionValue> {
+        let converted = match (self.kind, &value) {
+            (OptionKind::Switch, OptionValue::Bool(_)) => Some(value.clone()),
+            (OptionKind::Switch, OptionValue::Number(n)) => Some(OptionValue::Bool(*n != 0)),
+            (OptionKind::Switch, OptionValue::Text(text)) => match text.to_lowercase().as_str() {
+                "on" | "true" | "yes" | "1" => Some(OptionValue::Bool(true)),
+                "off" | "false" | "no" | "0" => Some(OptionValue::Bool(false)),
+                _ => None,
+            },
+            (OptionKind::Number, OptionValue::Number(_)) => Some(value.clone()),
+            (OptionKind::Number, OptionValue::Text(text)) => {
+                text.parse().ok().map(OptionValue::Number)
+            }
+            (OptionKind::Choice(choices), OptionValue::Text(text)) => {
+                let text = text.to_lowercase();
+                choices
+                    .contains(&text.as_str())
+                    .then_some(OptionValue::Text(text))
+            }
+            (OptionKind::Text, OptionValue::Text(_)) => Some(value.clone()),
+            _ => None,
+        };
+        match converted {
+            Some(value) => Ok(value),
+            None => match self.kind {
+                OptionKind::Choice(choices) => bail!(
+                    "{}: invalid value '{}' (expected {})",
+                    self.name,
+                    value,
+                    choices.join(", ")
+                ),
+                _ => bail!("{}: invalid value '{}'", self.name, value),
+            },
+        }
+    }
+
+    pub fn get(&self, env: &Environment) -> OptionValue {
+        (self.get)(env)
+    }
+
+    pub fn set(&self, env: &mut Environment, value: OptionValue) -> Result<()> {
+        let value = self.coerce(value)?;
+        (self.set)(env, value);
+        Ok(())
+    }
+
+    /// The value a toggle in the command palette moves to: a switch flips and a choice
+    /// goes to the next word. Numbers have no next value.
+    pub fn next_value(&self, current: &OptionValue) -> Option<OptionValue> {
+        match (self.kind, current) {
+            (OptionKind::Switch, OptionValue::Bool(on)) => Some(OptionValue::Bool(!on)),
+            (OptionKind::Choice(choices), OptionValue::Text(text)) => {
+                let next = choices
+                    .iter()
+                    .position(|choice| choice == text)
+                    .map_or(0, |i| (i + 1) % choices.len());
+                Some(OptionValue::Text(choices[next].to_string()))
+            }
+            _ => None,
+        }
+    }
+}
+
+macro_rules! switch {
+    ($name:literal, $description:literal, $($field:ident).+) => {
+        OptionSpec {
+            name: $name,
+            kind: OptionKind::Switch,
+            description: $description,
+            get: |env| OptionValue::Bool(env.$($field).+),
+            set: |env, value| env.$($field).+ = value == OptionValue::Bool(true),
+        }
+    };
+}
+
+/// Every option of the shell, in the order `set -o` lists them.
+pub static OPTIONS: &[OptionSpec] = &[
+    switch!(
+        "errexit",
+        "Exit when a command fails (set -e)",
+        shell_options.errexit
+    ),
+    switch!(
+        "nounset",
+        "Treat expanding an unset variable as an error (set -u)",
+        shell_options.nounset
+    ),
+    switch!(
+        "xtrace",
+        "Print each command before running it (set -x)",
+        shell_options.xtrace
+    ),
+    switch!(
+        "pipefail",
+        "A pipeline fails when any of its commands fails",
+        shell_options.pipefail
+    ),
+    switch!(
+        "noclobber",
+        "Refuse to overwrite existing files with > (set -C)",
+        shell_options.noclobber
+    ),
+    switch!(
+        "globstar",
+        "** matches any number of directories",
+        shell_options.globstar
+    ),
+    switch!(
+        "dotglob",
+        "Wildcards also match hidden files",
+        shell_options.dotglob
+    ),
+    switch!(
+        "nullglob",
+        "A pattern matching nothing expands to nothing",
+        shell_options.nullglob
+    ),
+    switch!(
+        "failglob",
+        "A pattern matching nothing is an error",
+        shell_options.failglob
+    ),
+    switch!(
+        "extglob",
+        "Enable ?(...), *(...), +(...), @(...) and !(...) patterns",
+        shell_options.extglob
+    ),
+    OptionSpec {
+        name: "suggestion-mode",
+        kind: OptionKind::Choice(&["ghost", "off"]),
+        description: "Show suggestions as ghost text while typing",
+        get: |env| {
+            OptionValue::Text(match env.suggestion_mode() {
+                SuggestionMode::Ghost => "ghost".to_string(),
+                SuggestionMode::Off => "off".to_string(),
+            })
+        },
+        set: |env, value| {
+            env.set_suggestion_mode(match value {
+                OptionValue::Text(text) if text == "off" => SuggestionMode::Off,
+                _ => SuggestionMode::Ghost,
+            })
+        },
+    },
+    switch!(
+        "suggestion-ai",
+        "Complete suggestions with AI",
+        input_preferences.ai_backfill
+    ),
+    switch!(
+        "auto-pair",
+        "Insert closing brackets and quotes",
+        input_preferences.auto_pair
+    ),
+    switch!(
+        "auto-notify",
+        "Send a desktop notification when a long command finishes",
+        input_preferences.auto_notify_enabled
+    ),
+    OptionSpec {
+        name: "auto-notify-threshold",
+        kind: OptionKind::Number,
+        description: "Seconds a command runs before auto-notify applies",
+        get: |env| OptionValue::Number(env.input_preferences.auto_notify_threshold),
+        set: |env, value| {
+            if let OptionValue::Number(threshold) = value {
+                env.set_auto_notify_threshold(threshold);
+            }
+        },
+    },
+    switch!(
+        "auto-fix",
+        "Suggest an AI fix when a command fails",
+        input_preferences.auto_fix
+    ),
+    switch!(
+        "auto-diagnose",
+        "Offer to diagnose a command that failed",
+        input_preferences.auto_diagnose
+    ),
+    switch!(
+        "ai-explanation",
+        "Explain the command line after a pause",
+        input_preferences.ai_explanation
+    ),
+    switch!(
+        "transient-prompt",
+        "Shorten the prompt of commands that already ran",
+        input_preferences.transient_prompt
+    ),
+    switch!(
+        "block-separator",
+        "Separate the output of each command",
+        input_preferences.block_separator
+    ),
+    OptionSpec {
+        name: "safety-level",
+        kind: OptionKind::Choice(&["strict", "normal", "loose"]),
+        description: "How strictly SafetyGuard checks commands",
+        get: |env| OptionValue::Text(format!("{:?}", env.safety_level.read()).to_lowercase()),
+        set: |env, value| {
+            if let Ok(level) = value.to_string().parse() {
+                env.set_safety_level(level);
+            }
+        },
+    },
+    OptionSpec {
+        name: "completion-framework",
+        kind: OptionKind::Choice(&["inline", "skim"]),
+        description: "Show completion candidates inline or in skim (DSH_COMPLETION_FRAMEWORK)",
+        get: |env| OptionValue::Text(env.completion_framework().name().to_string()),
+        set: |env, value| {
+            env.set_system_env_var(COMPLETION_FRAMEWORK_ENV.to_string(), value.to_string())
+        },
+    },
+    OptionSpec {
+        name: "external-completer",
+        kind: OptionKind::Text,
+        description: "Command that completes what nothing else does (DSH_EXTERNAL_COMPLETER)",
+        get: |env| OptionValue::Text(env.external_completer().unwrap_or_default()),
+        set: |env, value| {
+            let command = value.to_string();
+            if command.trim().is_empty() {
+                env.variables.remove(EXTERNAL_COMPLETER_VAR);
+                env.unset_system_env_var(EXTERNAL_COMPLETER_VAR);
+            } else {
+                env.variables
+                    .insert(EXTERNAL_COMPLETER_VAR.to_string(), command);
+            }
+        },
+    },
+    OptionSpec {
+        name: "no-pty",
+        kind: OptionKind::Switch,
+        description: "Run foreground commands without a PTY (DSH_NO_PTY)",
+        get: |env| OptionValue::Bool(env.no_pty()),
+        set: |env, value| {
+            if value == OptionValue::Bool(true) {
+                env.set_system_env_var(NO_PTY_ENV.to_string(), "1".to_string());
+            } else {
+                env.unset_system_env_var(NO_PTY_ENV);
+            }
+        },
+    },
+];
+
+/// Looks up a registered option by name.
+pub fn find_option(name: &str) -> Option<&'static OptionSpec> {
+    OPTIONS.iter().find(|option| option.name == name)
+}
 
 impl Environment {
+    /// Current value of a registered option.
+    pub fn option(&self, name: &str) -> Option<OptionValue> {
+        find_option(name).map(|option| option.get(self))
+    }
+
+    /// Changes a registered option, converting the value to its kind.
+    pub fn set_option(&mut self, name: &str, value: OptionValue) -> Result<()> {
+        let Some(option) = find_option(name) else {
+            bail!("{}: invalid option name", name);
+        };
+        option.set(self, value)
+    }
+
+    /// Every registered option with its current value.
+    pub fn options(&self) -> Vec<OptionEntry> {
+        OPTIONS
+            .iter()
+            .map(|option| OptionEntry {
+                name: option.name.to_string(),
+                value: option.get(self),
+                description: option.description.to_string(),
+            })
+            .collect()
+    }
+
+    /// Whether `>` must not overwrite existing files: `set -o noclobber`, which the
+    /// strict safety level implies.
+    pub fn noclobber(&self) -> bool {
+        self.shell_options.noclobber || *self.safety_level.read() == SafetyLevel::Strict
+    }
+
+    /// Sets the safety level and the `SAFETY_LEVEL` variable that shows it.
+    pub fn set_safety_level(&mut self, level: SafetyLevel) {
+        self.variables.insert(
+            "SAFETY_LEVEL".to_string(),
+            format!("{:?}", level).to_lowercase(),
+        );
+        *self.safety_level.write() = level;
+    }
+
+    /// Whether foreground jobs run without a PTY, because `DSH_NO_PTY` is set.
+    pub fn no_pty(&self) -> bool {
+        self.system_env_vars.contains_key(NO_PTY_ENV)
+    }
+
+    /// How completion candidates are shown, from `DSH_COMPLETION_FRAMEWORK`; inline unless
+    /// it names skim.
+    pub fn completion_framework(&self) -> CompletionFrameworkKind {
+        self.get_var(COMPLETION_FRAMEWORK_ENV)
+            .and_then(|name| CompletionFrameworkKind::parse(&name))
+            .unwrap_or(CompletionFrameworkKind::Inline)
+    }
+
+    /// The command set in `DSH_EXTERNAL_COMPLETER`, if any.
+    pub fn external_completer(&self) -> Option<String> {
+        self.get_var(EXTERNAL_COMPLETER_VAR)
+            .filter(|command| !command.trim().is_empty())
+    }
+
     /// Get the current suggestion mode.
     pub fn suggestion_mode(&self) -> SuggestionMode {
         self.input_preferences.suggestion_mode
@@ -65,6 +65,71 @@ fn search_prefix_uses_prewarmed_names() {
     assert_eq!(env.read().search_prefix("ca"), Some("cargo".to_string()));
 }
 
+#[test]
+fn test_get_var_positional_params() {
+    init();
+    let env = Environment::new();
+    env.write().positional_params = vec!["one".to_string(), "two words".to_string()];
+
+    let env = env.read();
+    assert_eq!(env.get_var("$#"), Some("2".to_string()));
+    assert_eq!(env.get_var("$1"), Some("one".to_string()));
+    assert_eq!(env.get_var("$2"), Some("two words".to_string()));
+    assert_eq!(env.get_var("$3"), Some(String::new()));
+    assert_eq!(env.get_var("$@"), Some("one two words".to_string()));
+}
+
+#[test]
+fn test_get_var_exit_status() {
+    init();
+    let env = Environment::new();
+    {
+        let mut env = env.write();
+        env.last_status = 1;
+        env.pipe_status = vec![0, 1, 141];
+    }
+
+    let env = env.read();
+    assert_eq!(env.get_var("$?"), Some("1".to_string()));
+    assert_eq!(env.get_var("$PIPESTATUS"), Some("0 1 141".to_string()));
+    assert_eq!(env.get_var("$PIPESTATUS[2]"), Some("141".to_string()));
+    assert_eq!(env.get_var("$PIPESTATUS[3]"), None);
+    assert_eq!(env.get_var("$PIPESTATUS[@]"), Some("0 1 141".to_string()));
+}
+
+#[test]
+fn test_dir_stack_tilde() {
+    init();
+    let env = Environment::new();
+    {
+        let mut env = env.write();
+        env.dir_stack = vec!["/usr".to_string(), "/etc".to_string()];
+        env.variables
+            .insert("OLDPWD".to_string(), "/var".to_string());
+    }
+
+    let env = env.read();
+    let cwd = std::env::current_dir()
+        .unwrap()
+        .to_string_lossy()
+        .into_owned();
+    let expand = |word| {
+        env.expand_dir_stack_tilde(word)
+            .map(|(dir, rest)| dir + rest)
+    };
+    assert_eq!(expand("~1"), Some("/usr".to_string()));
+    assert_eq!(expand("~+2/hosts"), Some("/etc/hosts".to_string()));
+    assert_eq!(expand("~-0"), Some("/etc".to_string()));
+    assert_eq!(expand("~0"), Some(cwd.clone()));
+    assert_eq!(expand("~+"), Some(cwd));
+    assert_eq!(expand("~-/log"), Some("/var/log".to_string()));
+    assert_eq!(expand("~3"), None);
+    assert_eq!(expand("~user"), None);
+    assert_eq!(expand("~"), None);
+    assert_eq!(dir_stack_index("-2", 3), Some(0));
+    assert_eq!(dir_stack_index("+", 3), None);
+}
+
 #[test]
 fn test_resolve_alias() {
     init();
@@ -185,3 +250,123 @@ fn test_unset_system_env_updates_z_exclude() {
 
     assert!(env.read().z_exclude.is_empty());
 }
+
+#[test]
+fn test_option_registry() {
+    use dsh_types::options::OptionValue;
+
+    init();
+    let env = Environment::new();
+    let mut guard = env.write();
+
+    let names: Vec<&str> = OPTIONS.iter().map(|spec| spec.name).collect();
+    for name in [
+        "errexit",
+        "auto-pair",
+        "suggestion-mode",
+        "safety-level",
+        "no-pty",
+        "completion-framework",
+        "external-completer",
+    ] {
+        assert!(names.contains(&name), "{name} is not registered");
+    }
+
+    guard
+        .set_option("auto-pair", OptionValue::Text("off".to_string()))
+        .unwrap();
+    assert!(!guard.input_preferences().auto_pair);
+    guard
+        .set_option("auto-notify-threshold", OptionValue::Text("30".to_string()))
+        .unwrap();
+    assert_eq!(
+        guard.option("auto-notify-threshold"),
+        Some(OptionValue::Number(30))
+    );
+    guard
+        .set_option("safety-level", OptionValue::Text("strict".to_string()))
+        .unwrap();
+    assert!(guard.noclobber());
+    assert!(
+        guard
+            .set_option("safety-level", OptionValue::Text("reckless".to_string()))
+            .is_err()
+    );
+    assert!(
+        guard
+            .set_option("no-such-option", OptionValue::Bool(true))
+            .is_err()
+    );
+
+    assert_eq!(
+        guard.option("completion-framework"),
+        Some(OptionValue::Text("inline".to_string()))
+    );
+    guard
+        .set_option(
+            "completion-framework",
+            OptionValue::Text("Skim".to_string()),
+        )
+        .unwrap();
+    assert_eq!(
+        guard.completion_framework(),
+        crate::completion::framework::CompletionFrameworkKind::Skim
+    );
+    assert!(
+        guard
+            .set_option("completion-framework", OptionValue::Text("fzf".to_string()))
+            .is_err()
+    );
+    guard
+        .set_option(
+            "external-completer",
+            OptionValue::Text("carapace _carapace".to_string()),
+        )
+        .unwrap();
+    assert_eq!(
+        guard.get_var("DSH_EXTERNAL_COMPLETER"),
+        Some("carapace _carapace".to_string())
+    );
+    guard
+        .set_option("external-completer", OptionValue::Text(String::new()))
+        .unwrap();
+    assert_eq!(guard.external_completer(), None);
+    assert!(
+        guard
+            .set_option("external-completer", OptionValue::Bool(true))
+            .is_err()
+    );
+
+    let mode = find_option("suggestion-mode").unwrap();
+    let current = mode.get(&guard);
+    assert_ne!(mode.next_value(&current), Some(current));
+    assert_eq!(
+        find_option("auto-notify-threshold")
+            .unwrap()
+            .next_value(&OptionValue::Number(30)),
+        None
+    );
+}
+
+#[test]
+fn test_coproc_vars() {
+    init();
+    let env = Environment::new();
+    let mut guard = env.write();
+    guard
+        .exec_fds
+        .insert(60, std::fs::File::open("/dev/null").unwrap().into());
+
+    let (output, input) = nix::unistd::pipe().unwrap();
+    let coproc = guard.add_coproc("CALC", 4242, output, input);
+    assert_eq!((coproc.read_fd, coproc.write_fd), (61, 62));
+    assert_eq!(guard.get_var("$CALC[0]"), Some("61".to_string()));
+    assert_eq!(guard.get_var("$CALC[1]"), Some("62".to_string()));
+    assert_eq!(guard.get_var("$CALC"), Some("61".to_string()));
+    assert_eq!(guard.get_var("$CALC_PID"), Some("4242".to_string()));
+    assert_eq!(guard.get_var("$CALC[2]"), None);
+
+    guard.remove_coproc("CALC");
+    assert_eq!(guard.get_var("$CALC[0]"), None);
+    assert!(!guard.exec_fds.contains_key(&61));
+}
@@ -15,6 +15,49 @@ impl Environment {
             return self.output_history.get_stderr(index).map(|s| s.to_string());
         }
 
+        // Positional parameters of the current function or script and special parameters
+        match key {
+            "$#" => return Some(self.positional_params.len().to_string()),
+            "$@" | "$*" => return Some(self.positional_params.join(" ")),
+            "$$" => return Some(std::process::id().to_string()),
+            "$?" => return Some(self.last_status.to_string()),
+            _ => {}
+        }
+        // `$PIPESTATUS` and `${PIPESTATUS[@]}` list every stage, `${PIPESTATUS[N]}` counts
+        // from 0
+        if let Some(rest) = key.strip_prefix("$PIPESTATUS") {
+            if matches!(rest, "" | "[@]" | "[*]") {
+                return Some(
+                    self.pipe_status
+                        .iter()
+                        .map(|status| status.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                );
+            }
+            if let Some(index) = rest
+                .strip_prefix('[')
+                .and_then(|rest| rest.strip_suffix(']'))
+                .and_then(|index| index.parse::<usize>().ok())
+            {
+                return self.pipe_status.get(index).map(|status| status.to_string());
+            }
+        }
+        if let Some(index) = key.strip_prefix('$').and_then(|n| n.parse::<usize>().ok())
+            && index > 0
+        {
+            return Some(
+                self.positional_params
+                    .get(index - 1)
+                    .cloned()
+                    .unwrap_or_default(),
+            );
+        }
+
+        if let Some(value) = key.strip_prefix('$').and_then(|name| self.coproc_var(name)) {
+            return Some(value);
+        }
+
         // Check MCP-related dynamic variables
         match key {
             "MCP_SERVERS" => {
@@ -35,6 +78,10 @@ impl Environment {
         }
 
         if let Some(var) = key.strip_prefix('$') {
+            // Shell variables set by `set` or loops are stored without the '$' prefix
+            if let Some(val) = self.variables.get(var) {
+                return Some(val.to_string());
+            }
             // expand env var
             self.system_env_vars.get(var).cloned()
         } else {
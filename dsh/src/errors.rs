@@ -1,5 +1,40 @@
 use tracing::debug;
 
+/// Errors the shell reacts to by their kind, such as with the exit status of a command.
+#[derive(Debug)]
+pub enum ShellError {
+    /// A parameter expanded under `set -u` is not set.
+    UnboundVariable(String),
+    /// `${name:?message}` found the parameter unset or empty.
+    ParameterUnset { name: String, message: String },
+    /// No builtin, function or executable has the name; `hint` lists similar commands.
+    CommandNotFound { name: String, hint: String },
+}
+
+impl ShellError {
+    /// Finds the `ShellError` that `err` was created from.
+    pub fn of(err: &anyhow::Error) -> Option<&ShellError> {
+        err.downcast_ref::<ShellError>()
+    }
+}
+
+impl std::fmt::Display for ShellError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShellError::UnboundVariable(name) => write!(f, "{name}: unbound variable"),
+            ShellError::ParameterUnset { name, message } => write!(f, "{name}: {message}"),
+            ShellError::CommandNotFound { name, hint } if hint.is_empty() => {
+                write!(f, "unknown command: {name}")
+            }
+            ShellError::CommandNotFound { name, hint } => {
+                write!(f, "unknown command: {name}\n{hint}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShellError {}
+
 /// Display error in a user-friendly format without stack traces.
 ///
 /// When `log_normal` is true, normal exit conditions are logged for diagnostics; when false
@@ -9,6 +44,8 @@ pub fn display_user_error(err: &anyhow::Error, log_normal: bool) {
 
     if error_msg.contains("unknown command:") {
         if let Some(cmd_start) = error_msg.find("unknown command: ") {
+            // Keeps a location such as `script.dsh:3: ` in front of the message
+            let location = &error_msg[..cmd_start];
             let rest = &error_msg[cmd_start + 17..];
             // Split command name from suggestion (separated by newline)
             let (cmd, suggestion) = if let Some(newline_pos) = rest.find('\n') {
@@ -16,7 +53,7 @@ pub fn display_user_error(err: &anyhow::Error, log_normal: bool) {
             } else {
                 (rest, None)
             };
-            eprintln!("dsh: {}: command not found", cmd.trim());
+            eprintln!("dsh: {}{}: command not found", location, cmd.trim());
             if let Some(suggestion_msg) = suggestion {
                 eprint!("{}", suggestion_msg);
             }
@@ -1,7 +1,7 @@
 use crate::environment::Environment;
 use crate::lisp::Value;
 use crate::repl::Repl;
-use crate::shell::Shell;
+use crate::shell::{Shell, trap};
 use anyhow::Result;
 use clap::Parser;
 use dsh_types::Context;
@@ -37,6 +37,7 @@ pub mod prompt;
 pub mod proxy;
 pub mod repl;
 pub mod safety;
+pub mod script;
 pub mod secrets;
 pub mod shell;
 pub mod snippet;
@@ -79,6 +80,13 @@ pub struct Cli {
     #[arg(long)]
     pub notebook: Option<String>,
 
+    /// Script file to run non-interactively
+    pub script: Option<String>,
+
+    /// Arguments passed to the script as positional parameters
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    pub script_args: Vec<String>,
+
     #[command(subcommand)]
     pub subcommand: Option<SubCommand>,
 }
@@ -96,6 +104,12 @@ pub enum SubCommand {
     },
 }
 
+/// Stack size of the thread running the shell.
+///
+/// Every nested function call goes through the whole evaluator, so reaching the
+/// function nesting limit needs far more stack than the default main thread has.
+const SHELL_STACK_SIZE: usize = 256 * 1024 * 1024;
+
 pub fn lib_main() -> ExitCode {
     if let Err(err) = init_tracing() {
         eprintln!("Failed to initialize tracing: {err}");
@@ -112,7 +126,17 @@ pub fn lib_main() -> ExitCode {
             return ExitCode::FAILURE;
         }
     };
-    rt.block_on(run_shell())
+    let shell_thread = std::thread::Builder::new()
+        .name("dsh".to_string())
+        .stack_size(SHELL_STACK_SIZE)
+        .spawn(move || rt.block_on(run_shell()));
+    match shell_thread {
+        Ok(handle) => handle.join().unwrap_or(ExitCode::FAILURE),
+        Err(err) => {
+            eprintln!("Failed to start shell thread: {err}");
+            ExitCode::FAILURE
+        }
+    }
 }
 
 pub async fn run_shell() -> ExitCode {
@@ -130,6 +154,11 @@ pub async fn run_shell() -> ExitCode {
     let env = Environment::new();
     let mut shell = Shell::new(env);
 
+    // Scripts run with a plain shell: no config.lisp, MCP servers or history
+    if let Some(script) = cli.script {
+        return execute_script(&mut shell, &script, cli.script_args).await;
+    }
+
     // Initialize command history (Async)
     let cmd_history = std::sync::Arc::new(parking_lot::Mutex::new(crate::history::History::new()));
     shell.cmd_history = Some(cmd_history.clone());
@@ -202,22 +231,7 @@ pub async fn run_shell() -> ExitCode {
         }
     }
 
-    // Load config.lisp to initialize aliases, variables, and other settings
-    // Enable startup mode to prevent blocking MCP server connections
-    shell.environment.write().startup_mode = true;
-    if let Err(e) = shell.lisp_engine.borrow().run_config_lisp() {
-        // Only warn if it's not a "file not found" error (config.lisp is optional)
-        let err_str = e.to_string();
-        if !err_str.contains("No such file or directory") && !err_str.contains("config file") {
-            tracing::warn!("Failed to load config.lisp: {}", e);
-            eprintln!("Warning: Failed to load config.lisp: {}", e);
-        }
-    }
-    // Disable startup mode
-    shell.environment.write().startup_mode = false;
-
-    // Reload MCP configuration from environment after config.lisp execution
-    shell.reload_mcp_config();
+    load_config_lisp(&mut shell);
 
     // Prewarm executable names cache in background for faster command prefix search
     {
@@ -256,6 +270,25 @@ pub async fn run_shell() -> ExitCode {
     }
 }
 
+/// Loads config.lisp to initialize aliases, variables, and other settings.
+fn load_config_lisp(shell: &mut Shell) {
+    // Enable startup mode to prevent blocking MCP server connections
+    shell.environment.write().startup_mode = true;
+    if let Err(e) = shell.lisp_engine.borrow().run_config_lisp() {
+        // Only warn if it's not a "file not found" error (config.lisp is optional)
+        let err_str = e.to_string();
+        if !err_str.contains("No such file or directory") && !err_str.contains("config file") {
+            tracing::warn!("Failed to load config.lisp: {}", e);
+            eprintln!("Warning: Failed to load config.lisp: {}", e);
+        }
+    }
+    // Disable startup mode
+    shell.environment.write().startup_mode = false;
+
+    // Reload MCP configuration from environment after config.lisp execution
+    shell.reload_mcp_config();
+}
+
 pub fn handle_import_command(shell_name: &str, custom_path: Option<&str>) -> ExitCode {
     use crate::history::History;
     use crate::history_import::create_importer;
@@ -573,7 +606,7 @@ pub async fn execute_command(shell: &mut Shell, _ctx: &mut Context, command: &st
     // Set appropriate context flags for non-interactive execution
     ctx.interactive = false;
 
-    match shell.eval_str(&mut ctx, command.to_string(), false).await {
+    let exit_code = match shell.eval_str(&mut ctx, command.to_string(), false).await {
         Ok(code) => {
             shell.record_history_outcome(command, code, std::time::Duration::from_millis(0));
             debug!("run command mode {:?} : {:?}", command, &code);
@@ -583,7 +616,30 @@ pub async fn execute_command(shell: &mut Shell, _ctx: &mut Context, command: &st
             display_user_error(&err, true);
             ExitCode::FAILURE
         }
-    }
+    };
+    trap::run_exit_trap(shell, &ctx).await;
+    exit_code
+}
+
+/// Runs a script file without config.lisp, the REPL, AI backends or history loading.
+pub async fn execute_script(shell: &mut Shell, script: &str, args: Vec<String>) -> ExitCode {
+    debug!("Executing script: {} {:?}", script, args);
+    shell.set_signals();
+
+    let mut ctx = create_context_for_command(shell);
+    ctx.interactive = false;
+    ctx.save_history = false;
+
+    let exit_code =
+        match script::run_script(shell, &mut ctx, std::path::Path::new(script), args).await {
+            Ok(code) => ExitCode::from(code.clamp(0, 255) as u8),
+            Err(err) => {
+                display_user_error(&err, true);
+                ExitCode::from(127)
+            }
+        };
+    trap::run_exit_trap(shell, &ctx).await;
+    exit_code
 }
 
 pub async fn execute_lisp(shell: &mut Shell, _ctx: &mut Context, lisp_script: &str) -> ExitCode {
@@ -624,7 +680,9 @@ pub async fn run_interactive(shell: &mut Shell, ctx: &mut Context) -> ExitCode {
     if isatty(unsafe { BorrowedFd::borrow_raw(std::io::stdin().as_raw_fd()) }).unwrap_or(false) {
         // Interactive mode
         debug!("Running in interactive mode");
-        match repl.run_interactive().await {
+        let result = repl.run_interactive().await;
+        trap::run_exit_trap(repl.shell, ctx).await;
+        match result {
             Ok(()) => ExitCode::from(0),
             Err(err) => {
                 // Don't display error message for normal exit
@@ -646,17 +704,19 @@ pub async fn run_interactive(shell: &mut Shell, ctx: &mut Context) -> ExitCode {
         debug!("Running in pipe mode");
         let stdin = io::stdin();
         let reader = BufReader::new(stdin);
+        // Joins continuation lines and compound commands that span several lines
+        let mut statements = script::StatementReader::new();
 
         for line in reader.lines() {
             match line {
-                Ok(input) => {
-                    let input = input.trim();
-                    if input.is_empty() {
+                Ok(line) => {
+                    let Some(statement) = statements.push_line(&line) else {
                         continue;
-                    }
-                    if input == "exit" {
+                    };
+                    if statement.text == "exit" {
                         break;
                     }
+                    let input = statement.text.as_str();
                     debug!("Processing pipe input: {}", input);
                     match repl.shell.eval_str(ctx, input.to_string(), false).await {
                         Ok(_) => {}
@@ -671,7 +731,11 @@ pub async fn run_interactive(shell: &mut Shell, ctx: &mut Context) -> ExitCode {
                     break;
                 }
             }
+            if repl.shell.exited.is_some() {
+                break;
+            }
         }
+        trap::run_exit_trap(repl.shell, ctx).await;
         ExitCode::from(0)
     }
 }
@@ -1,9 +1,12 @@
 use crate::direnv::DirEnvironment;
-use crate::lisp::model::{Env, RuntimeError, Value};
+use crate::environment::{OPTIONS, UnsafeSend};
+use crate::lisp::model::{Env, IntType, List, RuntimeError, Value};
 use crate::shell::Shell;
+use crate::shell::trap::{TrapAction, TrapSignal, set_trap};
 use crate::utils::editor::launch_editor;
 use anyhow::Result;
 use dsh_types::Context;
+use dsh_types::options::OptionValue;
 use nix::sys::termios::tcgetattr;
 use nix::unistd::pipe;
 use std::borrow::Cow;
@@ -57,10 +60,10 @@ pub fn set_env(env: Rc<RefCell<Env>>, args: Vec<Value>) -> Result<Value, Runtime
             match guard.check_environment_modification(&key, val_str, &safety_level) {
                 SafetyResult::Allowed => {}
                 SafetyResult::Confirm(msg) => {
-                    return Err(RuntimeError::new(&format!("SafetyGuard Blocked: {}", msg)));
+                    return Err(RuntimeError::new(format!("SafetyGuard Blocked: {}", msg)));
                 }
                 SafetyResult::Denied(msg) => {
-                    return Err(RuntimeError::new(&format!("SafetyGuard Denied: {}", msg)));
+                    return Err(RuntimeError::new(format!("SafetyGuard Denied: {}", msg)));
                 }
             }
         }
@@ -112,10 +115,10 @@ pub fn set_variable(env: Rc<RefCell<Env>>, args: Vec<Value>) -> Result<Value, Ru
         match guard.check_environment_modification(&key, &val, &safety_level) {
             SafetyResult::Allowed => {}
             SafetyResult::Confirm(msg) => {
-                return Err(RuntimeError::new(&format!("SafetyGuard Blocked: {}", msg)));
+                return Err(RuntimeError::new(format!("SafetyGuard Blocked: {}", msg)));
             }
             SafetyResult::Denied(msg) => {
-                return Err(RuntimeError::new(&format!("SafetyGuard Denied: {}", msg)));
+                return Err(RuntimeError::new(format!("SafetyGuard Denied: {}", msg)));
             }
         }
     }
@@ -163,6 +166,38 @@ pub fn abbr(env: Rc<RefCell<Env>>, args: Vec<Value>) -> Result<Value, RuntimeErr
     Ok(Value::NIL)
 }
 
+/// Built-in trap function for Lisp
+/// Registers a lambda (or a shell command string) as the handler of one or more signals.
+/// `nil` resets the handler and `""` ignores the signal.
+/// Usage: (trap "EXIT" (lambda () (print "bye"))) or (trap "INT TERM" "echo stop")
+pub fn trap(env: Rc<RefCell<Env>>, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    if args.len() != 2 {
+        return Err(RuntimeError::new(
+            "trap requires exactly 2 arguments: signals and handler",
+        ));
+    }
+
+    let action = match &args[1] {
+        Value::List(list) if *list == List::NIL => None,
+        Value::String(command) => Some(TrapAction::Command(command.clone())),
+        func @ Value::Lambda(_) => Some(TrapAction::Lisp(UnsafeSend(func.clone()))),
+        other => {
+            return Err(RuntimeError::new(format!(
+                "trap handler must be a lambda, a string or nil, got {other}"
+            )));
+        }
+    };
+
+    let env = env.borrow();
+    let mut shell_env = env.shell_env.write();
+    for spec in args[0].to_string().split_whitespace() {
+        let signal = TrapSignal::parse(spec).map_err(|e| RuntimeError::new(e.to_string()))?;
+        set_trap(&mut shell_env, signal, action.clone())
+            .map_err(|e| RuntimeError::new(format!("trap: {e}")))?;
+    }
+    Ok(Value::NIL)
+}
+
 pub fn allow_direnv(env: Rc<RefCell<Env>>, args: Vec<Value>) -> Result<Value, RuntimeError> {
     for arg in args {
         let root = arg.to_string();
@@ -224,13 +259,13 @@ pub fn command(env: Rc<RefCell<Env>>, args: Vec<Value>) -> Result<Value, Runtime
             }
             SafetyResult::Confirm(msg) => {
                 // In Lisp execution context (non-interactive usually), we block Confirm actions.
-                return Err(RuntimeError::new(&format!(
+                return Err(RuntimeError::new(format!(
                     "SafetyGuard Blocked: {} (You can change safety-level to 'loose' to bypass)",
                     msg
                 )));
             }
             SafetyResult::Denied(msg) => {
-                return Err(RuntimeError::new(&format!("SafetyGuard Denied: {}", msg)));
+                return Err(RuntimeError::new(format!("SafetyGuard Denied: {}", msg)));
             }
         }
     }
@@ -251,9 +286,7 @@ pub fn command(env: Rc<RefCell<Env>>, args: Vec<Value>) -> Result<Value, Runtime
                 Ok(Value::String(stderr))
             }
         }
-        Err(err) => Err(RuntimeError {
-            msg: err.to_string(),
-        }),
+        Err(err) => Err(RuntimeError::new(err.to_string())),
     }
 }
 
@@ -270,9 +303,7 @@ async fn sh_core(
         Ok(tmode) => tmode,
         Err(err) => {
             eprintln!("error: {err}");
-            return Err(RuntimeError {
-                msg: err.to_string(),
-            });
+            return Err(RuntimeError::new(err.to_string()));
         }
     };
 
@@ -281,18 +312,14 @@ async fn sh_core(
         Ok(p) => p,
         Err(err) => {
             eprintln!("error: {err}");
-            return Err(RuntimeError {
-                msg: err.to_string(),
-            });
+            return Err(RuntimeError::new(err.to_string()));
         }
     };
 
     ctx.captured_out = Some(pin.as_raw_fd());
     if let Err(err) = shell.eval_str(&mut ctx, input, false).await {
         eprintln!("error: {err}");
-        return Err(RuntimeError {
-            msg: err.to_string(),
-        });
+        return Err(RuntimeError::new(err.to_string()));
     }
 
     // Close write end in parent to avoid hang
@@ -306,9 +333,7 @@ async fn sh_core(
         Ok(str) => str.trim_matches('\n').to_owned(),
         Err(err) => {
             eprintln!("error: {err}");
-            return Err(RuntimeError {
-                msg: err.to_string(),
-            });
+            return Err(RuntimeError::new(err.to_string()));
         }
     };
     debug!("'{}'", output);
@@ -326,13 +351,11 @@ pub fn block_sh(env: Rc<RefCell<Env>>, args: Vec<Value>) -> Result<Value, Runtim
         let rt = tokio::runtime::Builder::new_current_thread()
             .enable_all()
             .build()
-            .map_err(|e| RuntimeError { msg: e.to_string() })?;
+            .map_err(|e| RuntimeError::new(e.to_string()))?;
         rt.block_on(sh_core(shell_env, cmd_args))
     })
     .join()
-    .map_err(|_| RuntimeError {
-        msg: "Thread panicked".to_string(),
-    })?
+    .map_err(|_| RuntimeError::new("Thread panicked"))?
     .map(Value::String)
 }
 
@@ -345,19 +368,56 @@ pub fn safety_level(env: Rc<RefCell<Env>>, args: Vec<Value>) -> Result<Value, Ru
     let level_str = args[0].to_string();
     let level: crate::safety::SafetyLevel = level_str
         .parse()
-        .map_err(|e| RuntimeError::new(&format!("Error parsing safety level: {}", e)))?;
+        .map_err(|e| RuntimeError::new(format!("Error parsing safety level: {}", e)))?;
 
     debug!("setting safety level to {:?}", level);
-    {
-        let env_ref = env.borrow();
-        let mut shell_env = env_ref.shell_env.write();
-        *shell_env.safety_level.write() = level.clone();
-        shell_env.variables.insert(
-            "SAFETY_LEVEL".to_string(),
-            format!("{:?}", level).to_lowercase(),
-        );
+    env.borrow().shell_env.write().set_safety_level(level);
+
+    Ok(Value::NIL)
+}
+
+fn option_value(value: OptionValue) -> Value {
+    match value {
+        OptionValue::Bool(on) => Value::from(on),
+        OptionValue::Number(n) => IntType::try_from(n).map_or(Value::NIL, Value::Int),
+        OptionValue::Text(text) => Value::String(text),
     }
+}
 
+/// `(option 'globstar)` returns a registered option, `(option 'safety-level 'strict)`
+/// changes it and `(option)` lists the option names.
+pub fn option(env: Rc<RefCell<Env>>, args: Vec<Value>) -> Result<Value, RuntimeError> {
+    let Some(name) = args.first().map(|name| name.to_string()) else {
+        return Ok(Value::List(
+            OPTIONS
+                .iter()
+                .map(|option| Value::String(option.name.to_string()))
+                .collect(),
+        ));
+    };
+    let env_ref = env.borrow();
+    let Some(value) = args.get(1) else {
+        return env_ref
+            .shell_env
+            .read()
+            .option(&name)
+            .map(option_value)
+            .ok_or_else(|| RuntimeError::new(format!("{name}: invalid option name")));
+    };
+    debug!("setting option {} to {}", name, value);
+    let value = match value {
+        Value::Int(n) => u64::try_from(*n)
+            .map(OptionValue::Number)
+            .map_err(|_| RuntimeError::new(format!("{name}: invalid value {n}")))?,
+        Value::String(text) => OptionValue::Text(text.clone()),
+        Value::Symbol(symbol) => OptionValue::Text(symbol.to_string()),
+        other => OptionValue::Bool(bool::from(other)),
+    };
+    env_ref
+        .shell_env
+        .write()
+        .set_option(&name, value)
+        .map_err(|e| RuntimeError::new(e.to_string()))?;
     Ok(Value::NIL)
 }
 
@@ -433,9 +493,7 @@ async fn sh_no_cap_core(
         Ok(tmode) => tmode,
         Err(err) => {
             eprintln!("error: {err}");
-            return Err(RuntimeError {
-                msg: err.to_string(),
-            });
+            return Err(RuntimeError::new(err.to_string()));
         }
     };
 
@@ -443,9 +501,7 @@ async fn sh_no_cap_core(
     // ctx.captured_out = Some(pin);
     if let Err(err) = shell.eval_str(&mut ctx, input, false).await {
         eprintln!("error: {err}");
-        return Err(RuntimeError {
-            msg: err.to_string(),
-        });
+        return Err(RuntimeError::new(err.to_string()));
     }
 
     Ok(())
@@ -462,36 +518,29 @@ pub fn block_sh_no_cap(env: Rc<RefCell<Env>>, args: Vec<Value>) -> Result<Value,
         let rt = tokio::runtime::Builder::new_current_thread()
             .enable_all()
             .build()
-            .map_err(|e| RuntimeError { msg: e.to_string() })?;
+            .map_err(|e| RuntimeError::new(e.to_string()))?;
         rt.block_on(sh_no_cap_core(shell_env, cmd_args))
     })
     .join()
-    .map_err(|_| RuntimeError {
-        msg: "Thread panicked".to_string(),
-    })?
+    .map_err(|_| RuntimeError::new("Thread panicked"))?
     .map(|_| Value::NIL)
 }
 
 pub fn edit(_env: Rc<RefCell<Env>>, args: Vec<Value>) -> Result<Value, RuntimeError> {
     if args.len() != 1 {
-        return Err(RuntimeError {
-            msg: "edit requires 1 argument".to_string(),
-        });
+        return Err(RuntimeError::new("edit requires 1 argument"));
     }
 
     let path_str = match &args[0] {
         Value::String(s) => s,
         _ => {
-            return Err(RuntimeError {
-                msg: "edit argument must be a string".to_string(),
-            });
+            return Err(RuntimeError::new("edit argument must be a string"));
         }
     };
 
     let path = std::path::Path::new(path_str);
-    launch_editor(path).map_err(|e| RuntimeError {
-        msg: format!("Failed to launch editor: {}", e),
-    })?;
+    launch_editor(path)
+        .map_err(|e| RuntimeError::new(format!("Failed to launch editor: {}", e)))?;
     Ok(Value::True)
 }
 
@@ -567,7 +616,7 @@ pub fn secret_history_mode(env: Rc<RefCell<Env>>, args: Vec<Value>) -> Result<Va
     let mode_str = args[0].to_string();
     let mode: SecretHistoryMode = mode_str
         .parse()
-        .map_err(|e| RuntimeError::new(&format!("Invalid mode: {}", e)))?;
+        .map_err(|e| RuntimeError::new(format!("Invalid mode: {}", e)))?;
 
     env.borrow()
         .shell_env
@@ -669,6 +718,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_option() {
+        init();
+        let env = Environment::new();
+        let engine = LispEngine::new(Arc::clone(&env));
+
+        assert_eq!(
+            engine.borrow().run("(option 'globstar)").unwrap(),
+            Value::True
+        );
+        engine.borrow().run("(option 'dotglob t)").unwrap();
+        engine.borrow().run("(option \"globstar\" nil)").unwrap();
+        let options = env.read().shell_options;
+        assert!(options.dotglob);
+        assert!(!options.globstar);
+        assert!(engine.borrow().run("(option 'no-such-option)").is_err());
+
+        engine
+            .borrow()
+            .run("(option 'safety-level 'strict)")
+            .unwrap();
+        engine
+            .borrow()
+            .run("(option 'auto-notify-threshold 30)")
+            .unwrap();
+        assert_eq!(
+            engine.borrow().run("(option 'safety-level)").unwrap(),
+            Value::String("strict".to_string())
+        );
+        assert_eq!(
+            engine
+                .borrow()
+                .run("(option 'auto-notify-threshold)")
+                .unwrap(),
+            Value::Int(30)
+        );
+        assert!(env.read().noclobber());
+        assert!(
+            engine
+                .borrow()
+                .run("(option 'safety-level 'reckless)")
+                .is_err()
+        );
+        engine.borrow().run("(pref-auto-pair t)").unwrap();
+        assert_eq!(
+            engine.borrow().run("(option 'auto-pair)").unwrap(),
+            Value::True
+        );
+    }
+
     #[test]
     fn test_builtin_argument_length_checks() {
         init();
@@ -68,9 +68,10 @@ pub fn default_env(environment: Arc<RwLock<Environment>>) -> Env {
                 if let Value::String(s) = val {
                     items.push(s.clone());
                 } else {
-                    return Err(RuntimeError {
-                        msg: format!("selector requires a list of strings; found {}", val),
-                    });
+                    return Err(RuntimeError::new(format!(
+                        "selector requires a list of strings; found {}",
+                        val
+                    )));
                 }
             }
 
@@ -78,9 +79,7 @@ pub fn default_env(environment: Arc<RwLock<Environment>>) -> Env {
             for item in items {
                 tx_item
                     .send(vec![Arc::new(StringItem { text: item })])
-                    .map_err(|_| RuntimeError {
-                        msg: "Failed to send item to skim".to_string(),
-                    })?;
+                    .map_err(|_| RuntimeError::new("Failed to send item to skim"))?;
             }
             drop(tx_item);
 
@@ -89,9 +88,7 @@ pub fn default_env(environment: Arc<RwLock<Environment>>) -> Env {
                 .prompt(prompt)
                 .bind(vec!["Enter:accept".to_string(), "Esc:abort".to_string()])
                 .build()
-                .map_err(|e| RuntimeError {
-                    msg: format!("Failed to build skim options: {}", e),
-                })?;
+                .map_err(|e| RuntimeError::new(format!("Failed to build skim options: {}", e)))?;
 
             let selected_items = crate::utils::skim::run_skim_with(options, Some(rx_item))
                 .map(|out| out.selected_items)
@@ -128,12 +125,10 @@ pub fn default_env(environment: Arc<RwLock<Environment>>) -> Env {
                 let command = match arg {
                     Value::String(s) => s.clone(),
                     _ => {
-                        return Err(RuntimeError {
-                            msg: format!(
-                                "chat-execute-add requires all arguments to be strings; found {}",
-                                arg
-                            ),
-                        });
+                        return Err(RuntimeError::new(format!(
+                            "chat-execute-add requires all arguments to be strings; found {}",
+                            arg
+                        )));
                     }
                 };
                 shell_env.add_execute_allowlist_entry(command);
@@ -151,9 +146,9 @@ pub fn default_env(environment: Arc<RwLock<Environment>>) -> Env {
                 "ghost" => SuggestionMode::Ghost,
                 "off" => SuggestionMode::Off,
                 other => {
-                    return Err(RuntimeError {
-                        msg: format!("Unknown suggestion mode: {other}"),
-                    });
+                    return Err(RuntimeError::new(format!(
+                        "Unknown suggestion mode: {other}"
+                    )));
                 }
             };
             env.borrow().shell_env.write().set_suggestion_mode(mode);
@@ -197,9 +192,9 @@ pub fn default_env(environment: Arc<RwLock<Environment>>) -> Env {
             if args.len() > 1 {
                 let threshold_val = require_typed_arg::<IntType>("set-notify-config", &args, 1)?;
                 use std::convert::TryInto;
-                let threshold: u64 = threshold_val.try_into().map_err(|_| RuntimeError {
-                    msg: "Threshold must be a non-negative integer".to_string(),
-                })?;
+                let threshold: u64 = threshold_val
+                    .try_into()
+                    .map_err(|_| RuntimeError::new("Threshold must be a non-negative integer"))?;
                 env_write.set_auto_notify_threshold(threshold);
             }
 
@@ -245,10 +240,9 @@ pub fn default_env(environment: Arc<RwLock<Environment>>) -> Env {
         Symbol::from("add-hook"),
         Value::NativeFunc(|env, args| {
             if args.len() != 2 {
-                return Err(RuntimeError {
-                    msg: "add-hook requires exactly 2 arguments: hook-name and function"
-                        .to_string(),
-                });
+                return Err(RuntimeError::new(
+                    "add-hook requires exactly 2 arguments: hook-name and function".to_string(),
+                ));
             }
 
             let hook_name = require_typed_arg::<&Symbol>("add-hook", &args, 0)?;
@@ -261,14 +255,16 @@ pub fn default_env(environment: Arc<RwLock<Environment>>) -> Env {
             let current_value = match env.borrow().get(&hook_var_name) {
                 Some(Value::List(list)) => list,
                 Some(_) => {
-                    return Err(RuntimeError {
-                        msg: format!("{} is not a hook variable", hook_var_name).to_string(),
-                    });
+                    return Err(RuntimeError::new(format!(
+                        "{} is not a hook variable",
+                        hook_var_name
+                    )));
                 }
                 None => {
-                    return Err(RuntimeError {
-                        msg: format!("Hook variable {} does not exist", hook_var_name).to_string(),
-                    });
+                    return Err(RuntimeError::new(format!(
+                        "Hook variable {} does not exist",
+                        hook_var_name
+                    )));
                 }
             };
 
@@ -297,9 +293,9 @@ pub fn default_env(environment: Arc<RwLock<Environment>>) -> Env {
         Symbol::from("bound?"),
         Value::NativeFunc(|env, args| {
             if args.len() != 1 {
-                return Err(RuntimeError {
-                    msg: "bound? requires exactly 1 argument: symbol".to_string(),
-                });
+                return Err(RuntimeError::new(
+                    "bound? requires exactly 1 argument: symbol",
+                ));
             }
 
             let symbol = require_typed_arg::<&Symbol>("bound?", &args, 0)?;
@@ -380,18 +376,14 @@ pub fn default_env(environment: Arc<RwLock<Environment>>) -> Env {
 
             for pair in chunks {
                 let Some(key) = pair.first() else {
-                    return Err(RuntimeError {
-                        msg: "internal error: empty key/value chunk while building hash".into(),
-                    });
+                    return Err(RuntimeError::new("internal error: empty key/value chunk while building hash"));
                 };
                 let value = pair.get(1);
 
                 if let Some(value) = value {
                     hash.insert(key.clone(), value.clone());
                 } else {
-                    return Err(RuntimeError {
-                        msg: format!("Must pass an even number of arguments to 'hash', because they're used as key/value pairs; found extra argument {key}")
-                    });
+                    return Err(RuntimeError::new(format!("Must pass an even number of arguments to 'hash', because they're used as key/value pairs; found extra argument {key}")));
                 }
             }
 
@@ -82,9 +82,10 @@ fn eval_inner(
 
     match expression {
         // look up symbol
-        Value::Symbol(symbol) => env.borrow().get(symbol).ok_or_else(|| RuntimeError {
-            msg: format!("\"{symbol}\" is not defined"),
-        }),
+        Value::Symbol(symbol) => env
+            .borrow()
+            .get(symbol)
+            .ok_or_else(|| RuntimeError::new(format!("\"{symbol}\" is not defined"))),
 
         // s-expression
         Value::List(list) if *list != List::NIL => {
@@ -181,9 +182,9 @@ fn eval_inner(
                     let args = &list.cdr().into_iter().collect::<Vec<Value>>();
 
                     if args.len() < 2 {
-                        return Err(RuntimeError {
-                            msg: "autocomplete requires at least 2 arguments".to_string(),
-                        });
+                        return Err(RuntimeError::new(
+                            "autocomplete requires at least 2 arguments",
+                        ));
                     }
 
                     let name_symbol = require_typed_arg::<&Symbol>(keyword, args, 0)?;
@@ -253,12 +254,14 @@ fn eval_inner(
                     for decl in declarations.into_iter() {
                         let decl = &decl;
 
-                        let decl_cons: &List = decl.try_into().map_err(|_| RuntimeError {
-                            msg: format!("Expected declaration clause, found {decl}"),
+                        let decl_cons: &List = decl.try_into().map_err(|_| {
+                            RuntimeError::new(format!("Expected declaration clause, found {decl}"))
                         })?;
                         let symbol = &decl_cons.car()?;
-                        let symbol: &Symbol = symbol.try_into().map_err(|_| RuntimeError {
-                            msg: format!("Expected symbol for let declaration, found {symbol}"),
+                        let symbol: &Symbol = symbol.try_into().map_err(|_| {
+                            RuntimeError::new(format!(
+                                "Expected symbol for let declaration, found {symbol}"
+                            ))
                         })?;
                         let expr = &decl_cons.cdr().car()?;
 
@@ -268,8 +271,10 @@ fn eval_inner(
                     }
 
                     let body = &Value::List(list.cdr().cdr());
-                    let body: &List = body.try_into().map_err(|_| RuntimeError {
-                        msg: format!("Expected expression(s) after let-declarations, found {body}"),
+                    let body: &List = body.try_into().map_err(|_| {
+                        RuntimeError::new(format!(
+                            "Expected expression(s) after let-declarations, found {body}"
+                        ))
                     })?;
 
                     eval_block_inner(let_env, body.into_iter(), context)
@@ -285,12 +290,14 @@ fn eval_inner(
                     for decl in declarations.into_iter() {
                         let decl = &decl;
 
-                        let decl_cons: &List = decl.try_into().map_err(|_| RuntimeError {
-                            msg: format!("Expected declaration clause, found {decl}"),
+                        let decl_cons: &List = decl.try_into().map_err(|_| {
+                            RuntimeError::new(format!("Expected declaration clause, found {decl}"))
                         })?;
                         let symbol = &decl_cons.car()?;
-                        let symbol: &Symbol = symbol.try_into().map_err(|_| RuntimeError {
-                            msg: format!("Expected symbol for let declaration, found {symbol}"),
+                        let symbol: &Symbol = symbol.try_into().map_err(|_| {
+                            RuntimeError::new(format!(
+                                "Expected symbol for let declaration, found {symbol}"
+                            ))
                         })?;
                         let expr = &decl_cons.cdr().car()?;
 
@@ -306,8 +313,10 @@ fn eval_inner(
                     }
 
                     let body = &Value::List(list.cdr().cdr());
-                    let body: &List = body.try_into().map_err(|_| RuntimeError {
-                        msg: format!("Expected expression(s) after let-declarations, found {body}"),
+                    let body: &List = body.try_into().map_err(|_| {
+                        RuntimeError::new(format!(
+                            "Expected expression(s) after let-declarations, found {body}"
+                        ))
                     })?;
                     debug!(
                         "variables {:?}",
@@ -320,14 +329,27 @@ fn eval_inner(
                     eval_block_inner(env, list.cdr().into_iter(), context)
                 }
 
+                Value::Symbol(Symbol(keyword)) if keyword == "try" => {
+                    eval_try(env, list.cdr(), context)
+                }
+
+                Value::Symbol(Symbol(keyword)) if keyword == "unwind-protect" => {
+                    let protected = list.cdr().car()?;
+                    let result = eval_inner(env.clone(), &protected, context.found_tail(true));
+                    eval_block_inner(env, list.cdr().cdr().into_iter(), context.found_tail(true))?;
+                    result
+                }
+
                 Value::Symbol(Symbol(keyword)) if keyword == "cond" => {
                     let clauses = list.cdr();
 
                     for clause in clauses.into_iter() {
                         let clause = &clause;
 
-                        let clause: &List = clause.try_into().map_err(|_| RuntimeError {
-                            msg: format!("Expected conditional clause, found {clause}"),
+                        let clause: &List = clause.try_into().map_err(|_| {
+                            RuntimeError::new(format!(
+                                "Expected conditional clause, found {clause}"
+                            ))
                         })?;
 
                         let condition = &clause.car()?;
@@ -345,19 +367,17 @@ fn eval_inner(
                     let args = &list.cdr().into_iter().collect::<Vec<Value>>();
 
                     if args.is_empty() {
-                        return Err(RuntimeError {
-                            msg: "when requires at least a condition".to_string(),
-                        });
+                        return Err(RuntimeError::new("when requires at least a condition"));
                     }
 
                     let condition = require_arg(keyword, args, 0)?;
 
                     if eval_inner(env.clone(), condition, context.found_tail(true))?.into() {
                         let body = &Value::List(list.cdr().cdr());
-                        let body: &List = body.try_into().map_err(|_| RuntimeError {
-                            msg: format!(
+                        let body: &List = body.try_into().map_err(|_| {
+                            RuntimeError::new(format!(
                                 "Expected expression(s) after when-condition, found {body}"
-                            ),
+                            ))
                         })?;
 
                         eval_block_inner(env, body.into_iter(), context)
@@ -414,7 +434,8 @@ fn eval_inner(
                     if matches!(func_or_macro, Value::Macro(_)) {
                         let args = list.into_iter().skip(1).collect::<Vec<Value>>();
 
-                        let expanded = call_function_or_macro(env.clone(), &func_or_macro, args)?;
+                        let expanded = call_function_or_macro(env.clone(), &func_or_macro, args)
+                            .map_err(|e| e.in_frame(frame_name(list)))?;
 
                         eval_inner(env.clone(), &expanded, Context::new())
                     } else {
@@ -436,7 +457,7 @@ fn eval_inner(
                                 res = call_function_or_macro(env.clone(), func.as_ref(), args);
                             }
 
-                            res
+                            res.map_err(|e| e.in_frame(frame_name(list)))
                         }
                     }
                 }
@@ -449,19 +470,91 @@ fn eval_inner(
 }
 // 🦀 Boo! Did I scare ya? Haha!
 
+/// `(try body... (catch e handler...) (finally cleanup...))`
+///
+/// When the body fails, the handler runs with `e` bound to the condition and its value is
+/// the result. The cleanup runs in every case; an error it raises replaces the result.
+fn eval_try(env: Rc<RefCell<Env>>, forms: List, context: Context) -> Result<Value, RuntimeError> {
+    let mut body = Vec::new();
+    let mut catch: Option<(Symbol, List)> = None;
+    let mut finally: Option<List> = None;
+
+    for form in forms.into_iter() {
+        let clause = match &form {
+            Value::List(clause) => match clause.car() {
+                Ok(Value::Symbol(Symbol(keyword)))
+                    if keyword == "catch" || keyword == "finally" =>
+                {
+                    Some((keyword, clause.cdr()))
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        match clause {
+            Some((keyword, rest)) if keyword == "catch" => {
+                let var = match rest.car()? {
+                    Value::Symbol(var) => var,
+                    other => {
+                        return Err(RuntimeError::new(format!(
+                            "catch requires a symbol to bind the condition to; got {other}"
+                        )));
+                    }
+                };
+                catch = Some((var, rest.cdr()));
+            }
+            Some((_, rest)) => finally = Some(rest),
+            None if catch.is_some() || finally.is_some() => {
+                return Err(RuntimeError::new(
+                    "try requires its body before catch and finally",
+                ));
+            }
+            None => body.push(form),
+        }
+    }
+
+    // Nothing may escape as a tail call, or it would run outside the handlers
+    let context = context.found_tail(true);
+    let result = match (
+        eval_block_inner(env.clone(), body.into_iter(), context),
+        catch,
+    ) {
+        (Err(err), Some((var, handler))) => {
+            let mut catch_env = Env::extend(env.clone());
+            catch_env.define(var, Value::from(err));
+            eval_block_inner(
+                Rc::new(RefCell::new(catch_env)),
+                handler.into_iter(),
+                context,
+            )
+        }
+        (result, _) => result,
+    };
+    if let Some(cleanup) = finally {
+        eval_block_inner(env, cleanup.into_iter(), context)?;
+    }
+    result
+}
+
+/// How a failed call shows in the frames of a condition
+fn frame_name(call: &List) -> String {
+    match call.car() {
+        Ok(Value::Symbol(Symbol(name))) => name,
+        _ => "lambda".to_string(),
+    }
+}
+
 fn value_to_argnames(argnames: List) -> Result<Vec<Symbol>, RuntimeError> {
     argnames
         .into_iter()
         .enumerate()
         .map(|(index, arg)| match arg {
             Value::Symbol(s) => Ok(s),
-            _ => Err(RuntimeError {
-                msg: format!(
-                    "Expected list of arg names, but arg {} is a {}",
-                    index,
-                    arg.type_name()
-                ),
-            }),
+            _ => Err(RuntimeError::new(format!(
+                "Expected list of arg names, but arg {} is a {}",
+                index,
+                arg.type_name()
+            ))),
         })
         .collect()
 }
@@ -514,9 +607,7 @@ fn call_function_or_macro(
                 },
             )
         } else {
-            Err(RuntimeError {
-                msg: format!("{func} is not callable"),
-            })
+            Err(RuntimeError::new(format!("{func} is not callable")))
         }
     }
 }
@@ -568,6 +659,7 @@ mod tests {
     use crate::environment::Environment;
     use crate::lisp::default_environment::default_env;
     use crate::lisp::model::{Env, Symbol, Value};
+    use crate::lisp::parser::parse;
 
     use std::cell::RefCell;
     use std::rc::Rc;
@@ -659,6 +751,83 @@ mod tests {
         assert_eq!(lookup, Value::Int(100.into()));
     }
 
+    fn run(env: &Rc<RefCell<Env>>, src: &str) -> Result<Value, RuntimeError> {
+        let expr = parse(src).next().unwrap().unwrap();
+        eval(env.clone(), &expr)
+    }
+
+    #[test]
+    fn test_try_catch_finally() {
+        let env = create_test_env();
+        run(&env, "(define cleaned 0)").unwrap();
+
+        let result = run(
+            &env,
+            r#"(try (+ 1 (error 'io-failure "disk full")) (catch e (condition-message e)) (finally (set cleaned 1)))"#,
+        );
+        assert_eq!(result, Ok(Value::String("disk full".to_string())));
+        assert_eq!(run(&env, "cleaned"), Ok(Value::Int(1.into())));
+
+        assert_eq!(
+            run(
+                &env,
+                r#"(try (error 'io-failure "x") (catch e (condition-kind e)))"#
+            ),
+            Ok(Value::Symbol(Symbol::from("io-failure")))
+        );
+        // Without an error the body's value is kept and the handler is skipped
+        assert_eq!(
+            run(&env, r#"(try 1 2 (catch e "caught"))"#),
+            Ok(Value::Int(2.into()))
+        );
+        // Errors raised by the interpreter are caught too
+        assert_eq!(
+            run(
+                &env,
+                r#"(try (no-such-function) (catch e (string-contains? (condition-message e) "no-such-function")))"#
+            ),
+            Ok(Value::True)
+        );
+        // A handler can raise the condition again
+        let err = run(&env, r#"(try (error 'bad "again") (catch e (error e)))"#).unwrap_err();
+        assert_eq!((err.kind.as_str(), err.msg.as_str()), ("bad", "again"));
+    }
+
+    #[test]
+    fn test_unwind_protect_runs_cleanup_on_error() {
+        let env = create_test_env();
+        run(&env, "(define cleaned 0)").unwrap();
+
+        let err = run(&env, r#"(unwind-protect (error "boom") (set cleaned 1))"#).unwrap_err();
+        assert_eq!(err.msg, "boom");
+        assert_eq!(run(&env, "cleaned"), Ok(Value::Int(1.into())));
+        assert_eq!(
+            run(&env, "(unwind-protect 5 (set cleaned 2))"),
+            Ok(Value::Int(5.into()))
+        );
+        assert_eq!(run(&env, "cleaned"), Ok(Value::Int(2.into())));
+    }
+
+    #[test]
+    fn test_error_frames() {
+        let env = create_test_env();
+        run(&env, r#"(defun inner () (error "deep"))"#).unwrap();
+        run(&env, "(defun outer () (inner) 1)").unwrap();
+
+        // The call to error is a tail call, made on behalf of inner
+        let err = run(&env, "(outer)").unwrap_err();
+        assert_eq!(err.frames, vec!["inner", "outer"]);
+        assert_eq!(
+            run(&env, "(try (outer) (catch e (condition-frames e)))"),
+            Ok(Value::List(
+                ["inner", "outer"]
+                    .into_iter()
+                    .map(|frame| Value::String(frame.to_string()))
+                    .collect()
+            ))
+        );
+    }
+
     #[test]
     fn test_performance_no_unnecessary_clones() {
         let env = create_test_env();
@@ -6,7 +6,7 @@ use crate::lisp::model::IntType;
 pub use crate::lisp::model::Value;
 pub use crate::lisp::model::{Env, Symbol};
 use crate::lisp::model::{List, RuntimeError};
-use crate::lisp::parser::parse;
+use crate::lisp::parser::{line_at, parse, parse_with_offsets};
 use crate::secrets::SecretManagerSnapshot;
 use crate::suggestion::InputPreferences;
 use anyhow::Context;
@@ -14,6 +14,7 @@ use dsh_builtin::McpRuntimeStateSnapshot;
 use parking_lot::RwLock;
 use std::collections::{HashMap, HashSet};
 use std::ffi::OsString;
+use std::path::Path;
 use std::sync::Arc;
 use std::{cell::RefCell, rc::Rc};
 
@@ -95,9 +96,7 @@ impl LispEngine {
     pub fn run_config_lisp(&self) -> anyhow::Result<()> {
         let file_path = environment::get_config_file(CONFIG_FILE)?;
         let config_lisp: String = std::fs::read_to_string(&file_path)
-            .with_context(|| format!("Failed to read config file: {}", file_path.display()))?
-            .trim()
-            .to_string();
+            .with_context(|| format!("Failed to read config file: {}", file_path.display()))?;
 
         let env_snapshot = {
             let env = self.shell_env.read();
@@ -114,9 +113,12 @@ impl LispEngine {
             env.startup_mode = true;
             env.clear_mcp_servers();
         }
+        // Modules are evaluated again, so that a reload picks up their changes
+        self.env
+            .borrow_mut()
+            .define(Symbol::from(stdlib::module::LOADED_MODULES), Value::NIL);
 
-        let wrapped_config = format!("(begin {config_lisp}\n)");
-        let run_result = self.run(&wrapped_config);
+        let run_result = self.run_file(&config_lisp, &file_path);
 
         match run_result {
             Ok(_) => {
@@ -163,6 +165,20 @@ impl LispEngine {
         env.secret_manager.restore(snapshot.secret_manager);
     }
 
+    /// Evaluates every form of a file in order. An error names the file and the line of
+    /// the form that failed, followed by the calls it passed through.
+    fn run_file(&self, src: &str, path: &Path) -> anyhow::Result<Value> {
+        let mut result = Value::NIL;
+        for form in parse_with_offsets(src) {
+            let (offset, expr) =
+                form.map_err(|err| anyhow::anyhow!("{}: {err}", path.display()))?;
+            result = eval(Rc::clone(&self.env), &expr).map_err(|err| {
+                anyhow::anyhow!("{}:{}: {err}", path.display(), line_at(src, offset))
+            })?;
+        }
+        Ok(result)
+    }
+
     pub fn run(&self, src: &str) -> anyhow::Result<Value> {
         let mut ast_iter = parse(src);
 
@@ -312,6 +328,8 @@ pub fn make_env(environment: Arc<RwLock<Environment>>) -> Rc<RefCell<Env>> {
         Symbol::from("sh"),
         Value::NativeFunc(builtin::block_sh_no_cap),
     );
+    env.borrow_mut()
+        .define(Symbol::from("trap"), Value::NativeFunc(builtin::trap));
     env.borrow_mut().define(
         Symbol::from("allow-direnv"),
         Value::NativeFunc(builtin::allow_direnv),
@@ -330,6 +348,8 @@ pub fn make_env(environment: Arc<RwLock<Environment>>) -> Rc<RefCell<Env>> {
         Symbol::from("safety-level"),
         Value::NativeFunc(builtin::safety_level),
     );
+    env.borrow_mut()
+        .define(Symbol::from("option"), Value::NativeFunc(builtin::option));
     env.borrow_mut().define(
         Symbol::from("pref-auto-pair"),
         Value::NativeFunc(builtin::pref_auto_pair),
@@ -463,6 +483,34 @@ mod tests {
         assert!(res.is_ok());
     }
 
+    #[test]
+    fn test_trap_registers_handlers() {
+        use crate::shell::trap::{TrapAction, TrapSignal};
+        init();
+        let env = Environment::new();
+        let engine = LispEngine::new(Arc::clone(&env));
+        engine.borrow().run("(trap 'EXIT (lambda () 1))").unwrap();
+        engine
+            .borrow()
+            .run("(trap \"ERR DEBUG\" \"echo failed\")")
+            .unwrap();
+        {
+            let env = env.read();
+            assert!(matches!(
+                env.traps.get(&TrapSignal::Exit),
+                Some(TrapAction::Lisp(_))
+            ));
+            assert!(matches!(
+                env.traps.get(&TrapSignal::Debug),
+                Some(TrapAction::Command(command)) if command == "echo failed"
+            ));
+        }
+
+        engine.borrow().run("(trap 'ERR nil)").unwrap();
+        assert!(!env.read().traps.contains_key(&TrapSignal::Err));
+        assert!(engine.borrow().run("(trap 'NOPE \"x\")").is_err());
+    }
+
     #[tokio::test]
     #[ignore = "requires shell execution context"]
     async fn test_call_fn() {
@@ -650,4 +698,89 @@ mod tests {
             assert!(!has_broken_action);
         });
     }
+
+    #[test]
+    fn run_config_lisp_reports_file_and_line() {
+        init();
+        with_test_config_home(|| {
+            let env = Environment::new();
+            let engine = LispEngine::new(env);
+            let config_path = crate::environment::get_config_file(CONFIG_FILE).unwrap();
+            std::fs::write(
+                &config_path,
+                "(defun deploy () (sh-missing \"x\"))\n\n(alias \"ok\" \"echo ok\")\n(deploy)\n",
+            )
+            .unwrap();
+
+            let err = engine.borrow().run_config_lisp().unwrap_err().to_string();
+            assert!(
+                err.starts_with(&format!("{}:4: ", config_path.display())),
+                "{err}"
+            );
+            assert!(err.contains("sh-missing"), "{err}");
+            assert!(err.contains("in deploy"), "{err}");
+        });
+    }
+
+    #[test]
+    fn require_and_load_lisp_modules() {
+        init();
+        with_test_config_home(|| {
+            let env = Environment::new();
+            let engine = LispEngine::new(env);
+            let config_path = crate::environment::get_config_file(CONFIG_FILE).unwrap();
+            let module_dir = config_path.parent().unwrap().join("lisp");
+            std::fs::create_dir_all(&module_dir).unwrap();
+            std::fs::write(
+                module_dir.join("git-utils.lisp"),
+                "(set *required* (+ *required* 1))\n(defun helper () \"git\")\n(defun branch () (helper))\n(export 'branch)\n",
+            )
+            .unwrap();
+            std::fs::write(
+                module_dir.join("docker.lisp"),
+                "(defun helper () \"docker\")\n",
+            )
+            .unwrap();
+            std::fs::write(
+                module_dir.join("broken.lisp"),
+                "(defun ok () 1)\n(ok 1 2)\n(missing)\n",
+            )
+            .unwrap();
+            let extra_dir = tempfile::tempdir().unwrap();
+            std::fs::write(extra_dir.path().join("extra.lisp"), "(defun extra () 42)").unwrap();
+            unsafe {
+                std::env::set_var(stdlib::module::LISP_PATH_ENV, extra_dir.path());
+            }
+
+            let engine = engine.borrow();
+            engine.run("(define *required* 0)").unwrap();
+            engine.run("(require 'git-utils)").unwrap();
+            engine.run("(require \"git-utils\")").unwrap();
+            engine.run("(require 'docker)").unwrap();
+            assert_eq!(engine.run("*required*").unwrap(), Value::Int(1));
+            assert_eq!(
+                engine.run("(git-utils/helper)").unwrap(),
+                Value::String("git".into())
+            );
+            assert_eq!(
+                engine.run("(docker/helper)").unwrap(),
+                Value::String("docker".into())
+            );
+            assert_eq!(engine.run("(branch)").unwrap(), Value::String("git".into()));
+            assert!(!engine.has("helper"));
+
+            engine.run("(load \"extra.lisp\")").unwrap();
+            assert_eq!(engine.run("(extra)").unwrap(), Value::Int(42));
+
+            let err = engine.run("(require 'broken)").unwrap_err().to_string();
+            assert!(err.contains("missing"), "{err}");
+            assert!(err.contains("broken.lisp:3"), "{err}");
+            assert!(engine.run("(require 'no-such-module)").is_err());
+            assert!(engine.run("(export 'x)").is_err());
+
+            unsafe {
+                std::env::remove_var(stdlib::module::LISP_PATH_ENV);
+            }
+        });
+    }
 }
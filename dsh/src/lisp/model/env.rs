@@ -35,6 +35,18 @@ impl Env {
         }
     }
 
+    /// The outermost environment, where global definitions live
+    pub fn root(env: &Rc<RefCell<Env>>) -> Rc<RefCell<Env>> {
+        let mut current = Rc::clone(env);
+        loop {
+            let parent = current.borrow().parent.clone();
+            match parent {
+                Some(parent) => current = parent,
+                None => return current,
+            }
+        }
+    }
+
     /// Walks up the environment hierarchy until it finds the symbol's value or
     /// runs out of environments.
     pub fn get(&self, key: &Symbol) -> Option<Value> {
@@ -65,9 +77,9 @@ impl Env {
                 if let Some(parent) = &self.parent {
                     parent.borrow_mut().set(key, value)
                 } else {
-                    Err(RuntimeError {
-                        msg: format!("Tried to set value of undefined symbol \"{key}\""),
-                    })
+                    Err(RuntimeError::new(format!(
+                        "Tried to set value of undefined symbol \"{key}\""
+                    )))
                 }
             }
         }
@@ -21,9 +21,7 @@ impl List {
         self.head
             .as_ref()
             .map(|rc| rc.borrow().car.clone())
-            .ok_or_else(|| RuntimeError {
-                msg: String::from("Attempted to apply car on nil"),
-            })
+            .ok_or_else(|| RuntimeError::new("Attempted to apply car on nil"))
     }
     #[must_use]
     pub fn cdr(&self) -> List {
@@ -1,22 +1,50 @@
 use std::fmt::Debug;
 
+/// Kind of the errors raised by the interpreter and native functions.
+pub const DEFAULT_ERROR_KIND: &str = "error";
+
 /// An error that occurred while evaluating some lisp code
+///
+/// It is also the condition a `catch` clause receives: `(error 'kind "message")` sets
+/// the kind, and every function call the error leaves adds a frame.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RuntimeError {
     pub msg: String,
+    pub kind: String,
+    /// The calls the error passed through, innermost first
+    pub frames: Vec<String>,
 }
 
 impl RuntimeError {
-    pub fn new(msg: &str) -> Self {
+    pub fn new(msg: impl Into<String>) -> Self {
+        Self::with_kind(DEFAULT_ERROR_KIND, msg)
+    }
+
+    pub fn with_kind(kind: impl Into<String>, msg: impl Into<String>) -> Self {
         RuntimeError {
-            msg: msg.to_string(),
+            msg: msg.into(),
+            kind: kind.into(),
+            frames: Vec::new(),
         }
     }
+
+    /// Records that the error left a call to `frame`.
+    pub fn in_frame(mut self, frame: impl Into<String>) -> Self {
+        self.frames.push(frame.into());
+        self
+    }
 }
 
 impl std::fmt::Display for RuntimeError {
     fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(formatter, "Runtime error: {}", self.msg)
+        write!(formatter, "Runtime error: {}", self.msg)?;
+        if self.kind != DEFAULT_ERROR_KIND {
+            write!(formatter, " ({})", self.kind)?;
+        }
+        if !self.frames.is_empty() {
+            write!(formatter, "\n  in {}", self.frames.join("\n  in "))?;
+        }
+        Ok(())
     }
 }
 
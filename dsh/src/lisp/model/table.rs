@@ -425,6 +425,19 @@ impl Table {
         new_table
     }
 
+    /// Filters rows where the string column matches the regular expression.
+    pub fn where_match(&self, column: &str, regex: &regex::Regex) -> Self {
+        let mut new_table = Self::new(self.columns.clone());
+        for record in &self.rows {
+            if let Some(Value::String(s)) = record.get(column)
+                && regex.is_match(s)
+            {
+                new_table.rows.push(record.clone());
+            }
+        }
+        new_table
+    }
+
     /// Filters rows where the numeric column matches a comparison.
     /// op can be: ">" "<" ">=" "<=" "=" "!="
     pub fn where_cmp(&self, column: &str, op: &str, value: super::IntType) -> Self {
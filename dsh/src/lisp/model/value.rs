@@ -120,9 +120,7 @@ impl TryFrom<&Value> for IntType {
                 #[cfg(not(feature = "bigint"))]
                 return Ok(*this);
             }
-            _ => Err(RuntimeError {
-                msg: format!("Expected int, got a {value}"),
-            }),
+            _ => Err(RuntimeError::new(format!("Expected int, got a {value}"))),
         }
     }
 }
@@ -139,9 +137,7 @@ impl TryFrom<&Value> for FloatType {
     fn try_from(value: &Value) -> Result<Self, Self::Error> {
         match value {
             Value::Float(this) => Ok(*this),
-            _ => Err(RuntimeError {
-                msg: format!("Expected float, got a {value}"),
-            }),
+            _ => Err(RuntimeError::new(format!("Expected float, got a {value}"))),
         }
     }
 }
@@ -158,9 +154,7 @@ impl<'a> TryFrom<&'a Value> for &'a String {
     fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
         match value {
             Value::String(this) => Ok(this),
-            _ => Err(RuntimeError {
-                msg: format!("Expected string, got a {value}"),
-            }),
+            _ => Err(RuntimeError::new(format!("Expected string, got a {value}"))),
         }
     }
 }
@@ -177,9 +171,7 @@ impl<'a> TryFrom<&'a Value> for &'a Symbol {
     fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
         match value {
             Value::Symbol(this) => Ok(this),
-            _ => Err(RuntimeError {
-                msg: format!("Expected symbol, got a {value}"),
-            }),
+            _ => Err(RuntimeError::new(format!("Expected symbol, got a {value}"))),
         }
     }
 }
@@ -196,9 +188,7 @@ impl<'a> TryFrom<&'a Value> for &'a List {
     fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
         match value {
             Value::List(this) => Ok(this),
-            _ => Err(RuntimeError {
-                msg: format!("Expected list, got a {value}"),
-            }),
+            _ => Err(RuntimeError::new(format!("Expected list, got a {value}"))),
         }
     }
 }
@@ -215,9 +205,9 @@ impl<'a> TryFrom<&'a Value> for &'a Lambda {
     fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
         match value {
             Value::Lambda(this) => Ok(this),
-            _ => Err(RuntimeError {
-                msg: format!("Expected function, got a {value}"),
-            }),
+            _ => Err(RuntimeError::new(format!(
+                "Expected function, got a {value}"
+            ))),
         }
     }
 }
@@ -234,9 +224,9 @@ impl<'a> TryFrom<&'a Value> for &'a HashMapRc {
     fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
         match value {
             Value::HashMap(this) => Ok(this),
-            _ => Err(RuntimeError {
-                msg: format!("Expected hash map, got a {value}"),
-            }),
+            _ => Err(RuntimeError::new(format!(
+                "Expected hash map, got a {value}"
+            ))),
         }
     }
 }
@@ -259,9 +249,7 @@ impl<'a> TryFrom<&'a Value> for &'a TableRc {
     fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
         match value {
             Value::Table(this) => Ok(this),
-            _ => Err(RuntimeError {
-                msg: format!("Expected table, got a {value}"),
-            }),
+            _ => Err(RuntimeError::new(format!("Expected table, got a {value}"))),
         }
     }
 }
@@ -272,9 +260,9 @@ impl<'a> TryFrom<&'a Value> for &'a Rc<dyn Any> {
     fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
         match value {
             Value::Foreign(this) => Ok(this),
-            _ => Err(RuntimeError {
-                msg: format!("Expected foreign value, got a {value}"),
-            }),
+            _ => Err(RuntimeError::new(format!(
+                "Expected foreign value, got a {value}"
+            ))),
         }
     }
 }
@@ -285,6 +273,13 @@ impl From<Rc<dyn Any>> for Value {
     }
 }
 
+/// A caught error, as bound by `catch`
+impl From<RuntimeError> for Value {
+    fn from(err: RuntimeError) -> Self {
+        Value::Foreign(Rc::new(err))
+    }
+}
+
 impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -311,7 +306,10 @@ impl std::fmt::Display for Value {
             Value::Int(this) => write!(f, "{this}"),
             Value::Float(this) => write!(f, "{this}"),
             Value::Symbol(Symbol(this)) => write!(f, "{this}"),
-            Value::Foreign(_) => f.write_str("<foreign_value>"),
+            Value::Foreign(this) => match this.downcast_ref::<RuntimeError>() {
+                Some(err) => write!(f, "<condition {}: {}>", err.kind, err.msg),
+                None => f.write_str("<foreign_value>"),
+            },
             Value::Table(table) => write!(f, "{}", table.borrow()),
             Value::TailCall { func, args } => {
                 write!(f, "<tail-call: {func:?} with {args:?} >")
@@ -9,16 +9,25 @@ use std::fmt::Display;
 /// are more than one expressions when the base string has more than one
 /// independent parenthesized lists at its root.
 pub fn parse(code: &str) -> impl Iterator<Item = Result<Value, ParseError>> + '_ {
+    parse_with_offsets(code).map(|res| res.map(|(_, value)| value))
+}
+
+/// Like `parse()`, with the byte offset each expression starts at, so that
+/// errors can point at the line of the form that failed.
+pub fn parse_with_offsets(
+    code: &str,
+) -> impl Iterator<Item = Result<(usize, Value), ParseError>> + '_ {
     let mut index = 0;
     index = consume_whitespace_and_comments(code, index);
 
     std::iter::from_fn(move || {
         if let Some(res) = parse_expression(code, index) {
             if let Ok(res) = res {
+                let start = index;
                 index = res.index;
                 index = consume_whitespace_and_comments(code, index);
 
-                Some(Ok(res.parsed.into_value()))
+                Some(Ok((start, res.parsed.into_value())))
             } else {
                 Some(Err(res.unwrap_err()))
             }
@@ -34,6 +43,15 @@ pub fn parse(code: &str) -> impl Iterator<Item = Result<Value, ParseError>> + '_
     })
 }
 
+/// The line, counting from 1, that a byte offset of `code` is on.
+pub fn line_at(code: &str, offset: usize) -> usize {
+    code.as_bytes()[..offset.min(code.len())]
+        .iter()
+        .filter(|&&b| b == b'\n')
+        .count()
+        + 1
+}
+
 /// A slightly more convenient data structure for building the parse tree, before
 /// eventually converting it into proper s-expressions.
 #[derive(Debug, Clone)]
@@ -265,33 +283,25 @@ fn parse_number(code: &str, index: usize) -> ParseResult {
 }
 
 fn parse_string(code: &str, index: usize) -> ParseResult {
-    let (last_index, _) = consume_while(code, index, |(index, ch)| {
-        (index == 0 && ch == '"') || (index > 0 && ch != '"')
-    })?;
-
-    if last_index > index {
-        if code.as_bytes().get(last_index + 1) == Some(&b'"') {
-            Some(Ok(ParsedAndIndex {
-                parsed: ParseTree::Atom(Value::String(
-                    code.get(index + 1..last_index + 1).unwrap_or("").to_owned(),
-                )),
-                index: last_index + 2,
-            }))
-        } else {
-            Some(Err(ParseError {
-                msg: format!("Unclosed string at index {last_index}"),
-            }))
-        }
-    } else {
-        None
+    let body = code.get(index..)?.strip_prefix('"')?;
+
+    // Byte offsets, so that multi-byte characters and "" are read whole
+    match body.find('"') {
+        Some(len) => Some(Ok(ParsedAndIndex {
+            parsed: ParseTree::Atom(Value::String(body[..len].to_owned())),
+            index: index + len + 2,
+        })),
+        None => Some(Err(ParseError {
+            msg: format!("Unclosed string at index {index}"),
+        })),
     }
 }
 
 fn parse_symbol(code: &str, index: usize) -> ParseResult {
-    let (last_index, _) = consume_while(code, index, |(index, ch)| {
+    let (last_index, last_char) = consume_while(code, index, |(index, ch)| {
         (index == 0 && is_symbol_start(ch)) || (index > 0 && is_symbolic(ch))
     })?;
-    let last_index = last_index + 1;
+    let last_index = last_index + last_char.len_utf8();
 
     if last_index > index {
         Some(Ok(ParsedAndIndex {
@@ -386,4 +396,21 @@ mod tests {
         assert!(res.is_err());
         assert_eq!(res.unwrap_err().msg, "Unexpected token at index 8");
     }
+
+    #[test]
+    fn test_parse_strings() {
+        let parsed: Vec<Value> = parse(r#""" "日本語" "a" 日本"#)
+            .map(|res| res.unwrap())
+            .collect();
+        assert_eq!(
+            parsed,
+            vec![
+                Value::String(String::new()),
+                Value::String("日本語".to_string()),
+                Value::String("a".to_string()),
+                Value::Symbol(Symbol::from("日本")),
+            ]
+        );
+        assert!(parse(r#""open"#).next().unwrap().is_err());
+    }
 }
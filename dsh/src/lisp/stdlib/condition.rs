@@ -0,0 +1,90 @@
+//! Raising errors and inspecting the conditions caught by `try`.
+//!
+//! `(error "message")` raises an error of kind `error`, `(error 'kind "message")` one of
+//! the given kind, and `(error condition)` raises a caught condition again with its
+//! frames. Inside `(catch e ...)`, `condition-message`, `condition-kind` and
+//! `condition-frames` take the condition apart.
+
+use crate::lisp::model::{Env, RuntimeError, Symbol, Value};
+use crate::lisp::utils::{require_arg, require_typed_arg};
+
+pub fn register(env: &mut Env) {
+    env.define(
+        Symbol::from("error"),
+        Value::NativeFunc(|_env, args| {
+            let first = require_arg("error", &args, 0)?;
+
+            Err(match first {
+                Value::Foreign(_) if args.len() == 1 => require_condition("error", &args, 0)?,
+                Value::Symbol(kind) => {
+                    let msg = require_typed_arg::<&String>("error", &args, 1)?;
+                    RuntimeError::with_kind(kind.0.as_str(), msg.as_str())
+                }
+                Value::String(msg) => RuntimeError::new(msg.as_str()),
+                other => RuntimeError::new(format!(
+                    "\"error\" requires a message, a kind and a message, or a condition; got {other}"
+                )),
+            })
+        }),
+    );
+
+    env.define(
+        Symbol::from("is_condition"),
+        Value::NativeFunc(|_env, args| {
+            let val = require_arg("is_condition", &args, 0)?;
+
+            Ok(match val {
+                Value::Foreign(foreign) if foreign.is::<RuntimeError>() => Value::True,
+                _ => Value::NIL,
+            })
+        }),
+    );
+
+    env.define(
+        Symbol::from("condition-message"),
+        Value::NativeFunc(|_env, args| {
+            let err = require_condition("condition-message", &args, 0)?;
+
+            Ok(Value::String(err.msg))
+        }),
+    );
+
+    env.define(
+        Symbol::from("condition-kind"),
+        Value::NativeFunc(|_env, args| {
+            let err = require_condition("condition-kind", &args, 0)?;
+
+            Ok(Value::Symbol(Symbol(err.kind)))
+        }),
+    );
+
+    // Innermost call first, e.g. ("sh!" "deploy")
+    env.define(
+        Symbol::from("condition-frames"),
+        Value::NativeFunc(|_env, args| {
+            let err = require_condition("condition-frames", &args, 0)?;
+
+            Ok(Value::List(
+                err.frames.into_iter().map(Value::String).collect(),
+            ))
+        }),
+    );
+}
+
+fn require_condition(
+    name: &str,
+    args: &[Value],
+    index: usize,
+) -> Result<RuntimeError, RuntimeError> {
+    match require_arg(name, args, index)? {
+        Value::Foreign(foreign) => foreign.downcast_ref::<RuntimeError>().cloned(),
+        _ => None,
+    }
+    .ok_or_else(|| {
+        RuntimeError::new(format!(
+            "\"{name}\" requires argument {} to be a condition; got {}",
+            index + 1,
+            args[index]
+        ))
+    })
+}
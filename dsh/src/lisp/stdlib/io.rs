@@ -1,5 +1,25 @@
-use crate::lisp::model::{Env, Symbol, Value};
-use crate::lisp::utils::require_arg;
+//! Output, files and paths.
+//!
+//! The file functions work in-process, so hooks that run on every prompt can check for a
+//! file or read a `package.json` without starting `sh!`. Relative paths are relative to
+//! the shell's current directory and `~` is expanded. `write-file` and `append-file` go
+//! through the `SafetyGuard` first; a write it wants confirmed fails with a `safety-error`,
+//! as Lisp code can't answer the prompt.
+
+use crate::lisp::model::{Env, IntType, RuntimeError, Symbol, Value};
+use crate::lisp::utils::{require_arg, require_typed_arg};
+use crate::safety::{SafetyGuard, SafetyResult};
+use std::cell::RefCell;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+use std::time::UNIX_EPOCH;
+
+/// Kind of the errors raised when a file can't be read or written
+const FILE_ERROR: &str = "file-error";
+/// Kind of the errors raised when the `SafetyGuard` stops a write
+const SAFETY_ERROR: &str = "safety-error";
 
 pub fn register(env: &mut Env) {
     env.define(
@@ -11,4 +31,237 @@ pub fn register(env: &mut Env) {
             Ok(expr.clone())
         }),
     );
+
+    env.define(
+        Symbol::from("file-exists?"),
+        Value::NativeFunc(|_env, args| {
+            let path = require_path("file-exists?", &args, 0)?;
+
+            Ok(Value::from(path.exists()))
+        }),
+    );
+
+    env.define(
+        Symbol::from("dir?"),
+        Value::NativeFunc(|_env, args| {
+            let path = require_path("dir?", &args, 0)?;
+
+            Ok(Value::from(path.is_dir()))
+        }),
+    );
+
+    env.define(
+        Symbol::from("read-file"),
+        Value::NativeFunc(|_env, args| {
+            let path = require_path("read-file", &args, 0)?;
+
+            std::fs::read_to_string(&path)
+                .map(Value::String)
+                .map_err(|e| file_error("read-file", &path, e))
+        }),
+    );
+
+    env.define(
+        Symbol::from("write-file"),
+        Value::NativeFunc(|env, args| write_file(env, "write-file", &args, false)),
+    );
+
+    env.define(
+        Symbol::from("append-file"),
+        Value::NativeFunc(|env, args| write_file(env, "append-file", &args, true)),
+    );
+
+    // (list-dir [dir]) lists the names in a directory, sorted
+    env.define(
+        Symbol::from("list-dir"),
+        Value::NativeFunc(|_env, args| {
+            let path = match args.first() {
+                Some(_) => require_path("list-dir", &args, 0)?,
+                None => expand(".")?,
+            };
+
+            let entries = std::fs::read_dir(&path).map_err(|e| file_error("list-dir", &path, e))?;
+            let mut names: Vec<String> = entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect();
+            names.sort();
+            Ok(Value::List(names.into_iter().map(Value::String).collect()))
+        }),
+    );
+
+    // (glob "src/**/*.rs") lists the matching paths, as written in the pattern
+    env.define(
+        Symbol::from("glob"),
+        Value::NativeFunc(|_env, args| {
+            let pattern = require_typed_arg::<&String>("glob", &args, 0)?;
+            let pattern = shellexpand::tilde(pattern);
+
+            let paths = glob::glob(&pattern)
+                .map_err(|e| RuntimeError::new(format!("\"glob\" invalid pattern: {e}")))?;
+            Ok(Value::List(
+                paths
+                    .filter_map(|path| path.ok())
+                    .map(|path| Value::String(path.to_string_lossy().into_owned()))
+                    .collect(),
+            ))
+        }),
+    );
+
+    env.define(
+        Symbol::from("path-join"),
+        Value::NativeFunc(|_env, args| {
+            let mut path = PathBuf::new();
+            for index in 0..args.len().max(1) {
+                path.push(require_typed_arg::<&String>("path-join", &args, index)?);
+            }
+
+            Ok(path_value(&path))
+        }),
+    );
+
+    env.define(
+        Symbol::from("path-basename"),
+        Value::NativeFunc(|_env, args| {
+            let path = require_typed_arg::<&String>("path-basename", &args, 0)?;
+
+            Ok(Path::new(path)
+                .file_name()
+                .map_or(Value::NIL, |name| path_value(Path::new(name))))
+        }),
+    );
+
+    env.define(
+        Symbol::from("path-dirname"),
+        Value::NativeFunc(|_env, args| {
+            let path = require_typed_arg::<&String>("path-dirname", &args, 0)?;
+
+            Ok(match Path::new(path).parent() {
+                Some(parent) if parent.as_os_str().is_empty() => Value::String(".".to_string()),
+                Some(parent) => path_value(parent),
+                None => Value::String(path.clone()),
+            })
+        }),
+    );
+
+    // (path-ext "a/b.tar.gz") is "gz", without the dot; nil when there is none
+    env.define(
+        Symbol::from("path-ext"),
+        Value::NativeFunc(|_env, args| {
+            let path = require_typed_arg::<&String>("path-ext", &args, 0)?;
+
+            Ok(Path::new(path)
+                .extension()
+                .map_or(Value::NIL, |ext| path_value(Path::new(ext))))
+        }),
+    );
+
+    // (expand-path "~/src/../notes") is an absolute path, without . and ..
+    env.define(
+        Symbol::from("expand-path"),
+        Value::NativeFunc(|_env, args| {
+            let path = require_path("expand-path", &args, 0)?;
+
+            Ok(path_value(&path))
+        }),
+    );
+
+    // (file-mtime path) is the modification time in seconds since the epoch
+    env.define(
+        Symbol::from("file-mtime"),
+        Value::NativeFunc(|_env, args| {
+            let path = require_path("file-mtime", &args, 0)?;
+
+            let modified = std::fs::metadata(&path)
+                .and_then(|meta| meta.modified())
+                .map_err(|e| file_error("file-mtime", &path, e))?;
+            let secs = modified
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs());
+            Ok(Value::Int(IntType::try_from(secs).unwrap_or_default()))
+        }),
+    );
+}
+
+fn write_file(
+    env: Rc<RefCell<Env>>,
+    name: &str,
+    args: &[Value],
+    append: bool,
+) -> Result<Value, RuntimeError> {
+    let path = require_path(name, args, 0)?;
+    let content = match require_arg(name, args, 1)? {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+
+    {
+        let env_ref = env.borrow();
+        let shell_env = env_ref.shell_env.read();
+        let safety_level = shell_env.safety_level.read();
+
+        match SafetyGuard::new().check_file_write(&path.to_string_lossy(), &safety_level) {
+            SafetyResult::Allowed => {}
+            SafetyResult::Confirm(msg) => {
+                return Err(RuntimeError::with_kind(
+                    SAFETY_ERROR,
+                    format!("SafetyGuard Blocked: {}", msg),
+                ));
+            }
+            SafetyResult::Denied(msg) => {
+                return Err(RuntimeError::with_kind(
+                    SAFETY_ERROR,
+                    format!("SafetyGuard Denied: {}", msg),
+                ));
+            }
+        }
+    }
+
+    OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(&path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .map_err(|e| file_error(name, &path, e))?;
+    Ok(Value::True)
+}
+
+/// A path argument made absolute, with `~` expanded and `.` and `..` resolved
+fn require_path(name: &str, args: &[Value], index: usize) -> Result<PathBuf, RuntimeError> {
+    let path = require_typed_arg::<&String>(name, args, index)?;
+    expand(path)
+}
+
+fn expand(path: &str) -> Result<PathBuf, RuntimeError> {
+    let path = PathBuf::from(shellexpand::tilde(path).as_ref());
+    let path = if path.is_absolute() {
+        path
+    } else {
+        std::env::current_dir()
+            .map_err(|e| RuntimeError::with_kind(FILE_ERROR, e.to_string()))?
+            .join(path)
+    };
+
+    // Lexically, so that paths which don't exist yet can be expanded too
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    Ok(normalized)
+}
+
+fn path_value(path: &Path) -> Value {
+    Value::String(path.to_string_lossy().into_owned())
+}
+
+fn file_error(name: &str, path: &Path, err: std::io::Error) -> RuntimeError {
+    RuntimeError::with_kind(FILE_ERROR, format!("\"{name}\" {}: {err}", path.display()))
 }
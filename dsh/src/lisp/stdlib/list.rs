@@ -42,9 +42,8 @@ pub fn register(env: &mut Env) {
             let index = require_typed_arg::<IntType>("nth", &args, 0)?;
             let list = require_typed_arg::<&List>("nth", &args, 1)?;
 
-            let index = TryInto::<usize>::try_into(index).map_err(|_| RuntimeError {
-                msg: "Failed converting to `usize`".to_owned(),
-            })?;
+            let index = TryInto::<usize>::try_into(index)
+                .map_err(|_| RuntimeError::new("Failed converting to `usize`"))?;
 
             Ok(list.into_iter().nth(index).unwrap_or(Value::NIL))
         }),
@@ -26,18 +26,16 @@ pub fn register(env: &mut Env) {
                 Value::Int(_) => Ok(Value::Int(IntType::default())),
                 Value::Float(_) => Ok(Value::Float(0.0)),
                 Value::String(_) => Ok(Value::String("".into())),
-                _ => Err(RuntimeError {
-                    msg: format!(
-                        "Function \"+\" requires arguments to be numbers or strings; found {first_arg}"
-                    ),
-                }),
+                _ => Err(RuntimeError::new(format!(
+                    "Function \"+\" requires arguments to be numbers or strings; found {first_arg}"
+                ))),
             }?;
 
             for arg in args {
-                total = (&total + &arg).map_err(|_| RuntimeError {
-                    msg: format!(
+                total = (&total + &arg).map_err(|_| {
+                    RuntimeError::new(format!(
                         "Function \"+\" requires arguments to be numbers or strings; found {arg}"
-                    ),
+                    ))
                 })?;
             }
 
@@ -51,9 +49,8 @@ pub fn register(env: &mut Env) {
             let a = require_arg("-", &args, 0)?;
             let b = require_arg("-", &args, 1)?;
 
-            (a - b).map_err(|_| RuntimeError {
-                msg: String::from("Function \"-\" requires arguments to be numbers"),
-            })
+            (a - b)
+                .map_err(|_| RuntimeError::new("Function \"-\" requires arguments to be numbers"))
         }),
     );
 
@@ -63,8 +60,10 @@ pub fn register(env: &mut Env) {
             let mut product = Value::Int(IntType::from(1));
 
             for arg in args {
-                product = (&product * &arg).map_err(|_| RuntimeError {
-                    msg: format!("Function \"*\" requires arguments to be numbers; found {arg}"),
+                product = (&product * &arg).map_err(|_| {
+                    RuntimeError::new(format!(
+                        "Function \"*\" requires arguments to be numbers; found {arg}"
+                    ))
                 })?;
             }
 
@@ -78,9 +77,8 @@ pub fn register(env: &mut Env) {
             let a = require_arg("/", &args, 0)?;
             let b = require_arg("/", &args, 1)?;
 
-            (a / b).map_err(|_| RuntimeError {
-                msg: String::from("Function \"/\" requires arguments to be numbers"),
-            })
+            (a / b)
+                .map_err(|_| RuntimeError::new("Function \"/\" requires arguments to be numbers"))
         }),
     );
 
@@ -97,9 +95,9 @@ pub fn register(env: &mut Env) {
                 return Ok(Value::Int(a / b));
             }
 
-            Err(RuntimeError {
-                msg: String::from("Function \"truncate\" requires arguments to be integers"),
-            })
+            Err(RuntimeError::new(
+                "Function \"truncate\" requires arguments to be integers",
+            ))
         }),
     );
 
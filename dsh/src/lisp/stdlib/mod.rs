@@ -1,19 +1,25 @@
+pub mod condition;
 pub mod core;
 pub mod io;
 pub mod list;
 pub mod math;
 pub mod mcp;
+pub mod module;
+pub mod regex;
 pub mod string;
 pub mod table;
 
 use crate::lisp::model::Env;
 
 pub fn register(env: &mut Env) {
+    condition::register(env);
     core::register(env);
     io::register(env);
     list::register(env);
     math::register(env);
     mcp::register(env);
+    module::register(env);
+    regex::register(env);
     string::register(env);
     table::register(env);
 }
@@ -0,0 +1,195 @@
+//! Splitting configuration into files with `load` and `require`.
+//!
+//! `(load "file.lisp")` evaluates a file as if its forms were written in place.
+//! `(require 'name)` evaluates `name.lisp` once, in an environment of its own: what the
+//! module defines is bound globally as `name/symbol`, so two modules can each have a
+//! `helper`. Symbols listed with `(export 'symbol ...)` and commands defined with `fn`
+//! are also bound without the prefix.
+//!
+//! Relative names are looked up in the directories of `DSH_LISP_PATH`, then in
+//! `~/.config/dsh/lisp/`.
+
+use crate::environment;
+use crate::lisp::CONFIG_FILE;
+use crate::lisp::interpreter::eval;
+use crate::lisp::model::{Env, List, RuntimeError, Symbol, Value};
+use crate::lisp::parser::{line_at, parse_with_offsets};
+use crate::lisp::utils::require_arg;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Colon-separated directories searched before `~/.config/dsh/lisp/`
+pub const LISP_PATH_ENV: &str = "DSH_LISP_PATH";
+
+/// Names of the modules `require` has loaded, so that each one is evaluated once
+pub const LOADED_MODULES: &str = "*loaded-modules*";
+
+/// Symbols a module asked to bind without its prefix
+const MODULE_EXPORTS: &str = "*module-exports*";
+
+pub fn register(env: &mut Env) {
+    env.define(Symbol::from(LOADED_MODULES), Value::NIL);
+
+    env.define(
+        Symbol::from("load"),
+        Value::NativeFunc(|env, args| {
+            let name = require_name("load", &args)?;
+            let path = resolve(&name).ok_or_else(|| {
+                RuntimeError::with_kind("file-error", format!("load: {name} not found"))
+            })?;
+
+            eval_file(Env::root(&env), &path)
+        }),
+    );
+
+    env.define(
+        Symbol::from("require"),
+        Value::NativeFunc(|env, args| {
+            let name = require_name("require", &args)?;
+            let root = Env::root(&env);
+            let loaded = loaded_modules(&root);
+            if loaded.contains(&name) {
+                return Ok(Value::Symbol(Symbol(name)));
+            }
+            let path = resolve(&format!("{name}.lisp")).ok_or_else(|| {
+                RuntimeError::with_kind("file-error", format!("require: module {name} not found"))
+            })?;
+
+            // Recorded first, so that modules requiring each other don't loop
+            set_loaded_modules(&root, loaded.iter().cloned().chain([name.clone()]))?;
+            let module_env = Rc::new(RefCell::new(Env::extend(Rc::clone(&root))));
+            module_env
+                .borrow_mut()
+                .define(Symbol::from(MODULE_EXPORTS), Value::NIL);
+            if let Err(err) = eval_file(Rc::clone(&module_env), &path) {
+                set_loaded_modules(&root, loaded.into_iter())?;
+                return Err(err);
+            }
+
+            bind_module(&root, &name, &module_env.borrow());
+            Ok(Value::Symbol(Symbol(name)))
+        }),
+    );
+
+    // (export 'helper 'other) inside a module binds those symbols without the prefix
+    env.define(
+        Symbol::from("export"),
+        Value::NativeFunc(|env, args| {
+            let exports_symbol = Symbol::from(MODULE_EXPORTS);
+            let Some(Value::List(exports)) = env.borrow().get(&exports_symbol) else {
+                return Err(RuntimeError::new(
+                    "\"export\" can only be used in a module loaded by require",
+                ));
+            };
+
+            let mut exports: Vec<Value> = exports.into_iter().collect();
+            for arg in &args {
+                match arg {
+                    Value::Symbol(_) => exports.push(arg.clone()),
+                    other => {
+                        return Err(RuntimeError::new(format!(
+                            "\"export\" requires symbols; got {other}"
+                        )));
+                    }
+                }
+            }
+            env.borrow_mut()
+                .set(exports_symbol, Value::List(exports.into_iter().collect()))?;
+            Ok(Value::NIL)
+        }),
+    );
+}
+
+/// Evaluates the forms of a file in order. An error gets the file and line of the form
+/// that failed as a frame.
+pub fn eval_file(env: Rc<RefCell<Env>>, path: &Path) -> Result<Value, RuntimeError> {
+    let src = std::fs::read_to_string(path).map_err(|e| {
+        RuntimeError::with_kind(
+            "file-error",
+            format!("failed to read {}: {e}", path.display()),
+        )
+    })?;
+
+    let mut result = Value::NIL;
+    for form in parse_with_offsets(&src) {
+        let (offset, expr) = form.map_err(|err| {
+            RuntimeError::with_kind("parse-error", format!("{}: {}", path.display(), err.msg))
+        })?;
+        result = eval(Rc::clone(&env), &expr)
+            .map_err(|err| err.in_frame(format!("{}:{}", path.display(), line_at(&src, offset))))?;
+    }
+    Ok(result)
+}
+
+/// Where `name` is: as given when it is absolute or starts with `~` or `.`, otherwise
+/// the first match in the search path.
+fn resolve(name: &str) -> Option<PathBuf> {
+    let expanded = PathBuf::from(shellexpand::tilde(name).as_ref());
+    if expanded.is_absolute() || name.starts_with('.') {
+        return expanded.is_file().then_some(expanded);
+    }
+    search_path()
+        .into_iter()
+        .map(|dir| dir.join(&expanded))
+        .find(|path| path.is_file())
+}
+
+fn search_path() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = std::env::var_os(LISP_PATH_ENV)
+        .map(|paths| std::env::split_paths(&paths).collect())
+        .unwrap_or_default();
+    if let Some(config_dir) = environment::get_config_file(CONFIG_FILE)
+        .ok()
+        .and_then(|config| config.parent().map(|dir| dir.join("lisp")))
+    {
+        dirs.push(config_dir);
+    }
+    dirs
+}
+
+fn require_name(func: &str, args: &[Value]) -> Result<String, RuntimeError> {
+    match require_arg(func, args, 0)? {
+        Value::String(name) => Ok(name.clone()),
+        Value::Symbol(Symbol(name)) => Ok(name.clone()),
+        other => Err(RuntimeError::new(format!(
+            "\"{func}\" requires a string or a symbol; got {other}"
+        ))),
+    }
+}
+
+fn loaded_modules(root: &Rc<RefCell<Env>>) -> Vec<String> {
+    match root.borrow().get(&Symbol::from(LOADED_MODULES)) {
+        Some(Value::List(list)) => list.into_iter().map(|name| name.to_string()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn set_loaded_modules(
+    root: &Rc<RefCell<Env>>,
+    names: impl Iterator<Item = String>,
+) -> Result<(), RuntimeError> {
+    let list: List = names.map(Value::String).collect();
+    root.borrow_mut()
+        .set(Symbol::from(LOADED_MODULES), Value::List(list))
+}
+
+/// Binds what a module defined as `name/symbol`, and the exported symbols and `fn`
+/// commands as they are.
+fn bind_module(root: &Rc<RefCell<Env>>, name: &str, module_env: &Env) {
+    let mut definitions = module_env.snapshot_entries();
+    let exports = match definitions.remove(&Symbol::from(MODULE_EXPORTS)) {
+        Some(Value::List(exports)) => exports.into_iter().collect(),
+        _ => Vec::new(),
+    };
+
+    let mut root = root.borrow_mut();
+    for (symbol, value) in definitions {
+        let unqualified = exports.contains(&Value::Symbol(symbol.clone()))
+            || matches!(&value, Value::Lambda(lambda) if lambda.export);
+        if unqualified {
+            root.define(symbol.clone(), value.clone());
+        }
+        root.define(Symbol(format!("{name}/{symbol}")), value);
+    }
+}
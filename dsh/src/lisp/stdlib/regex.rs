@@ -0,0 +1,204 @@
+//! Regular expressions for config and hook code.
+//!
+//! Every function takes either a pattern string or a regex made by `re-compile`.
+//! Patterns are compiled once and cached, so passing the same string in a hook that runs
+//! on every command does not recompile it.
+
+use crate::lisp::model::{Env, List, RuntimeError, Symbol, Value};
+use crate::lisp::utils::{require_arg, require_typed_arg};
+use regex::{Captures, Regex};
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Compiled patterns kept at most; the cache starts over when it is full.
+const CACHE_SIZE: usize = 128;
+
+thread_local! {
+    static CACHE: RefCell<HashMap<String, Rc<Regex>>> = RefCell::new(HashMap::new());
+}
+
+pub fn register(env: &mut Env) {
+    env.define(
+        Symbol::from("re-compile"),
+        Value::NativeFunc(|_env, args| {
+            let regex: Rc<dyn Any> = require_regex("re-compile", &args, 0)?;
+
+            Ok(Value::Foreign(regex))
+        }),
+    );
+
+    env.define(
+        Symbol::from("is_regex"),
+        Value::NativeFunc(|_env, args| {
+            let val = require_arg("is_regex", &args, 0)?;
+
+            Ok(match val {
+                Value::Foreign(foreign) if foreign.is::<Regex>() => Value::True,
+                _ => Value::NIL,
+            })
+        }),
+    );
+
+    env.define(
+        Symbol::from("re-matches?"),
+        Value::NativeFunc(|_env, args| {
+            let regex = require_regex("re-matches?", &args, 0)?;
+            let s = require_typed_arg::<&String>("re-matches?", &args, 1)?;
+
+            Ok(Value::from(regex.is_match(s)))
+        }),
+    );
+
+    // (re-match re s) is the first match as a list of the whole match and its groups,
+    // with nil for groups that took no part, or nil when nothing matches
+    env.define(
+        Symbol::from("re-match"),
+        Value::NativeFunc(|_env, args| {
+            let regex = require_regex("re-match", &args, 0)?;
+            let s = require_typed_arg::<&String>("re-match", &args, 1)?;
+
+            Ok(regex
+                .captures(s)
+                .map_or(Value::NIL, |caps| group_list(&caps)))
+        }),
+    );
+
+    // (re-match-named re s) is a hash of the named groups of the first match
+    env.define(
+        Symbol::from("re-match-named"),
+        Value::NativeFunc(|_env, args| {
+            let regex = require_regex("re-match-named", &args, 0)?;
+            let s = require_typed_arg::<&String>("re-match-named", &args, 1)?;
+
+            Ok(regex
+                .captures(s)
+                .map_or(Value::NIL, |caps| group_hash(&regex, &caps)))
+        }),
+    );
+
+    // (re-find-all re s) lists every match; with capture groups each one is a list
+    // as returned by re-match
+    env.define(
+        Symbol::from("re-find-all"),
+        Value::NativeFunc(|_env, args| {
+            let regex = require_regex("re-find-all", &args, 0)?;
+            let s = require_typed_arg::<&String>("re-find-all", &args, 1)?;
+
+            let matches: List = if regex.captures_len() > 1 {
+                regex
+                    .captures_iter(s)
+                    .map(|caps| group_list(&caps))
+                    .collect()
+            } else {
+                regex
+                    .find_iter(s)
+                    .map(|m| Value::String(m.as_str().to_string()))
+                    .collect()
+            };
+            Ok(Value::List(matches))
+        }),
+    );
+
+    // (re-replace re s replacement) replaces every match; $1 and ${name} in the
+    // replacement stand for groups
+    env.define(
+        Symbol::from("re-replace"),
+        Value::NativeFunc(|_env, args| {
+            let regex = require_regex("re-replace", &args, 0)?;
+            let s = require_typed_arg::<&String>("re-replace", &args, 1)?;
+            let replacement = require_typed_arg::<&String>("re-replace", &args, 2)?;
+
+            Ok(Value::String(
+                regex.replace_all(s, replacement.as_str()).into_owned(),
+            ))
+        }),
+    );
+
+    env.define(
+        Symbol::from("re-replace-first"),
+        Value::NativeFunc(|_env, args| {
+            let regex = require_regex("re-replace-first", &args, 0)?;
+            let s = require_typed_arg::<&String>("re-replace-first", &args, 1)?;
+            let replacement = require_typed_arg::<&String>("re-replace-first", &args, 2)?;
+
+            Ok(Value::String(
+                regex.replace(s, replacement.as_str()).into_owned(),
+            ))
+        }),
+    );
+
+    env.define(
+        Symbol::from("re-split"),
+        Value::NativeFunc(|_env, args| {
+            let regex = require_regex("re-split", &args, 0)?;
+            let s = require_typed_arg::<&String>("re-split", &args, 1)?;
+
+            Ok(Value::List(
+                regex
+                    .split(s)
+                    .map(|part| Value::String(part.to_string()))
+                    .collect(),
+            ))
+        }),
+    );
+}
+
+/// Takes a regex made by `re-compile`, or compiles a pattern string through the cache.
+pub fn require_regex(name: &str, args: &[Value], index: usize) -> Result<Rc<Regex>, RuntimeError> {
+    match require_arg(name, args, index)? {
+        Value::String(pattern) => compile(name, pattern),
+        Value::Foreign(foreign) => Rc::clone(foreign).downcast::<Regex>().map_err(|_| {
+            RuntimeError::new(format!(
+                "\"{name}\" requires argument {} to be a regex",
+                index + 1
+            ))
+        }),
+        other => Err(RuntimeError::new(format!(
+            "\"{name}\" requires argument {} to be a regex or a pattern string; got {other}",
+            index + 1
+        ))),
+    }
+}
+
+fn compile(name: &str, pattern: &str) -> Result<Rc<Regex>, RuntimeError> {
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if let Some(regex) = cache.get(pattern) {
+            return Ok(Rc::clone(regex));
+        }
+        let regex = Rc::new(
+            Regex::new(pattern)
+                .map_err(|e| RuntimeError::new(format!("\"{name}\" invalid regex: {e}")))?,
+        );
+        if cache.len() >= CACHE_SIZE {
+            cache.clear();
+        }
+        cache.insert(pattern.to_string(), Rc::clone(&regex));
+        Ok(regex)
+    })
+}
+
+fn group_list(caps: &Captures) -> Value {
+    Value::List(
+        caps.iter()
+            .map(|group| group.map_or(Value::NIL, |m| Value::String(m.as_str().to_string())))
+            .collect(),
+    )
+}
+
+#[allow(clippy::mutable_key_type)]
+fn group_hash(regex: &Regex, caps: &Captures) -> Value {
+    let groups: HashMap<Value, Value> = regex
+        .capture_names()
+        .flatten()
+        .map(|name| {
+            let value = caps
+                .name(name)
+                .map_or(Value::NIL, |m| Value::String(m.as_str().to_string()));
+            (Value::String(name.to_string()), value)
+        })
+        .collect();
+    Value::from(groups)
+}
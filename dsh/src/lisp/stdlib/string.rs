@@ -1,5 +1,393 @@
-use crate::lisp::model::Env;
+//! String functions for config and hook code.
+//!
+//! Positions and lengths count characters, not bytes, so `substring` and
+//! `string-length` behave the same for `"abc"` and `"日本語"`. Padding uses the display
+//! width, so wide characters line up in a terminal.
 
-pub fn register(_env: &mut Env) {
-    // Placeholder
+use crate::lisp::model::{Env, FloatType, IntType, List, RuntimeError, Symbol, Value};
+use crate::lisp::utils::{require_arg, require_typed_arg};
+use std::convert::TryInto;
+use unicode_width::UnicodeWidthStr;
+
+pub fn register(env: &mut Env) {
+    env.define(
+        Symbol::from("is_string"),
+        Value::NativeFunc(|_env, args| {
+            let val = require_arg("is_string", &args, 0)?;
+
+            Ok(match val {
+                Value::String(_) => Value::True,
+                _ => Value::NIL,
+            })
+        }),
+    );
+
+    env.define(
+        Symbol::from("string-length"),
+        Value::NativeFunc(|_env, args| {
+            let s = require_typed_arg::<&String>("string-length", &args, 0)?;
+
+            Ok(int_value(s.chars().count()))
+        }),
+    );
+
+    // (string-split "a,b" ",") splits on a separator; without one, on whitespace
+    env.define(
+        Symbol::from("string-split"),
+        Value::NativeFunc(|_env, args| {
+            let s = require_typed_arg::<&String>("string-split", &args, 0)?;
+
+            let parts: Vec<&str> = match optional_string_arg("string-split", &args, 1)? {
+                Some(sep) if sep.is_empty() => {
+                    return Ok(char_list(s));
+                }
+                Some(sep) => s.split(sep.as_str()).collect(),
+                None => s.split_whitespace().collect(),
+            };
+            Ok(string_list(parts))
+        }),
+    );
+
+    env.define(
+        Symbol::from("string-join"),
+        Value::NativeFunc(|_env, args| {
+            let list = require_typed_arg::<&List>("string-join", &args, 0)?;
+            let sep = optional_string_arg("string-join", &args, 1)?.unwrap_or_default();
+
+            let parts: Vec<String> = list.into_iter().map(|v| display_string(&v)).collect();
+            Ok(Value::String(parts.join(&sep)))
+        }),
+    );
+
+    env.define(
+        Symbol::from("string-lines"),
+        Value::NativeFunc(|_env, args| {
+            let s = require_typed_arg::<&String>("string-lines", &args, 0)?;
+
+            Ok(string_list(s.lines().collect()))
+        }),
+    );
+
+    env.define(
+        Symbol::from("string->list"),
+        Value::NativeFunc(|_env, args| {
+            let s = require_typed_arg::<&String>("string->list", &args, 0)?;
+
+            Ok(char_list(s))
+        }),
+    );
+
+    env.define(
+        Symbol::from("string-trim"),
+        Value::NativeFunc(|_env, args| {
+            let s = require_typed_arg::<&String>("string-trim", &args, 0)?;
+
+            Ok(Value::String(s.trim().to_string()))
+        }),
+    );
+
+    env.define(
+        Symbol::from("string-trim-start"),
+        Value::NativeFunc(|_env, args| {
+            let s = require_typed_arg::<&String>("string-trim-start", &args, 0)?;
+
+            Ok(Value::String(s.trim_start().to_string()))
+        }),
+    );
+
+    env.define(
+        Symbol::from("string-trim-end"),
+        Value::NativeFunc(|_env, args| {
+            let s = require_typed_arg::<&String>("string-trim-end", &args, 0)?;
+
+            Ok(Value::String(s.trim_end().to_string()))
+        }),
+    );
+
+    env.define(
+        Symbol::from("string-upcase"),
+        Value::NativeFunc(|_env, args| {
+            let s = require_typed_arg::<&String>("string-upcase", &args, 0)?;
+
+            Ok(Value::String(s.to_uppercase()))
+        }),
+    );
+
+    env.define(
+        Symbol::from("string-downcase"),
+        Value::NativeFunc(|_env, args| {
+            let s = require_typed_arg::<&String>("string-downcase", &args, 0)?;
+
+            Ok(Value::String(s.to_lowercase()))
+        }),
+    );
+
+    env.define(
+        Symbol::from("string-reverse"),
+        Value::NativeFunc(|_env, args| {
+            let s = require_typed_arg::<&String>("string-reverse", &args, 0)?;
+
+            Ok(Value::String(s.chars().rev().collect()))
+        }),
+    );
+
+    env.define(
+        Symbol::from("string-contains?"),
+        Value::NativeFunc(|_env, args| {
+            let s = require_typed_arg::<&String>("string-contains?", &args, 0)?;
+            let needle = require_typed_arg::<&String>("string-contains?", &args, 1)?;
+
+            Ok(Value::from(s.contains(needle.as_str())))
+        }),
+    );
+
+    env.define(
+        Symbol::from("string-starts-with?"),
+        Value::NativeFunc(|_env, args| {
+            let s = require_typed_arg::<&String>("string-starts-with?", &args, 0)?;
+            let prefix = require_typed_arg::<&String>("string-starts-with?", &args, 1)?;
+
+            Ok(Value::from(s.starts_with(prefix.as_str())))
+        }),
+    );
+
+    env.define(
+        Symbol::from("string-ends-with?"),
+        Value::NativeFunc(|_env, args| {
+            let s = require_typed_arg::<&String>("string-ends-with?", &args, 0)?;
+            let suffix = require_typed_arg::<&String>("string-ends-with?", &args, 1)?;
+
+            Ok(Value::from(s.ends_with(suffix.as_str())))
+        }),
+    );
+
+    // (string-index "héllo" "l") is the character position of the first match, or nil
+    env.define(
+        Symbol::from("string-index"),
+        Value::NativeFunc(|_env, args| {
+            let s = require_typed_arg::<&String>("string-index", &args, 0)?;
+            let needle = require_typed_arg::<&String>("string-index", &args, 1)?;
+
+            Ok(match s.find(needle.as_str()) {
+                Some(byte) => int_value(s[..byte].chars().count()),
+                None => Value::NIL,
+            })
+        }),
+    );
+
+    // (string-replace s from to) replaces every occurrence
+    env.define(
+        Symbol::from("string-replace"),
+        Value::NativeFunc(|_env, args| {
+            let s = require_typed_arg::<&String>("string-replace", &args, 0)?;
+            let from = require_typed_arg::<&String>("string-replace", &args, 1)?;
+            let to = require_typed_arg::<&String>("string-replace", &args, 2)?;
+
+            if from.is_empty() {
+                return Err(RuntimeError::new(
+                    "\"string-replace\" requires a non-empty string to replace",
+                ));
+            }
+            Ok(Value::String(s.replace(from.as_str(), to)))
+        }),
+    );
+
+    // (substring s start [end]) takes characters from start up to end, which defaults
+    // to the end of the string; negative positions count from the end
+    env.define(
+        Symbol::from("substring"),
+        Value::NativeFunc(|_env, args| {
+            let s = require_typed_arg::<&String>("substring", &args, 0)?;
+            let chars: Vec<char> = s.chars().collect();
+            let start = char_position("substring", &args, 1, chars.len())?;
+            let end = match args.get(2) {
+                Some(_) => char_position("substring", &args, 2, chars.len())?,
+                None => chars.len(),
+            };
+
+            if start > end {
+                return Err(RuntimeError::new(format!(
+                    "\"substring\" start {start} is after end {end}"
+                )));
+            }
+            Ok(Value::String(chars[start..end].iter().collect()))
+        }),
+    );
+
+    env.define(
+        Symbol::from("string-repeat"),
+        Value::NativeFunc(|_env, args| {
+            let s = require_typed_arg::<&String>("string-repeat", &args, 0)?;
+            let count = require_typed_arg::<IntType>("string-repeat", &args, 1)?;
+            let count: usize = count.try_into().map_err(|_| {
+                RuntimeError::new("\"string-repeat\" requires a non-negative count")
+            })?;
+
+            Ok(Value::String(s.repeat(count)))
+        }),
+    );
+
+    env.define(
+        Symbol::from("string-pad-left"),
+        Value::NativeFunc(|_env, args| pad("string-pad-left", &args, true)),
+    );
+
+    env.define(
+        Symbol::from("string-pad-right"),
+        Value::NativeFunc(|_env, args| pad("string-pad-right", &args, false)),
+    );
+
+    // (string->number "42") is 42, "1.5" gives a float and anything else nil
+    env.define(
+        Symbol::from("string->number"),
+        Value::NativeFunc(|_env, args| {
+            let s = require_typed_arg::<&String>("string->number", &args, 0)?;
+            let s = s.trim();
+
+            if let Ok(int) = s.parse::<IntType>() {
+                return Ok(Value::Int(int));
+            }
+            Ok(match s.parse::<FloatType>() {
+                Ok(float) if float.is_finite() => Value::Float(float),
+                _ => Value::NIL,
+            })
+        }),
+    );
+
+    env.define(
+        Symbol::from("number->string"),
+        Value::NativeFunc(|_env, args| {
+            let val = require_arg("number->string", &args, 0)?;
+
+            match val {
+                Value::Int(_) | Value::Float(_) => Ok(Value::String(val.to_string())),
+                _ => Err(RuntimeError::new(format!(
+                    "\"number->string\" requires a number; got {val}"
+                ))),
+            }
+        }),
+    );
+
+    // (format "{} has {} files" name count) fills each {} with the next argument;
+    // {{ and }} stand for literal braces
+    env.define(
+        Symbol::from("format"),
+        Value::NativeFunc(|_env, args| {
+            let template = require_typed_arg::<&String>("format", &args, 0)?;
+
+            format_string(template, &args[1..])
+        }),
+    );
+}
+
+fn format_string(template: &str, values: &[Value]) -> Result<Value, RuntimeError> {
+    let mut out = String::with_capacity(template.len());
+    let mut values = values.iter();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                out.push(c);
+            }
+            ('{', Some('}')) => {
+                chars.next();
+                let value = values
+                    .next()
+                    .ok_or_else(|| RuntimeError::new("\"format\" has more {} than arguments"))?;
+                out.push_str(&display_string(value));
+            }
+            _ => out.push(c),
+        }
+    }
+    if values.next().is_some() {
+        return Err(RuntimeError::new("\"format\" has more arguments than {}"));
+    }
+    Ok(Value::String(out))
+}
+
+/// Pads to a display width with spaces or the given character
+fn pad(name: &str, args: &[Value], left: bool) -> Result<Value, RuntimeError> {
+    let s = require_typed_arg::<&String>(name, args, 0)?;
+    let width = require_typed_arg::<IntType>(name, args, 1)?;
+    let width: usize = width
+        .try_into()
+        .map_err(|_| RuntimeError::new(format!("\"{name}\" requires a non-negative width")))?;
+    let fill = match optional_string_arg(name, args, 2)? {
+        Some(fill) => {
+            let mut chars = fill.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => c,
+                _ => {
+                    return Err(RuntimeError::new(format!(
+                        "\"{name}\" requires a single character to pad with"
+                    )));
+                }
+            }
+        }
+        None => ' ',
+    };
+
+    let padding: String = std::iter::repeat_n(fill, width.saturating_sub(s.width())).collect();
+    Ok(Value::String(if left {
+        padding + s
+    } else {
+        s.clone() + &padding
+    }))
+}
+
+/// Strings are used as they are, other values as they print
+fn display_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn optional_string_arg(
+    name: &str,
+    args: &[Value],
+    index: usize,
+) -> Result<Option<String>, RuntimeError> {
+    match args.get(index) {
+        None => Ok(None),
+        Some(_) => Ok(Some(
+            require_typed_arg::<&String>(name, args, index)?.clone(),
+        )),
+    }
+}
+
+/// A character position, from the end when negative, clamped to the string
+fn char_position(
+    name: &str,
+    args: &[Value],
+    index: usize,
+    len: usize,
+) -> Result<usize, RuntimeError> {
+    let pos = require_typed_arg::<IntType>(name, args, index)?;
+    if pos < IntType::default() {
+        // -(pos + 1) can't overflow, unlike -pos
+        let back: usize = (-(pos + IntType::from(1))).try_into().unwrap_or(usize::MAX);
+        Ok(len.saturating_sub(back.saturating_add(1)))
+    } else {
+        let pos: usize = pos.try_into().unwrap_or(usize::MAX);
+        Ok(pos.min(len))
+    }
+}
+
+fn int_value(n: usize) -> Value {
+    Value::Int(IntType::try_from(n).unwrap_or_default())
+}
+
+fn string_list(parts: Vec<&str>) -> Value {
+    Value::List(
+        parts
+            .into_iter()
+            .map(|part| Value::String(part.to_string()))
+            .collect(),
+    )
+}
+
+fn char_list(s: &str) -> Value {
+    Value::List(s.chars().map(|c| Value::String(c.to_string())).collect())
 }
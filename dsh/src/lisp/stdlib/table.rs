@@ -1,4 +1,5 @@
 use crate::lisp::model::{Env, IntType, List, RuntimeError, Symbol, Table, TableRc, Value};
+use crate::lisp::stdlib::regex::require_regex;
 use crate::lisp::utils::{require_arg, require_typed_arg};
 use cfg_if::cfg_if;
 use std::cell::RefCell;
@@ -13,9 +14,7 @@ pub fn register(env: &mut Env) {
 
             match Table::from_json(json_str) {
                 Ok(table) => Ok(Value::Table(TableRc::new(RefCell::new(table)))),
-                Err(e) => Err(RuntimeError {
-                    msg: format!("json-parse error: {}", e),
-                }),
+                Err(e) => Err(RuntimeError::new(format!("json-parse error: {}", e))),
             }
         }),
     );
@@ -38,9 +37,7 @@ pub fn register(env: &mut Env) {
 
             match Table::from_csv(csv_str) {
                 Ok(table) => Ok(Value::Table(TableRc::new(RefCell::new(table)))),
-                Err(e) => Err(RuntimeError {
-                    msg: format!("csv-parse error: {}", e),
-                }),
+                Err(e) => Err(RuntimeError::new(format!("csv-parse error: {}", e))),
             }
         }),
     );
@@ -53,9 +50,7 @@ pub fn register(env: &mut Env) {
             let table = table_rc.borrow();
             match table.to_csv() {
                 Ok(s) => Ok(Value::String(s)),
-                Err(e) => Err(RuntimeError {
-                    msg: format!("csv-stringify error: {}", e),
-                }),
+                Err(e) => Err(RuntimeError::new(format!("csv-stringify error: {}", e))),
             }
         }),
     );
@@ -89,9 +84,9 @@ pub fn register(env: &mut Env) {
         Value::NativeFunc(|_env, args| {
             let table_rc = require_typed_arg::<&TableRc>("table-head", &args, 0)?;
             let n = require_typed_arg::<IntType>("table-head", &args, 1)?;
-            let n: usize = n.try_into().map_err(|_| RuntimeError {
-                msg: "table-head: n must be a non-negative integer".to_string(),
-            })?;
+            let n: usize = n
+                .try_into()
+                .map_err(|_| RuntimeError::new("table-head: n must be a non-negative integer"))?;
 
             let table = table_rc.borrow();
             let new_table = table.head(n);
@@ -105,9 +100,9 @@ pub fn register(env: &mut Env) {
         Value::NativeFunc(|_env, args| {
             let table_rc = require_typed_arg::<&TableRc>("table-tail", &args, 0)?;
             let n = require_typed_arg::<IntType>("table-tail", &args, 1)?;
-            let n: usize = n.try_into().map_err(|_| RuntimeError {
-                msg: "table-tail: n must be a non-negative integer".to_string(),
-            })?;
+            let n: usize = n
+                .try_into()
+                .map_err(|_| RuntimeError::new("table-tail: n must be a non-negative integer"))?;
 
             let table = table_rc.borrow();
             let new_table = table.tail(n);
@@ -201,6 +196,21 @@ pub fn register(env: &mut Env) {
         }),
     );
 
+    // table-where-match: Filter rows where string column matches a regex
+    // Usage: (table-where-match table "column" "^feat")
+    env.define(
+        Symbol::from("table-where-match"),
+        Value::NativeFunc(|_env, args| {
+            let table_rc = require_typed_arg::<&TableRc>("table-where-match", &args, 0)?;
+            let column = require_typed_arg::<&String>("table-where-match", &args, 1)?;
+            let regex = require_regex("table-where-match", &args, 2)?;
+
+            let table = table_rc.borrow();
+            let new_table = table.where_match(column, &regex);
+            Ok(Value::Table(TableRc::new(RefCell::new(new_table))))
+        }),
+    );
+
     // table-where-cmp: Filter rows with numeric comparison
     // Usage: (table-where-cmp table "column" ">" 10)
     env.define(
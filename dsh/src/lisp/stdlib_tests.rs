@@ -4,6 +4,8 @@ mod tests {
     use crate::lisp::default_environment::default_env;
     use crate::lisp::interpreter::eval;
     use crate::lisp::model::{Env, IntType, Symbol, Value};
+    use crate::lisp::parser::parse;
+    use crate::safety::SafetyLevel;
     use dsh_types::mcp::McpTransport;
     use std::cell::RefCell;
     use std::rc::Rc;
@@ -13,6 +15,15 @@ mod tests {
         Rc::new(RefCell::new(default_env(shell_env)))
     }
 
+    fn run(env: &Rc<RefCell<Env>>, src: &str) -> Value {
+        let expr = parse(src).next().unwrap().unwrap();
+        eval(env.clone(), &expr).unwrap()
+    }
+
+    fn string(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+
     #[test]
     fn test_math_basic() {
         let env = create_test_env();
@@ -245,6 +256,222 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_string_functions() {
+        let env = create_test_env();
+
+        assert_eq!(
+            run(&env, r#"(string-split "a,b,,c" ",")"#),
+            run(&env, r#"(list "a" "b" "" "c")"#)
+        );
+        assert_eq!(
+            run(&env, r#"(string-split "  one  two three ")"#),
+            run(&env, r#"(list "one" "two" "three")"#)
+        );
+        assert_eq!(
+            run(&env, r#"(string-join (list "a" 1 "b") "-")"#),
+            string("a-1-b")
+        );
+        assert_eq!(
+            run(&env, r#"(format "{} has {} files {{}}" "src" 3)"#),
+            string("src has 3 files {}")
+        );
+        assert_eq!(
+            run(&env, r#"(string-replace "a.b.c" "." "/")"#),
+            string("a/b/c")
+        );
+        assert_eq!(run(&env, r#"(string-contains? "doge" "og")"#), Value::True);
+        assert_eq!(
+            run(&env, r#"(string-starts-with? "doge" "og")"#),
+            Value::False
+        );
+        assert_eq!(run(&env, r#"(string-ends-with? "doge" "ge")"#), Value::True);
+        assert_eq!(run(&env, r#"(string-trim "  x y  ")"#), string("x y"));
+        assert_eq!(run(&env, r#"(string-upcase "straße")"#), string("STRASSE"));
+        assert_eq!(run(&env, r#"(string->number " 42 ")"#), Value::Int(42));
+        assert_eq!(run(&env, r#"(string->number "1.5")"#), Value::Float(1.5));
+        assert_eq!(run(&env, r#"(string->number "x1")"#), Value::NIL);
+        assert_eq!(run(&env, "(number->string 7)"), string("7"));
+    }
+
+    #[test]
+    fn test_string_functions_count_characters() {
+        let env = create_test_env();
+
+        assert_eq!(run(&env, r#"(string-length "日本語")"#), Value::Int(3));
+        assert_eq!(
+            run(&env, r#"(substring "日本語テキスト" 1 3)"#),
+            string("本語")
+        );
+        assert_eq!(run(&env, r#"(substring "héllo" -3)"#), string("llo"));
+        assert_eq!(run(&env, r#"(string-index "héllo" "l")"#), Value::Int(2));
+        assert_eq!(run(&env, r#"(string-reverse "añb")"#), string("bña"));
+        assert_eq!(
+            run(&env, r#"(string->list "añ")"#),
+            run(&env, r#"(list "a" "ñ")"#)
+        );
+        // Padding counts display columns, two for each wide character
+        assert_eq!(run(&env, r#"(string-pad-left "日本" 6)"#), string("  日本"));
+        assert_eq!(
+            run(&env, r#"(string-pad-right "ab" 4 ".")"#),
+            string("ab..")
+        );
+        let expr = parse(r#"(substring "abc" 2 1)"#).next().unwrap().unwrap();
+        assert!(eval(env.clone(), &expr).is_err());
+    }
+
+    #[test]
+    fn test_regex_functions() {
+        let env = create_test_env();
+
+        assert_eq!(
+            run(
+                &env,
+                r#"(re-match "(\w+)@(\w+)(x)?" "mail bob@example now")"#
+            ),
+            run(&env, r#"(list "bob@example" "bob" "example" nil)"#)
+        );
+        assert_eq!(run(&env, r#"(re-match "^\d+$" "12a")"#), Value::NIL);
+        assert_eq!(
+            run(&env, r#"(re-matches? "^git" "git status")"#),
+            Value::True
+        );
+        assert_eq!(
+            run(&env, r#"(re-find-all "\d+" "a1 b22 c333")"#),
+            run(&env, r#"(list "1" "22" "333")"#)
+        );
+        assert_eq!(
+            run(&env, r#"(re-find-all "(\w)=(\d)" "a=1 b=2")"#),
+            run(&env, r#"(list (list "a=1" "a" "1") (list "b=2" "b" "2"))"#)
+        );
+        assert_eq!(
+            run(&env, r#"(re-replace "(\w+)-(\w+)" "ab-cd ef-gh" "$2-$1")"#),
+            string("cd-ab gh-ef")
+        );
+        assert_eq!(
+            run(&env, r#"(re-replace-first "o" "foo" "0")"#),
+            string("f0o")
+        );
+        assert_eq!(
+            run(&env, r#"(re-split ",\s*" "a, b,c")"#),
+            run(&env, r#"(list "a" "b" "c")"#)
+        );
+
+        let named = run(
+            &env,
+            r#"(re-match-named "(?P<cmd>\w+) (?P<arg>\S+)" "git push")"#,
+        );
+        let Value::HashMap(named) = named else {
+            panic!("Expected hash from re-match-named");
+        };
+        assert_eq!(named.borrow().get(&string("cmd")), Some(&string("git")));
+        assert_eq!(named.borrow().get(&string("arg")), Some(&string("push")));
+
+        run(&env, r#"(define re (re-compile "^feat"))"#);
+        assert_eq!(run(&env, "(is_regex re)"), Value::True);
+        assert_eq!(run(&env, r#"(re-matches? re "feat: x")"#), Value::True);
+        let expr = parse(r#"(re-compile "(")"#).next().unwrap().unwrap();
+        assert!(eval(env.clone(), &expr).is_err());
+
+        let table = run(
+            &env,
+            "(table-where-match (csv-parse \"msg,n\nfeat: a,1\nfix: b,2\nfeat: c,3\") \"msg\" re)",
+        );
+        let Value::Table(table) = table else {
+            panic!("Expected table from table-where-match");
+        };
+        assert_eq!(table.borrow().count(), 2);
+    }
+
+    #[test]
+    fn test_file_functions() {
+        let env = create_test_env();
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_string_lossy().into_owned();
+        run(&env, &format!("(define root \"{root}\")"));
+
+        run(&env, "(define notes (path-join root \"notes.txt\"))");
+        assert_eq!(run(&env, "(file-exists? notes)"), Value::False);
+        assert_eq!(run(&env, "(write-file notes \"one\")"), Value::True);
+        assert_eq!(run(&env, "(append-file notes 2)"), Value::True);
+        assert_eq!(run(&env, "(read-file notes)"), string("one2"));
+        assert_eq!(run(&env, "(file-exists? notes)"), Value::True);
+        assert_eq!(run(&env, "(dir? notes)"), Value::False);
+        assert_eq!(run(&env, "(dir? root)"), Value::True);
+        assert!(matches!(run(&env, "(file-mtime notes)"), Value::Int(_)));
+
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "").unwrap();
+        assert_eq!(
+            run(&env, "(list-dir root)"),
+            run(&env, r#"(list "notes.txt" "src")"#)
+        );
+        assert_eq!(
+            run(&env, r#"(glob (path-join root "*" "*.rs"))"#),
+            Value::List(
+                [string(&format!("{root}/src/main.rs"))]
+                    .into_iter()
+                    .collect()
+            )
+        );
+        assert_eq!(
+            run(
+                &env,
+                r#"(expand-path (path-join root "src" ".." "notes.txt"))"#
+            ),
+            string(&format!("{root}/notes.txt"))
+        );
+
+        let expr = parse(r#"(read-file (path-join root "missing"))"#)
+            .next()
+            .unwrap()
+            .unwrap();
+        let err = eval(env.clone(), &expr).unwrap_err();
+        assert_eq!(err.kind, "file-error");
+
+        env.borrow()
+            .shell_env
+            .write()
+            .set_safety_level(SafetyLevel::Strict);
+        let expr = parse(r#"(write-file notes "blocked")"#)
+            .next()
+            .unwrap()
+            .unwrap();
+        assert!(eval(env.clone(), &expr).is_err());
+        assert_eq!(run(&env, "(read-file notes)"), string("one2"));
+
+        env.borrow()
+            .shell_env
+            .write()
+            .set_safety_level(SafetyLevel::Normal);
+        assert_eq!(
+            run(
+                &env,
+                r#"(try (write-file "/etc/x" "") (catch e (condition-kind e)))"#
+            ),
+            Value::Symbol(Symbol::from("safety-error"))
+        );
+    }
+
+    #[test]
+    fn test_path_functions() {
+        let env = create_test_env();
+
+        assert_eq!(
+            run(&env, r#"(path-join "a" "b" "c.txt")"#),
+            string("a/b/c.txt")
+        );
+        assert_eq!(run(&env, r#"(path-basename "a/b/c.txt")"#), string("c.txt"));
+        assert_eq!(run(&env, r#"(path-dirname "a/b/c.txt")"#), string("a/b"));
+        assert_eq!(run(&env, r#"(path-dirname "c.txt")"#), string("."));
+        assert_eq!(run(&env, r#"(path-ext "a/b.tar.gz")"#), string("gz"));
+        assert_eq!(run(&env, r#"(path-ext "Makefile")"#), Value::NIL);
+        assert!(matches!(
+            run(&env, r#"(expand-path "~")"#),
+            Value::String(path) if path.starts_with('/')
+        ));
+    }
+
     #[test]
     fn lisp_mcp_helpers_add_servers() {
         let shell_env = Environment::new();
@@ -11,12 +11,12 @@ pub fn require_arg<'a>(
     args: &'a [Value],
     index: usize,
 ) -> Result<&'a Value, RuntimeError> {
-    args.get(index).ok_or_else(|| RuntimeError {
-        msg: format!(
+    args.get(index).ok_or_else(|| {
+        RuntimeError::new(format!(
             "\"{}\" requires an argument {}",
             func_or_form_name,
             index + 1
-        ),
+        ))
     })
 }
 
@@ -33,14 +33,14 @@ where
 {
     require_arg(func_or_form_name, args, index)?
         .try_into()
-        .map_err(|_| RuntimeError {
-            msg: format!(
+        .map_err(|_| {
+            RuntimeError::new(format!(
                 "\"{}\" requires argument {} to be a {}; got {}",
                 func_or_form_name,
                 index + 1,
                 T::get_name(),
                 args.get(index).unwrap_or(&Value::NIL)
-            ),
+            ))
         })
 }
 
@@ -123,15 +123,15 @@ pub fn list_of_strings(name: &str, value: &Value) -> Result<Vec<String>, Runtime
             .into_iter()
             .map(|item| match item {
                 Value::String(s) => Ok(s),
-                other => Err(RuntimeError {
-                    msg: format!("\"{name}\" expects a list of strings; got element {other}"),
-                }),
+                other => Err(RuntimeError::new(format!(
+                    "\"{name}\" expects a list of strings; got element {other}"
+                ))),
             })
             .collect(),
         Value::False => Ok(Vec::new()),
-        other => Err(RuntimeError {
-            msg: format!("\"{name}\" expects a list of strings or NIL; got {other}"),
-        }),
+        other => Err(RuntimeError::new(format!(
+            "\"{name}\" expects a list of strings or NIL; got {other}"
+        ))),
     }
 }
 
@@ -147,52 +147,44 @@ pub fn list_of_pairs(name: &str, value: &Value) -> Result<HashMap<String, String
                         let key = match iter.next() {
                             Some(Value::String(s)) => s,
                             Some(other) => {
-                                return Err(RuntimeError {
-                                    msg: format!(
-                                        "\"{name}\" expects env entries as (key value); got key {other}"
-                                    ),
-                                });
+                                return Err(RuntimeError::new(format!(
+                                    "\"{name}\" expects env entries as (key value); got key {other}"
+                                )));
                             }
                             None => {
-                                return Err(RuntimeError {
-                                    msg: format!(
-                                        "\"{name}\" expects env entries with two elements"
-                                    ),
-                                });
+                                return Err(RuntimeError::new(format!(
+                                    "\"{name}\" expects env entries with two elements"
+                                )));
                             }
                         };
                         let value = match iter.next() {
                             Some(Value::String(s)) => s,
                             Some(other) => {
-                                return Err(RuntimeError {
-                                    msg: format!(
-                                        "\"{name}\" expects env entries as (key value); got value {other}"
-                                    ),
-                                });
+                                return Err(RuntimeError::new(format!(
+                                    "\"{name}\" expects env entries as (key value); got value {other}"
+                                )));
                             }
                             None => {
-                                return Err(RuntimeError {
-                                    msg: format!(
-                                        "\"{name}\" expects env entries with two elements"
-                                    ),
-                                });
+                                return Err(RuntimeError::new(format!(
+                                    "\"{name}\" expects env entries with two elements"
+                                )));
                             }
                         };
                         map.insert(key, value);
                     }
                     other => {
-                        return Err(RuntimeError {
-                            msg: format!("\"{name}\" expects env entries as lists; got {other}"),
-                        });
+                        return Err(RuntimeError::new(format!(
+                            "\"{name}\" expects env entries as lists; got {other}"
+                        )));
                     }
                 }
             }
             Ok(map)
         }
         Value::False => Ok(HashMap::new()),
-        other => Err(RuntimeError {
-            msg: format!("\"{name}\" expects a list of key/value pairs or NIL; got {other}"),
-        }),
+        other => Err(RuntimeError::new(format!(
+            "\"{name}\" expects a list of key/value pairs or NIL; got {other}"
+        ))),
     }
 }
 
@@ -201,9 +193,9 @@ pub fn optional_string(name: &str, value: &Value) -> Result<Option<String>, Runt
         Value::List(list) if *list == List::NIL => Ok(None),
         Value::False => Ok(None),
         Value::String(s) => Ok(Some(s.clone())),
-        other => Err(RuntimeError {
-            msg: format!("\"{name}\" expects a string or NIL; got {other}"),
-        }),
+        other => Err(RuntimeError::new(format!(
+            "\"{name}\" expects a string or NIL; got {other}"
+        ))),
     }
 }
 
@@ -212,8 +204,8 @@ pub fn optional_bool(name: &str, value: &Value) -> Result<Option<bool>, RuntimeE
         Value::List(list) if *list == List::NIL => Ok(None),
         Value::False => Ok(Some(false)),
         Value::True => Ok(Some(true)),
-        other => Err(RuntimeError {
-            msg: format!("\"{name}\" expects a boolean or NIL; got {other}"),
-        }),
+        other => Err(RuntimeError::new(format!(
+            "\"{name}\" expects a boolean or NIL; got {other}"
+        ))),
     }
 }
@@ -0,0 +1,519 @@
+//! Integer arithmetic for `$(( ))`, `(( ))` and `let`.
+//!
+//! Expressions work on 64-bit signed integers with the C operators and precedence,
+//! from lowest to highest:
+//!
+//! ```text
+//! ,                       sequence
+//! = += -= *= /= %= <<= >>= &= ^= |=
+//! ?:                      conditional
+//! ||  &&                  logical, short-circuit
+//! |  ^  &                 bitwise
+//! == !=  < <= > >=        comparison, 1 or 0
+//! << >>  + -  * / %       shift and arithmetic
+//! **                      power, right associative
+//! ! ~ + - ++x --x x++ x--
+//! ```
+//!
+//! Numbers can be written as `42`, `0x2a`, `052` or `base#digits` (`2#101010`). A variable
+//! name stands for its value; unset and empty variables count as 0 and a value that is not
+//! a number is evaluated as an expression itself.
+
+use crate::environment::Environment;
+use anyhow::{Result, anyhow, bail};
+
+/// Access to shell variables during evaluation.
+pub trait ArithVars {
+    fn get(&self, name: &str) -> Option<String>;
+    fn set(&mut self, name: &str, value: i64);
+}
+
+impl ArithVars for Environment {
+    fn get(&self, name: &str) -> Option<String> {
+        self.get_var(&format!("${name}"))
+    }
+
+    fn set(&mut self, name: &str, value: i64) {
+        self.variables.insert(name.to_string(), value.to_string());
+    }
+}
+
+/// How deeply variable values may refer to further expressions.
+const MAX_DEPTH: usize = 32;
+
+/// Evaluates `expr`, assigning variables through `vars`.
+///
+/// An empty expression evaluates to 0.
+pub fn evaluate(expr: &str, vars: &mut dyn ArithVars) -> Result<i64> {
+    evaluate_at(expr, vars, 0)
+}
+
+fn evaluate_at(expr: &str, vars: &mut dyn ArithVars, depth: usize) -> Result<i64> {
+    if depth > MAX_DEPTH {
+        bail!("{}: expression recursion level exceeded", expr.trim());
+    }
+    let tokens = tokenize(expr)?;
+    if tokens.is_empty() {
+        return Ok(0);
+    }
+    let mut parser = Parser {
+        tokens: &tokens,
+        pos: 0,
+    };
+    let ast = parser.comma()?;
+    if let Some(token) = parser.peek() {
+        bail!(
+            "{}: syntax error in expression (error token is \"{}\")",
+            expr.trim(),
+            token
+        );
+    }
+    Evaluator { vars, depth }.eval(&ast)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Num(i64),
+    Ident(String),
+    Op(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Num(n) => write!(f, "{n}"),
+            Token::Ident(name) => f.write_str(name),
+            Token::Op(op) => f.write_str(op),
+        }
+    }
+}
+
+/// Operators, longest first so that `<<=` wins over `<<` and `<`.
+const OPERATORS: &[&str] = &[
+    "<<=", ">>=", "**", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "+=", "-=",
+    "*=", "/=", "%=", "&=", "^=", "|=", "+", "-", "*", "/", "%", "<", ">", "&", "|", "^", "!", "~",
+    "?", ":", "=", ",", "(", ")",
+];
+
+fn tokenize(expr: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = expr;
+    loop {
+        rest = rest.trim_start();
+        let Some(c) = rest.chars().next() else {
+            break;
+        };
+        if c.is_ascii_digit() {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '#' || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Num(parse_number(&rest[..len])?));
+            rest = &rest[len..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..len].to_string()));
+            rest = &rest[len..];
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else {
+            bail!(
+                "{}: syntax error: invalid arithmetic operator (error token is \"{}\")",
+                expr.trim(),
+                rest
+            );
+        }
+    }
+    Ok(tokens)
+}
+
+/// Parses `42`, `0x2a`, `052` and `base#digits`.
+fn parse_number(text: &str) -> Result<i64> {
+    let invalid = || anyhow!("{}: value too great for base", text);
+    let (base, digits) = if let Some((base, digits)) = text.split_once('#') {
+        let base: u32 = base.parse().map_err(|_| invalid())?;
+        if !(2..=36).contains(&base) {
+            bail!("{}: invalid arithmetic base", text);
+        }
+        (base, digits)
+    } else if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        (16, hex)
+    } else if text.len() > 1 && text.starts_with('0') {
+        (8, &text[1..])
+    } else {
+        (10, text)
+    };
+    if digits.is_empty() {
+        return Err(invalid());
+    }
+    i64::from_str_radix(digits, base).map_err(|_| invalid())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Num(i64),
+    Var(String),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    /// `name op= value`; `op` is `=` for a plain assignment.
+    Assign(String, &'static str, Box<Expr>),
+    /// `++x`/`--x` (prefix) and `x++`/`x--`.
+    Step {
+        name: String,
+        delta: i64,
+        prefix: bool,
+    },
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+/// Binary operators by precedence level, lowest first.
+const BINARY_LEVELS: &[&[&str]] = &[
+    &["||"],
+    &["&&"],
+    &["|"],
+    &["^"],
+    &["&"],
+    &["==", "!="],
+    &["<", "<=", ">", ">="],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+const ASSIGN_OPS: &[&str] = &[
+    "=", "+=", "-=", "*=", "/=", "%=", "<<=", ">>=", "&=", "^=", "|=",
+];
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_op(&self) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<()> {
+        if self.peek_op() == Some(op) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn unexpected(&self) -> anyhow::Error {
+        match self.peek() {
+            Some(token) => anyhow!("syntax error: operand expected (error token is \"{token}\")"),
+            None => anyhow!("syntax error: operand expected"),
+        }
+    }
+
+    fn comma(&mut self) -> Result<Expr> {
+        let mut expr = self.assignment()?;
+        while self.peek_op() == Some(",") {
+            self.pos += 1;
+            let next = self.assignment()?;
+            expr = Expr::Binary(",", Box::new(expr), Box::new(next));
+        }
+        Ok(expr)
+    }
+
+    fn assignment(&mut self) -> Result<Expr> {
+        if let Some(Token::Ident(name)) = self.peek()
+            && let Some(Token::Op(op)) = self.tokens.get(self.pos + 1)
+            && ASSIGN_OPS.contains(op)
+        {
+            let name = name.clone();
+            self.pos += 2;
+            let value = self.assignment()?;
+            return Ok(Expr::Assign(name, op, Box::new(value)));
+        }
+        self.conditional()
+    }
+
+    fn conditional(&mut self) -> Result<Expr> {
+        let condition = self.binary(0)?;
+        if self.peek_op() != Some("?") {
+            return Ok(condition);
+        }
+        self.pos += 1;
+        let then = self.assignment()?;
+        self.expect(":")?;
+        let otherwise = self.assignment()?;
+        Ok(Expr::Conditional(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr> {
+        let Some(ops) = BINARY_LEVELS.get(level) else {
+            return self.power();
+        };
+        let mut expr = self.binary(level + 1)?;
+        while let Some(op) = self.peek_op().filter(|op| ops.contains(op)) {
+            self.pos += 1;
+            let rhs = self.binary(level + 1)?;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(rhs));
+        }
+        Ok(expr)
+    }
+
+    fn power(&mut self) -> Result<Expr> {
+        let base = self.unary()?;
+        if self.peek_op() == Some("**") {
+            self.pos += 1;
+            let exponent = self.power()?;
+            return Ok(Expr::Binary("**", Box::new(base), Box::new(exponent)));
+        }
+        Ok(base)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        match self.peek_op() {
+            Some(op @ ("!" | "~" | "+" | "-")) => {
+                self.pos += 1;
+                Ok(Expr::Unary(op, Box::new(self.unary()?)))
+            }
+            Some(op @ ("++" | "--")) => {
+                self.pos += 1;
+                match self.peek() {
+                    Some(Token::Ident(name)) => {
+                        let name = name.clone();
+                        self.pos += 1;
+                        Ok(Expr::Step {
+                            name,
+                            delta: if op == "++" { 1 } else { -1 },
+                            prefix: true,
+                        })
+                    }
+                    _ => Err(self.unexpected()),
+                }
+            }
+            _ => self.postfix(),
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Expr> {
+        let token = self.peek().cloned().ok_or_else(|| self.unexpected())?;
+        self.pos += 1;
+        match token {
+            Token::Num(n) => Ok(Expr::Num(n)),
+            Token::Ident(name) => match self.peek_op() {
+                Some(op @ ("++" | "--")) => {
+                    self.pos += 1;
+                    Ok(Expr::Step {
+                        name,
+                        delta: if op == "++" { 1 } else { -1 },
+                        prefix: false,
+                    })
+                }
+                _ => Ok(Expr::Var(name)),
+            },
+            Token::Op("(") => {
+                let expr = self.comma()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Op(_) => {
+                self.pos -= 1;
+                Err(self.unexpected())
+            }
+        }
+    }
+}
+
+struct Evaluator<'a> {
+    vars: &'a mut dyn ArithVars,
+    depth: usize,
+}
+
+impl Evaluator<'_> {
+    fn eval(&mut self, expr: &Expr) -> Result<i64> {
+        match expr {
+            Expr::Num(n) => Ok(*n),
+            Expr::Var(name) => self.variable(name),
+            Expr::Unary(op, operand) => {
+                let value = self.eval(operand)?;
+                Ok(match *op {
+                    "!" => (value == 0) as i64,
+                    "~" => !value,
+                    "-" => value.wrapping_neg(),
+                    _ => value,
+                })
+            }
+            Expr::Binary("&&", lhs, rhs) => {
+                Ok((self.eval(lhs)? != 0 && self.eval(rhs)? != 0) as i64)
+            }
+            Expr::Binary("||", lhs, rhs) => {
+                Ok((self.eval(lhs)? != 0 || self.eval(rhs)? != 0) as i64)
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = self.eval(lhs)?;
+                let rhs = self.eval(rhs)?;
+                apply(op, lhs, rhs)
+            }
+            Expr::Assign(name, op, value) => {
+                let value = self.eval(value)?;
+                let value = match op.strip_suffix('=').filter(|op| !op.is_empty()) {
+                    Some(op) => apply(op, self.variable(name)?, value)?,
+                    None => value,
+                };
+                self.vars.set(name, value);
+                Ok(value)
+            }
+            Expr::Step {
+                name,
+                delta,
+                prefix,
+            } => {
+                let old = self.variable(name)?;
+                let new = old.wrapping_add(*delta);
+                self.vars.set(name, new);
+                Ok(if *prefix { new } else { old })
+            }
+            Expr::Conditional(condition, then, otherwise) => {
+                if self.eval(condition)? != 0 {
+                    self.eval(then)
+                } else {
+                    self.eval(otherwise)
+                }
+            }
+        }
+    }
+
+    fn variable(&mut self, name: &str) -> Result<i64> {
+        let value = self.vars.get(name).unwrap_or_default();
+        let value = value.trim();
+        if value.is_empty() {
+            return Ok(0);
+        }
+        match parse_number(value) {
+            Ok(n) => Ok(n),
+            Err(_) => evaluate_at(value, self.vars, self.depth + 1),
+        }
+    }
+}
+
+fn apply(op: &str, lhs: i64, rhs: i64) -> Result<i64> {
+    Ok(match op {
+        "," => rhs,
+        "|" => lhs | rhs,
+        "^" => lhs ^ rhs,
+        "&" => lhs & rhs,
+        "==" => (lhs == rhs) as i64,
+        "!=" => (lhs != rhs) as i64,
+        "<" => (lhs < rhs) as i64,
+        "<=" => (lhs <= rhs) as i64,
+        ">" => (lhs > rhs) as i64,
+        ">=" => (lhs >= rhs) as i64,
+        "<<" => lhs.wrapping_shl(rhs as u32),
+        ">>" => lhs.wrapping_shr(rhs as u32),
+        "+" => lhs.wrapping_add(rhs),
+        "-" => lhs.wrapping_sub(rhs),
+        "*" => lhs.wrapping_mul(rhs),
+        "/" | "%" if rhs == 0 => bail!("division by 0"),
+        "/" => lhs.wrapping_div(rhs),
+        "%" => lhs.wrapping_rem(rhs),
+        "**" if rhs < 0 => bail!("exponent less than 0"),
+        "**" => lhs.wrapping_pow(rhs.min(u32::MAX as i64) as u32),
+        _ => bail!("{}: unknown operator", op),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    impl ArithVars for HashMap<String, String> {
+        fn get(&self, name: &str) -> Option<String> {
+            HashMap::get(self, name).cloned()
+        }
+
+        fn set(&mut self, name: &str, value: i64) {
+            self.insert(name.to_string(), value.to_string());
+        }
+    }
+
+    fn eval(expr: &str) -> i64 {
+        evaluate(expr, &mut HashMap::new()).unwrap()
+    }
+
+    #[test]
+    fn test_precedence_and_associativity() {
+        assert_eq!(eval("1 + 2 * 3"), 7);
+        assert_eq!(eval("(1 + 2) * 3"), 9);
+        assert_eq!(eval("2 ** 3 ** 2"), 512);
+        assert_eq!(eval("-2 ** 2"), 4);
+        assert_eq!(eval("10 - 4 - 3"), 3);
+        assert_eq!(eval("7 / 2 + 7 % 2"), 4);
+        assert_eq!(eval("1 << 4 | 3 & 1 ^ 2"), 19);
+        assert_eq!(eval("3 > 2 && 2 >= 2 || 0"), 1);
+        assert_eq!(eval("!5 + ~0"), -1);
+        assert_eq!(eval("1 ? 2 : 3"), 2);
+        assert_eq!(eval("0 ? 2 : 0 ? 3 : 4"), 4);
+        assert_eq!(eval(""), 0);
+    }
+
+    #[test]
+    fn test_number_formats() {
+        assert_eq!(eval("0x1F + 010 + 2#101"), 31 + 8 + 5);
+        assert_eq!(eval("36#z"), 35);
+        assert!(evaluate("08", &mut HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_variables_and_assignment() {
+        let mut vars = HashMap::new();
+        vars.insert("x".to_string(), "5".to_string());
+        vars.insert("expr".to_string(), "x * 2".to_string());
+        assert_eq!(evaluate("x + unset", &mut vars).unwrap(), 5);
+        assert_eq!(evaluate("expr + 1", &mut vars).unwrap(), 11);
+        assert_eq!(evaluate("i = 1, i += 2, i <<= 1", &mut vars).unwrap(), 6);
+        assert_eq!(evaluate("i++ + ++i", &mut vars).unwrap(), 14);
+        assert_eq!(vars["i"], "8");
+        assert_eq!(evaluate("x-- , --x", &mut vars).unwrap(), 3);
+        assert_eq!(evaluate("y = z = 4", &mut vars).unwrap(), 4);
+        assert_eq!(vars["y"], "4");
+    }
+
+    #[test]
+    fn test_short_circuit_skips_assignments() {
+        let mut vars = HashMap::new();
+        assert_eq!(evaluate("0 && (a = 1)", &mut vars).unwrap(), 0);
+        assert_eq!(evaluate("1 || (b = 1)", &mut vars).unwrap(), 1);
+        assert_eq!(evaluate("1 ? (c = 1) : (d = 1)", &mut vars).unwrap(), 1);
+        assert!(!vars.contains_key("a"));
+        assert!(!vars.contains_key("b"));
+        assert!(!vars.contains_key("d"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut vars = HashMap::new();
+        assert_eq!(
+            evaluate("1 / 0", &mut vars).unwrap_err().to_string(),
+            "division by 0"
+        );
+        assert!(evaluate("2 ** -1", &mut vars).is_err());
+        assert!(evaluate("1 +", &mut vars).is_err());
+        assert!(evaluate("(1 + 2", &mut vars).is_err());
+        assert!(evaluate("1 2", &mut vars).is_err());
+        assert!(evaluate("1 $ 2", &mut vars).is_err());
+        vars.insert("loop".to_string(), "loop".to_string());
+        assert!(evaluate("loop", &mut vars).is_err());
+    }
+}
@@ -6,7 +6,7 @@ use tracing::debug;
 
 pub fn get_string(pair: Pair<Rule>) -> Option<String> {
     match pair.as_rule() {
-        Rule::s_quoted | Rule::d_quoted => {
+        Rule::s_quoted => {
             let res = if let Some(next) = pair.into_inner().next() {
                 next.as_str().to_string()
             } else {
@@ -14,6 +14,28 @@ pub fn get_string(pair: Pair<Rule>) -> Option<String> {
             };
             Some(res)
         }
+        Rule::d_quoted => {
+            let Some(next) = pair.into_inner().next() else {
+                return Some("".to_string());
+            };
+            // Inside double quotes a backslash only escapes `$`, `` ` ``, `"`, `\` and newline
+            let s = next.as_str();
+            let mut res = String::with_capacity(s.len());
+            let mut chars = s.chars().peekable();
+            while let Some(c) = chars.next() {
+                match (c, chars.peek()) {
+                    ('\\', Some('\n')) => {
+                        chars.next();
+                    }
+                    ('\\', Some(&next @ ('$' | '`' | '"' | '\\'))) => {
+                        chars.next();
+                        res.push(next);
+                    }
+                    _ => res.push(c),
+                }
+            }
+            Some(res)
+        }
         Rule::span => {
             if let Some(inner) = pair.into_inner().next() {
                 get_string(inner)
@@ -297,7 +319,9 @@ fn get_span(pair: Pair<Rule>, pos: usize) -> Option<(Span, bool)> {
         | Rule::proc_subst_direction
         | Rule::stdout_redirect_direction
         | Rule::stderr_redirect_direction
-        | Rule::stdouterr_redirect_direction => {
+        | Rule::stdouterr_redirect_direction
+        | Rule::fd_redirect_direction
+        | Rule::dup_redirect_direction => {
             let pair_span = pair.as_span();
             if pair_span.start() < pos && pos <= pair_span.end() {
                 return Some((pair_span, true));
@@ -305,7 +329,7 @@ fn get_span(pair: Pair<Rule>, pos: usize) -> Option<(Span, bool)> {
                 return Some((pair_span, false));
             }
         }
-        Rule::proc_subst_direction_in => {
+        Rule::proc_subst_direction_in | Rule::proc_subst_direction_out => {
             // skip
         }
 
@@ -1,16 +1,35 @@
+use super::heredoc::strip_heredoc_bodies;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
 /// Checks if the input string is incomplete and more input is expected.
 /// This happens if:
 /// 1. There are unclosed quotes (' or ").
 /// 2. There are unclosed delimiters ((, [, {).
 /// 3. The line ends with a backslash (\).
 /// 4. The line ends with an operator that expects more input (|, &&, ||).
+/// 5. A compound command (if, case, for, select, while, until) has not been closed yet.
+/// 6. A here-document has not reached its delimiter line yet.
 pub fn is_incomplete_input(input: &str) -> bool {
+    // Here-document bodies are free text; only the command lines around them count
+    let (input, open_heredoc) = strip_heredoc_bodies(input);
+    if open_heredoc {
+        return true;
+    }
+    let input = input.as_str();
     let chars = input.chars().peekable();
     let mut quote_char = None;
     let mut in_backslash = false;
+    let mut in_comment = false;
+    let mut prev = None;
     let mut braces = Vec::new();
 
     for c in chars {
+        let prev_char = prev.replace(c);
+        if in_comment {
+            in_comment = c != '\n';
+            continue;
+        }
         if in_backslash {
             in_backslash = false;
             continue;
@@ -25,6 +44,9 @@ pub fn is_incomplete_input(input: &str) -> bool {
         } else {
             match c {
                 '\\' => in_backslash = true,
+                '#' if prev_char.is_none_or(|p| p.is_whitespace() || ";&|()".contains(p)) => {
+                    in_comment = true;
+                }
                 '\'' | '"' => quote_char = Some(c),
                 '(' | '[' | '{' => braces.push(c),
                 ')' => {
@@ -83,6 +105,19 @@ pub fn is_incomplete_input(input: &str) -> bool {
     // Operators | && || usually just fail in strict parse, checking them for "continuation" is a nice to have.
     // Let's implement trailing operator check carefully.
 
+    // 5. Unterminated compound commands (if/fi, case/esac, for/while/until ... done)
+    let mut depth = 0_i32;
+    for keyword in command_position_keywords(input) {
+        match keyword.as_str() {
+            "if" | "case" | "for" | "select" | "while" | "until" => depth += 1,
+            "fi" | "esac" | "done" => depth -= 1,
+            _ => {}
+        }
+    }
+    if depth > 0 {
+        return true;
+    }
+
     let trimmed = input.trim_end();
     if trimmed.ends_with('|') || trimmed.ends_with("&&") || trimmed.ends_with("||") {
         // Need to verify these are not inside comments or strings.
@@ -98,6 +133,97 @@ pub fn is_incomplete_input(input: &str) -> bool {
     false
 }
 
+/// Reserved words of the shell grammar.
+pub const RESERVED_WORDS: &[&str] = &[
+    "if", "then", "elif", "else", "fi", "while", "until", "for", "select", "do", "done", "case",
+    "esac", "function", "coproc", "{", "}", "!",
+];
+
+/// Returns true if the input uses a reserved word in command position,
+/// meaning it has to be evaluated as a program with compound commands.
+pub fn has_compound_keyword(input: &str) -> bool {
+    !command_position_keywords(input).is_empty()
+}
+
+/// Returns true if the input defines a shell function with `name() { ... }`.
+pub fn has_function_definition(input: &str) -> bool {
+    static FUNCTION_DEF: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?m)(?:^|[;&|{}]|\bthen|\bdo|\belse)\s*[A-Za-z_][A-Za-z0-9_-]*\s*\(\s*\)")
+            .expect("valid function definition regex")
+    });
+    FUNCTION_DEF.is_match(input)
+}
+
+/// Collects the reserved words that appear in command position.
+/// Quoted text and comments are skipped.
+pub fn command_position_keywords(input: &str) -> Vec<String> {
+    let mut keywords = Vec::new();
+    let mut word = String::new();
+    let mut command_pos = true;
+    let mut quote_char: Option<char> = None;
+    let mut in_backslash = false;
+    let mut in_comment = false;
+
+    // Closes the current word and decides whether the next word is in command position.
+    let mut finish_word = |word: &mut String, command_pos: &mut bool| {
+        if word.is_empty() {
+            return;
+        }
+        if *command_pos && RESERVED_WORDS.contains(&word.as_str()) {
+            keywords.push(word.clone());
+            // `for NAME`, `select NAME`, `case WORD` and `function NAME` are followed by a
+            // non-command word
+            *command_pos = !matches!(word.as_str(), "for" | "select" | "case" | "function");
+        } else {
+            *command_pos = false;
+        }
+        word.clear();
+    };
+
+    for c in input.chars() {
+        if in_comment {
+            if c == '\n' {
+                in_comment = false;
+                command_pos = true;
+            }
+            continue;
+        }
+        if in_backslash {
+            in_backslash = false;
+            word.push(c);
+            continue;
+        }
+        if let Some(q) = quote_char {
+            if c == '\\' && q == '"' {
+                in_backslash = true;
+            } else if c == q {
+                quote_char = None;
+            }
+            word.push(c);
+            continue;
+        }
+        match c {
+            '\\' => {
+                in_backslash = true;
+                word.push(c);
+            }
+            '\'' | '"' => {
+                quote_char = Some(c);
+                word.push(c);
+            }
+            '#' if word.is_empty() => in_comment = true,
+            ' ' | '\t' | '\r' => finish_word(&mut word, &mut command_pos),
+            ';' | '&' | '|' | '\n' | '(' | ')' => {
+                finish_word(&mut word, &mut command_pos);
+                command_pos = true;
+            }
+            _ => word.push(c),
+        }
+    }
+    finish_word(&mut word, &mut command_pos);
+    keywords
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -130,6 +256,24 @@ mod tests {
         assert!(!is_incomplete_input("hello \\\\"));
     }
 
+    #[test]
+    fn test_comments() {
+        assert!(!is_incomplete_input("echo ok # don't"));
+        assert!(!is_incomplete_input("# it's (fine"));
+        assert!(is_incomplete_input("echo 'a # b"));
+        assert!(is_incomplete_input("echo a#'b"));
+        assert!(!is_incomplete_input("echo $# ${#x}"));
+    }
+
+    #[test]
+    fn test_heredocs() {
+        assert!(is_incomplete_input("cat <<EOF"));
+        assert!(is_incomplete_input("cat <<EOF\nit's open"));
+        assert!(!is_incomplete_input("cat <<EOF\nit's done\nEOF"));
+        assert!(is_incomplete_input("cat <<-END\n\tbody\n\tEND |"));
+        assert!(!is_incomplete_input("cat <<< 'word'"));
+    }
+
     #[test]
     fn test_operators() {
         assert!(is_incomplete_input("hello |"));
@@ -137,4 +281,43 @@ mod tests {
         assert!(is_incomplete_input("hello ||"));
         assert!(!is_incomplete_input("hello | world"));
     }
+
+    #[test]
+    fn test_compound_commands() {
+        assert!(is_incomplete_input("if true; then"));
+        assert!(is_incomplete_input("for f in a b\ndo\n  echo $f"));
+        assert!(is_incomplete_input(
+            "while true; do if false; then echo; fi"
+        ));
+        assert!(is_incomplete_input("case $x in"));
+        assert!(is_incomplete_input("select x in a b; do"));
+        assert!(!is_incomplete_input("if true; then echo ok; fi"));
+        assert!(!is_incomplete_input("for f in a b; do echo $f; done"));
+        assert!(!is_incomplete_input("case a in a) echo a;; esac"));
+        assert!(!is_incomplete_input("echo if for while"));
+        assert!(!is_incomplete_input("echo 'if' # for"));
+    }
+
+    #[test]
+    fn test_command_position_keywords() {
+        assert_eq!(
+            command_position_keywords("if true; then echo fi; fi"),
+            vec!["if", "then", "fi"]
+        );
+        assert_eq!(
+            command_position_keywords("for do in a; do :; done"),
+            vec!["for", "do", "done"]
+        );
+        assert!(!has_compound_keyword("echo done"));
+        assert!(has_compound_keyword("function greet { echo hi; }"));
+        assert!(has_compound_keyword("ls && { echo a; }"));
+    }
+
+    #[test]
+    fn test_has_function_definition() {
+        assert!(has_function_definition("greet() { echo hi; }"));
+        assert!(has_function_definition("true; greet () {\n echo hi\n}"));
+        assert!(!has_function_definition("echo greet"));
+        assert!(!has_function_definition("echo $(date)"));
+    }
 }
@@ -0,0 +1,589 @@
+//! Compound commands and multi-line programs.
+//!
+//! Input that uses `if`, `while`, `until`, `for`, `select`, `case` or `{ }` (or that spans several
+//! lines) is parsed with the `program` rule into a small AST. Plain commands are kept as
+//! source text so that expansion and command lookup happen when they actually run.
+
+use super::check::{has_compound_keyword, has_function_definition};
+use super::{Rule, ShellParser, get_string};
+use crate::process::ListOp;
+use anyhow::{Result, anyhow};
+use pest::Parser;
+use pest::iterators::Pair;
+
+/// A sequence of commands separated by `;`, newlines, `&&` or `||`.
+pub type CommandList = Vec<ListItem>;
+
+/// Name of a coprocess started without one, as in `coproc bc -q`.
+pub const DEFAULT_COPROC_NAME: &str = "COPROC";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListItem {
+    pub command: Command,
+    /// Operator that joins this item with the next one, like `Job::list_op`.
+    pub list_op: ListOp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// A plain command line (pipelines included) evaluated through the job machinery.
+    Simple(String),
+    If {
+        /// `(condition, body)` pairs for `if` and each `elif`.
+        branches: Vec<(CommandList, CommandList)>,
+        else_body: Option<CommandList>,
+    },
+    While {
+        condition: CommandList,
+        body: CommandList,
+        /// `until` loops run while the condition fails.
+        until: bool,
+    },
+    For {
+        name: String,
+        /// Raw word list after `in`; `None` iterates over the positional parameters.
+        words: Option<String>,
+        body: CommandList,
+    },
+    /// `select NAME in WORDS; do ...; done` runs the body with the word picked from a menu.
+    Select {
+        name: String,
+        /// Raw word list after `in`; `None` offers the positional parameters.
+        words: Option<String>,
+        body: CommandList,
+    },
+    Case {
+        word: String,
+        items: Vec<CaseItem>,
+    },
+    Group(CommandList),
+    /// `coproc NAME { ... }` runs the body in the background, connected to the shell by
+    /// two pipes.
+    Coproc {
+        name: String,
+        body: Box<Command>,
+    },
+    /// `! pipeline` succeeds when the pipeline fails and the other way round.
+    Not(Box<Command>),
+    /// `name() { ... }` stores the body under `name` when evaluated.
+    FunctionDef {
+        name: String,
+        body: Box<Command>,
+    },
+    /// A compound command with redirections, or a pipeline with compound commands among
+    /// its stages.
+    Pipeline {
+        text: String,
+        stages: Vec<Stage>,
+    },
+}
+
+/// A stage of a [`Command::Pipeline`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stage {
+    /// A plain command, kept as source text like `Command::Simple`.
+    Simple(String),
+    Compound {
+        text: String,
+        body: Box<Command>,
+        /// Source text of the redirections, expanded when the stage runs.
+        redirects: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseItem {
+    pub patterns: Vec<CasePattern>,
+    pub body: CommandList,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CasePattern {
+    pub text: String,
+    /// Quoted patterns are compared literally.
+    pub quoted: bool,
+}
+
+/// Parses the input as a program if it needs one.
+///
+/// Returns `Ok(None)` for plain one-line input, unless it is a list whose commands use
+/// parameters, so the caller keeps using the regular `commands` path, and an error when a
+/// compound command is malformed.
+pub fn parse_program(input: &str) -> Result<Option<CommandList>> {
+    let multi_line = input.trim().contains('\n');
+    let compound = has_compound_keyword(input) || has_function_definition(input);
+    // A list like `false; echo $?` or `pushd /tmp; ls ~1` expands each command after the
+    // previous one ran
+    let parameters = input.contains('$') || input.contains('~');
+    if !multi_line && !compound && !parameters {
+        return Ok(None);
+    }
+
+    match ShellParser::parse(Rule::program, input) {
+        Ok(mut pairs) => {
+            let list = pairs
+                .next()
+                .and_then(|program| {
+                    program
+                        .into_inner()
+                        .find(|p| p.as_rule() == Rule::compound_list)
+                })
+                .map(build_list)
+                .unwrap_or_default();
+            if !multi_line && !compound && list.len() < 2 {
+                return Ok(None);
+            }
+            Ok(Some(list))
+        }
+        Err(err) if compound => Err(anyhow!("syntax error: {}", err)),
+        Err(_) => Ok(None),
+    }
+}
+
+fn build_list(pair: Pair<Rule>) -> CommandList {
+    let mut list = CommandList::new();
+    for and_or in pair.into_inner() {
+        if and_or.as_rule() != Rule::and_or {
+            continue;
+        }
+        for inner in and_or.into_inner() {
+            match inner.as_rule() {
+                Rule::pipeline_unit => {
+                    let mut parts = inner.into_inner().peekable();
+                    let negated = parts.next_if(|part| part.as_rule() == Rule::bang);
+                    if let Some(command) = parts.next().map(build_command) {
+                        let command = match negated {
+                            Some(_) => Command::Not(Box::new(command)),
+                            None => command,
+                        };
+                        list.push(ListItem {
+                            command,
+                            list_op: ListOp::None,
+                        });
+                    }
+                }
+                Rule::and_or_op => {
+                    if let Some(last) = list.last_mut() {
+                        last.list_op = match inner.into_inner().next().map(|p| p.as_rule()) {
+                            Some(Rule::and_op) => ListOp::And,
+                            Some(Rule::or_op) => ListOp::Or,
+                            _ => ListOp::None,
+                        };
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    list
+}
+
+fn build_command(pair: Pair<Rule>) -> Command {
+    match pair.as_rule() {
+        Rule::compound_command => match pair.into_inner().next() {
+            Some(inner) => build_command(inner),
+            None => Command::Group(CommandList::new()),
+        },
+        Rule::if_clause => {
+            let mut branches = Vec::new();
+            let mut else_body = None;
+            let mut lists = Vec::new();
+            for inner in pair.into_inner() {
+                match inner.as_rule() {
+                    Rule::compound_list => lists.push(build_list(inner)),
+                    Rule::elif_clause => {
+                        let mut parts = inner.into_inner().map(build_list);
+                        let condition = parts.next().unwrap_or_default();
+                        let body = parts.next().unwrap_or_default();
+                        branches.push((condition, body));
+                    }
+                    Rule::else_clause => {
+                        else_body = inner.into_inner().next().map(build_list);
+                    }
+                    _ => {}
+                }
+            }
+            let mut lists = lists.into_iter();
+            let condition = lists.next().unwrap_or_default();
+            let body = lists.next().unwrap_or_default();
+            branches.insert(0, (condition, body));
+            Command::If {
+                branches,
+                else_body,
+            }
+        }
+        Rule::while_clause | Rule::until_clause => {
+            let until = pair.as_rule() == Rule::until_clause;
+            let mut condition = CommandList::new();
+            let mut body = CommandList::new();
+            for inner in pair.into_inner() {
+                match inner.as_rule() {
+                    Rule::compound_list => condition = build_list(inner),
+                    Rule::do_group => body = build_do_group(inner),
+                    _ => {}
+                }
+            }
+            Command::While {
+                condition,
+                body,
+                until,
+            }
+        }
+        Rule::for_clause | Rule::select_clause => {
+            let select = pair.as_rule() == Rule::select_clause;
+            let mut name = String::new();
+            let mut words = None;
+            let mut body = CommandList::new();
+            for inner in pair.into_inner() {
+                match inner.as_rule() {
+                    Rule::for_name => name = inner.as_str().to_string(),
+                    Rule::for_words => words = Some(inner.as_str().trim().to_string()),
+                    Rule::do_group => body = build_do_group(inner),
+                    _ => {}
+                }
+            }
+            if select {
+                Command::Select { name, words, body }
+            } else {
+                Command::For { name, words, body }
+            }
+        }
+        Rule::case_clause => {
+            let mut word = String::new();
+            let mut items = Vec::new();
+            for inner in pair.into_inner() {
+                match inner.as_rule() {
+                    Rule::case_word => word = inner.as_str().to_string(),
+                    Rule::case_item => {
+                        let mut patterns = Vec::new();
+                        let mut body = CommandList::new();
+                        for part in inner.into_inner() {
+                            match part.as_rule() {
+                                Rule::case_pattern => patterns.push(build_case_pattern(part)),
+                                Rule::compound_list => body = build_list(part),
+                                _ => {}
+                            }
+                        }
+                        items.push(CaseItem { patterns, body });
+                    }
+                    _ => {}
+                }
+            }
+            Command::Case { word, items }
+        }
+        Rule::function_def => {
+            let mut name = String::new();
+            let mut body = Command::Group(CommandList::new());
+            for inner in pair.into_inner() {
+                match inner.as_rule() {
+                    Rule::function_name => name = inner.as_str().to_string(),
+                    Rule::compound_command => body = build_command(inner),
+                    _ => {}
+                }
+            }
+            Command::FunctionDef {
+                name,
+                body: Box::new(body),
+            }
+        }
+        Rule::coproc_clause => {
+            let mut name = DEFAULT_COPROC_NAME.to_string();
+            let mut body = Command::Group(CommandList::new());
+            for inner in pair.into_inner() {
+                match inner.as_rule() {
+                    Rule::for_name => name = inner.as_str().to_string(),
+                    _ => body = build_command(inner),
+                }
+            }
+            Command::Coproc {
+                name,
+                body: Box::new(body),
+            }
+        }
+        Rule::brace_group => {
+            Command::Group(pair.into_inner().next().map(build_list).unwrap_or_default())
+        }
+        Rule::pipeline => build_pipeline(pair),
+        _ => Command::Simple(pair.as_str().trim().to_string()),
+    }
+}
+
+/// A compound command on its own, without redirections, is kept as it is.
+fn build_pipeline(pair: Pair<Rule>) -> Command {
+    let text = pair.as_str().trim().to_string();
+    let mut stages: Vec<Stage> = pair
+        .into_inner()
+        .filter(|stage| {
+            matches!(
+                stage.as_rule(),
+                Rule::plain_stage | Rule::redirected_compound
+            )
+        })
+        .map(|stage| {
+            if stage.as_rule() == Rule::plain_stage {
+                return Stage::Simple(stage.as_str().trim().to_string());
+            }
+            let text = stage.as_str().trim().to_string();
+            let mut inner = stage.into_inner();
+            let body = inner
+                .next()
+                .map_or_else(|| Command::Group(CommandList::new()), build_command);
+            let redirects: Vec<&str> = inner.map(|redirect| redirect.as_str().trim()).collect();
+            Stage::Compound {
+                text,
+                body: Box::new(body),
+                redirects: redirects.join(" "),
+            }
+        })
+        .collect();
+    if let [Stage::Compound { redirects, .. }] = stages.as_slice()
+        && redirects.is_empty()
+        && let Some(Stage::Compound { body, .. }) = stages.pop()
+    {
+        return *body;
+    }
+    Command::Pipeline { text, stages }
+}
+
+fn build_do_group(pair: Pair<Rule>) -> CommandList {
+    pair.into_inner().next().map(build_list).unwrap_or_default()
+}
+
+fn build_case_pattern(pair: Pair<Rule>) -> CasePattern {
+    let quoted = pair.as_str().starts_with(['\'', '"']);
+    let text = pair
+        .into_inner()
+        .next()
+        .and_then(get_string)
+        .unwrap_or_default();
+    CasePattern { text, quoted }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simple(cmd: &str) -> ListItem {
+        ListItem {
+            command: Command::Simple(cmd.to_string()),
+            list_op: ListOp::None,
+        }
+    }
+
+    #[test]
+    fn test_plain_input_is_not_a_program() {
+        assert_eq!(parse_program("echo hello; ls -la").unwrap(), None);
+        assert_eq!(parse_program("echo if then fi").unwrap(), None);
+        assert_eq!(parse_program("echo $HOME | cat").unwrap(), None);
+    }
+
+    #[test]
+    fn test_list_with_parameters_is_a_program() {
+        let list = parse_program("false; echo $?").unwrap().unwrap();
+        assert_eq!(list, vec![simple("false"), simple("echo $?")]);
+        let list = parse_program("pushd /tmp; echo ~1").unwrap().unwrap();
+        assert_eq!(list, vec![simple("pushd /tmp"), simple("echo ~1")]);
+    }
+
+    #[test]
+    fn test_parse_if() {
+        let list = parse_program(
+            "if test -f x; then echo yes; elif true; then echo maybe; else echo no; fi",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            list,
+            vec![ListItem {
+                command: Command::If {
+                    branches: vec![
+                        (vec![simple("test -f x")], vec![simple("echo yes")]),
+                        (vec![simple("true")], vec![simple("echo maybe")]),
+                    ],
+                    else_body: Some(vec![simple("echo no")]),
+                },
+                list_op: ListOp::None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_multiline_loops() {
+        let input = "for f in a b c\ndo\n  echo $f # comment\ndone\nwhile false; do echo x; done";
+        let list = parse_program(input).unwrap().unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(
+            list[0].command,
+            Command::For {
+                name: "f".to_string(),
+                words: Some("a b c".to_string()),
+                body: vec![simple("echo $f")],
+            }
+        );
+        assert!(matches!(
+            list[1].command,
+            Command::While { until: false, .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_select() {
+        let list =
+            parse_program("select fruit in apple 'green melon'; do echo $fruit; break; done")
+                .unwrap()
+                .unwrap();
+        assert_eq!(
+            list[0].command,
+            Command::Select {
+                name: "fruit".to_string(),
+                words: Some("apple 'green melon'".to_string()),
+                body: vec![simple("echo $fruit"), simple("break")],
+            }
+        );
+        let list = parse_program("select arg\ndo\n  echo $arg\ndone")
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            &list[0].command,
+            Command::Select { words: None, .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_coproc() {
+        let list = parse_program("coproc CALC { bc -q; }").unwrap().unwrap();
+        assert_eq!(
+            list[0].command,
+            Command::Coproc {
+                name: "CALC".to_string(),
+                body: Box::new(Command::Group(vec![simple("bc -q")])),
+            }
+        );
+        let list = parse_program("coproc cat -n").unwrap().unwrap();
+        assert_eq!(
+            list[0].command,
+            Command::Coproc {
+                name: DEFAULT_COPROC_NAME.to_string(),
+                body: Box::new(Command::Simple("cat -n".to_string())),
+            }
+        );
+        let list = parse_program("coproc while read line; do echo $line; done")
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            &list[0].command,
+            Command::Coproc { name, body } if name == DEFAULT_COPROC_NAME
+                && matches!(**body, Command::While { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_case() {
+        let list = parse_program(
+            "case $x in\n  a|b) echo ab;;\n  '*') echo star;;\n  *) echo other\nesac",
+        )
+        .unwrap()
+        .unwrap();
+        let Command::Case { word, items } = &list[0].command else {
+            panic!("expected case");
+        };
+        assert_eq!(word, "$x");
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].patterns.len(), 2);
+        assert!(items[1].patterns[0].quoted);
+        assert_eq!(items[1].patterns[0].text, "*");
+        assert_eq!(items[2].body, vec![simple("echo other")]);
+    }
+
+    #[test]
+    fn test_parse_list_ops() {
+        let list = parse_program("true && { echo a; echo b; } || echo c")
+            .unwrap()
+            .unwrap();
+        assert_eq!(list.len(), 3);
+        assert_eq!(list[0].list_op, ListOp::And);
+        assert_eq!(
+            list[1].command,
+            Command::Group(vec![simple("echo a"), simple("echo b")])
+        );
+        assert_eq!(list[1].list_op, ListOp::Or);
+        assert_eq!(list[2].list_op, ListOp::None);
+    }
+
+    #[test]
+    fn test_parse_negation() {
+        let list = parse_program("if ! grep -q x f; then echo none; fi; ! true")
+            .unwrap()
+            .unwrap();
+        let Command::If { branches, .. } = &list[0].command else {
+            panic!("expected if: {:?}", list[0].command);
+        };
+        assert_eq!(
+            branches[0].0[0].command,
+            Command::Not(Box::new(Command::Simple("grep -q x f".to_string())))
+        );
+        assert_eq!(
+            list[1].command,
+            Command::Not(Box::new(Command::Simple("true".to_string())))
+        );
+        assert_eq!(parse_program("echo !x").unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_function_def() {
+        let list = parse_program("greet() { echo hello $1; }\nfunction bye {\n  echo bye\n}")
+            .unwrap()
+            .unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(
+            list[0].command,
+            Command::FunctionDef {
+                name: "greet".to_string(),
+                body: Box::new(Command::Group(vec![simple("echo hello $1")])),
+            }
+        );
+        assert!(matches!(
+            &list[1].command,
+            Command::FunctionDef { name, .. } if name == "bye"
+        ));
+    }
+
+    #[test]
+    fn test_parse_pipeline_with_compound_stages() {
+        let list = parse_program("cat f | while read l; do echo $l; done < in 2>/dev/null | sort")
+            .unwrap()
+            .unwrap();
+        let Command::Pipeline { text, stages } = &list[0].command else {
+            panic!("expected pipeline");
+        };
+        assert_eq!(
+            text,
+            "cat f | while read l; do echo $l; done < in 2>/dev/null | sort"
+        );
+        assert_eq!(stages.len(), 3);
+        assert_eq!(stages[0], Stage::Simple("cat f".to_string()));
+        assert!(matches!(
+            &stages[1],
+            Stage::Compound { body, redirects, .. }
+                if matches!(**body, Command::While { .. }) && redirects == "< in 2>/dev/null"
+        ));
+        assert_eq!(stages[2], Stage::Simple("sort".to_string()));
+
+        // Without pipes or redirections a compound command is left as it is
+        let list = parse_program("for i in 1 2; do echo $i; done")
+            .unwrap()
+            .unwrap();
+        assert!(matches!(list[0].command, Command::For { .. }));
+        let list = parse_program("{ echo a; } > out").unwrap().unwrap();
+        assert!(matches!(
+            &list[0].command,
+            Command::Pipeline { stages, .. } if stages.len() == 1
+        ));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(parse_program("if true; then echo").is_err());
+        assert!(parse_program("for x in a b; echo $x; done").is_err());
+    }
+}
@@ -47,3 +47,24 @@ fn test_unclosed_bracket() -> Result<()> {
     assert_eq!(expanded, "val[1");
     Ok(())
 }
+
+#[test]
+fn test_test_command_brackets() -> Result<()> {
+    init();
+    let input = "[ -f foo ]";
+    let pair = ShellParser::parse(Rule::simple_command, input)?
+        .next()
+        .unwrap();
+    assert_eq!(pair.as_str(), input);
+    Ok(())
+}
+
+#[test]
+fn test_trailing_comment_is_not_an_argument() -> Result<()> {
+    init();
+    let pair = ShellParser::parse(Rule::simple_command, "echo a#b # note")?
+        .next()
+        .unwrap();
+    assert_eq!(pair.as_str(), "echo a#b");
+    Ok(())
+}
@@ -1,50 +1,17 @@
-use super::{Rule, ShellParser, ast::get_string};
-use crate::environment::Environment;
-use anyhow::{Result, anyhow};
+use super::arithmetic::{self, ArithVars};
+use super::{Rule, ShellParser, ast::get_string, pathname};
+use crate::environment::{Environment, ShellOptions};
+use crate::errors::ShellError;
+use anyhow::{Result, anyhow, bail};
+use dsh_types::output_history::parse_output_var;
 use parking_lot::RwLock;
 use pest::Parser;
 use pest::iterators::Pair;
 use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tracing::debug;
 
-fn find_glob_root(path: &str) -> (String, String) {
-    let mut root = Vec::new();
-    let mut glob = Vec::new();
-    let mut find_glob = false;
-    let path = Path::new(path);
-    if path.is_relative() {
-        return (".".to_string(), path.to_string_lossy().to_string());
-    }
-    for p in path.iter() {
-        let file = p.to_string_lossy();
-        if !find_glob && (file.contains("*") || file.contains("?") || file.contains("[")) {
-            find_glob = true;
-        }
-        if find_glob {
-            glob.push(file.to_string());
-        } else {
-            root.push(file.to_string());
-        }
-    }
-
-    let mut root = root.join(std::path::MAIN_SEPARATOR_STR);
-    let mut glob = glob.join(std::path::MAIN_SEPARATOR_STR);
-    if Path::new(&glob).is_absolute() {
-        glob = glob[1..].to_string();
-    }
-
-    if root.is_empty() {
-        (".".to_string(), glob.to_string())
-    } else {
-        if root.starts_with("//") {
-            root = root[1..].to_string();
-        }
-        (root.to_string(), glob.to_string())
-    }
-}
-
 pub(crate) fn expand_braces(pattern: &str) -> Vec<String> {
     let mut result = Vec::new();
     let mut stack = Vec::new();
@@ -104,7 +71,8 @@ pub(crate) fn expand_braces(pattern: &str) -> Vec<String> {
 pub fn expand_alias_tilde(
     pair: Pair<Rule>,
     alias: &HashMap<String, String>,
-    _current_dir: &PathBuf,
+    current_dir: &PathBuf,
+    options: &ShellOptions,
 ) -> Result<Vec<String>> {
     let mut argv: Vec<String> = vec![];
 
@@ -115,48 +83,20 @@ pub fn expand_alias_tilde(
             let expanded_patterns = expand_braces(&pattern);
 
             for pat in expanded_patterns {
-                // Check if the expanded pattern actually contains glob characters
-                // Note: expand_braces handles brace expansion, so we check for glob chars in the result
-                if pat.contains('*') || pat.contains('?') || pat.contains('[') {
-                    let (root, pattern) = find_glob_root(&pat);
-                    debug!("glob pattern: root:{} {:?} ", root, pattern);
-
-                    let effective_root = if Path::new(&root).is_absolute() {
-                        PathBuf::from(&root)
-                    } else {
-                        _current_dir.join(&root)
-                    };
-
-                    match globmatch::Builder::new(&pattern).build(&effective_root) {
-                        Ok(builder) => {
-                            let paths: Vec<_> = builder.into_iter().flatten().collect();
-                            if paths.is_empty() {
-                                debug!("dsh: no matches for wildcard '{}'", &pattern);
-                                argv.push(pat);
-                            } else {
-                                for path in paths {
-                                    debug!("glob match {}", path.display());
-                                    // Try to make path relative to current_dir for cleaner display
-                                    // if it was a relative pattern
-                                    let display_path = if Path::new(&root).is_relative() {
-                                        path.strip_prefix(_current_dir)
-                                            .unwrap_or(&path)
-                                            .to_path_buf()
-                                    } else {
-                                        path
-                                    };
-                                    argv.push(format!("\"{}\"", display_path.display()));
-                                }
-                            }
+                match pathname::expand(&pat, current_dir, options) {
+                    None => argv.push(pat),
+                    Some(paths) if paths.is_empty() => {
+                        debug!("dsh: no matches for wildcard '{}'", &pat);
+                        if options.failglob {
+                            bail!("no match: {}", pat);
                         }
-                        Err(err) => {
-                            debug!("dsh: failed resolve paths. {}. treating as literal.", err);
+                        if !options.nullglob {
                             argv.push(pat);
                         }
                     }
-                } else {
-                    // No glob chars, just push the brace-expanded string
-                    argv.push(pat);
+                    Some(paths) => {
+                        argv.extend(paths.iter().map(|path| format!("\"{}\"", path)));
+                    }
                 }
             }
         }
@@ -171,12 +111,20 @@ pub fn expand_alias_tilde(
         | Rule::stdouterr_redirect_direction
         | Rule::stdin_redirect_direction
         | Rule::stdin_redirect_direction_in
+        | Rule::herestring_redirect_direction
+        | Rule::fd_redirect_direction
+        | Rule::dup_redirect_direction
+        | Rule::dup_redirect_target
+        | Rule::heredoc
         | Rule::command_subst => {
             argv.push(shellexpand::tilde(pair.as_str()).to_string());
         }
+        Rule::arith_subst | Rule::arith_command => {
+            argv.push(pair.as_str().to_string());
+        }
         Rule::argv0 => {
             for inner_pair in pair.into_inner() {
-                let v = expand_alias_tilde(inner_pair, alias, _current_dir)?;
+                let v = expand_alias_tilde(inner_pair, alias, current_dir, options)?;
                 for (i, arg) in v.iter().enumerate() {
                     if i == 0 {
                         if let Some(val) = alias.get(arg) {
@@ -195,13 +143,13 @@ pub fn expand_alias_tilde(
             debug!("expand pipe_command {}", pair.as_str());
             // Pipe character is added by expand_alias function, so don't add it here
             for inner_pair in pair.into_inner() {
-                let mut v = expand_alias_tilde(inner_pair, alias, _current_dir)?;
+                let mut v = expand_alias_tilde(inner_pair, alias, current_dir, options)?;
                 argv.append(&mut v);
             }
         }
         Rule::redirect => {
             for inner_pair in pair.into_inner() {
-                let mut v = expand_alias_tilde(inner_pair, alias, _current_dir)?;
+                let mut v = expand_alias_tilde(inner_pair, alias, current_dir, options)?;
                 argv.append(&mut v);
             }
         }
@@ -214,16 +162,21 @@ pub fn expand_alias_tilde(
                             if inner_pair.as_rule() == Rule::background_op {
                                 argv.push(inner_pair.as_str().to_string());
                             } else {
-                                let mut v = expand_alias_tilde(inner_pair, alias, _current_dir)?;
+                                let mut v =
+                                    expand_alias_tilde(inner_pair, alias, current_dir, options)?;
                                 argv.append(&mut v);
                             }
                         }
                     }
                     Rule::proc_subst => {
                         debug!("expand proc_subst {}", inner_pair.as_str());
-                        argv.push("<(".to_string());
                         for inner_pair in inner_pair.into_inner() {
-                            let mut v = expand_alias_tilde(inner_pair, alias, _current_dir)?;
+                            if inner_pair.as_rule() == Rule::proc_subst_direction {
+                                argv.push(inner_pair.as_str().to_string());
+                                continue;
+                            }
+                            let mut v =
+                                expand_alias_tilde(inner_pair, alias, current_dir, options)?;
                             argv.append(&mut v);
                         }
                         argv.push(")".to_string());
@@ -236,14 +189,15 @@ pub fn expand_alias_tilde(
                         debug!("expand subshell {}", inner_pair.as_str());
                         argv.push("(".to_string());
                         for inner_pair in inner_pair.into_inner() {
-                            let mut v = expand_alias_tilde(inner_pair, alias, _current_dir)?;
+                            let mut v =
+                                expand_alias_tilde(inner_pair, alias, current_dir, options)?;
                             argv.append(&mut v);
                         }
                         argv.push(")".to_string());
                     }
                     Rule::argv0 => {
                         for inner_pair in inner_pair.into_inner() {
-                            let v = expand_alias_tilde(inner_pair, alias, _current_dir)?;
+                            let v = expand_alias_tilde(inner_pair, alias, current_dir, options)?;
                             for (i, arg) in v.iter().enumerate() {
                                 if i == 0 {
                                     if let Some(val) = alias.get(arg) {
@@ -263,18 +217,20 @@ pub fn expand_alias_tilde(
                             if inner_pair.as_rule() == Rule::pipeline_op {
                                 argv.push(inner_pair.as_str().to_string());
                             } else {
-                                let mut v = expand_alias_tilde(inner_pair, alias, _current_dir)?;
+                                let mut v =
+                                    expand_alias_tilde(inner_pair, alias, current_dir, options)?;
                                 argv.append(&mut v);
                             }
                         }
                     }
-                    Rule::commands
-                    | Rule::command
-                    | Rule::simple_command
-                    | Rule::args
-                    | Rule::span => {
+                    Rule::args => {
+                        let mut v = expand_args(inner_pair, alias, current_dir, options)?;
+                        argv.append(&mut v);
+                    }
+                    Rule::commands | Rule::command | Rule::simple_command | Rule::span => {
                         for inner_pair in inner_pair.into_inner() {
-                            let mut v = expand_alias_tilde(inner_pair, alias, _current_dir)?;
+                            let mut v =
+                                expand_alias_tilde(inner_pair, alias, current_dir, options)?;
                             argv.append(&mut v);
                         }
                     }
@@ -282,15 +238,17 @@ pub fn expand_alias_tilde(
                     | Rule::glob_word
                     | Rule::brace_word
                     | Rule::variable
+                    | Rule::arith_subst
                     | Rule::s_quoted
                     | Rule::d_quoted
                     | Rule::literal_s_quoted
                     | Rule::literal_d_quoted
                     | Rule::proc_subst_direction_in
+                    | Rule::proc_subst_direction_out
                     | Rule::stdout_redirect_direction
                     | Rule::stderr_redirect_direction
                     | Rule::stdouterr_redirect_direction => {
-                        let mut v = expand_alias_tilde(inner_pair, alias, _current_dir)?;
+                        let mut v = expand_alias_tilde(inner_pair, alias, current_dir, options)?;
                         argv.append(&mut v);
                     }
                     _ => {
@@ -307,6 +265,33 @@ pub fn expand_alias_tilde(
     Ok(argv)
 }
 
+/// Expands the words of `args`, keeping spans written without a space between them
+/// (`L$x`, `a"b"c`) together as one word.
+fn expand_args(
+    pair: Pair<Rule>,
+    alias: &HashMap<String, String>,
+    current_dir: &PathBuf,
+    options: &ShellOptions,
+) -> Result<Vec<String>> {
+    let mut argv: Vec<String> = Vec::new();
+    let mut prev_end = None;
+    for inner_pair in pair.into_inner() {
+        let span = inner_pair.as_span();
+        let adjacent = inner_pair.as_rule() == Rule::span && prev_end == Some(span.start());
+        prev_end = (inner_pair.as_rule() == Rule::span).then_some(span.end());
+
+        let mut v = expand_alias_tilde(inner_pair, alias, current_dir, options)?;
+        if adjacent
+            && !v.is_empty()
+            && let Some(last) = argv.last_mut()
+        {
+            last.push_str(&v.remove(0));
+        }
+        argv.append(&mut v);
+    }
+    Ok(argv)
+}
+
 #[allow(dead_code)]
 pub fn expand_alias(input: String, environment: Arc<RwLock<Environment>>) -> Result<String> {
     let (cow, _) = parse_with_expansion(&input, environment)?;
@@ -327,7 +312,8 @@ pub fn parse_with_expansion<'a>(
         || input.contains('{')
         || input.contains('*')
         || input.contains('?')
-        || input.contains('[');
+        || input.contains('[')
+        || ["@(", "+(", "!("].iter().any(|group| input.contains(group));
 
     if !has_meta {
         let env_read = environment.read();
@@ -373,11 +359,17 @@ fn check_expansion_needed(pair: Pair<Rule>, alias: &HashMap<String, String>) ->
             s.contains('*')
                 || s.contains('?')
                 || s.contains('[')
+                || s.contains('(')
                 || s.contains('~')
                 || s.contains('$')
                 || s.contains('{')
         }
-        Rule::word | Rule::variable | Rule::s_quoted | Rule::d_quoted => {
+        Rule::word
+        | Rule::variable
+        | Rule::s_quoted
+        | Rule::d_quoted
+        | Rule::arith_subst
+        | Rule::arith_command => {
             let s = pair.as_str();
             s.contains('~') || s.contains('$')
         }
@@ -435,24 +427,32 @@ pub fn expand_alias_from_pairs(
     Ok(buf.join(" "))
 }
 
-fn expand_var_args(args: Vec<String>, env: &Environment, buf: &mut Vec<String>) {
+fn expand_var_args(
+    args: Vec<String>,
+    expander: &mut ParamExpander,
+    buf: &mut Vec<String>,
+) -> Result<()> {
     for arg in args {
-        if let Some(val) = env.get_var(&arg) {
-            if val.is_empty() {
-                buf.push("\"\"".to_string());
-            } else {
-                let needs_quote = val.contains('\n') || val.contains(' ') || val.contains('\t');
-                if needs_quote {
-                    let escaped = val.replace('"', r#"\""#);
-                    buf.push(format!("\"{}\"", escaped));
-                } else {
-                    buf.push(val.trim().to_string());
-                }
+        if matches!(arg.as_str(), "$@" | "$*" | "${@}" | "${*}" | "\"$@\"") {
+            // Each positional parameter becomes its own word
+            for param in &expander.env.positional_params {
+                buf.push(quote_word(param));
             }
-        } else {
+            continue;
+        }
+        // `~N`, `~+` and `~-` name entries of the directory stack
+        let arg = match expander.env.expand_dir_stack_tilde(&arg) {
+            Some((dir, rest)) => format!("{}{rest}", quote_word(&dir)),
+            None => arg,
+        };
+        // Here-document bodies are expanded when the command runs
+        if !arg.contains('$') || (arg.starts_with("<<") && !arg.starts_with("<<<")) {
             buf.push(arg);
+            continue;
         }
+        buf.push(expander.expand(&arg, Output::Text)?);
     }
+    Ok(())
 }
 
 fn expand_command_alias(
@@ -464,21 +464,37 @@ fn expand_command_alias(
 
     if let Rule::command = pair.as_rule() {
         let env_guard = environment.read();
+        let mut expander = ParamExpander::new(&env_guard);
         for inner_pair in pair.into_inner() {
             match inner_pair.as_rule() {
                 Rule::simple_command => {
-                    let args = expand_alias_tilde(inner_pair, &env_guard.alias, _current_dir)?;
-                    expand_var_args(args, &env_guard, &mut buf);
+                    let args = expand_alias_tilde(
+                        inner_pair,
+                        &env_guard.alias,
+                        _current_dir,
+                        &env_guard.shell_options,
+                    )?;
+                    expand_var_args(args, &mut expander, &mut buf)?;
                 }
                 Rule::simple_command_bg => {
-                    let args = expand_alias_tilde(inner_pair, &env_guard.alias, _current_dir)?;
-                    expand_var_args(args, &env_guard, &mut buf);
+                    let args = expand_alias_tilde(
+                        inner_pair,
+                        &env_guard.alias,
+                        _current_dir,
+                        &env_guard.shell_options,
+                    )?;
+                    expand_var_args(args, &mut expander, &mut buf)?;
                     buf.push("&".to_string());
                 }
                 Rule::pipe_command => {
                     buf.push("|".to_string());
-                    let args = expand_alias_tilde(inner_pair, &env_guard.alias, _current_dir)?;
-                    expand_var_args(args, &env_guard, &mut buf);
+                    let args = expand_alias_tilde(
+                        inner_pair,
+                        &env_guard.alias,
+                        _current_dir,
+                        &env_guard.shell_options,
+                    )?;
+                    expand_var_args(args, &mut expander, &mut buf)?;
                 }
                 Rule::struct_pipe_command => {
                     // Preserve struct_pipe_command (|: lisp_expr) during alias expansion
@@ -497,9 +513,594 @@ fn expand_command_alias(
                 }
             }
         }
+        let assigned = std::mem::take(&mut expander.assigned);
+        drop(env_guard);
+        if !assigned.is_empty() {
+            environment.write().variables.extend(assigned);
+        }
     } else if let Rule::command_list_sep = pair.as_rule() {
         buf.push(pair.as_str().to_string());
     }
 
     Ok(buf)
 }
+
+/// How [`ParamExpander::expand`] writes its result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Output {
+    /// Shell text for the parser: quotes are kept and substituted values are quoted.
+    Text,
+    /// The final string, with quotes removed.
+    Value,
+    /// A glob pattern in which quoted characters only match themselves.
+    Pattern,
+}
+
+/// Parameter expansion: `$name`, `${name}`, positional and special parameters and the
+/// `${...}` operators.
+///
+/// | Form | Result |
+/// |------|--------|
+/// | `${name:-word}` / `${name-word}` | `word` if `name` is unset or empty / unset |
+/// | `${name:=word}` / `${name=word}` | as above, also assigning `word` to `name` |
+/// | `${name:?msg}` / `${name?msg}` | fails with `msg` if `name` is unset or empty / unset |
+/// | `${name:+word}` / `${name+word}` | `word` if `name` is set and not empty / set |
+/// | `${#name}` | length of the value in characters |
+/// | `${name[@]}` / `${#name[@]}` | every element of `PIPESTATUS` or a coprocess / their number |
+/// | `${name#pat}` / `${name##pat}` | removes the shortest / longest matching prefix |
+/// | `${name%pat}` / `${name%%pat}` | removes the shortest / longest matching suffix |
+/// | `${name/pat/rep}` / `${name//pat/rep}` | replaces the first / every match, `/#` and `/%` anchor it |
+/// | `${name:offset}` / `${name:offset:length}` | substring, negative values count from the end |
+/// | `${name^}` `${name^^}` `${name,}` `${name,,}` | upper / lower case of the first or every character |
+/// | `${!name}` | value of the variable named by `name` |
+/// | `$((expr))` | result of the arithmetic expression, see [`arithmetic`] |
+///
+/// Values are looked up with [`Environment::get_var`], so `$OUT[2]`, `${ERR[1]}`, `$1` and
+/// `$#` work as well. Unset parameters expand to nothing, or fail under `set -u`, and
+/// captured output loses its trailing newlines.
+struct ParamExpander<'a> {
+    env: &'a Environment,
+    /// Variables assigned by `${name:=word}`, stored once the command line is expanded.
+    assigned: Vec<(String, String)>,
+}
+
+impl<'a> ParamExpander<'a> {
+    fn new(env: &'a Environment) -> Self {
+        Self {
+            env,
+            assigned: Vec::new(),
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<String> {
+        if let Some((_, value)) = self.assigned.iter().rev().find(|(key, _)| key == name) {
+            return Some(value.clone());
+        }
+        let value = self.env.get_var(&format!("${name}"))?;
+        // Captured output drops its trailing newlines, like a command substitution
+        if parse_output_var(name, "OUT").is_some() || parse_output_var(name, "ERR").is_some() {
+            return Some(value.trim_end_matches('\n').to_string());
+        }
+        Some(value)
+    }
+
+    /// Looks up a parameter expanded without a default, which is an error under `set -u`
+    /// when it is unset.
+    fn value(&self, name: &str) -> Result<String> {
+        match self.lookup(name) {
+            Some(value) => Ok(value),
+            None if self.env.shell_options.nounset && !matches!(name, "@" | "*") => {
+                Err(ShellError::UnboundVariable(name.to_string()).into())
+            }
+            None => Ok(String::new()),
+        }
+    }
+
+    /// Expands the parameters in `text`.
+    fn expand(&mut self, text: &str, output: Output) -> Result<String> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut out = String::with_capacity(text.len());
+        let mut quote: Option<char> = None;
+        let mut i = 0;
+
+        while i < chars.len() {
+            let c = chars[i];
+            match c {
+                '\'' | '"' if quote.is_none() => {
+                    quote = Some(c);
+                    if output == Output::Text {
+                        out.push(c);
+                    }
+                }
+                _ if quote == Some(c) => {
+                    quote = None;
+                    if output == Output::Text {
+                        out.push(c);
+                    }
+                }
+                _ if quote == Some('\'') => push_literal(&mut out, c, true, output),
+                '\\' => {
+                    let Some(&next) = chars.get(i + 1) else {
+                        push_literal(&mut out, c, quote.is_some(), output);
+                        break;
+                    };
+                    // Inside double quotes a backslash only escapes these
+                    let escapes = quote.is_none() || matches!(next, '$' | '`' | '"' | '\\' | '\n');
+                    match output {
+                        Output::Text => {
+                            out.push(c);
+                            out.push(next);
+                        }
+                        _ if escapes => push_literal(&mut out, next, true, output),
+                        _ => {
+                            push_literal(&mut out, c, true, output);
+                            push_literal(&mut out, next, true, output);
+                        }
+                    }
+                    i += 2;
+                    continue;
+                }
+                '$' if chars.get(i + 1) == Some(&'(') && is_arithmetic(&chars, i + 1) => {
+                    let end = find_closing(&chars, i + 1, '(', ')').unwrap_or(chars.len() - 1);
+                    let expr: String = chars[i + 3..end - 1].iter().collect();
+                    let value = self.arithmetic(&expr)?;
+                    push_value(&mut out, &value.to_string(), quote.is_some(), output);
+                    i = end + 1;
+                    continue;
+                }
+                '$' if chars.get(i + 1) == Some(&'(') => {
+                    // Command substitutions are run by the parser
+                    let end = find_closing(&chars, i + 1, '(', ')').unwrap_or(chars.len() - 1);
+                    out.extend(&chars[i..=end]);
+                    i = end + 1;
+                    continue;
+                }
+                '$' => {
+                    if let Some((value, len)) = self.parameter(&chars[i + 1..])? {
+                        push_value(&mut out, &value, quote.is_some(), output);
+                        i += len + 1;
+                        continue;
+                    }
+                    push_literal(&mut out, c, quote.is_some(), output);
+                }
+                _ => push_literal(&mut out, c, quote.is_some(), output),
+            }
+            i += 1;
+        }
+        Ok(out)
+    }
+
+    /// Evaluates the expression of `$(( ))` after expanding the parameters in it.
+    fn arithmetic(&mut self, expr: &str) -> Result<i64> {
+        let expr = self.expand(expr, Output::Value)?;
+        arithmetic::evaluate(&expr, self)
+    }
+
+    /// Reads the parameter following a `$`, returning its value and how many characters
+    /// it used.
+    fn parameter(&mut self, chars: &[char]) -> Result<Option<(String, usize)>> {
+        let Some(&first) = chars.first() else {
+            return Ok(None);
+        };
+        if first == '{' {
+            let end = find_closing(chars, 0, '{', '}')
+                .ok_or_else(|| anyhow!("{}: bad substitution", chars.iter().collect::<String>()))?;
+            let inner: String = chars[1..end].iter().collect();
+            return Ok(Some((self.braced(&inner)?, end + 1)));
+        }
+        if first.is_ascii_digit() || "#@*?$!-".contains(first) {
+            return Ok(Some((self.value(&first.to_string())?, 1)));
+        }
+        if !(first.is_ascii_alphabetic() || first == '_') {
+            return Ok(None);
+        }
+
+        let mut len = chars
+            .iter()
+            .take_while(|c| c.is_ascii_alphanumeric() || **c == '_')
+            .count();
+        let name: String = chars[..len].iter().collect();
+        // `$OUT[N]` and `$ERR[N]` index the output history, `$PIPESTATUS[N]` the last pipeline
+        if matches!(name.as_str(), "OUT" | "ERR" | "PIPESTATUS") && chars.get(len) == Some(&'[') {
+            let digits = chars[len + 1..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit())
+                .count();
+            if digits > 0 && chars.get(len + 1 + digits) == Some(&']') {
+                len += digits + 2;
+            }
+        }
+        let key: String = chars[..len].iter().collect();
+        Ok(Some((self.value(&key)?, len)))
+    }
+
+    /// Expands the text between `${` and `}`.
+    fn braced(&mut self, inner: &str) -> Result<String> {
+        let bad = || anyhow!("${{{}}}: bad substitution", inner);
+
+        if let Some(rest) = inner.strip_prefix('#')
+            && !rest.is_empty()
+        {
+            let (name, tail) = split_parameter_name(rest);
+            if name.is_empty() || !tail.is_empty() {
+                return Err(bad());
+            }
+            if matches!(name, "@" | "*") {
+                return Ok(self.env.positional_params.len().to_string());
+            }
+            if name.ends_with("[@]") || name.ends_with("[*]") {
+                let elements = self.lookup(name).unwrap_or_default();
+                return Ok(elements.split_whitespace().count().to_string());
+            }
+            let value = self.value(name)?;
+            return Ok(value.chars().count().to_string());
+        }
+        if let Some(rest) = inner.strip_prefix('!')
+            && !rest.is_empty()
+        {
+            let (name, tail) = split_parameter_name(rest);
+            if name.is_empty() || !tail.is_empty() {
+                return Err(bad());
+            }
+            let target = self.lookup(name).unwrap_or_default();
+            return Ok(self.lookup(&target).unwrap_or_default());
+        }
+
+        let (name, rest) = split_parameter_name(inner);
+        if name.is_empty() {
+            return Err(bad());
+        }
+        if rest.is_empty() {
+            return self.value(name);
+        }
+        let value = self.lookup(name);
+
+        let (colon, op) = match rest.strip_prefix(':') {
+            Some(op) => (true, op),
+            None => (false, rest),
+        };
+        if let Some(kind) = op.chars().next().filter(|c| "-=?+".contains(*c)) {
+            let word = &op[1..];
+            let missing = match &value {
+                None => true,
+                Some(value) => colon && value.is_empty(),
+            };
+            return match kind {
+                '-' if missing => self.expand(word, Output::Value),
+                '=' if missing => {
+                    if !is_identifier(name) {
+                        bail!("${}: cannot assign in this way", name);
+                    }
+                    let word = self.expand(word, Output::Value)?;
+                    self.assigned.push((name.to_string(), word.clone()));
+                    Ok(word)
+                }
+                '?' if missing => {
+                    let mut message = self.expand(word, Output::Value)?;
+                    if message.is_empty() {
+                        message = "parameter null or not set".to_string();
+                    }
+                    Err(ShellError::ParameterUnset {
+                        name: name.to_string(),
+                        message,
+                    }
+                    .into())
+                }
+                '+' if missing => Ok(String::new()),
+                '+' => self.expand(word, Output::Value),
+                _ => Ok(value.unwrap_or_default()),
+            };
+        }
+        let value = match value {
+            Some(value) => value,
+            None => self.value(name)?,
+        };
+        if colon {
+            return substring(&value, op).ok_or_else(bad);
+        }
+
+        if let Some(pattern) = rest.strip_prefix("##") {
+            return self.trim_prefix(&value, pattern, true);
+        }
+        if let Some(pattern) = rest.strip_prefix('#') {
+            return self.trim_prefix(&value, pattern, false);
+        }
+        if let Some(pattern) = rest.strip_prefix("%%") {
+            return self.trim_suffix(&value, pattern, true);
+        }
+        if let Some(pattern) = rest.strip_prefix('%') {
+            return self.trim_suffix(&value, pattern, false);
+        }
+        if let Some(spec) = rest.strip_prefix('/') {
+            return self.replace(&value, spec);
+        }
+        match rest {
+            "^^" => Ok(value.to_uppercase()),
+            ",," => Ok(value.to_lowercase()),
+            "^" | "," => {
+                let mut chars = value.chars();
+                Ok(match chars.next() {
+                    Some(first) if rest == "^" => first.to_uppercase().chain(chars).collect(),
+                    Some(first) => first.to_lowercase().chain(chars).collect(),
+                    None => value,
+                })
+            }
+            _ => Err(bad()),
+        }
+    }
+
+    fn pattern(&mut self, text: &str) -> Result<glob::Pattern> {
+        let pattern = self.expand(text, Output::Pattern)?;
+        Ok(glob::Pattern::new(&pattern)
+            .or_else(|_| glob::Pattern::new(&glob::Pattern::escape(&pattern)))?)
+    }
+
+    fn trim_prefix(&mut self, value: &str, pattern: &str, longest: bool) -> Result<String> {
+        let pattern = self.pattern(pattern)?;
+        let mut ends: Vec<usize> = char_boundaries(value).collect();
+        if longest {
+            ends.reverse();
+        }
+        Ok(ends
+            .into_iter()
+            .find(|end| pattern.matches(&value[..*end]))
+            .map_or_else(|| value.to_string(), |end| value[end..].to_string()))
+    }
+
+    fn trim_suffix(&mut self, value: &str, pattern: &str, longest: bool) -> Result<String> {
+        let pattern = self.pattern(pattern)?;
+        let mut starts: Vec<usize> = char_boundaries(value).collect();
+        if !longest {
+            starts.reverse();
+        }
+        Ok(starts
+            .into_iter()
+            .find(|start| pattern.matches(&value[*start..]))
+            .map_or_else(|| value.to_string(), |start| value[..start].to_string()))
+    }
+
+    /// `${name/pat/rep}` and its `//`, `/#` and `/%` forms; `spec` follows the first `/`.
+    fn replace(&mut self, value: &str, spec: &str) -> Result<String> {
+        let (all, anchor, spec) = match spec.chars().next() {
+            Some('/') => (true, None, &spec[1..]),
+            Some(c @ ('#' | '%')) => (false, Some(c), &spec[1..]),
+            _ => (false, None, spec),
+        };
+        let (pattern, replacement) = match find_unquoted(spec, '/') {
+            Some(pos) => (&spec[..pos], &spec[pos + 1..]),
+            None => (spec, ""),
+        };
+        if pattern.is_empty() {
+            return Ok(value.to_string());
+        }
+        let pattern = self.pattern(pattern)?;
+        let replacement = self.expand(replacement, Output::Value)?;
+
+        let bounds: Vec<usize> = char_boundaries(value).collect();
+        // The longest non-empty match starting at `start`
+        let match_at = |start: usize| {
+            bounds
+                .iter()
+                .rev()
+                .copied()
+                .filter(|end| *end > start)
+                .filter(|end| anchor != Some('%') || *end == value.len())
+                .find(|end| pattern.matches(&value[start..*end]))
+        };
+
+        let mut out = String::with_capacity(value.len());
+        let mut copied = 0;
+        for &start in &bounds {
+            if start < copied || (anchor == Some('#') && start > 0) {
+                continue;
+            }
+            if let Some(end) = match_at(start) {
+                out.push_str(&value[copied..start]);
+                out.push_str(&replacement);
+                copied = end;
+                if !all {
+                    break;
+                }
+            }
+        }
+        out.push_str(&value[copied..]);
+        Ok(out)
+    }
+}
+
+impl ArithVars for ParamExpander<'_> {
+    fn get(&self, name: &str) -> Option<String> {
+        self.lookup(name)
+    }
+
+    fn set(&mut self, name: &str, value: i64) {
+        self.assigned.push((name.to_string(), value.to_string()));
+    }
+}
+
+/// Whether the `(` at `open_at` starts `(( ... ))` rather than a command substitution
+/// or subshell.
+fn is_arithmetic(chars: &[char], open_at: usize) -> bool {
+    if chars.get(open_at + 1) != Some(&'(') {
+        return false;
+    }
+    match find_closing(chars, open_at, '(', ')') {
+        Some(end) => find_closing(chars, open_at + 1, '(', ')') == Some(end - 1),
+        None => false,
+    }
+}
+
+/// Expands the parameters in `text` and removes its quotes.
+pub fn expand_parameters(text: &str, env: &Environment) -> Result<String> {
+    ParamExpander::new(env).expand(text, Output::Value)
+}
+
+/// Quotes a value so the parser reads it back as a single word.
+fn quote_word(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_alphanumeric() || "-_./:=,+@%^".contains(c));
+    if plain {
+        value.to_string()
+    } else {
+        format!("\"{}\"", escape_double_quoted(value))
+    }
+}
+
+fn escape_double_quoted(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '$' | '`') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn push_literal(out: &mut String, c: char, quoted: bool, output: Output) {
+    if quoted && output == Output::Pattern && "*?[]".contains(c) {
+        out.push('[');
+        out.push(c);
+        out.push(']');
+    } else {
+        out.push(c);
+    }
+}
+
+fn push_value(out: &mut String, value: &str, quoted: bool, output: Output) {
+    match output {
+        Output::Text if quoted => out.push_str(&escape_double_quoted(value)),
+        Output::Text => out.push_str(&quote_word(value)),
+        Output::Value => out.push_str(value),
+        Output::Pattern => value
+            .chars()
+            .for_each(|c| push_literal(out, c, quoted, output)),
+    }
+}
+
+/// Splits `${...}` contents into the parameter name and the operator that follows it.
+fn split_parameter_name(inner: &str) -> (&str, &str) {
+    let Some(first) = inner.chars().next() else {
+        return ("", "");
+    };
+    let len = if first.is_ascii_digit() {
+        inner.chars().take_while(|c| c.is_ascii_digit()).count()
+    } else if "#@*?$!-".contains(first) {
+        1
+    } else if first.is_ascii_alphabetic() || first == '_' {
+        let mut len = inner
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .count();
+        // `${name[N]}`
+        if inner[len..].starts_with('[')
+            && let Some(close) = inner[len..].find(']')
+        {
+            len += close + 1;
+        }
+        len
+    } else {
+        0
+    };
+    inner.split_at(len)
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// `${name:offset}` and `${name:offset:length}`; `spec` follows the colon.
+fn substring(value: &str, spec: &str) -> Option<String> {
+    let (offset, length) = match spec.split_once(':') {
+        Some((offset, length)) => (offset, Some(length)),
+        None => (spec, None),
+    };
+    let count = value.chars().count() as i64;
+    let offset: i64 = offset.trim().parse().ok()?;
+    let start = if offset < 0 { count + offset } else { offset };
+    if start < 0 || start > count {
+        return Some(String::new());
+    }
+    let end = match length {
+        None => count,
+        Some(length) => {
+            let length: i64 = length.trim().parse().ok()?;
+            if length < 0 {
+                count + length
+            } else {
+                (start + length).min(count)
+            }
+        }
+    };
+    if end < start {
+        return None;
+    }
+    Some(
+        value
+            .chars()
+            .skip(start as usize)
+            .take((end - start) as usize)
+            .collect(),
+    )
+}
+
+fn char_boundaries(value: &str) -> impl DoubleEndedIterator<Item = usize> + '_ {
+    value
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(value.len()))
+}
+
+/// Index of the bracket closing the one at `open_at`, skipping quoted text.
+fn find_closing(chars: &[char], open_at: usize, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    let mut i = open_at;
+    while i < chars.len() {
+        let c = chars[i];
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some('"') if c == '\\' => i += 1,
+            Some(_) => {}
+            None if c == '\\' => i += 1,
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c == open => depth += 1,
+            None if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            None => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Byte index of the first `target` outside quotes and not escaped.
+fn find_unquoted(text: &str, target: char) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '\\' => escaped = true,
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c == target => return Some(i),
+            None => {}
+        }
+    }
+    None
+}
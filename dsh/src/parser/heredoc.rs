@@ -0,0 +1,473 @@
+//! Here-documents.
+//!
+//! `cmd <<EOF` feeds the lines that follow the command, up to a line containing only
+//! `EOF`, to the command's standard input. The body is not part of the command line, so
+//! before parsing [`normalize_heredocs`] moves each body right behind its operator:
+//!
+//! ```text
+//! cat <<EOF | grep x        cat <<EOF
+//! one                  =>   one
+//! EOF                       EOF | grep x
+//! ```
+//!
+//! The grammar then reads `<<EOF\n...\nEOF` as a single redirect. `<<-` strips leading
+//! tabs from the body and the delimiter line, and a quoted delimiter (`<<'EOF'`,
+//! `<<"EOF"`, `<<\EOF`) turns off expansion in the body.
+
+use super::expansion::expand_parameters;
+use crate::environment::Environment;
+use anyhow::Result;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HereDoc {
+    /// Byte range of the operator and delimiter word in the input.
+    op_start: usize,
+    op_end: usize,
+    delimiter: String,
+    quoted: bool,
+    strip_tabs: bool,
+    body: String,
+    terminated: bool,
+    /// End of the command line the operator is on.
+    line_end: usize,
+    /// Where the command text continues after the bodies of that line.
+    resume: usize,
+}
+
+/// Rewrites here-documents into the form the grammar expects.
+///
+/// Input without here-documents is returned unchanged. A body that reaches the end of
+/// the input without its delimiter ends there.
+pub fn normalize_heredocs(input: &str) -> String {
+    let docs = scan(input);
+    if docs.is_empty() {
+        return input.to_string();
+    }
+    rewrite(input, &docs, true)
+}
+
+/// Removes here-document bodies from the input.
+///
+/// Returns the remaining command text and whether a body is still waiting for its
+/// delimiter line.
+pub fn strip_heredoc_bodies(input: &str) -> (String, bool) {
+    let docs = scan(input);
+    if docs.is_empty() {
+        return (input.to_string(), false);
+    }
+    let open = docs.iter().any(|doc| !doc.terminated);
+    (rewrite(input, &docs, false), open)
+}
+
+fn rewrite(input: &str, docs: &[HereDoc], with_bodies: bool) -> String {
+    let mut out = String::with_capacity(input.len() + 16);
+    let mut cursor = 0;
+    for (i, doc) in docs.iter().enumerate() {
+        out.push_str(&input[cursor..doc.op_start]);
+        out.push_str("<<");
+        if doc.quoted {
+            out.push('\'');
+            out.push_str(&doc.delimiter);
+            out.push('\'');
+        } else {
+            out.push_str(&doc.delimiter);
+        }
+        if with_bodies {
+            out.push('\n');
+            out.push_str(&doc.body);
+            out.push_str(&doc.delimiter);
+        }
+        cursor = doc.op_end;
+
+        let last_on_line = docs
+            .get(i + 1)
+            .is_none_or(|next| next.line_end != doc.line_end);
+        if last_on_line {
+            out.push_str(&input[cursor..doc.line_end]);
+            cursor = doc.resume;
+        }
+    }
+    out.push_str(&input[cursor..]);
+    out
+}
+
+/// Finds the here-document operators outside quotes and comments and reads their bodies.
+fn scan(input: &str) -> Vec<HereDoc> {
+    let bytes = input.as_bytes();
+    let mut docs: Vec<HereDoc> = Vec::new();
+    let mut pending = 0; // operators on the current line still waiting for their bodies
+    let mut quote: Option<u8> = None;
+    let mut in_comment = false;
+    let mut arith_depth = 0; // `<<` inside `(( ))` is a shift
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        if in_comment {
+            if c == b'\n' {
+                in_comment = false;
+            } else {
+                i += 1;
+                continue;
+            }
+        }
+        if let Some(q) = quote {
+            if c == b'\\' && q == b'"' {
+                i += 2;
+                continue;
+            }
+            if c == q {
+                quote = None;
+            }
+            i += 1;
+            continue;
+        }
+
+        match c {
+            b'\\' => {
+                i += 2;
+                continue;
+            }
+            b'\'' | b'"' => quote = Some(c),
+            b'(' if bytes.get(i + 1) == Some(&b'(') => {
+                arith_depth += 1;
+                i += 2;
+                continue;
+            }
+            b')' if arith_depth > 0 && bytes.get(i + 1) == Some(&b')') => {
+                arith_depth -= 1;
+                i += 2;
+                continue;
+            }
+            b'#' if i == 0 || b" \t\n;&|()".contains(&bytes[i - 1]) => in_comment = true,
+            b'<' if arith_depth == 0 && bytes.get(i + 1) == Some(&b'<') => {
+                if bytes.get(i + 2) == Some(&b'<') {
+                    // Here-string
+                    i += 3;
+                    continue;
+                }
+                if let Some(doc) = read_operator(input, i) {
+                    i = doc.op_end;
+                    docs.push(doc);
+                    pending += 1;
+                    continue;
+                }
+                i += 2;
+                continue;
+            }
+            b'\n' if pending > 0 => {
+                let start = docs.len() - pending;
+                let resume = read_bodies(input, i, &mut docs[start..]);
+                pending = 0;
+                i = resume;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    if pending > 0 {
+        // The command line ends without a newline, so the bodies have not started yet
+        let start = docs.len() - pending;
+        for doc in &mut docs[start..] {
+            doc.line_end = input.len();
+            doc.resume = input.len();
+        }
+    }
+    docs
+}
+
+/// Parses `<<[-] WORD` at `start`.
+fn read_operator(input: &str, start: usize) -> Option<HereDoc> {
+    let bytes = input.as_bytes();
+    let mut i = start + 2;
+    let strip_tabs = bytes.get(i) == Some(&b'-');
+    if strip_tabs {
+        i += 1;
+    }
+    while matches!(bytes.get(i), Some(b' ' | b'\t')) {
+        i += 1;
+    }
+
+    let word_start = i;
+    let mut quote: Option<u8> = None;
+    while let Some(&c) = bytes.get(i) {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == b'\'' || c == b'"' => quote = Some(c),
+            None if c == b'\\' => i += 1,
+            None if c.is_ascii_whitespace() || b";&|()<>".contains(&c) => break,
+            None => {}
+        }
+        i += 1;
+    }
+    let word_end = i.min(bytes.len());
+    let word = &input[word_start..word_end];
+    let quoted = word.contains(['\'', '"', '\\']);
+    let delimiter: String = word.chars().filter(|c| !"'\"\\".contains(*c)).collect();
+    if delimiter.is_empty() {
+        return None;
+    }
+
+    Some(HereDoc {
+        op_start: start,
+        op_end: word_end,
+        delimiter,
+        quoted,
+        strip_tabs,
+        body: String::new(),
+        terminated: false,
+        line_end: 0,
+        resume: 0,
+    })
+}
+
+/// Reads the bodies following the newline at `line_end` and returns where the command
+/// text continues.
+fn read_bodies(input: &str, line_end: usize, docs: &mut [HereDoc]) -> usize {
+    let mut pos = line_end + 1;
+    let mut resume = input.len();
+    for doc in docs.iter_mut() {
+        doc.line_end = line_end;
+        while pos < input.len() {
+            let next = input[pos..].find('\n').map(|n| pos + n);
+            let line = &input[pos..next.unwrap_or(input.len())];
+            let line = if doc.strip_tabs {
+                line.trim_start_matches('\t')
+            } else {
+                line
+            };
+            if line.trim_end_matches('\r') == doc.delimiter {
+                doc.terminated = true;
+                resume = next.unwrap_or(input.len());
+                pos = next.map_or(input.len(), |n| n + 1);
+                break;
+            }
+            doc.body.push_str(line);
+            doc.body.push('\n');
+            pos = next.map_or(input.len(), |n| n + 1);
+        }
+        if !doc.terminated {
+            resume = input.len();
+        }
+    }
+    for doc in docs.iter_mut() {
+        doc.resume = resume;
+    }
+    resume
+}
+
+/// Replaces the `$(...)` and `` `...` `` of an unquoted here-document body with the
+/// output of `run` for their commands.
+///
+/// The output is escaped, so that [`expand_heredoc_body`] keeps it as is. `$((...))` is
+/// left alone.
+pub fn substitute_commands(
+    body: &str,
+    mut run: impl FnMut(&str) -> Result<String>,
+) -> Result<String> {
+    let mut out = String::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        let command = match c {
+            '\\' => {
+                out.push(c);
+                if let Some(escaped @ ('$' | '`' | '\\')) = chars.peek().copied() {
+                    out.push(escaped);
+                    chars.next();
+                }
+                continue;
+            }
+            '$' if chars.peek() == Some(&'(') => {
+                chars.next();
+                if chars.peek() == Some(&'(') {
+                    out.push_str("$(");
+                    continue;
+                }
+                let mut command = String::new();
+                let mut depth = 0;
+                for c in chars.by_ref() {
+                    match c {
+                        '(' => depth += 1,
+                        ')' if depth == 0 => break,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    command.push(c);
+                }
+                command
+            }
+            '`' => {
+                let mut command = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '`' => break,
+                        '\\' if chars.peek() == Some(&'`') => command.extend(chars.next()),
+                        _ => command.push(c),
+                    }
+                }
+                command
+            }
+            _ => {
+                out.push(c);
+                continue;
+            }
+        };
+        for c in run(&command)?.chars() {
+            if matches!(c, '$' | '`' | '\\') {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+    }
+    Ok(out)
+}
+
+/// Expands `$name`, `${...}`, positional and special parameters in an unquoted
+/// here-document.
+///
+/// A backslash keeps a following `$`, `` ` `` or `\` literal and joins lines when it
+/// ends one, everything else is copied as is.
+pub fn expand_heredoc_body(body: &str, env: &Environment) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.peek() {
+                Some('$' | '`' | '\\') => out.extend(chars.next()),
+                Some('\n') => {
+                    chars.next();
+                }
+                _ => out.push(c),
+            },
+            '$' => {
+                let name = match chars.peek() {
+                    Some('{') => {
+                        chars.next();
+                        let mut inner = String::new();
+                        let mut depth = 0;
+                        for c in chars.by_ref() {
+                            match c {
+                                '{' => depth += 1,
+                                '}' if depth == 0 => break,
+                                '}' => depth -= 1,
+                                _ => {}
+                            }
+                            inner.push(c);
+                        }
+                        let expanded = expand_parameters(&format!("${{{inner}}}"), env);
+                        out.push_str(&expanded.unwrap_or_default());
+                        continue;
+                    }
+                    Some(c) if c.is_ascii_digit() || "#@*?$!-".contains(*c) => {
+                        chars.next().map(String::from).unwrap_or_default()
+                    }
+                    Some(c) if c.is_ascii_alphabetic() || *c == '_' => {
+                        let mut name = String::new();
+                        while let Some(c) = chars.peek() {
+                            if !c.is_ascii_alphanumeric() && *c != '_' {
+                                break;
+                            }
+                            name.extend(chars.next());
+                        }
+                        name
+                    }
+                    _ => {
+                        out.push(c);
+                        continue;
+                    }
+                };
+                out.push_str(&env.get_var(&format!("${name}")).unwrap_or_default());
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moves_body_behind_operator() {
+        assert_eq!(
+            normalize_heredocs("cat <<EOF | grep a\nabc\nxyz\nEOF\necho done"),
+            "cat <<EOF\nabc\nxyz\nEOF | grep a\necho done"
+        );
+        assert_eq!(normalize_heredocs("echo hi"), "echo hi");
+        assert_eq!(normalize_heredocs("cat <<< word"), "cat <<< word");
+    }
+
+    #[test]
+    fn test_quoted_delimiter_and_tab_stripping() {
+        assert_eq!(
+            normalize_heredocs("cat <<'END'\n$HOME\nEND"),
+            "cat <<'END'\n$HOME\nEND"
+        );
+        assert_eq!(
+            normalize_heredocs("cat <<-\"END\"\n\t\tindented\n\tEND\n"),
+            "cat <<'END'\nindented\nEND\n"
+        );
+    }
+
+    #[test]
+    fn test_several_heredocs_on_one_line() {
+        assert_eq!(
+            normalize_heredocs("cat <<A; cat <<B\na\nA\nb\nB"),
+            "cat <<A\na\nA; cat <<B\nb\nB"
+        );
+    }
+
+    #[test]
+    fn test_operators_in_quotes_and_comments_are_ignored() {
+        assert_eq!(normalize_heredocs("echo '<<EOF'"), "echo '<<EOF'");
+        assert_eq!(normalize_heredocs("echo x # <<EOF\ny"), "echo x # <<EOF\ny");
+        assert_eq!(normalize_heredocs("echo $((1 << 2))"), "echo $((1 << 2))");
+    }
+
+    #[test]
+    fn test_strip_heredoc_bodies() {
+        assert_eq!(
+            strip_heredoc_bodies("cat <<EOF\ndon't\nEOF"),
+            ("cat <<EOF".to_string(), false)
+        );
+        assert_eq!(
+            strip_heredoc_bodies("cat <<EOF\nstill typing"),
+            ("cat <<EOF".to_string(), true)
+        );
+        assert_eq!(
+            strip_heredoc_bodies("cat <<EOF"),
+            ("cat <<EOF".to_string(), true)
+        );
+    }
+
+    #[test]
+    fn test_expand_heredoc_body() {
+        let env = Environment::new();
+        {
+            let mut env = env.write();
+            env.variables.insert("name".to_string(), "dsh".to_string());
+            env.positional_params = vec!["first".to_string()];
+            env.last_status = 2;
+        }
+        let env = env.read();
+        assert_eq!(
+            expand_heredoc_body(
+                "hi $name ${name}! $1 \\$name ${name^^} ${nope:-none}\n",
+                &env
+            ),
+            "hi dsh dsh! first $name DSH none\n"
+        );
+        assert_eq!(expand_heredoc_body("cost: $ 5\n", &env), "cost: $ 5\n");
+        assert_eq!(expand_heredoc_body("status $?\n", &env), "status 2\n");
+
+        let run = |command: &str| Ok(format!("<{command}>"));
+        let body = substitute_commands("$(echo $(pwd)) `ls` \\$(no) $((1 + 2))\n", run).unwrap();
+        assert_eq!(body, "<echo \\$(pwd)> <ls> \\$(no) $((1 + 2))\n");
+        let body = substitute_commands("$(cmd)\n", |_| Ok("a$b".to_string())).unwrap();
+        assert_eq!(expand_heredoc_body(&body, &env), "a$b\n");
+    }
+}
@@ -78,17 +78,26 @@ fn collect_highlight_from_pair(
             };
             push_token(pair.as_span(), kind, out);
         }
-        Rule::variable => push_token(pair.as_span(), HighlightKind::Variable, out),
+        Rule::variable | Rule::arith_subst | Rule::arith_command => {
+            push_token(pair.as_span(), HighlightKind::Variable, out)
+        }
         Rule::s_quoted => push_token(pair.as_span(), HighlightKind::SingleQuoted, out),
         Rule::d_quoted => push_token(pair.as_span(), HighlightKind::DoubleQuoted, out),
         Rule::stdout_redirect_direction
         | Rule::stderr_redirect_direction
         | Rule::stdouterr_redirect_direction
         | Rule::stdin_redirect_direction
-        | Rule::stdin_redirect_direction_in => {
+        | Rule::stdin_redirect_direction_in
+        | Rule::herestring_redirect_direction
+        | Rule::heredoc_redirect_direction
+        | Rule::fd_redirect_direction
+        | Rule::dup_redirect_direction
+        | Rule::dup_redirect_target => {
             push_token(pair.as_span(), HighlightKind::Redirect, out);
         }
-        Rule::proc_subst_direction | Rule::proc_subst_direction_in => {
+        Rule::proc_subst_direction
+        | Rule::proc_subst_direction_in
+        | Rule::proc_subst_direction_out => {
             push_token(pair.as_span(), HighlightKind::ProcSubstitution, out);
         }
         Rule::pipeline_op => push_token(pair.as_span(), HighlightKind::Pipe, out),
@@ -4,9 +4,13 @@ use pest_derive::Parser;
 #[grammar = "shell.pest"]
 pub struct ShellParser;
 
+pub mod arithmetic;
 pub mod ast;
+pub mod compound;
 pub mod expansion;
+pub mod heredoc;
 pub mod highlight;
+pub(crate) mod pathname;
 
 #[cfg(test)]
 mod edge_case_tests;
@@ -0,0 +1,415 @@
+//! Pathname expansion of words with `*`, `?` and `[...]`.
+//!
+//! Patterns are matched one path component at a time, so a wildcard never matches a `/`.
+//! The shell options decide the rest: `globstar` makes a `**` component match any number
+//! of directories, `dotglob` lets wildcards match names starting with a dot, and
+//! `extglob` enables `?(a|b)`, `*(...)`, `+(...)`, `@(...)` and `!(...)`.
+
+use crate::environment::ShellOptions;
+use std::fs;
+use std::path::Path;
+
+/// `?(...)`, `*(...)`, `+(...)`, `@(...)` and `!(...)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Repeat {
+    ZeroOrOne,
+    ZeroOrMore,
+    OneOrMore,
+    One,
+    Not,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    /// `?`
+    AnyChar,
+    /// `*`
+    AnyString,
+    /// `[a-z]`, `[!0-9]`
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+    Ext {
+        repeat: Repeat,
+        alternatives: Vec<Vec<Token>>,
+    },
+}
+
+/// One path component of a pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Pattern {
+    tokens: Vec<Token>,
+}
+
+impl Pattern {
+    /// Compiles a single path component.
+    pub(crate) fn new(component: &str, extglob: bool) -> Self {
+        let chars: Vec<char> = component.chars().collect();
+        let mut pos = 0;
+        let tokens = parse_tokens(&chars, &mut pos, extglob, false);
+        Self { tokens }
+    }
+
+    /// Whether the pattern has any wildcard, as opposed to only literal characters.
+    pub(crate) fn is_wild(&self) -> bool {
+        self.tokens
+            .iter()
+            .any(|token| !matches!(token, Token::Literal(_)))
+    }
+
+    /// The pattern as a plain name, with escapes removed.
+    fn literal(&self) -> String {
+        self.tokens
+            .iter()
+            .filter_map(|token| match token {
+                Token::Literal(c) => Some(*c),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn starts_with_dot(&self) -> bool {
+        self.tokens.first() == Some(&Token::Literal('.'))
+    }
+
+    /// Whether `name` matches the whole pattern.
+    pub(crate) fn matches(&self, name: &str) -> bool {
+        let chars: Vec<char> = name.chars().collect();
+        match_tokens(&self.tokens, &chars)
+    }
+}
+
+/// Parses tokens up to the end of `chars`, or inside an extglob group up to its `|` or `)`.
+fn parse_tokens(chars: &[char], pos: &mut usize, extglob: bool, in_group: bool) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    while let Some(&c) = chars.get(*pos) {
+        if in_group && (c == '|' || c == ')') {
+            break;
+        }
+        if extglob
+            && chars.get(*pos + 1) == Some(&'(')
+            && let Some(token) = parse_ext(chars, pos)
+        {
+            tokens.push(token);
+            continue;
+        }
+        *pos += 1;
+        let token = match c {
+            '\\' => match chars.get(*pos) {
+                Some(&escaped) => {
+                    *pos += 1;
+                    Token::Literal(escaped)
+                }
+                None => Token::Literal('\\'),
+            },
+            '?' => Token::AnyChar,
+            '*' => Token::AnyString,
+            '[' => parse_class(chars, pos).unwrap_or(Token::Literal('[')),
+            c => Token::Literal(c),
+        };
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// Parses `?(a|b)` and the like at `pos`, leaving `pos` alone when the group is unclosed.
+fn parse_ext(chars: &[char], pos: &mut usize) -> Option<Token> {
+    let repeat = match chars[*pos] {
+        '?' => Repeat::ZeroOrOne,
+        '*' => Repeat::ZeroOrMore,
+        '+' => Repeat::OneOrMore,
+        '@' => Repeat::One,
+        '!' => Repeat::Not,
+        _ => return None,
+    };
+    let mut end = *pos + 2;
+    let mut alternatives = Vec::new();
+    loop {
+        alternatives.push(parse_tokens(chars, &mut end, true, true));
+        match chars.get(end) {
+            Some('|') => end += 1,
+            Some(')') => break,
+            _ => return None,
+        }
+    }
+    *pos = end + 1;
+    Some(Token::Ext {
+        repeat,
+        alternatives,
+    })
+}
+
+/// Parses a bracket expression after its `[`, or None when it is not closed.
+fn parse_class(chars: &[char], pos: &mut usize) -> Option<Token> {
+    let mut i = *pos;
+    let negated = matches!(chars.get(i), Some('!' | '^'));
+    if negated {
+        i += 1;
+    }
+    let mut ranges = Vec::new();
+    let mut first = true;
+    loop {
+        let c = *chars.get(i)?;
+        if c == ']' && !first {
+            break;
+        }
+        first = false;
+        let c = if c == '\\' {
+            i += 1;
+            *chars.get(i)?
+        } else {
+            c
+        };
+        if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|end| *end != ']') {
+            ranges.push((c, chars[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((c, c));
+            i += 1;
+        }
+    }
+    *pos = i + 1;
+    Some(Token::Class { negated, ranges })
+}
+
+fn match_tokens(tokens: &[Token], text: &[char]) -> bool {
+    let Some((first, rest)) = tokens.split_first() else {
+        return text.is_empty();
+    };
+    match first {
+        Token::Literal(c) => text.first() == Some(c) && match_tokens(rest, &text[1..]),
+        Token::AnyChar => !text.is_empty() && match_tokens(rest, &text[1..]),
+        Token::AnyString => (0..=text.len()).any(|i| match_tokens(rest, &text[i..])),
+        Token::Class { negated, ranges } => text.first().is_some_and(|c| {
+            ranges.iter().any(|(lo, hi)| lo <= c && c <= hi) != *negated
+                && match_tokens(rest, &text[1..])
+        }),
+        Token::Ext {
+            repeat,
+            alternatives,
+        } => {
+            let alternative =
+                |part: &[char]| alternatives.iter().any(|alt| match_tokens(alt, part));
+            match repeat {
+                Repeat::One => (0..=text.len())
+                    .any(|i| alternative(&text[..i]) && match_tokens(rest, &text[i..])),
+                Repeat::ZeroOrOne => {
+                    match_tokens(rest, text)
+                        || (0..=text.len())
+                            .any(|i| alternative(&text[..i]) && match_tokens(rest, &text[i..]))
+                }
+                Repeat::ZeroOrMore => match_repeated(alternatives, rest, text),
+                Repeat::OneOrMore => (1..=text.len()).any(|i| {
+                    alternative(&text[..i]) && match_repeated(alternatives, rest, &text[i..])
+                }),
+                Repeat::Not => (0..=text.len())
+                    .any(|i| !alternative(&text[..i]) && match_tokens(rest, &text[i..])),
+            }
+        }
+    }
+}
+
+/// Matches any number of `alternatives` followed by `rest`.
+fn match_repeated(alternatives: &[Vec<Token>], rest: &[Token], text: &[char]) -> bool {
+    match_tokens(rest, text)
+        || (1..=text.len()).any(|i| {
+            alternatives.iter().any(|alt| match_tokens(alt, &text[..i]))
+                && match_repeated(alternatives, rest, &text[i..])
+        })
+}
+
+/// Whether a word would be expanded as a pattern with these options.
+pub(crate) fn is_pattern(word: &str, options: &ShellOptions) -> bool {
+    word.split('/')
+        .any(|component| Pattern::new(component, options.extglob).is_wild())
+}
+
+fn join(prefix: &str, name: &str) -> String {
+    match prefix {
+        "" => name.to_string(),
+        "/" => format!("/{name}"),
+        _ => format!("{prefix}/{name}"),
+    }
+}
+
+/// Directories below `prefix`, `prefix` itself first, for a `**` component.
+fn subdirectories(prefix: &str, current_dir: &Path, dotglob: bool, found: &mut Vec<String>) {
+    found.push(prefix.to_string());
+    let dir = current_dir.join(if prefix.is_empty() { "." } else { prefix });
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        // Symbolic links are not followed, so a link to a parent cannot loop
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| dotglob || !name.starts_with('.'))
+        .collect();
+    names.sort();
+    for name in names {
+        subdirectories(&join(prefix, &name), current_dir, dotglob, found);
+    }
+}
+
+/// Expands `word` to the paths it matches, relative to `current_dir` unless it is
+/// absolute, in sorted order. Returns None when the word has no wildcard.
+pub(crate) fn expand(
+    word: &str,
+    current_dir: &Path,
+    options: &ShellOptions,
+) -> Option<Vec<String>> {
+    if !is_pattern(word, options) {
+        return None;
+    }
+    let only_dirs = word.ends_with('/');
+    let mut components: Vec<&str> = word.split('/').filter(|c| !c.is_empty()).collect();
+    // A trailing `**` matches everything below, like `**/*`
+    if options.globstar && components.last() == Some(&"**") {
+        components.push("*");
+    }
+
+    let mut paths = vec![if word.starts_with('/') { "/" } else { "" }.to_string()];
+    let last = components.len().saturating_sub(1);
+    for (index, component) in components.iter().enumerate() {
+        if options.globstar && *component == "**" {
+            let mut found = Vec::new();
+            for prefix in &paths {
+                subdirectories(prefix, current_dir, options.dotglob, &mut found);
+            }
+            paths = found;
+            continue;
+        }
+
+        let pattern = Pattern::new(component, options.extglob);
+        let want_dir = index < last || only_dirs;
+        let mut next = Vec::new();
+        for prefix in &paths {
+            if !pattern.is_wild() {
+                next.push(join(prefix, &pattern.literal()));
+                continue;
+            }
+            let dir = current_dir.join(if prefix.is_empty() { "." } else { prefix });
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+            let hidden_ok = options.dotglob || pattern.starts_with_dot();
+            let mut names: Vec<String> = entries
+                .flatten()
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|name| hidden_ok || !name.starts_with('.'))
+                .filter(|name| pattern.matches(name))
+                .collect();
+            names.sort();
+            next.extend(names.iter().map(|name| join(prefix, name)));
+        }
+        if want_dir {
+            next.retain(|path| current_dir.join(path).is_dir());
+        }
+        paths = next;
+    }
+
+    let mut matched: Vec<String> = paths
+        .into_iter()
+        .filter(|path| !path.is_empty() && fs::symlink_metadata(current_dir.join(path)).is_ok())
+        .map(|path| if only_dirs { format!("{path}/") } else { path })
+        .collect();
+    matched.sort();
+    matched.dedup();
+    Some(matched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, name: &str) -> bool {
+        Pattern::new(pattern, true).matches(name)
+    }
+
+    #[test]
+    fn test_pattern_matches() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(!matches("*.rs", "main.rsx"));
+        assert!(matches("file?.txt", "file1.txt"));
+        assert!(matches("file[0-9].txt", "file7.txt"));
+        assert!(!matches("file[!0-9].txt", "file7.txt"));
+        assert!(matches("[]a]", "]"));
+        assert!(matches("\\*", "*"));
+        assert!(!matches("\\*", "x"));
+        assert!(!Pattern::new("val[1", true).is_wild());
+
+        assert!(matches("!(*.rs)", "Cargo.toml"));
+        assert!(!matches("!(*.rs)", "main.rs"));
+        assert!(matches("*.@(jpg|png)", "a.png"));
+        assert!(!matches("*.@(jpg|png)", "a.gif"));
+        assert!(matches("ab?(c)", "ab"));
+        assert!(matches("ab?(c)", "abc"));
+        assert!(matches("a*(xy)b", "axyxyb"));
+        assert!(!matches("a+(xy)b", "ab"));
+        assert!(matches("a+(xy)b", "axyb"));
+        // Without extglob the group is plain text
+        assert!(!Pattern::new("@(a|b)", false).matches("a"));
+    }
+
+    #[test]
+    fn test_expand_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/nested")).unwrap();
+        fs::create_dir(root.join(".hidden")).unwrap();
+        for file in [
+            "a.rs",
+            ".dot.rs",
+            "b.txt",
+            "src/c.rs",
+            "src/nested/d.rs",
+            ".hidden/e.rs",
+        ] {
+            fs::write(root.join(file), "").unwrap();
+        }
+        let mut options = ShellOptions::default();
+
+        assert_eq!(expand("plain", root, &options), None);
+        assert_eq!(
+            expand("*.rs", root, &options),
+            Some(vec!["a.rs".to_string()])
+        );
+        assert_eq!(
+            expand("**/*.rs", root, &options),
+            Some(vec![
+                "a.rs".to_string(),
+                "src/c.rs".to_string(),
+                "src/nested/d.rs".to_string()
+            ])
+        );
+        assert_eq!(expand("*/", root, &options), Some(vec!["src/".to_string()]));
+
+        options.dotglob = true;
+        assert_eq!(
+            expand("*.rs", root, &options),
+            Some(vec![".dot.rs".to_string(), "a.rs".to_string()])
+        );
+        assert!(
+            expand("**/*.rs", root, &options)
+                .unwrap()
+                .contains(&".hidden/e.rs".to_string())
+        );
+
+        options.dotglob = false;
+        options.globstar = false;
+        assert_eq!(
+            expand("**/*.rs", root, &options),
+            Some(vec!["src/c.rs".to_string()])
+        );
+
+        options.extglob = true;
+        assert_eq!(
+            expand("!(*.rs)", root, &options),
+            Some(vec!["b.txt".to_string(), "src".to_string()])
+        );
+        assert_eq!(expand("*.md", root, &options), Some(vec![]));
+    }
+}
@@ -1,7 +1,7 @@
 use super::ast::{get_pos_word, get_string};
 use super::expansion::{expand_alias, expand_alias_tilde};
 use super::{Rule, ShellParser};
-use crate::environment::Environment;
+use crate::environment::{Environment, ShellOptions};
 use anyhow::Result;
 use pest::Parser;
 use std::cell::RefCell;
@@ -188,8 +188,13 @@ fn expand_alias_preserves_input_redirect() {
     let mut pairs = ShellParser::parse(Rule::simple_command, "cat < input.txt")
         .unwrap_or_else(|e| panic!("{}", e));
     let alias_simple = pairs.next().unwrap();
-    let tokens = expand_alias_tilde(alias_simple, &env.read().alias, &PathBuf::from("."))
-        .expect("tokenize redirect");
+    let tokens = expand_alias_tilde(
+        alias_simple,
+        &env.read().alias,
+        &PathBuf::from("."),
+        &ShellOptions::default(),
+    )
+    .expect("tokenize redirect");
     assert_eq!(tokens, vec!["cat", "<", "input.txt"]);
 
     let result =
@@ -197,6 +202,133 @@ fn expand_alias_preserves_input_redirect() {
     assert_eq!(result, "cat < input.txt");
 }
 
+#[test]
+fn parse_heredoc_redirect() {
+    init();
+    let mut pairs = ShellParser::parse(Rule::commands, "cat <<'EOF'\nEOFX $HOME\n\nEOF | wc -l")
+        .unwrap_or_else(|e| panic!("{}", e));
+    let commands = pairs.next().expect("commands");
+    assert_eq!(commands.as_str(), "cat <<'EOF'\nEOFX $HOME\n\nEOF | wc -l");
+
+    let heredoc = commands
+        .into_inner()
+        .flatten()
+        .find(|p| p.as_rule() == Rule::heredoc)
+        .expect("heredoc");
+    let mut inner = heredoc.into_inner();
+    assert_eq!(
+        Rule::heredoc_redirect_direction,
+        inner.next().unwrap().as_rule()
+    );
+    assert_eq!("'EOF'", inner.next().unwrap().as_str());
+    let body = inner.next().expect("heredoc body");
+    assert_eq!(Rule::heredoc_body, body.as_rule());
+    assert_eq!("EOFX $HOME\n\n", body.as_str());
+}
+
+#[test]
+fn parse_herestring_redirect() {
+    init();
+    let mut pairs = ShellParser::parse(Rule::simple_command, "cat <<< 'a b'")
+        .unwrap_or_else(|e| panic!("{}", e));
+    let redirect = pairs
+        .next()
+        .unwrap()
+        .into_inner()
+        .flatten()
+        .find(|p| p.as_rule() == Rule::redirect)
+        .expect("redirect");
+    let mut inner = redirect.into_inner();
+    assert_eq!(
+        Rule::herestring_redirect_direction,
+        inner.next().unwrap().as_rule()
+    );
+    assert_eq!("'a b'", inner.next().unwrap().as_str());
+}
+
+#[test]
+fn parse_fd_redirects() {
+    init();
+    let mut pairs = ShellParser::parse(Rule::simple_command, "cmd 2>&1 3> log >&- 0<&3")
+        .unwrap_or_else(|e| panic!("{}", e));
+    let redirects: Vec<Vec<(Rule, &str)>> = pairs
+        .next()
+        .unwrap()
+        .into_inner()
+        .flatten()
+        .filter(|p| p.as_rule() == Rule::redirect)
+        .map(|p| p.into_inner().map(|p| (p.as_rule(), p.as_str())).collect())
+        .collect();
+    assert_eq!(
+        redirects,
+        vec![
+            vec![
+                (Rule::dup_redirect_direction, "2>&"),
+                (Rule::dup_redirect_target, "1")
+            ],
+            vec![(Rule::fd_redirect_direction, "3>"), (Rule::span, "log")],
+            vec![
+                (Rule::dup_redirect_direction, ">&"),
+                (Rule::dup_redirect_target, "-")
+            ],
+            vec![
+                (Rule::dup_redirect_direction, "0<&"),
+                (Rule::dup_redirect_target, "3")
+            ],
+        ]
+    );
+
+    let env = Environment::new();
+    let mut pairs = ShellParser::parse(Rule::simple_command, "cmd > out 2>&1 3>>log")
+        .unwrap_or_else(|e| panic!("{}", e));
+    let tokens = expand_alias_tilde(
+        pairs.next().unwrap(),
+        &env.read().alias,
+        &PathBuf::from("."),
+        &ShellOptions::default(),
+    )
+    .expect("tokenize redirects");
+    assert_eq!(tokens, vec!["cmd", ">", "out", "2>&", "1", "3>>", "log"]);
+
+    // The target may be a parameter, like the descriptors of a coprocess
+    let mut pairs = ShellParser::parse(Rule::simple_command, "cmd >&${CALC[1]}")
+        .unwrap_or_else(|e| panic!("{}", e));
+    let target = pairs
+        .next()
+        .unwrap()
+        .into_inner()
+        .flatten()
+        .find(|p| p.as_rule() == Rule::dup_redirect_target)
+        .expect("dup target");
+    assert_eq!(target.as_str(), "${CALC[1]}");
+}
+
+#[test]
+fn parse_clobber_redirects() {
+    init();
+    let mut pairs = ShellParser::parse(Rule::simple_command, "cmd >| out 2>|err 3>| log")
+        .unwrap_or_else(|e| panic!("{}", e));
+    let redirects: Vec<Vec<(Rule, &str)>> = pairs
+        .next()
+        .unwrap()
+        .into_inner()
+        .flatten()
+        .filter(|p| p.as_rule() == Rule::redirect)
+        .map(|p| p.into_inner().map(|p| (p.as_rule(), p.as_str())).collect())
+        .collect();
+    assert_eq!(
+        redirects,
+        vec![
+            vec![(Rule::stdout_redirect_direction, ">|"), (Rule::span, "out")],
+            vec![
+                (Rule::stderr_redirect_direction, "2>|"),
+                (Rule::span, "err")
+            ],
+            vec![(Rule::fd_redirect_direction, "3>|"), (Rule::span, "log")],
+        ]
+    );
+}
+
 #[test]
 fn parse_simple_command3() {
     init();
@@ -742,6 +874,25 @@ fn parse_proc_subst() {
     }
 }
 
+#[test]
+fn parse_output_proc_subst() {
+    init();
+    let pairs = ShellParser::parse(Rule::simple_command, "tee >(gzip) >(sha256sum) > out")
+        .unwrap_or_else(|e| panic!("{}", e));
+
+    let mut substs = Vec::new();
+    let mut redirects = Vec::new();
+    for pair in pairs.flatten() {
+        match pair.as_rule() {
+            Rule::proc_subst => substs.push(pair.as_str()),
+            Rule::redirect => redirects.push(pair.as_str()),
+            _ => {}
+        }
+    }
+    assert_eq!(substs, vec![">(gzip)", ">(sha256sum)"]);
+    assert_eq!(redirects, vec!["> out"]);
+}
+
 #[test]
 fn test_exec_subshell() {
     init();
@@ -788,6 +939,108 @@ fn test_variable() {
     assert!(find);
 }
 
+#[test]
+fn parse_braced_and_special_variables() {
+    init();
+    let pairs = ShellParser::parse(Rule::simple_command, r#"echo ${name:-a b}x $? "q $HOME""#)
+        .unwrap_or_else(|e| panic!("{}", e));
+    let spans: Vec<(Rule, &str)> = pairs
+        .flatten()
+        .filter(|p| matches!(p.as_rule(), Rule::variable | Rule::literal_d_quoted))
+        .map(|p| (p.as_rule(), p.as_str()))
+        .collect();
+    assert_eq!(
+        spans,
+        vec![
+            (Rule::variable, "${name:-a b}"),
+            (Rule::variable, "$?"),
+            (Rule::literal_d_quoted, "q $HOME"),
+        ]
+    );
+}
+
+#[test]
+fn test_parameter_expansion() {
+    init();
+    let env = Environment::new();
+    {
+        let mut env = env.write();
+        env.variables
+            .insert("file".to_string(), "archive.tar.gz".to_string());
+        env.variables.insert("x".to_string(), "v".to_string());
+        env.variables
+            .insert("spaced".to_string(), "a \"b\"".to_string());
+        env.variables.insert("empty".to_string(), "".to_string());
+    }
+    let expand = |input: &str| expand_alias(input.to_string(), Arc::clone(&env)).unwrap();
+
+    assert_eq!(expand("echo L$x ${x}suffix"), "echo Lv vsuffix");
+    assert_eq!(expand("echo x"), "echo x");
+    assert_eq!(expand("echo ${#file}"), "echo 14");
+    assert_eq!(
+        expand("echo ${file%.*} ${file%%.*} ${file#*.} ${file##*.}"),
+        "echo archive.tar archive tar.gz gz"
+    );
+    assert_eq!(
+        expand("echo ${file/a/A} ${file//a/A} ${file/#arc/ARC} ${file/%gz/xz}"),
+        "echo Archive.tar.gz Archive.tAr.gz ARChive.tar.gz archive.tar.xz"
+    );
+    assert_eq!(
+        expand("echo ${file:0:7} ${file: -2} ${file:8:-3} ${x^^} ${file^}"),
+        "echo archive gz tar V Archive.tar.gz"
+    );
+    assert_eq!(
+        expand("echo ${unset:-default} ${empty-kept} ${empty:+alt} ${x:+alt}"),
+        "echo default \"\" \"\" alt"
+    );
+    assert_eq!(expand("echo $unset"), "echo \"\"");
+    assert_eq!(
+        expand(r#"echo "<$spaced>" $spaced"#),
+        r#"echo "<a \"b\">" "a \"b\"""#
+    );
+    assert_eq!(expand("echo '$x' \\$x"), "echo '$x' \\$x");
+    assert_eq!(
+        expand("echo ${new:=assigned} $new"),
+        "echo assigned assigned"
+    );
+    assert_eq!(
+        env.read().variables.get("new").map(String::as_str),
+        Some("assigned")
+    );
+
+    let err = expand_alias("echo ${unset:?is required}".to_string(), Arc::clone(&env)).unwrap_err();
+    assert_eq!(err.to_string(), "unset: is required");
+    assert!(expand_alias("echo ${x!}".to_string(), Arc::clone(&env)).is_err());
+}
+
+#[test]
+fn parse_arithmetic() {
+    init();
+    let pairs = ShellParser::parse(Rule::commands, "(( i < (n + 1) )) && echo $((i * 2))x")
+        .unwrap_or_else(|e| panic!("{}", e));
+    let spans: Vec<(Rule, &str)> = pairs
+        .flatten()
+        .filter(|p| matches!(p.as_rule(), Rule::arith_command | Rule::arith_subst))
+        .map(|p| (p.as_rule(), p.as_str()))
+        .collect();
+    assert_eq!(
+        spans,
+        vec![
+            (Rule::arith_command, "(( i < (n + 1) ))"),
+            (Rule::arith_subst, "$((i * 2))"),
+        ]
+    );
+
+    let env = Environment::new();
+    env.write()
+        .variables
+        .insert("i".to_string(), "3".to_string());
+    let expand = |input: &str| expand_alias(input.to_string(), Arc::clone(&env)).unwrap();
+    assert_eq!(expand("echo $((i * 2))x \"$(( 7 % 3 ))\""), "echo 6x \"1\"");
+    assert_eq!(expand("echo $((i++)) $i"), "echo 3 4");
+    assert!(expand_alias("echo $((1 / 0))".to_string(), Arc::clone(&env)).is_err());
+}
+
 #[test]
 fn test_redirect() {
     init();
@@ -964,7 +1217,12 @@ fn test_glob_expansion() -> Result<()> {
     let pairs = ShellParser::parse(Rule::glob_word, "*.txt").unwrap_or_else(|e| panic!("{}", e));
 
     for pair in pairs {
-        let expanded = expand_alias_tilde(pair, alias, &dir.path().to_path_buf())?;
+        let expanded = expand_alias_tilde(
+            pair,
+            alias,
+            &dir.path().to_path_buf(),
+            &ShellOptions::default(),
+        )?;
         // Should contain glob_test_a.txt and glob_test_b.txt (quoted or not?)
         // expand_alias_tilde returns fully qualified paths if using absolute root?
         // Or relative?
@@ -995,7 +1253,12 @@ fn test_glob_no_match() -> Result<()> {
     let pairs = ShellParser::parse(Rule::glob_word, "*.rs").unwrap_or_else(|e| panic!("{}", e));
 
     for pair in pairs {
-        let expanded = expand_alias_tilde(pair, alias, &dir.path().to_path_buf())?;
+        let expanded = expand_alias_tilde(
+            pair,
+            alias,
+            &dir.path().to_path_buf(),
+            &ShellOptions::default(),
+        )?;
         // Should return literal if no match
         assert_eq!(expanded.len(), 1);
         assert_eq!(expanded[0], "*.rs");
@@ -1003,6 +1266,43 @@ fn test_glob_no_match() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_glob_no_match_options() -> Result<()> {
+    init();
+    let dir = tempfile::tempdir()?;
+    let env = crate::environment::Environment::new();
+    let alias = &env.read().alias;
+    let mut options = ShellOptions {
+        nullglob: true,
+        ..ShellOptions::default()
+    };
+
+    let pair = ShellParser::parse(Rule::glob_word, "*.tmp")?
+        .next()
+        .unwrap();
+    let expanded = expand_alias_tilde(pair.clone(), alias, &dir.path().to_path_buf(), &options)?;
+    assert!(expanded.is_empty());
+
+    options.failglob = true;
+    let err = expand_alias_tilde(pair, alias, &dir.path().to_path_buf(), &options)
+        .expect_err("failglob rejects the pattern");
+    assert_eq!(err.to_string(), "no match: *.tmp");
+    Ok(())
+}
+
+#[test]
+fn parse_extglob_words() {
+    init();
+    let pairs = ShellParser::parse(Rule::simple_command, "ls !(*.rs) +(a|b).txt @(x)")
+        .unwrap_or_else(|e| panic!("{}", e));
+    let globs: Vec<&str> = pairs
+        .flatten()
+        .filter(|p| p.as_rule() == Rule::glob_word)
+        .map(|p| p.as_str())
+        .collect();
+    assert_eq!(globs, vec!["!(*.rs)", "+(a|b).txt", "@(x)"]);
+}
+
 #[test]
 fn test_glob_question_mark() -> Result<()> {
     init();
@@ -1018,7 +1318,12 @@ fn test_glob_question_mark() -> Result<()> {
         ShellParser::parse(Rule::glob_word, "file?.txt").unwrap_or_else(|e| panic!("{}", e));
 
     for pair in pairs {
-        let expanded = expand_alias_tilde(pair, alias, &dir.path().to_path_buf())?;
+        let expanded = expand_alias_tilde(
+            pair,
+            alias,
+            &dir.path().to_path_buf(),
+            &ShellOptions::default(),
+        )?;
         assert_eq!(expanded.len(), 2);
         let s = expanded.join(" ");
         assert!(s.contains("file1.txt"));
@@ -1043,7 +1348,12 @@ fn test_glob_character_class() -> Result<()> {
         ShellParser::parse(Rule::glob_word, "file[0-9].txt").unwrap_or_else(|e| panic!("{}", e));
 
     for pair in pairs {
-        let expanded = expand_alias_tilde(pair, alias, &dir.path().to_path_buf())?;
+        let expanded = expand_alias_tilde(
+            pair,
+            alias,
+            &dir.path().to_path_buf(),
+            &ShellOptions::default(),
+        )?;
         assert_eq!(expanded.len(), 2);
         let s = expanded.join(" ");
         assert!(s.contains("file1.txt"));
@@ -1068,7 +1378,12 @@ fn test_glob_subdirectory() -> Result<()> {
     let pairs = ShellParser::parse(Rule::glob_word, "sub/*.rs").unwrap_or_else(|e| panic!("{}", e));
 
     for pair in pairs {
-        let expanded = expand_alias_tilde(pair, alias, &dir.path().to_path_buf())?;
+        let expanded = expand_alias_tilde(
+            pair,
+            alias,
+            &dir.path().to_path_buf(),
+            &ShellOptions::default(),
+        )?;
         assert_eq!(expanded.len(), 1);
         let s = expanded[0].clone();
         assert!(s.contains("sub"));
@@ -1098,7 +1413,12 @@ fn test_recursive_glob() -> Result<()> {
     let pairs = ShellParser::parse(Rule::glob_word, "**/*.rs").unwrap_or_else(|e| panic!("{}", e));
 
     for pair in pairs {
-        let expanded = expand_alias_tilde(pair, alias, &dir.path().to_path_buf())?;
+        let expanded = expand_alias_tilde(
+            pair,
+            alias,
+            &dir.path().to_path_buf(),
+            &ShellOptions::default(),
+        )?;
         // Should find all 3 .rs files
         assert_eq!(expanded.len(), 3);
         let s = expanded.join(" ");
@@ -1,13 +1,103 @@
 use anyhow::Result;
 use dsh_types::{Context, ExitStatus};
+use futures::future::{FutureExt, LocalBoxFuture};
 use libc::{STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO};
-use nix::unistd::Pid;
+use nix::unistd::{Pid, getpid};
 use std::os::unix::io::RawFd;
+use std::path::Path;
 use tracing::debug;
 
 use super::job_process::JobProcess;
+use super::redirect::Redirect;
 use super::state::ProcessState;
-use crate::shell::Shell;
+use super::wait::wait_pid_job;
+use crate::proxy::builtin::jobs;
+use crate::script;
+use crate::shell::{Shell, function};
+
+/// Commands that run shell code, awaited by the job instead of called through `cmd_fn`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellCall {
+    /// A function defined with `name() { ... }`.
+    Function,
+    /// `source FILE` and `. FILE`.
+    Source,
+    /// `wait`, which runs the traps of the signals that interrupt it.
+    Wait,
+}
+
+impl ShellCall {
+    /// The call behind a builtin that runs shell code.
+    pub fn for_builtin(name: &str) -> Option<ShellCall> {
+        match name {
+            "source" | "." => Some(ShellCall::Source),
+            "wait" => Some(ShellCall::Wait),
+            _ => None,
+        }
+    }
+
+    // Boxed, as running shell code launches jobs again
+    fn run<'a>(
+        self,
+        ctx: &'a Context,
+        argv: Vec<String>,
+        shell: &'a mut Shell,
+    ) -> LocalBoxFuture<'a, ExitStatus> {
+        match self {
+            ShellCall::Function => call_function(ctx, argv, shell).boxed_local(),
+            ShellCall::Source => source(ctx, argv, shell).boxed_local(),
+            ShellCall::Wait => wait(ctx, argv, shell).boxed_local(),
+        }
+    }
+}
+
+/// Stands in for the `cmd_fn` of a `ShellCall`, which the job awaits instead.
+fn awaited_by_job(
+    _ctx: &Context,
+    _argv: Vec<String>,
+    _proxy: &mut dyn dsh_builtin::ShellProxy,
+) -> ExitStatus {
+    ExitStatus::ExitedWith(1)
+}
+
+async fn call_function(ctx: &Context, argv: Vec<String>, shell: &mut Shell) -> ExitStatus {
+    let name = argv.first().cloned().unwrap_or_default();
+    match function::call_function(shell, ctx, argv).await {
+        Ok(status) => ExitStatus::ExitedWith(status),
+        Err(e) => {
+            ctx.write_stderr(&format!("{name}: {e}\n")).ok();
+            ExitStatus::ExitedWith(1)
+        }
+    }
+}
+
+async fn source(ctx: &Context, argv: Vec<String>, shell: &mut Shell) -> ExitStatus {
+    let Some(path) = argv.get(1) else {
+        ctx.write_stderr(&format!(
+            "{0}: filename argument required\nusage: {0} FILE [ARGS...]\n",
+            argv[0]
+        ))
+        .ok();
+        return ExitStatus::ExitedWith(2);
+    };
+    match script::source_file(shell, ctx, Path::new(path), argv[2..].to_vec()).await {
+        Ok(status) => ExitStatus::ExitedWith(status),
+        Err(err) => {
+            ctx.write_stderr(&format!("{}: {err}\n", argv[0])).ok();
+            ExitStatus::ExitedWith(1)
+        }
+    }
+}
+
+async fn wait(ctx: &Context, argv: Vec<String>, shell: &mut Shell) -> ExitStatus {
+    match jobs::wait_for_jobs(shell, ctx, argv).await {
+        Ok(status) => ExitStatus::ExitedWith(status),
+        Err(e) => {
+            ctx.write_stderr(&format!("wait: {e}")).ok();
+            ExitStatus::ExitedWith(2)
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct BuiltinProcess {
@@ -22,6 +112,10 @@ pub struct BuiltinProcess {
     pub stderr: RawFd,
     pub(crate) cap_stdout: Option<RawFd>,
     pub(crate) cap_stderr: Option<RawFd>,
+    pub(crate) redirects: Vec<Redirect>,
+    /// `exec` without a command: the redirections stay in effect for the shell.
+    pub(crate) keep_redirects: bool,
+    pub(crate) shell_call: Option<ShellCall>,
 }
 
 impl PartialEq for BuiltinProcess {
@@ -64,21 +158,31 @@ impl BuiltinProcess {
             stderr: STDERR_FILENO,
             cap_stdout: None,
             cap_stderr: None,
+            redirects: Vec::new(),
+            keep_redirects: false,
+            shell_call: None,
         }
     }
 
+    /// A command the shell runs itself, such as a call to a shell function.
+    pub fn shell_call(name: String, call: ShellCall, argv: Vec<String>) -> Self {
+        let mut process = BuiltinProcess::new(name, awaited_by_job, argv);
+        process.shell_call = Some(call);
+        process
+    }
+
     pub fn set_state(&mut self, pid: Pid, state: ProcessState) -> bool {
-        if let Some(ref mut next) = self.next {
-            if next.set_state_pid(pid, state) {
-                return true;
-            }
-            // Check if this process matches the PID
-            if let Some(self_pid) = self.pid
-                && self_pid == pid
-            {
-                self.state = state;
-                return true;
-            }
+        if let Some(ref mut next) = self.next
+            && next.set_state_pid(pid, state)
+        {
+            return true;
+        }
+        // Check if this process matches the PID
+        if let Some(self_pid) = self.pid
+            && self_pid == pid
+        {
+            self.state = state;
+            return true;
         }
         false
     }
@@ -94,15 +198,14 @@ impl BuiltinProcess {
         }
     }
 
-    pub fn launch(&mut self, ctx: &mut Context, shell: &mut Shell) -> Result<()> {
-        let exit = (self.cmd_fn)(ctx, self.argv.to_vec(), shell);
+    pub async fn launch(&mut self, ctx: &mut Context, shell: &mut Shell) -> Result<()> {
+        let exit = match self.shell_call {
+            Some(call) => call.run(ctx, self.argv.to_vec(), shell).await,
+            None => (self.cmd_fn)(ctx, self.argv.to_vec(), shell),
+        };
         match exit {
             ExitStatus::ExitedWith(code) => {
-                if code >= 0 {
-                    self.state = ProcessState::Completed(1, None);
-                } else {
-                    self.state = ProcessState::Completed(0, None);
-                }
+                self.state = ProcessState::Completed((code & 0xff) as u8, None);
                 debug!("Builtin process {} exited with code: {}", self.name, code);
             }
             ExitStatus::Running(_pid) => {
@@ -121,10 +224,19 @@ impl BuiltinProcess {
     }
 
     pub(crate) fn update_state(&mut self) -> Option<ProcessState> {
+        // A builtin run in the background is a forked child of the shell
+        if self.state == ProcessState::Running
+            && let Some(pid) = self.pid
+            && pid != getpid()
+            && let Some((_waited_pid, state)) = wait_pid_job(pid, true)
+        {
+            self.state = state;
+        }
+
         if let Some(next) = self.next.as_mut() {
             next.update_state()
         } else {
-            None
+            self.pid.map(|_| self.state)
         }
     }
 }
@@ -4,10 +4,14 @@ use tracing::debug;
 
 use super::builtin::BuiltinProcess;
 use super::process::Process;
+use crate::parser::compound::Command;
 use crate::shell::Shell;
+use crate::shell::eval::{child_runtime, eval_command, exit_child};
 use dsh_types::Context;
-use libc::{STDERR_FILENO, STDOUT_FILENO};
+use libc::{STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO};
 use nix::unistd::pipe;
+use std::convert::Infallible;
+use std::io::Write;
 use std::os::fd::IntoRawFd;
 
 pub(crate) fn fork_builtin_process(
@@ -21,6 +25,9 @@ pub(crate) fn fork_builtin_process(
         "🍴 BUILTIN: About to fork builtin process: {}",
         process.name
     );
+    // Output the shell still buffers would otherwise be written by the child as well
+    std::io::stdout().flush().ok();
+    std::io::stderr().flush().ok();
     let pid = unsafe { fork().context("failed fork for builtin")? };
 
     match pid {
@@ -44,12 +51,18 @@ pub(crate) fn fork_builtin_process(
             // Execute the builtin command
             // Note: process.launch might still use tracing internally if not careful.
             // Ideally builtins should be careful too, but at least we removed the immediate logging.
-            if let Err(_e) = process.launch(ctx, shell) {
-                std::process::exit(1);
-            }
+            let Ok(runtime) = child_runtime() else {
+                unsafe { libc::_exit(1) };
+            };
+            let status = match runtime.block_on(process.launch(ctx, shell)) {
+                Ok(()) => process.state.exit_status().unwrap_or(0),
+                Err(_e) => 1,
+            };
 
-            // Builtin commands complete immediately, so exit with success
-            std::process::exit(0);
+            // Flush once and leave without running the shell's exit handlers
+            std::io::stdout().flush().ok();
+            std::io::stderr().flush().ok();
+            unsafe { libc::_exit(status) };
         }
     }
 }
@@ -75,8 +88,8 @@ pub(crate) fn fork_process(
         ctx.infile, ctx.outfile, ctx.errfile
     );
 
-    // capture
-    if ctx.outfile == STDOUT_FILENO && !ctx.foreground && pty_slave.is_none() {
+    // Capture background output in the REPL, so that it does not run into the prompt
+    if ctx.outfile == STDOUT_FILENO && !ctx.foreground && ctx.interactive && pty_slave.is_none() {
         debug!("🍴 FORK: Creating capture pipe for stdout (background process)");
         let (pout, pin) = pipe().context("failed pipe")?;
         process.stdout = pin.into_raw_fd();
@@ -93,7 +106,7 @@ pub(crate) fn fork_process(
         );
     }
 
-    if ctx.errfile == STDERR_FILENO && !ctx.foreground && pty_slave.is_none() {
+    if ctx.errfile == STDERR_FILENO && !ctx.foreground && ctx.interactive && pty_slave.is_none() {
         debug!("🍴 FORK: Creating capture pipe for stderr (background process)");
         let (pout, pin) = pipe().context("failed pipe")?;
         process.stderr = pin.into_raw_fd();
@@ -115,6 +128,10 @@ pub(crate) fn fork_process(
         process.stdin, process.stdout, process.stderr
     );
 
+    if let Some(body) = process.compound.clone() {
+        return fork_compound(ctx, job_pgid, process, shell, pty_slave, &body);
+    }
+
     debug!("🍴 FORK: About to fork external process");
 
     // Prepare execution data BEFORE forking to avoid allocation/locks in child
@@ -154,3 +171,67 @@ pub(crate) fn fork_process(
         }
     }
 }
+
+/// Forks a copy of the shell that runs the compound command of a pipeline stage, with
+/// the stage's pipes and redirections as its standard descriptors.
+// Same fork-without-exec caveats as `spawn_subshell`.
+fn fork_compound(
+    ctx: &Context,
+    job_pgid: Option<Pid>,
+    process: &mut Process,
+    shell: &mut Shell,
+    pty_slave: Option<std::os::unix::io::RawFd>,
+    body: &Command,
+) -> Result<Pid> {
+    debug!("🍴 FORK: About to fork compound command {}", process.cmd);
+    match unsafe { fork().context("failed fork")? } {
+        ForkResult::Parent { child } => Ok(child),
+        ForkResult::Child => {
+            // SAFETY: Do NOT use tracing here. Unsafe after fork.
+            let pid = getpid();
+            let pgid = job_pgid.unwrap_or(pid);
+            if process
+                .enter_child(pid, pgid, ctx.interactive, ctx.foreground, pty_slave)
+                .is_err()
+            {
+                exit_child(1);
+            }
+
+            let mut ctx = ctx.clone();
+            ctx.infile = STDIN_FILENO;
+            ctx.outfile = STDOUT_FILENO;
+            ctx.errfile = STDERR_FILENO;
+            ctx.captured_out = None;
+            ctx.interactive = false;
+            ctx.foreground = true;
+            ctx.pgid = Some(pgid);
+            let Ok(runtime) = child_runtime() else {
+                exit_child(1);
+            };
+            let status = runtime
+                .block_on(eval_command(shell, &mut ctx, body, false))
+                .unwrap_or(1);
+            exit_child(status);
+        }
+    }
+}
+
+/// Replaces the shell with `process`, for `exec CMD`.
+///
+/// This is the child side of [`fork_process`] run by the shell itself. The command
+/// keeps the shell's pid and process group, so only the signal dispositions the
+/// shell changed are reset before the descriptors are set up and `execve` runs.
+pub(crate) fn exec_process(
+    ctx: &Context,
+    process: &mut Process,
+    shell: &mut Shell,
+) -> Result<Infallible> {
+    debug!("exec_process: replacing the shell with {}", process.cmd);
+    let prepared = process.prepare_execution(shell.environment.clone())?;
+    if ctx.interactive {
+        process.set_signals()?;
+    }
+    let pid = getpid();
+    process.launch_prepared(pid, pid, false, ctx.foreground, prepared, None)?;
+    unreachable!("execve only returns on failure")
+}
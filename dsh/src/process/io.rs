@@ -1,15 +1,18 @@
-use anyhow::{Context as _, Result};
+use anyhow::{Context as _, Result, bail};
 use nix::fcntl::{FcntlArg, OFlag, fcntl};
-use nix::unistd::{isatty, pipe};
+use nix::unistd::{isatty, pipe, pipe2};
 use std::io::{Read, Write};
-use std::os::fd::BorrowedFd;
+use std::os::fd::{BorrowedFd, OwnedFd};
 use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::path::Path;
 use std::time::Duration;
 use tokio::io::AsyncBufReadExt;
 use tokio::io::unix::AsyncFd;
 use tokio::{fs, io, time};
 
-use super::redirect::Redirect;
+use super::redirect::{FdOp, Redirect};
+use crate::environment::Environment;
+use crate::parser::heredoc::expand_heredoc_body;
 use crate::terminal::renderer::TerminalRenderer;
 use dsh_types::Context;
 use libc::STDIN_FILENO;
@@ -266,57 +269,219 @@ pub(crate) fn create_pipe(ctx: &mut Context) -> Result<Option<RawFd>> {
     Ok(Some(pout.into_raw_fd()))
 }
 
-pub(crate) fn handle_output_redirect(
-    ctx: &mut Context,
-    redirect: &Option<Redirect>,
-    stdout: RawFd,
-) -> Result<Option<RawFd>> {
-    if let Some(output) = redirect {
-        match output {
-            Redirect::StdoutOutput(_file) | Redirect::StdoutAppend(_file) => {
-                let (pout, pin) = pipe().context("failed pipe")?;
-                ctx.outfile = pin.into_raw_fd();
-                Ok(Some(pout.into_raw_fd()))
-            }
-            Redirect::StderrOutput(file) | Redirect::StderrAppend(file) => {
-                tracing::debug!("🔀 REDIRECT: StderrOutput/Append to file: {}", file);
-                let (pout, pin) = pipe().context("failed pipe")?;
-                tracing::debug!(
-                    "🔀 REDIRECT: Created redirect pipe - read_end={:?}, write_end={:?}",
-                    pout,
-                    pin
-                );
-                ctx.errfile = pin.into_raw_fd();
-                tracing::debug!("🔀 REDIRECT: Set ctx.errfile={}", ctx.errfile);
-                Ok(Some(pout.into_raw_fd()))
-            }
-            Redirect::StdouterrOutput(file) | Redirect::StdouterrAppend(file) => {
-                tracing::debug!("🔀 REDIRECT: StdouterrOutput/Append to file: {}", file);
-                let (pout, pin) = pipe().context("failed pipe")?;
-                tracing::debug!(
-                    "🔀 REDIRECT: Created redirect pipe - read_end={:?}, write_end={:?}",
-                    pout,
-                    pin
-                );
-                ctx.outfile = pin.as_raw_fd(); // Keep alive for errfile
-                ctx.errfile = pin.into_raw_fd();
-                tracing::debug!(
-                    "🔀 REDIRECT: Set ctx.outfile={}, ctx.errfile={}",
-                    ctx.outfile,
-                    ctx.errfile
-                );
-                Ok(Some(pout.into_raw_fd()))
+/// Points the last process of a pipeline back at the job's stdout (or the capture set
+/// up by the caller) after the previous processes wrote into pipes.
+pub(crate) fn reset_last_output(ctx: &mut Context, stdout: RawFd) {
+    if let Some(out) = ctx.captured_out {
+        ctx.outfile = out;
+    } else if ctx.infile != STDIN_FILENO {
+        ctx.outfile = stdout;
+    }
+}
+
+/// Lowest descriptor used for files opened by redirections, out of the way of the
+/// numbers scripts use themselves.
+const REDIRECT_FD_BASE: RawFd = 10;
+
+/// Files opened for the redirections of one command and the steps that apply them.
+#[derive(Debug, Default)]
+pub(crate) struct RedirectFds {
+    /// Close-on-exec descriptors that must stay open until the command has started.
+    files: Vec<OwnedFd>,
+    pub(crate) ops: Vec<FdOp>,
+    /// Leading steps that hand the descriptors of `exec 3>file` to the command.
+    inherited: usize,
+}
+
+impl RedirectFds {
+    /// Applies the steps to the standard descriptors of a builtin running in the shell.
+    ///
+    /// Builtins only see stdin, stdout and stderr. Other descriptors are followed so
+    /// that `1>&3` writes where fd 3 points; closing a standard descriptor is ignored.
+    pub(crate) fn apply_to_std(&self, mut fds: [RawFd; 3]) -> [RawFd; 3] {
+        let mut others: Vec<(RawFd, RawFd)> = Vec::new();
+        for op in &self.ops {
+            match *op {
+                FdOp::Dup { source, fd } => {
+                    let source = match source {
+                        0..=2 => fds[source as usize],
+                        _ => others
+                            .iter()
+                            .find(|(other, _)| *other == source)
+                            .map_or(source, |(_, target)| *target),
+                    };
+                    if (0..3).contains(&fd) {
+                        fds[fd as usize] = source;
+                    } else {
+                        others.retain(|(other, _)| *other != fd);
+                        others.push((fd, source));
+                    }
+                }
+                FdOp::Close(fd) => others.retain(|(other, _)| *other != fd),
             }
-            Redirect::Input(_) => Ok(None),
         }
-    } else {
-        if let Some(out) = ctx.captured_out {
-            ctx.outfile = out;
-        } else if ctx.infile != STDIN_FILENO {
-            ctx.outfile = stdout;
+        fds
+    }
+
+    /// Makes the steps permanent for the shell, for `exec` without a command.
+    ///
+    /// Standard descriptors are replaced in the shell itself. Others are stored in
+    /// `env.exec_fds`, so the shell's own descriptors with the same number are left alone.
+    pub(crate) fn keep(self, env: &mut Environment) -> Result<()> {
+        for op in &self.ops[self.inherited..] {
+            match *op {
+                FdOp::Dup { source, fd } => {
+                    let opened = self.files.iter().any(|file| file.as_raw_fd() == source);
+                    let source = match env.exec_fds.get(&source) {
+                        Some(file) if !opened => file.as_raw_fd(),
+                        _ => source,
+                    };
+                    if (0..3).contains(&fd) {
+                        if unsafe { libc::dup2(source, fd) } < 0 {
+                            bail!("{}: {}", fd, std::io::Error::last_os_error());
+                        }
+                    } else {
+                        let copy = fcntl(
+                            unsafe { BorrowedFd::borrow_raw(source) },
+                            FcntlArg::F_DUPFD_CLOEXEC(REDIRECT_FD_BASE),
+                        )
+                        .with_context(|| format!("{}: bad file descriptor", source))?;
+                        env.exec_fds
+                            .insert(fd, unsafe { OwnedFd::from_raw_fd(copy) });
+                    }
+                }
+                FdOp::Close(fd) => {
+                    if (0..3).contains(&fd) {
+                        unsafe { libc::close(fd) };
+                    } else {
+                        env.exec_fds.remove(&fd);
+                    }
+                }
+            }
         }
-        Ok(None)
+        Ok(())
+    }
+}
+
+/// Steps that give a command the descriptors opened with `exec 3>file`.
+pub(crate) fn exec_fd_ops(env: &Environment) -> Vec<FdOp> {
+    env.exec_fds
+        .iter()
+        .map(|(fd, file)| FdOp::Dup {
+            source: file.as_raw_fd(),
+            fd: *fd,
+        })
+        .collect()
+}
+
+/// Opens the files of `redirects` in order and turns them into [`FdOp`] steps.
+///
+/// Files are opened by the shell so errors are reported before anything runs; the
+/// child only duplicates and closes descriptors.
+pub(crate) fn open_redirects(redirects: &[Redirect], env: &Environment) -> Result<RedirectFds> {
+    let mut opened = RedirectFds {
+        ops: exec_fd_ops(env),
+        ..RedirectFds::default()
+    };
+    opened.inherited = opened.ops.len();
+    // Descriptors opened or closed so far in the sequence
+    let mut open_fds: Vec<RawFd> = vec![0, 1, 2];
+    open_fds.extend(env.exec_fds.keys());
+    let mut closed_fds: Vec<RawFd> = Vec::new();
+    for redirect in redirects {
+        let (fd, file) = match redirect {
+            Redirect::Output {
+                fd,
+                path,
+                append,
+                force,
+            } => {
+                let noclobber = !*append && !*force && env.noclobber();
+                if noclobber && redirect.clobbers() {
+                    bail!("{}: cannot overwrite existing file", path);
+                }
+                let mut options = std::fs::OpenOptions::new();
+                options.write(true).append(*append);
+                if noclobber && !Path::new(path).exists() {
+                    // Fails instead of truncating a file created since the check above
+                    options.create_new(true);
+                } else {
+                    options.create(true).truncate(!*append);
+                }
+                let file = options
+                    .open(path)
+                    .with_context(|| format!("failed to open output redirect file '{}'", path))?;
+                (*fd, OwnedFd::from(file))
+            }
+            Redirect::Input { fd, path } => {
+                let file = std::fs::File::open(path)
+                    .with_context(|| format!("failed to open input redirect file '{}'", path))?;
+                (*fd, OwnedFd::from(file))
+            }
+            Redirect::HereDoc { body, expand } => {
+                let content = if *expand {
+                    expand_heredoc_body(body, env)
+                } else {
+                    body.clone()
+                };
+                (STDIN_FILENO, feed_stdin(content)?)
+            }
+            Redirect::HereString(word) => (STDIN_FILENO, feed_stdin(format!("{word}\n"))?),
+            Redirect::Dup { fd, source } => {
+                // The shell's own descriptors are close-on-exec and not visible to commands
+                let is_open = open_fds.contains(source)
+                    || (!closed_fds.contains(source)
+                        && fcntl(
+                            unsafe { BorrowedFd::borrow_raw(*source) },
+                            FcntlArg::F_GETFD,
+                        )
+                        .is_ok_and(|flags| flags & libc::FD_CLOEXEC == 0));
+                if !is_open {
+                    bail!("{}: bad file descriptor", source);
+                }
+                opened.ops.push(FdOp::Dup {
+                    source: *source,
+                    fd: *fd,
+                });
+                open_fds.push(*fd);
+                closed_fds.retain(|closed| closed != fd);
+                continue;
+            }
+            Redirect::Close(fd) => {
+                opened.ops.push(FdOp::Close(*fd));
+                open_fds.retain(|open| open != fd);
+                closed_fds.push(*fd);
+                continue;
+            }
+        };
+
+        let moved = fcntl(&file, FcntlArg::F_DUPFD_CLOEXEC(REDIRECT_FD_BASE))
+            .context("failed to move redirect descriptor")?;
+        let file = unsafe { OwnedFd::from_raw_fd(moved) };
+        opened.ops.push(FdOp::Dup {
+            source: file.as_raw_fd(),
+            fd,
+        });
+        opened.files.push(file);
+        open_fds.push(fd);
+        closed_fds.retain(|closed| *closed != fd);
     }
+    Ok(opened)
+}
+
+/// Returns the read end of a pipe that delivers `content` and then EOF.
+///
+/// The content is written from a separate thread so bodies larger than the pipe buffer
+/// don't block the launch.
+fn feed_stdin(content: String) -> Result<OwnedFd> {
+    let (read_end, write_end) = pipe2(OFlag::O_CLOEXEC).context("failed pipe")?;
+    std::thread::spawn(move || {
+        let mut writer = std::fs::File::from(write_end);
+        if let Err(e) = writer.write_all(content.as_bytes()) {
+            tracing::debug!("here-document writer stopped: {}", e);
+        }
+    });
+    Ok(read_end)
 }
 
 #[cfg(test)]
@@ -1,8 +1,8 @@
-use anyhow::{Context as _, Result};
+use anyhow::Result;
 use libc::{STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO};
 use nix::unistd::{Pid, close, getpgrp, setpgid};
-use std::fs::File;
-use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::fd::OwnedFd;
+use std::os::unix::io::RawFd;
 use tracing::{debug, error};
 
 use super::io::OutputMonitor;
@@ -10,9 +10,10 @@ use super::job_process::JobProcess;
 use super::process::Process;
 use super::redirect::Redirect;
 use super::state::{ListOp, ProcessState, SubshellType};
-use super::wait::is_job_completed;
+use super::wait::{is_job_completed, wait_pid_job};
 use crate::process::pty::Pty;
 use crate::shell::Shell;
+use crate::shell::time::TimeFormat;
 use dsh_types::Context;
 
 use crate::process::job_pty;
@@ -30,10 +31,11 @@ pub struct Job {
     stderr: RawFd,
     pub foreground: bool,
     pub subshell: SubshellType,
-    pub redirect: Option<Redirect>,
     pub list_op: ListOp,
     pub job_id: usize,
     pub state: ProcessState,
+    /// Set by `disown -h`: the job is left running when the shell exits.
+    pub nohup: bool,
     pub(crate) monitors: Vec<OutputMonitor>,
     pub(crate) shell_pgid: Pid,
     /// Whether to capture output for $OUT variable
@@ -44,6 +46,13 @@ pub struct Job {
     pub disable_pty: bool,
     /// Lisp expressions to evaluate after command output (from |: operator)
     pub struct_pipe_exprs: Vec<String>,
+    /// Readers of the output process substitutions (`>(...)`) in the arguments; the job
+    /// is not done before they are.
+    pub(crate) proc_subst_readers: Vec<Pid>,
+    /// The shell's ends of the pipes to those readers, closed once the job is launched.
+    pub(crate) proc_subst_writers: Vec<OwnedFd>,
+    /// Set by the `time` keyword: report how long the job took.
+    pub(crate) timed: Option<TimeFormat>,
 }
 
 fn last_process_state(process: JobProcess) -> ProcessState {
@@ -77,10 +86,10 @@ impl Job {
             stderr: STDERR_FILENO,
             foreground: true,
             subshell: SubshellType::None,
-            redirect: None,
             list_op: ListOp::None,
             job_id: 1,
             state: ProcessState::Running,
+            nohup: false,
             monitors: Vec::new(),
             shell_pgid,
             capture_output: false,
@@ -89,6 +98,9 @@ impl Job {
             pty_input_task: None,
             disable_pty: false,
             struct_pipe_exprs: Vec::new(),
+            proc_subst_readers: Vec::new(),
+            proc_subst_writers: Vec::new(),
+            timed: None,
         }
     }
 
@@ -105,10 +117,10 @@ impl Job {
             stderr: STDERR_FILENO,
             foreground: true,
             subshell: SubshellType::None,
-            redirect: None,
             list_op: ListOp::None,
             job_id: 1,
             state: ProcessState::Running,
+            nohup: false,
             monitors: Vec::new(),
             shell_pgid,
             capture_output: false,
@@ -117,9 +129,46 @@ impl Job {
             pty_input_task: None,
             disable_pty: false,
             struct_pipe_exprs: Vec::new(),
+            proc_subst_readers: Vec::new(),
+            proc_subst_writers: Vec::new(),
+            timed: None,
         }
     }
 
+    /// A background job for a process the shell forked itself, such as a coprocess.
+    pub(crate) fn forked(cmd: String, shell_pgid: Pid, pid: Pid) -> Self {
+        let mut process = Process::new(cmd.clone(), vec![cmd.clone()]);
+        process.pid = Some(pid);
+        let mut job = Job::new(cmd, shell_pgid);
+        job.process = Some(Box::new(JobProcess::Command(process)));
+        job.pid = Some(pid);
+        job.pgid = Some(pid);
+        job.foreground = false;
+        job
+    }
+
+    /// Adds the processes of `other` to the end of the pipeline, along with the readers of
+    /// its output process substitutions.
+    pub(crate) fn append(&mut self, mut other: Job) {
+        if let Some(process) = other.process.take() {
+            self.set_process(*process);
+        }
+        self.proc_subst_readers
+            .append(&mut other.proc_subst_readers);
+        self.proc_subst_writers
+            .append(&mut other.proc_subst_writers);
+    }
+
+    /// Sets the stdin the job inherits, which is left open after launch.
+    pub fn set_stdin(&mut self, stdin: RawFd) {
+        self.stdin = stdin;
+    }
+
+    /// Sets the stderr the job inherits, which is left open after launch.
+    pub(crate) fn set_stderr(&mut self, stderr: RawFd) {
+        self.stderr = stderr;
+    }
+
     pub fn has_process(&self) -> bool {
         self.process.is_some()
     }
@@ -140,6 +189,93 @@ impl Job {
         }
     }
 
+    /// Files that `>` redirections of the pipeline would overwrite, which `noclobber`
+    /// refuses.
+    pub(crate) fn clobbered_files(&self) -> Vec<String> {
+        let mut files = Vec::new();
+        let mut process = self.process.clone();
+        while let Some(current) = process {
+            files.extend(
+                current
+                    .redirects()
+                    .iter()
+                    .filter_map(|redirect| match redirect {
+                        Redirect::Output { path, .. } if redirect.clobbers() => Some(path.clone()),
+                        _ => None,
+                    }),
+            );
+            process = current.next();
+        }
+        files
+    }
+
+    /// Lets the job's `>` redirections overwrite existing files, as if written `>|`.
+    pub(crate) fn force_clobber(&mut self) {
+        if let Some(process) = &mut self.process {
+            process.force_clobber();
+        }
+    }
+
+    /// Exit status of each process of the pipeline, in order.
+    ///
+    /// The job counts as done once its last process exits, so earlier stages may still be
+    /// running; they are waited for first.
+    pub async fn pipeline_status(&mut self) -> Vec<i32> {
+        for pid in self.running_pids() {
+            if let Ok(Some((pid, state))) =
+                tokio::task::spawn_blocking(move || wait_pid_job(pid, false)).await
+            {
+                self.set_process_state(pid, state);
+            }
+        }
+
+        let mut statuses = Vec::new();
+        let mut process = self.process.clone();
+        while let Some(current) = process {
+            statuses.push(current.get_state().exit_status().unwrap_or(0));
+            process = current.next();
+        }
+        statuses
+    }
+
+    /// PIDs of the processes of the pipeline that are still running.
+    pub(crate) fn running_pids(&self) -> Vec<Pid> {
+        let mut pids = Vec::new();
+        let mut process = self.process.clone();
+        while let Some(current) = process {
+            if current.get_state() == ProcessState::Running
+                && let Some(pid) = current.get_pid()
+            {
+                pids.push(pid);
+            }
+            process = current.next();
+        }
+        pids
+    }
+
+    /// Takes over the readers of output process substitutions and the shell's ends of
+    /// their pipes.
+    pub(crate) fn add_proc_subst_readers(&mut self, readers: Vec<(Pid, OwnedFd)>) {
+        for (pid, writer) in readers {
+            self.proc_subst_readers.push(pid);
+            self.proc_subst_writers.push(writer);
+        }
+    }
+
+    /// Reaps the readers of output process substitutions that have exited and returns
+    /// true once none is left.
+    pub(crate) fn reap_proc_subst_readers(&mut self, no_hang: bool) -> bool {
+        // Readers only see end of file once the shell's write ends are closed too
+        self.proc_subst_writers.clear();
+        self.proc_subst_readers.retain(|pid| {
+            !matches!(
+                wait_pid_job(*pid, no_hang),
+                Some((_, ProcessState::Completed(_, _)))
+            )
+        });
+        self.proc_subst_readers.is_empty()
+    }
+
     pub async fn launch(&mut self, ctx: &mut Context, shell: &mut Shell) -> Result<ProcessState> {
         debug!(
             "JOB_LAUNCH_START: Starting job {} launch (cmd: '{}', foreground: {}, pid: {:?})",
@@ -149,7 +285,8 @@ impl Job {
         ctx.foreground = self.foreground;
 
         // 1. Setup PTY if needed
-        let pty_slave_fd = self.setup_pty(ctx).await?;
+        let no_pty = shell.environment.read().no_pty();
+        let pty_slave_fd = self.setup_pty(ctx, no_pty).await?;
         let _pty_raw_mode_guard = job_pty::ForegroundPtyRawModeGuard::new(self, ctx);
 
         // 2. Launch processes
@@ -160,17 +297,26 @@ impl Job {
                 process.get_cmd()
             );
 
-            if let Err(e) = self.launch_process(ctx, shell, &mut process, pty_slave_fd) {
+            if let Err(e) = self
+                .launch_process(ctx, shell, &mut process, pty_slave_fd)
+                .await
+            {
                 error!(
                     "JOB_LAUNCH_PROCESS_ERROR: Failed to launch process for job {}: {}",
                     self.job_id, e
                 );
                 self.cleanup_pty_tasks().await;
+                self.proc_subst_writers.clear();
                 return Err(e);
             }
+            // The processes have their copies of the substitution pipes now
+            self.proc_subst_writers.clear();
 
             // 3. Manage execution (Foreground/Background)
             self.manage_execution(ctx).await?;
+            if ctx.foreground && is_job_completed(self) {
+                self.reap_proc_subst_readers(false);
+            }
         } else {
             debug!(
                 "JOB_LAUNCH_NO_PROCESS: Job {} has no process to launch",
@@ -195,8 +341,12 @@ impl Job {
         Ok(final_state)
     }
 
-    pub(crate) async fn setup_pty(&mut self, ctx: &mut Context) -> Result<Option<RawFd>> {
-        job_pty::setup_pty(self, ctx).await
+    pub(crate) async fn setup_pty(
+        &mut self,
+        ctx: &mut Context,
+        no_pty: bool,
+    ) -> Result<Option<RawFd>> {
+        job_pty::setup_pty(self, ctx, no_pty).await
     }
 
     #[allow(dead_code)]
@@ -216,29 +366,15 @@ impl Job {
         job_pty::capture_output_and_history(self, ctx, shell).await
     }
 
-    fn launch_process(
+    async fn launch_process(
         &mut self,
         ctx: &mut Context,
         shell: &mut Shell,
         process: &mut JobProcess,
         pty_slave: Option<RawFd>,
     ) -> Result<()> {
-        let previous_infile = ctx.infile;
-        let mut _input_file_guard: Option<File> = None;
-        let mut input_fd: Option<RawFd> = None;
-
-        if let Some(Redirect::Input(ref path)) = self.redirect {
-            let file = File::open(path)
-                .with_context(|| format!("failed to open input redirect file '{}'", path))?;
-            let fd = file.as_raw_fd();
-            ctx.infile = fd;
-            input_fd = Some(fd);
-            _input_file_guard = Some(file);
-        }
-
         // Use launch for automatic capture (modified internal logic)
-        let (pid, mut next_process) =
-            process.launch(ctx, shell, &self.redirect, self.stdout, pty_slave)?;
+        let (pid, mut next_process) = process.launch(ctx, shell, self.stdout, pty_slave).await?;
         if self.pid.is_none() {
             self.pid = Some(pid); // set process pid
         }
@@ -289,22 +425,17 @@ impl Job {
         }
 
         let (stdout, stderr) = process.get_cap_out();
-        if let Some(stdout) = stdout {
-            let monitor = OutputMonitor::new(stdout);
-            self.monitors.push(monitor);
-        }
-
-        if let Some(stderr) = stderr {
-            let monitor = OutputMonitor::new(stderr);
+        for fd in [stdout, stderr].into_iter().flatten() {
+            let mut monitor = OutputMonitor::new(fd);
+            // The first output starts on a new line to leave the prompt alone; without
+            // a prompt it is passed on unchanged, which coprocesses and pipes rely on
+            monitor.outputed = !ctx.interactive;
             self.monitors.push(monitor);
         }
 
         let (stdin, stdout, stderr) = process.get_io();
         if stdin != self.stdin {
-            let should_close = match input_fd {
-                Some(fd) => stdin != fd,
-                None => pty_slave != Some(stdin), // Don't close if it's pty_slave
-            };
+            let should_close = pty_slave != Some(stdin); // Don't close if it's pty_slave
             if should_close && let Err(e) = close(stdin) {
                 debug!("failed close stdin: {}", e);
                 // Don't error out here, just log (avoid crash if EBADF)
@@ -312,6 +443,7 @@ impl Job {
         }
         if stdout != self.stdout
             && pty_slave != Some(stdout)
+            && ctx.captured_out != Some(stdout) // owned by whoever set up the capture
             && let Err(e) = close(stdout)
         {
             debug!("failed close stdout: {}", e);
@@ -327,21 +459,9 @@ impl Job {
         self.set_process(process.to_owned());
         self.show_job_status();
 
-        if let Some(ref redirect) = self.redirect {
-            redirect.process(ctx);
-        }
-
-        if let Some(fd) = input_fd
-            && ctx.infile == fd
-        {
-            ctx.infile = previous_infile;
-        }
-
         // run next pipeline process
-        if let Some(Err(err)) = next_process
-            .take()
-            .as_mut()
-            .map(|process| self.launch_process(ctx, shell, process, pty_slave))
+        if let Some(process) = next_process.as_mut()
+            && let Err(err) = Box::pin(self.launch_process(ctx, shell, process, pty_slave)).await
         {
             debug!("err {:?}", err);
             return Err(err);
@@ -375,9 +495,12 @@ impl Job {
         job_wait::wait_job_sync(self, no_hang)
     }
 
-    pub(crate) fn set_process_state(&mut self, pid: Pid, state: ProcessState) {
-        if let Some(process) = self.process.as_mut() {
-            process.set_state_pid(pid, state);
+    /// Updates the state of the process with `pid`, returning false if it is not part of
+    /// this job.
+    pub(crate) fn set_process_state(&mut self, pid: Pid, state: ProcessState) -> bool {
+        match self.process.as_mut() {
+            Some(process) => process.set_state_pid(pid, state),
+            None => false,
         }
     }
 
@@ -444,7 +567,7 @@ impl Job {
             }
         }
 
-        let is_completed = is_job_completed(self);
+        let is_completed = is_job_completed(self) && self.reap_proc_subst_readers(true);
         debug!(
             "JOB_COMPLETION_CHECK: Job {} completion check result: {} (current state: {:?})",
             self.job_id, is_completed, self.state
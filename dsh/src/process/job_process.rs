@@ -4,11 +4,12 @@ use nix::sys::signal::Signal;
 use nix::unistd::{Pid, getpid, pipe};
 use std::os::fd::IntoRawFd;
 use std::os::unix::io::RawFd;
+use tokio::task;
 use tracing::debug;
 
 use super::builtin::BuiltinProcess;
-use super::fork::{fork_builtin_process, fork_process};
-use super::io::{create_pipe, handle_output_redirect};
+use super::fork::{exec_process, fork_builtin_process, fork_process};
+use super::io::{create_pipe, open_redirects, reset_last_output};
 use super::process::Process;
 use super::redirect::Redirect;
 use super::signal::send_signal;
@@ -91,6 +92,39 @@ impl JobProcess {
         }
     }
 
+    /// Redirections written after this command, in order.
+    pub fn redirects(&self) -> &[Redirect] {
+        match self {
+            JobProcess::Builtin(jprocess) => &jprocess.redirects,
+            JobProcess::Command(jprocess) => &jprocess.redirects,
+        }
+    }
+
+    /// Turns the plain `>` redirections of this process and the rest of the pipeline
+    /// into `>|`, so they overwrite files despite `noclobber`.
+    pub(crate) fn force_clobber(&mut self) {
+        let (redirects, next) = match self {
+            JobProcess::Builtin(jprocess) => (&mut jprocess.redirects, &mut jprocess.next),
+            JobProcess::Command(jprocess) => (&mut jprocess.redirects, &mut jprocess.next),
+        };
+        for redirect in redirects {
+            if let Redirect::Output { force, .. } = redirect {
+                *force = true;
+            }
+        }
+        if let Some(next) = next {
+            next.force_clobber();
+        }
+    }
+
+    pub fn with_redirects(mut self, redirects: Vec<Redirect>) -> Self {
+        match self {
+            JobProcess::Builtin(ref mut jprocess) => jprocess.redirects = redirects,
+            JobProcess::Command(ref mut jprocess) => jprocess.redirects = redirects,
+        }
+        self
+    }
+
     pub fn get_io(&self) -> (RawFd, RawFd, RawFd) {
         match self {
             JobProcess::Builtin(jprocess) => (jprocess.stdin, jprocess.stdout, jprocess.stderr),
@@ -109,7 +143,6 @@ impl JobProcess {
         }
     }
 
-    #[allow(dead_code)]
     pub fn get_pid(&self) -> Option<Pid> {
         match self {
             JobProcess::Builtin(_) => {
@@ -185,9 +218,10 @@ impl JobProcess {
     pub(crate) fn is_pipeline_consumer_terminated(&self) -> bool {
         // If this process has a next process, check if the consumer terminated
         if let Some(next) = self.next() {
-            // Recursively check the next process
-            if next.is_pipeline_consumer_terminated() {
-                return true;
+            // Only the last process is the consumer; a middle stage that is done leaves
+            // the stages after it running
+            if next.next().is_some() {
+                return next.is_pipeline_consumer_terminated();
             }
             // If the next process (consumer) completed normally, the pipeline should terminate
             if let ProcessState::Completed(0, None) = next.get_state() {
@@ -271,11 +305,10 @@ impl JobProcess {
         matches!(self, JobProcess::Command(_))
     }
 
-    pub fn launch(
+    pub async fn launch(
         &mut self,
         ctx: &mut Context,
         shell: &mut Shell,
-        redirect: &Option<Redirect>,
         stdout: RawFd,
         pty_slave: Option<RawFd>,
     ) -> Result<(Pid, Option<Box<JobProcess>>)> {
@@ -289,10 +322,14 @@ impl JobProcess {
             None => {
                 // Automatic capture for non-interactive mode (e.g. smart pipe tests)
                 // We don't do this in interactive mode to preserve TTY (colors, etc.)
+                // Output already going to a pipe, as in `$(...)`, is left there
                 if !ctx.interactive
-                    && redirect.is_none()
+                    && ctx.foreground
+                    && ctx.outfile == STDOUT_FILENO
+                    && self.redirects().is_empty()
                     && pty_slave.is_none()
                     && ctx.captured_out.is_none()
+                    && !matches!(self, JobProcess::Command(p) if p.replace_shell)
                 {
                     let (pout, pin) = pipe().context("failed pipe")?;
                     ctx.outfile = pin.into_raw_fd();
@@ -304,7 +341,8 @@ impl JobProcess {
                     None
                 } else {
                     // Manual capture or redirect
-                    handle_output_redirect(ctx, redirect, stdout)?
+                    reset_last_output(ctx, stdout);
+                    None
                 }
             }
         };
@@ -344,28 +382,55 @@ impl JobProcess {
 
         self.set_io(ctx.infile, ctx.outfile, ctx.errfile);
 
+        // Redirections apply on top of the pipeline; the opened files stay alive
+        // until the command has been started
+        let mut redirect_fds = open_redirects(self.redirects(), &shell.environment.read())?;
+
         // initial pid
         let current_pid = getpid();
 
         let pid = match self {
+            JobProcess::Builtin(process) if process.keep_redirects => {
+                std::mem::take(&mut redirect_fds).keep(&mut shell.environment.write())?;
+                process.pid = Some(current_pid);
+                process.launch(&mut ctx.clone(), shell).await?;
+                current_pid
+            }
             JobProcess::Builtin(process) => {
+                let mut builtin_ctx = ctx.clone();
+                [builtin_ctx.infile, builtin_ctx.outfile, builtin_ctx.errfile] =
+                    redirect_fds.apply_to_std([ctx.infile, ctx.outfile, ctx.errfile]);
                 if ctx.foreground {
                     process.pid = Some(current_pid);
-                    process.launch(ctx, shell)?;
+                    process.launch(&mut builtin_ctx, shell).await?;
                     current_pid
                 } else {
-                    // Fork for background execution
-                    let child_pid = fork_builtin_process(ctx, process, shell)?;
+                    // Fork for background execution; the child starts a runtime of its own
+                    let child_pid = task::block_in_place(|| {
+                        fork_builtin_process(&mut builtin_ctx, process, shell)
+                    })?;
                     process.pid = Some(child_pid);
                     child_pid
                 }
             }
+            JobProcess::Command(process) if process.replace_shell => {
+                process.fd_ops = redirect_fds.ops.clone();
+                match exec_process(ctx, process, shell)? {}
+            }
+            JobProcess::Command(process) if process.compound.is_some() => {
+                ctx.process_count += 1;
+                process.fd_ops = redirect_fds.ops.clone();
+                // The forked shell starts a runtime of its own
+                task::block_in_place(|| fork_process(ctx, ctx.pgid, process, shell, pty_slave))?
+            }
             JobProcess::Command(process) => {
                 ctx.process_count += 1;
+                process.fd_ops = redirect_fds.ops.clone();
                 // fork
                 fork_process(ctx, ctx.pgid, process, shell, pty_slave)?
             }
         };
+        drop(redirect_fds);
 
         self.set_pid(Some(pid));
 
@@ -457,6 +522,25 @@ mod tests {
         assert!(!cat_job_process.is_completed());
     }
 
+    #[test]
+    fn test_pipeline_middle_stage_is_not_consumer() {
+        init();
+
+        // Create a pipeline: cat | head | sort, where head is done first
+        let mut cat_process = Process::new("cat".to_string(), vec!["cat".to_string()]);
+        let mut head_process = Process::new("head".to_string(), vec!["head".to_string()]);
+        let mut sort_process = Process::new("sort".to_string(), vec!["sort".to_string()]);
+        cat_process.state = ProcessState::Running;
+        head_process.state = ProcessState::Completed(0, None);
+        sort_process.state = ProcessState::Running;
+
+        head_process.next = Some(Box::new(JobProcess::Command(sort_process)));
+        cat_process.next = Some(Box::new(JobProcess::Command(head_process)));
+        let cat_job_process = JobProcess::Command(cat_process);
+
+        assert!(!cat_job_process.is_pipeline_consumer_terminated());
+    }
+
     #[test]
     fn test_job_process_variants() {
         init();
@@ -13,8 +13,6 @@ use std::os::unix::io::{AsRawFd, IntoRawFd, RawFd};
 use tokio::io::unix::AsyncFd;
 use tracing::{debug, error};
 
-const DSH_NO_PTY_ENV: &str = "DSH_NO_PTY";
-
 #[derive(Debug)]
 pub(crate) struct ForegroundPtyRawModeGuard {
     enabled: bool,
@@ -61,8 +59,8 @@ fn should_enable_foreground_pty_raw_mode(job: &Job, ctx: &Context) -> bool {
     ctx.foreground && ctx.interactive && job.pty.is_some() && !is_builtin_job(job)
 }
 
-pub async fn setup_pty(job: &mut Job, ctx: &mut Context) -> Result<Option<RawFd>> {
-    if !should_create_pty(ctx, job.disable_pty, std::env::var(DSH_NO_PTY_ENV).is_ok()) {
+pub async fn setup_pty(job: &mut Job, ctx: &mut Context, no_pty: bool) -> Result<Option<RawFd>> {
+    if !should_create_pty(ctx, job.disable_pty, no_pty) {
         return Ok(None);
     }
 
@@ -138,7 +136,13 @@ pub async fn cleanup_pty_tasks(job: &mut Job) {
 }
 
 pub async fn manage_execution(job: &mut Job, ctx: &mut Context) -> Result<()> {
-    if !ctx.interactive {
+    if !ctx.interactive && !ctx.foreground {
+        // Left for `wait`, `kill` and the job table like in interactive mode
+        debug!(
+            "JOB_LAUNCH_NON_INTERACTIVE_BACKGROUND: Job {} keeps running in the background",
+            job.job_id
+        );
+    } else if !ctx.interactive {
         debug!(
             "JOB_LAUNCH_NON_INTERACTIVE: Non-interactive mode, waiting for job {} completion",
             job.job_id
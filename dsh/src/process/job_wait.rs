@@ -1,6 +1,6 @@
 use super::job::Job;
 use super::state::ProcessState;
-use crate::process::wait::{is_job_completed, is_job_stopped};
+use crate::process::wait::{is_job_completed, is_job_stopped, remember_reaped};
 use crate::shell::SHELL_TERMINAL;
 use anyhow::{Context, Result};
 use nix::sys::signal::Signal;
@@ -191,7 +191,9 @@ pub fn wait_process_sync(job: &mut Job) -> Result<()> {
             }
         };
 
-        job.set_process_state(pid, state);
+        if !job.set_process_state(pid, state) {
+            remember_reaped(pid, state);
+        }
 
         debug!(
             "fin waitpid pgid:{:?} pid:{:?} state:{:?}",
@@ -282,6 +284,13 @@ pub async fn wait_process_no_hang(job: &mut Job) -> Result<()> {
 
         check_background_all_output(job).await?;
 
+        if is_job_completed(job) {
+            // Builtins are done before the wait starts; waiting for the next child event
+            // would only reap background jobs
+            debug!("Job already completed, skipping waitpid");
+            break;
+        }
+
         let (pid, state) = match tokio::task::spawn_blocking(|| {
             waitpid(None, Some(WaitPidFlag::WUNTRACED | WaitPidFlag::WNOHANG))
         })
@@ -318,7 +327,9 @@ pub async fn wait_process_no_hang(job: &mut Job) -> Result<()> {
         };
 
         check_background_all_output(job).await?;
-        job.set_process_state(pid, state);
+        if !job.set_process_state(pid, state) {
+            remember_reaped(pid, state);
+        }
 
         debug!("fin wait: pid:{:?}", pid);
 
@@ -417,7 +428,9 @@ pub fn wait_process_no_hang_sync(job: &mut Job) -> Result<()> {
             }
         };
 
-        job.set_process_state(pid, state);
+        if !job.set_process_state(pid, state) {
+            remember_reaped(pid, state);
+        }
 
         debug!("fin wait: pid:{:?}", pid);
 
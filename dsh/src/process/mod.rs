@@ -15,7 +15,7 @@ pub mod signal;
 pub mod state;
 pub mod wait;
 
-pub use builtin::BuiltinProcess;
+pub use builtin::{BuiltinProcess, ShellCall};
 pub use job::Job;
 pub use job_process::JobProcess;
 pub use process::Process;
@@ -12,8 +12,10 @@ use std::sync::Arc;
 use tracing::{debug, error};
 
 use super::job_process::JobProcess;
+use super::redirect::{FdOp, Redirect};
 use super::state::ProcessState;
 use super::wait::wait_pid_job;
+use crate::parser::compound::Command;
 use crate::shell::SHELL_TERMINAL;
 use dsh_types::ExitStatus;
 
@@ -37,6 +39,14 @@ pub struct Process {
     pub stderr: RawFd,
     pub(crate) cap_stdout: Option<RawFd>,
     pub(crate) cap_stderr: Option<RawFd>,
+    pub(crate) redirects: Vec<Redirect>,
+    /// Redirections resolved by the shell, applied in the child before `exec`.
+    pub(crate) fd_ops: Vec<FdOp>,
+    /// `exec CMD`: the shell execs the command itself instead of forking.
+    pub(crate) replace_shell: bool,
+    /// A compound command of a pipeline, run by the forked copy of the shell instead of
+    /// `exec`.
+    pub(crate) compound: Option<Arc<Command>>,
 }
 
 impl std::fmt::Debug for Process {
@@ -51,6 +61,7 @@ impl std::fmt::Debug for Process {
             .field("stdin", &self.stdin)
             .field("stdout", &self.stdout)
             .field("stderr", &self.stderr)
+            .field("redirects", &self.redirects)
             .finish()
     }
 }
@@ -69,9 +80,20 @@ impl Process {
             stderr: STDERR_FILENO,
             cap_stdout: None,
             cap_stderr: None,
+            redirects: Vec::new(),
+            fd_ops: Vec::new(),
+            replace_shell: false,
+            compound: None,
         }
     }
 
+    /// A stage like `while ...; done` in `while ...; done | sort`.
+    pub fn compound(cmd: String, body: Command) -> Self {
+        let mut process = Process::new(cmd, Vec::new());
+        process.compound = Some(Arc::new(body));
+        process
+    }
+
     pub fn set_state(&mut self, pid: Pid, state: ProcessState) -> bool {
         if let Some(ppid) = self.pid
             && ppid == pid
@@ -99,7 +121,7 @@ impl Process {
         }
     }
 
-    fn set_signals(&self) -> Result<()> {
+    pub(crate) fn set_signals(&self) -> Result<()> {
         debug!("set signal action pid:{:?}", self.pid);
         // Accept job-control-related signals (refer https://www.gnu.org/software/libc/manual/html_node/Launching-Jobs.html)
         let action = SigAction::new(SigHandler::SigDfl, SaFlags::empty(), SigSet::empty());
@@ -247,6 +269,35 @@ impl Process {
         pty_slave: Option<RawFd>,
     ) -> Result<()> {
         let PreparedExecution { cmd, argv, envp } = prepared;
+        debug!(
+            "launch: execve cmd:{:?} argv:{:?} foreground:{:?} infile:{:?} outfile:{:?} pid:{:?} pgid:{:?} pty:{:?}",
+            cmd, argv, foreground, self.stdin, self.stdout, pid, pgid, pty_slave
+        );
+        self.enter_child(pid, pgid, interactive, foreground, pty_slave)?;
+
+        match execve(&cmd, &argv, &envp) {
+            Ok(_) => Ok(()),
+            Err(nix::errno::Errno::EACCES) => {
+                error!("Failed to exec {:?} (EACCESS). chmod(1) may help.", cmd);
+                std::process::exit(1);
+            }
+            Err(err) => {
+                error!("Failed to exec {:?} ({})", cmd, err);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    /// Sets up the forked child of a command: its process group, signals and descriptors,
+    /// before `exec` or before the shell runs a compound command in it.
+    pub(crate) fn enter_child(
+        &mut self,
+        pid: Pid,
+        pgid: Pid,
+        interactive: bool,
+        foreground: bool,
+        pty_slave: Option<RawFd>,
+    ) -> Result<()> {
         if interactive {
             // If using PTY, setsid() will be called later which sets the process group/session.
             // We must avoid setpgid() making us a leader before setsid() (which causes EPERM).
@@ -307,15 +358,27 @@ impl Process {
             }
         }
 
-        // cmd, argv, envp are already prepared in `prepared`
-
-        debug!(
-            "launch: execve cmd:{:?} argv:{:?} foreground:{:?} infile:{:?} outfile:{:?} pid:{:?} pgid:{:?} pty:{:?}",
-            cmd, argv, foreground, self.stdin, self.stdout, pid, pgid, pty_slave
-        );
-
         // Standard IO setup (PTY slave is handled via self.stdin/stdout/stderr being set to it by caller if needed)
 
+        // A standard descriptor that another one is taken from, like stderr from fd 1
+        // in `f 2>&1 >file`, is copied out of the way before it is overwritten
+        for (target, fd) in [&mut self.stdin, &mut self.stdout, &mut self.stderr]
+            .into_iter()
+            .enumerate()
+        {
+            if (0..=2).contains(fd) && *fd != target as RawFd {
+                let copy = unsafe { libc::fcntl(*fd, libc::F_DUPFD, 3) };
+                if copy < 0 {
+                    return Err(anyhow::anyhow!(
+                        "dup {} failed: {}",
+                        fd,
+                        std::io::Error::last_os_error()
+                    ));
+                }
+                *fd = copy;
+            }
+        }
+
         // 1. Handle STDIN
         if self.stdin != STDIN_FILENO && unsafe { libc::dup2(self.stdin, STDIN_FILENO) } < 0 {
             return Err(anyhow::anyhow!(
@@ -382,17 +445,26 @@ impl Process {
                 }
             }
         }
-        match execve(&cmd, &argv, &envp) {
-            Ok(_) => Ok(()),
-            Err(nix::errno::Errno::EACCES) => {
-                error!("Failed to exec {:?} (EACCESS). chmod(1) may help.", cmd);
-                std::process::exit(1);
-            }
-            Err(err) => {
-                error!("Failed to exec {:?} ({})", cmd, err);
-                std::process::exit(1);
+
+        // 3. Redirections, in the order they were written
+        for op in &self.fd_ops {
+            match *op {
+                FdOp::Dup { source, fd } => {
+                    if source != fd && unsafe { libc::dup2(source, fd) } < 0 {
+                        return Err(anyhow::anyhow!(
+                            "dup2 {} failed: {}",
+                            fd,
+                            std::io::Error::last_os_error()
+                        ));
+                    }
+                }
+                FdOp::Close(fd) => {
+                    let _ = close(fd);
+                }
             }
         }
+
+        Ok(())
     }
 
     pub(crate) fn update_state(&mut self) -> Option<ProcessState> {
@@ -1,71 +1,63 @@
-use dsh_types::Context;
-use std::os::unix::io::FromRawFd;
-use tokio::{fs, io};
+use std::os::unix::io::RawFd;
 
+/// A redirection of one simple command.
+///
+/// Redirections are kept in the order they were written and applied one after another
+/// once the pipeline is connected, so `> out 2>&1` and `2>&1 > out` differ as in POSIX
+/// shells.
 #[derive(Debug, Clone, PartialEq, Eq)]
-#[allow(dead_code)]
 pub enum Redirect {
-    StdoutOutput(String),
-    StdoutAppend(String),
-    StderrOutput(String),
-    StderrAppend(String),
-    StdouterrOutput(String),
-    StdouterrAppend(String),
-    Input(String),
+    /// `[n]> file`, `[n]>> file` and `[n]>| file`
+    Output {
+        fd: RawFd,
+        path: String,
+        append: bool,
+        /// `>|`: overwrite the file even when `noclobber` is set.
+        force: bool,
+    },
+    /// `[n]< file`
+    Input { fd: RawFd, path: String },
+    /// `[n]>&m` and `[n]<&m` make `fd` a copy of `source`.
+    Dup { fd: RawFd, source: RawFd },
+    /// `[n]>&-` and `[n]<&-`
+    Close(RawFd),
+    /// `<<EOF` body; `expand` is false when the delimiter was quoted.
+    HereDoc { body: String, expand: bool },
+    /// `<<< word`, fed to stdin followed by a newline.
+    HereString(String),
+}
+
+/// A step of the file-descriptor setup performed in the child before `exec`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FdOp {
+    Dup { source: RawFd, fd: RawFd },
+    Close(RawFd),
 }
 
 impl Redirect {
-    pub(crate) fn process(&self, ctx: &mut Context) {
-        match self {
-            Redirect::StdoutOutput(out)
-            | Redirect::StderrOutput(out)
-            | Redirect::StdouterrOutput(out) => {
-                let infile = ctx.infile;
-                let file = out.to_string();
-                // spawn and io copy
-                tokio::spawn(async move {
-                    // copy io
-                    let mut reader = unsafe { fs::File::from_raw_fd(infile) };
-                    match fs::File::create(&file).await {
-                        Ok(mut writer) => {
-                            if let Err(e) = io::copy(&mut reader, &mut writer).await {
-                                tracing::error!("Failed to copy to file {}: {}", file, e);
-                            }
-                        }
-                        Err(e) => {
-                            tracing::error!("Failed to create file {}: {}", file, e);
-                        }
-                    }
-                });
-            }
+    /// Parses the target of `>&`/`<&`: a descriptor number or `-` to close.
+    pub fn dup(fd: RawFd, target: &str) -> Option<Redirect> {
+        if target == "-" {
+            Some(Redirect::Close(fd))
+        } else {
+            target
+                .parse()
+                .ok()
+                .map(|source| Redirect::Dup { fd, source })
+        }
+    }
 
-            Redirect::StdoutAppend(out)
-            | Redirect::StderrAppend(out)
-            | Redirect::StdouterrAppend(out) => {
-                let infile = ctx.infile;
-                let file = out.to_string();
-                // spawn and io copy
-                tokio::spawn(async move {
-                    // copy io
-                    let mut reader = unsafe { fs::File::from_raw_fd(infile) };
-                    match fs::OpenOptions::new()
-                        .write(true)
-                        .append(true)
-                        .open(&file)
-                        .await
-                    {
-                        Ok(mut writer) => {
-                            if let Err(e) = io::copy(&mut reader, &mut writer).await {
-                                tracing::error!("Failed to append to file {}: {}", file, e);
-                            }
-                        }
-                        Err(e) => {
-                            tracing::error!("Failed to open file {} for append: {}", file, e);
-                        }
-                    }
-                });
-            }
-            Redirect::Input(_) => {}
+    /// Whether this is a plain `>` that would truncate an existing regular file, which
+    /// `noclobber` refuses. Devices such as `/dev/null` are not regular files.
+    pub fn clobbers(&self) -> bool {
+        match self {
+            Redirect::Output {
+                path,
+                append: false,
+                force: false,
+                ..
+            } => std::fs::metadata(path).is_ok_and(|meta| meta.is_file()),
+            _ => false,
         }
     }
 }
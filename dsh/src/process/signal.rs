@@ -5,17 +5,24 @@ use nix::unistd::Pid;
 use tracing::{debug, error};
 
 use nix::sys::signal::{SaFlags, SigAction, SigHandler, SigSet, sigaction};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 static RECEIVED_SIGINT: AtomicBool = AtomicBool::new(false);
+/// Bit N is set when signal N arrived and has not been handed to `trap` yet.
+static PENDING_SIGNALS: AtomicU64 = AtomicU64::new(0);
 
-extern "C" fn handle_sigint(_: i32) {
-    RECEIVED_SIGINT.store(true, Ordering::SeqCst);
+extern "C" fn handle_signal(signo: i32) {
+    if signo == Signal::SIGINT as i32 {
+        RECEIVED_SIGINT.store(true, Ordering::SeqCst);
+    }
+    if (0..64).contains(&signo) {
+        PENDING_SIGNALS.fetch_or(1 << signo, Ordering::SeqCst);
+    }
 }
 
 pub(crate) fn install_sigint_handler() -> Result<()> {
     tracing::info!("🔧 SIGNAL: Installing SIGINT handler");
-    let handler = SigHandler::Handler(handle_sigint);
+    let handler = SigHandler::Handler(handle_signal);
     let action = SigAction::new(handler, SaFlags::empty(), SigSet::empty());
     unsafe {
         sigaction(Signal::SIGINT, &action)?;
@@ -38,6 +45,64 @@ pub(crate) fn check_and_clear_sigint() -> bool {
     RECEIVED_SIGINT.swap(false, Ordering::SeqCst)
 }
 
+/// Catches `signal` so that its trap runs at the next safe point.
+pub(crate) fn install_trap_handler(signal: Signal) -> Result<()> {
+    debug!("📡 SIGNAL: Trapping {:?}", signal);
+    PENDING_SIGNALS.fetch_and(!(1 << signal as i32), Ordering::SeqCst);
+    let action = SigAction::new(
+        SigHandler::Handler(handle_signal),
+        SaFlags::SA_RESTART,
+        SigSet::empty(),
+    );
+    unsafe {
+        sigaction(signal, &action)?;
+    }
+    Ok(())
+}
+
+/// Ignores `signal` (`trap '' SIG`).
+pub(crate) fn ignore_signal(signal: Signal) -> Result<()> {
+    let action = SigAction::new(SigHandler::SigIgn, SaFlags::empty(), SigSet::empty());
+    unsafe {
+        sigaction(signal, &action)?;
+    }
+    Ok(())
+}
+
+/// Gives `signal` back the disposition the shell starts with (`trap - SIG`).
+pub(crate) fn restore_signal_handler(signal: Signal) -> Result<()> {
+    match signal {
+        Signal::SIGINT => install_sigint_handler(),
+        Signal::SIGQUIT | Signal::SIGTSTP | Signal::SIGTTIN | Signal::SIGTTOU => {
+            ignore_signal(signal)
+        }
+        _ => {
+            let action = SigAction::new(SigHandler::SigDfl, SaFlags::empty(), SigSet::empty());
+            unsafe {
+                sigaction(signal, &action)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Returns the signals that arrived since the last call, lowest number first.
+pub(crate) fn take_pending_signals() -> Vec<Signal> {
+    signals_in(PENDING_SIGNALS.swap(0, Ordering::SeqCst))
+}
+
+/// Returns the signals waiting for [`take_pending_signals`] without clearing them.
+pub(crate) fn pending_signals() -> Vec<Signal> {
+    signals_in(PENDING_SIGNALS.load(Ordering::SeqCst))
+}
+
+fn signals_in(mask: u64) -> Vec<Signal> {
+    (1..64)
+        .filter(|signo| mask & (1 << signo) != 0)
+        .filter_map(|signo| Signal::try_from(signo).ok())
+        .collect()
+}
+
 pub(crate) fn send_signal(pid: Pid, signal: Signal) -> Result<()> {
     debug!("📡 SIGNAL: Sending signal {:?} to pid {}", signal, pid);
     match kill(pid, signal) {
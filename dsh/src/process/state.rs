@@ -8,6 +8,17 @@ pub enum ProcessState {
     Stopped(Pid, Signal),
 }
 
+impl ProcessState {
+    /// Exit status as seen by the shell, `128 + N` for a process killed by signal N.
+    pub fn exit_status(&self) -> Option<i32> {
+        match self {
+            ProcessState::Completed(_, Some(signal)) => Some(128 + *signal as i32),
+            ProcessState::Completed(exit, None) => Some(i32::from(*exit)),
+            _ => None,
+        }
+    }
+}
+
 impl std::fmt::Display for ProcessState {
     fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -35,6 +46,8 @@ pub enum SubshellType {
     None,
     Subshell,
     ProcessSubstitution,
+    /// `>(...)`: the command reads what the main command writes to it.
+    OutputProcessSubstitution,
     CommandSubstitution,
 }
 
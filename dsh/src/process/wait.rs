@@ -1,10 +1,33 @@
 use nix::sys::wait::{WaitPidFlag, WaitStatus, waitpid};
 use nix::unistd::Pid;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::Mutex;
 use tracing::{debug, error};
 
 use super::job::Job;
 use super::state::ProcessState;
 
+/// Children that finished while the shell was waiting for another job.
+///
+/// Waiting for a foreground job reaps any child, so the exit status of a background job
+/// that finishes meanwhile is kept here until its own job asks for it.
+static REAPED_CHILDREN: Lazy<Mutex<HashMap<Pid, ProcessState>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Keeps the final state of a child that was reaped on behalf of another job.
+pub(crate) fn remember_reaped(pid: Pid, state: ProcessState) {
+    if matches!(state, ProcessState::Completed(_, _))
+        && let Ok(mut reaped) = REAPED_CHILDREN.lock()
+    {
+        reaped.insert(pid, state);
+    }
+}
+
+fn take_reaped(pid: Pid) -> Option<ProcessState> {
+    REAPED_CHILDREN.lock().ok()?.remove(&pid)
+}
+
 pub fn is_job_stopped(job: &Job) -> bool {
     if let Some(p) = &job.process {
         let stopped = p.is_stopped();
@@ -126,13 +149,22 @@ pub fn wait_pid_job(pid: Pid, no_hang: bool) -> Option<(Pid, ProcessState)> {
             );
             (pid, ProcessState::Stopped(pid, signal))
         }
-        Err(nix::errno::Errno::ECHILD) => {
-            debug!(
-                "WAIT_PID_ECHILD: No child process {} (ECHILD) - treating as completed",
-                pid
-            );
-            (pid, ProcessState::Completed(1, None))
-        }
+        Err(nix::errno::Errno::ECHILD) => match take_reaped(pid) {
+            Some(state) => {
+                debug!(
+                    "WAIT_PID_REAPED: Process {} was reaped earlier with state: {:?}",
+                    pid, state
+                );
+                (pid, state)
+            }
+            None => {
+                debug!(
+                    "WAIT_PID_ECHILD: No child process {} (ECHILD) - treating as completed",
+                    pid
+                );
+                (pid, ProcessState::Completed(1, None))
+            }
+        },
         Ok(WaitStatus::StillAlive) => {
             debug!("WAIT_PID_ALIVE: Process {} still alive (WNOHANG)", pid);
             return None;
@@ -0,0 +1,240 @@
+//! Command introspection handlers (`type`, `which`, `command -v`, `command -V`).
+//!
+//! The answers come from [`crate::shell::resolve`], which is also what picks the
+//! command to run, so they always match what the name would do.
+
+use crate::shell::Shell;
+use crate::shell::resolve::{Lookup, Resolution, resolve, resolve_all};
+use anyhow::{Result, bail};
+use dsh_types::Context;
+
+/// How `type` reports a name.
+#[derive(Debug, Default)]
+struct TypeOptions {
+    /// `-a`: every resolution instead of the first.
+    all: bool,
+    /// `-t`: only the kind.
+    kind: bool,
+    /// `-p`: the path if the name runs an executable.
+    path: bool,
+    /// `-P`: the path even if something else comes first.
+    force_path: bool,
+}
+
+/// Resolutions of `name`, the first one or all of them.
+fn resolutions(shell: &Shell, name: &str, all: bool) -> Vec<Resolution> {
+    let mut found = resolve_all(shell, name);
+    if !all {
+        // Aliases and abbreviations come first, otherwise the lookup decides
+        match found.first() {
+            Some(Resolution::Alias(_) | Resolution::Abbreviation(_)) => found.truncate(1),
+            _ => found = resolve(shell, name, Lookup::Default).into_iter().collect(),
+        }
+    }
+    found
+}
+
+/// Describes a resolution the way `type` prints it.
+fn type_line(name: &str, resolution: &Resolution) -> String {
+    match resolution {
+        Resolution::Alias(value) => format!("{name} is aliased to `{value}'"),
+        Resolution::Abbreviation(value) => format!("{name} is an abbreviation for `{value}'"),
+        Resolution::Function => format!("{name} is a function"),
+        Resolution::Builtin => format!("{name} is a shell builtin"),
+        Resolution::Lisp => format!("{name} is a Lisp function"),
+        Resolution::File(path) => format!("{name} is {path}"),
+        Resolution::Directory => format!("{name} is a directory (cd {name})"),
+    }
+}
+
+/// Describes a resolution the way `which` prints it.
+fn which_line(name: &str, resolution: &Resolution) -> String {
+    match resolution {
+        Resolution::Alias(value) => format!("{name}: aliased to {value}"),
+        Resolution::Abbreviation(value) => format!("{name}: abbreviation for {value}"),
+        Resolution::Function => format!("{name}: shell function"),
+        Resolution::Builtin => format!("{name}: shell built-in command"),
+        Resolution::Lisp => format!("{name}: Lisp function"),
+        Resolution::File(path) => path.clone(),
+        Resolution::Directory => format!("{name}: directory"),
+    }
+}
+
+/// Describes a resolution the way `command -v` prints it.
+fn command_line(name: &str, resolution: &Resolution) -> String {
+    match resolution {
+        Resolution::Alias(value) => format!("alias {name}='{value}'"),
+        Resolution::Abbreviation(value) => format!("abbr {name}='{value}'"),
+        Resolution::File(path) => path.clone(),
+        _ => name.to_string(),
+    }
+}
+
+/// Runs `type`, `which` or `command` (with `-v`/`-V`), depending on `argv[0]`, and
+/// returns the exit status: 1 if any name does not resolve.
+pub fn describe(shell: &mut Shell, ctx: &Context, argv: Vec<String>) -> Result<i32> {
+    match argv[0].as_str() {
+        "type" => execute_type(shell, ctx, &argv),
+        "which" => execute_which(shell, ctx, &argv),
+        "command" => execute_command(shell, ctx, &argv),
+        other => bail!("{other}: not a command introspection builtin"),
+    }
+}
+
+/// Execute the `type` builtin command.
+///
+/// `type NAME...` tells how each name resolves, `-a` lists every resolution, `-t`
+/// prints only the kind, `-p` the path of executables and `-P` searches PATH only.
+fn execute_type(shell: &Shell, ctx: &Context, argv: &[String]) -> Result<i32> {
+    let mut options = TypeOptions::default();
+    let mut names = Vec::new();
+    for arg in argv.iter().skip(1) {
+        match arg.as_str() {
+            flags if flags.starts_with('-') && flags.len() > 1 && names.is_empty() => {
+                for flag in flags[1..].chars() {
+                    match flag {
+                        'a' => options.all = true,
+                        't' => options.kind = true,
+                        'p' => options.path = true,
+                        'P' => options.force_path = true,
+                        _ => bail!("-{flag}: invalid option"),
+                    }
+                }
+            }
+            name => names.push(name),
+        }
+    }
+
+    let mut status = 0;
+    for name in names {
+        let found = if options.force_path {
+            let paths = shell.environment.read().lookup_all(name);
+            let paths = paths.into_iter().map(Resolution::File);
+            if options.all {
+                paths.collect()
+            } else {
+                paths.take(1).collect()
+            }
+        } else {
+            resolutions(shell, name, options.all)
+        };
+        if found.is_empty() {
+            // `-t`, `-p` and `-P` stay silent like in bash
+            if !(options.kind || options.path || options.force_path) {
+                ctx.write_stderr(&format!("type: {name}: not found"))?;
+            }
+            status = 1;
+            continue;
+        }
+        for resolution in &found {
+            if options.kind {
+                ctx.write_stdout(resolution.kind())?;
+            } else if options.path || options.force_path {
+                if let Resolution::File(path) = resolution {
+                    ctx.write_stdout(path)?;
+                }
+            } else {
+                ctx.write_stdout(&type_line(name, resolution))?;
+            }
+        }
+    }
+    Ok(status)
+}
+
+/// Execute the `which` builtin command.
+///
+/// `which NAME...` prints what each name runs, with the path of executables; `-a`
+/// prints every match.
+fn execute_which(shell: &Shell, ctx: &Context, argv: &[String]) -> Result<i32> {
+    let mut all = false;
+    let mut names = Vec::new();
+    for arg in argv.iter().skip(1) {
+        match arg.as_str() {
+            "-a" if names.is_empty() => all = true,
+            opt if opt.starts_with('-') && opt.len() > 1 && names.is_empty() => {
+                bail!("{opt}: invalid option")
+            }
+            name => names.push(name),
+        }
+    }
+
+    let mut status = 0;
+    for name in names {
+        let found = resolutions(shell, name, all);
+        if found.is_empty() {
+            ctx.write_stderr(&format!("{name} not found"))?;
+            status = 1;
+        }
+        for resolution in &found {
+            ctx.write_stdout(&which_line(name, resolution))?;
+        }
+    }
+    Ok(status)
+}
+
+/// Execute the `command` builtin with `-v` or `-V`.
+///
+/// Running `command NAME ARGS...` is handled when the command line is parsed; here
+/// `-v` prints how each name resolves in a form that can be run again and `-V` prints
+/// it like `type`.
+fn execute_command(shell: &Shell, ctx: &Context, argv: &[String]) -> Result<i32> {
+    let mut verbose = None;
+    let mut names = Vec::new();
+    for arg in argv.iter().skip(1) {
+        match arg.as_str() {
+            "-v" if names.is_empty() => verbose = Some(false),
+            "-V" if names.is_empty() => verbose = Some(true),
+            "-p" if names.is_empty() => {}
+            opt if opt.starts_with('-') && opt.len() > 1 && names.is_empty() => {
+                bail!("{opt}: invalid option")
+            }
+            name => names.push(name),
+        }
+    }
+    let Some(verbose) = verbose else {
+        // `command` alone or with `-p` only has nothing to run
+        return Ok(0);
+    };
+
+    let mut status = 0;
+    for name in names {
+        let Some(resolution) = resolutions(shell, name, false).pop() else {
+            if verbose {
+                ctx.write_stderr(&format!("command: {name}: not found"))?;
+            }
+            status = 1;
+            continue;
+        };
+        if verbose {
+            ctx.write_stdout(&type_line(name, &resolution))?;
+        } else {
+            ctx.write_stdout(&command_line(name, &resolution))?;
+        }
+    }
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_lines() {
+        let alias = Resolution::Alias("ls -l".to_string());
+        assert_eq!(type_line("ll", &alias), "ll is aliased to `ls -l'");
+        assert_eq!(which_line("ll", &alias), "ll: aliased to ls -l");
+        assert_eq!(command_line("ll", &alias), "alias ll='ls -l'");
+
+        let file = Resolution::File("/bin/ls".to_string());
+        assert_eq!(type_line("ls", &file), "ls is /bin/ls");
+        assert_eq!(which_line("ls", &file), "/bin/ls");
+        assert_eq!(command_line("ls", &file), "/bin/ls");
+
+        assert_eq!(
+            type_line("cd", &Resolution::Builtin),
+            "cd is a shell builtin"
+        );
+        assert_eq!(command_line("cd", &Resolution::Builtin), "cd");
+        assert_eq!(Resolution::Directory.kind(), "directory");
+    }
+}
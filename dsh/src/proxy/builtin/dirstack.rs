@@ -0,0 +1,226 @@
+//! Directory stack command handlers (`pushd`, `popd`, `dirs`).
+//!
+//! Every directory change goes through [`ShellProxy::changepwd`], so `OLDPWD`, the
+//! `z` history and the chdir hooks behave exactly as they do for `cd`.
+
+use crate::environment::dir_stack_index;
+use crate::shell::Shell;
+use anyhow::{Result, anyhow, bail};
+use dsh_builtin::ShellProxy;
+use dsh_types::Context;
+use std::path::Path;
+
+/// Options shared by `pushd` and `popd`.
+///
+/// Returns whether `-n` (change the stack only) was given and the remaining arguments.
+fn parse_stack_args(argv: &[String]) -> Result<(bool, Vec<&str>)> {
+    let mut no_cd = false;
+    let mut args = Vec::new();
+    for arg in argv.iter().skip(1) {
+        match arg.as_str() {
+            "-n" => no_cd = true,
+            "--" => {}
+            spec if is_stack_index(spec) => args.push(spec),
+            opt if opt.starts_with('-') && opt.len() > 1 => bail!("{opt}: invalid option"),
+            arg => args.push(arg),
+        }
+    }
+    if args.len() > 1 {
+        bail!("too many arguments");
+    }
+    Ok((no_cd, args))
+}
+
+/// Whether `arg` is a `+N` or `-N` stack index.
+fn is_stack_index(arg: &str) -> bool {
+    arg.len() > 1
+        && (arg.starts_with('+') || arg.starts_with('-'))
+        && arg[1..].bytes().all(|b| b.is_ascii_digit())
+}
+
+fn resolve_index(spec: &str, len: usize) -> Result<usize> {
+    dir_stack_index(spec, len).ok_or_else(|| anyhow!("{spec}: directory stack index out of range"))
+}
+
+/// Makes the first entry the current directory and saves the rest as the stack.
+fn apply_stack(shell: &mut Shell, mut entries: Vec<String>, change_dir: bool) -> Result<()> {
+    if change_dir {
+        shell.changepwd(&entries[0])?;
+    }
+    entries.remove(0);
+    shell.environment.write().dir_stack = entries;
+    Ok(())
+}
+
+/// Abbreviates the home directory as `~`.
+fn display_dir(dir: &str, home: Option<&str>) -> String {
+    match home.and_then(|home| dir.strip_prefix(home)) {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("~{rest}"),
+        _ => dir.to_string(),
+    }
+}
+
+fn stack_line(entries: &[String], home: Option<&str>) -> String {
+    entries
+        .iter()
+        .map(|dir| display_dir(dir, home))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn print_stack(shell: &Shell, ctx: &Context) -> Result<()> {
+    let home = dirs::home_dir().map(|home| home.to_string_lossy().into_owned());
+    let entries = shell.environment.read().dir_stack_entries();
+    ctx.write_stdout(&stack_line(&entries, home.as_deref()))
+}
+
+/// Execute the `pushd` builtin command.
+///
+/// `pushd DIR` saves the current directory and changes to DIR, `pushd +N` and
+/// `pushd -N` rotate the stack so that entry N is on top, and `pushd` alone swaps the
+/// top two entries.
+pub fn execute_pushd(shell: &mut Shell, ctx: &Context, argv: Vec<String>) -> Result<()> {
+    let (no_cd, args) = parse_stack_args(&argv)?;
+    let mut entries = shell.environment.read().dir_stack_entries();
+
+    // `-n` only applies when adding a directory, rotating always changes directory
+    let mut change_dir = true;
+    match args.first() {
+        None => {
+            if entries.len() < 2 {
+                bail!("no other directory");
+            }
+            entries.swap(0, 1);
+        }
+        Some(spec) if is_stack_index(spec) => {
+            let index = resolve_index(spec, entries.len())?;
+            entries.rotate_left(index);
+        }
+        Some(dir) => {
+            let expanded = shellexpand::tilde(dir).to_string();
+            let path = Path::new(&entries[0])
+                .join(expanded)
+                .canonicalize()
+                .map_err(|err| anyhow!("{err}: {dir}"))?;
+            if !path.is_dir() {
+                bail!("not a directory: {dir}");
+            }
+            let index = if no_cd { 1 } else { 0 };
+            entries.insert(index, path.to_string_lossy().into_owned());
+            change_dir = !no_cd;
+        }
+    }
+
+    apply_stack(shell, entries, change_dir)?;
+    print_stack(shell, ctx)
+}
+
+/// Execute the `popd` builtin command.
+///
+/// `popd` removes the top entry and changes to the next one, `popd +N` and `popd -N`
+/// remove entry N.
+pub fn execute_popd(shell: &mut Shell, ctx: &Context, argv: Vec<String>) -> Result<()> {
+    let (no_cd, args) = parse_stack_args(&argv)?;
+    let mut entries = shell.environment.read().dir_stack_entries();
+    if entries.len() < 2 {
+        bail!("directory stack empty");
+    }
+
+    let index = match args.first() {
+        None if no_cd => 1,
+        None => 0,
+        Some(spec) if is_stack_index(spec) => resolve_index(spec, entries.len())?,
+        Some(arg) => bail!("{arg}: invalid argument"),
+    };
+    entries.remove(index);
+
+    apply_stack(shell, entries, index == 0)?;
+    print_stack(shell, ctx)
+}
+
+/// Execute the `dirs` builtin command.
+///
+/// Prints the directory stack. `-c` clears it, `-l` prints full paths, `-p` prints one
+/// entry per line, `-v` numbers them and `+N`/`-N` print a single entry.
+pub fn execute_dirs(shell: &mut Shell, ctx: &Context, argv: Vec<String>) -> Result<()> {
+    let mut long = false;
+    let mut per_line = false;
+    let mut verbose = false;
+    let mut index = None;
+
+    for arg in argv.iter().skip(1) {
+        match arg.as_str() {
+            spec if is_stack_index(spec) => index = Some(spec.to_string()),
+            "-c" => {
+                shell.environment.write().dir_stack.clear();
+                return Ok(());
+            }
+            "-l" => long = true,
+            "-p" => per_line = true,
+            "-v" => verbose = true,
+            flags if flags.starts_with('-') && flags.len() > 1 => {
+                for flag in flags[1..].chars() {
+                    match flag {
+                        'l' => long = true,
+                        'p' => per_line = true,
+                        'v' => verbose = true,
+                        _ => bail!("-{flag}: invalid option"),
+                    }
+                }
+            }
+            arg => bail!("{arg}: invalid argument"),
+        }
+    }
+
+    let home = if long {
+        None
+    } else {
+        dirs::home_dir().map(|home| home.to_string_lossy().into_owned())
+    };
+    let entries = shell.environment.read().dir_stack_entries();
+
+    if let Some(spec) = index {
+        let index = resolve_index(&spec, entries.len())?;
+        return ctx.write_stdout(&display_dir(&entries[index], home.as_deref()));
+    }
+
+    if verbose {
+        for (i, dir) in entries.iter().enumerate() {
+            ctx.write_stdout(&format!("{i:>2}  {}", display_dir(dir, home.as_deref())))?;
+        }
+    } else if per_line {
+        for dir in &entries {
+            ctx.write_stdout(&display_dir(dir, home.as_deref()))?;
+        }
+    } else {
+        ctx.write_stdout(&stack_line(&entries, home.as_deref()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_stack_args() {
+        let argv = args(&["pushd", "-n", "/tmp"]);
+        assert_eq!(parse_stack_args(&argv).unwrap(), (true, vec!["/tmp"]));
+        let argv = args(&["popd", "-2"]);
+        assert_eq!(parse_stack_args(&argv).unwrap(), (false, vec!["-2"]));
+        assert!(parse_stack_args(&args(&["pushd", "-x"])).is_err());
+        assert!(parse_stack_args(&args(&["pushd", "a", "b"])).is_err());
+    }
+
+    #[test]
+    fn test_display_dir() {
+        assert_eq!(display_dir("/home/me", Some("/home/me")), "~");
+        assert_eq!(display_dir("/home/me/src", Some("/home/me")), "~/src");
+        assert_eq!(display_dir("/home/meta", Some("/home/me")), "/home/meta");
+        assert_eq!(display_dir("/tmp", None), "/tmp");
+    }
+}
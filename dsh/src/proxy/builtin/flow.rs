@@ -0,0 +1,63 @@
+//! Control flow command handlers (`break`, `continue` and `return`).
+
+use crate::shell::{ControlFlow, Shell, function};
+use anyhow::{Result, bail};
+use dsh_types::Context;
+
+/// Parses the optional loop count argument, clamped to the current loop depth.
+fn loop_count(shell: &Shell, name: &str, argv: &[String]) -> Result<usize> {
+    if shell.loop_depth == 0 {
+        bail!("only meaningful in a `for', `while', or `until' loop");
+    }
+    let count = match argv.get(1) {
+        Some(arg) => match arg.parse::<usize>() {
+            Ok(n) if n > 0 => n,
+            _ => bail!("{}: loop count out of range", arg),
+        },
+        None => 1,
+    };
+    if argv.len() > 2 {
+        bail!("{}: too many arguments", name);
+    }
+    Ok(count.min(shell.loop_depth))
+}
+
+/// Execute the `break` builtin command.
+///
+/// Exits from the innermost (or Nth enclosing) loop.
+pub fn execute_break(shell: &mut Shell, _ctx: &Context, argv: Vec<String>) -> Result<()> {
+    let count = loop_count(shell, "break", &argv)?;
+    shell.control_flow = Some(ControlFlow::Break(count));
+    Ok(())
+}
+
+/// Execute the `continue` builtin command.
+///
+/// Resumes the next iteration of the innermost (or Nth enclosing) loop.
+pub fn execute_continue(shell: &mut Shell, _ctx: &Context, argv: Vec<String>) -> Result<()> {
+    let count = loop_count(shell, "continue", &argv)?;
+    shell.control_flow = Some(ControlFlow::Continue(count));
+    Ok(())
+}
+
+/// Execute the `return` builtin command.
+///
+/// Leaves the current shell function or sourced file with status N, or the last status
+/// when omitted.
+pub fn execute_return(shell: &mut Shell, _ctx: &Context, argv: Vec<String>) -> Result<()> {
+    if !function::can_return(shell) {
+        bail!("can only `return' from a function or sourced script");
+    }
+    let code = match argv.get(1) {
+        Some(arg) => match arg.parse::<i32>() {
+            Ok(n) => Some(n & 0xff),
+            Err(_) => bail!("{}: numeric argument required", arg),
+        },
+        None => None,
+    };
+    if argv.len() > 2 {
+        bail!("too many arguments");
+    }
+    shell.control_flow = Some(ControlFlow::Return(code));
+    Ok(())
+}
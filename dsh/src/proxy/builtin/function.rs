@@ -0,0 +1,61 @@
+//! Shell function command handlers (`local` and `shift`).
+
+use crate::shell::{Shell, function};
+use anyhow::{Result, bail};
+use dsh_types::Context;
+
+/// Execute the `local` builtin command.
+///
+/// Declares each `NAME` or `NAME=VALUE` as local to the running function.
+pub fn execute_local(shell: &mut Shell, _ctx: &Context, argv: Vec<String>) -> Result<()> {
+    for arg in argv.iter().skip(1) {
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (arg.as_str(), None),
+        };
+        if !is_valid_name(name) {
+            bail!("`{}': not a valid identifier", arg);
+        }
+        function::declare_local(shell, name, value)?;
+    }
+    Ok(())
+}
+
+/// Execute the `shift` builtin command.
+///
+/// Drops the first N (default 1) positional parameters.
+pub fn execute_shift(shell: &mut Shell, _ctx: &Context, argv: Vec<String>) -> Result<()> {
+    let count = match argv.get(1) {
+        Some(arg) => match arg.parse::<usize>() {
+            Ok(n) => n,
+            Err(_) => bail!("{}: numeric argument required", arg),
+        },
+        None => 1,
+    };
+    let mut env = shell.environment.write();
+    if count > env.positional_params.len() {
+        bail!("{}: shift count out of range", count);
+    }
+    env.positional_params.drain(..count);
+    Ok(())
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_name() {
+        assert!(is_valid_name("foo"));
+        assert!(is_valid_name("_bar1"));
+        assert!(!is_valid_name("1abc"));
+        assert!(!is_valid_name("a-b"));
+        assert!(!is_valid_name(""));
+    }
+}
@@ -3,11 +3,16 @@
 //! This module contains handlers for shell builtin commands that are executed
 //! directly by the dispatch function rather than as external processes.
 
+pub mod command;
+pub mod dirstack;
 pub mod exit;
+pub mod flow;
+pub mod function;
 pub mod history;
 pub mod jobs;
 pub mod lisp;
 pub mod registry;
 pub mod reload;
+pub mod trap;
 pub mod var;
 pub mod z;
@@ -9,7 +9,7 @@ use dsh_types::Context;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
 
-use super::{exit, history, jobs, lisp, reload, var, z};
+use super::{dirstack, exit, flow, function, history, jobs, lisp, reload, trap, var, z};
 
 /// Type alias for builtin command handler functions.
 pub type CommandHandler = fn(&mut Shell, &Context, Vec<String>) -> Result<()>;
@@ -31,19 +31,34 @@ impl BuiltinRegistry {
         commands.insert("exit", exit::execute);
         commands.insert("history", history::execute);
         commands.insert("reload", reload::execute);
+        commands.insert("trap", trap::execute);
 
         // Navigation
         commands.insert("z", z::execute);
+        commands.insert("pushd", dirstack::execute_pushd);
+        commands.insert("popd", dirstack::execute_popd);
+        commands.insert("dirs", dirstack::execute_dirs);
 
         // Job control
         commands.insert("jobs", jobs::execute_jobs);
         commands.insert("fg", jobs::execute_fg);
         commands.insert("bg", jobs::execute_bg);
+        commands.insert("kill", jobs::execute_kill);
+        commands.insert("disown", jobs::execute_disown);
 
         // Lisp
         commands.insert("lisp", lisp::execute_lisp);
         commands.insert("lisp-run", lisp::execute_lisp_run);
 
+        // Control flow
+        commands.insert("break", flow::execute_break);
+        commands.insert("continue", flow::execute_continue);
+
+        // Shell functions
+        commands.insert("return", flow::execute_return);
+        commands.insert("local", function::execute_local);
+        commands.insert("shift", function::execute_shift);
+
         // Variables
         commands.insert("var", var::execute_var);
         commands.insert("read", var::execute_read);
@@ -87,7 +102,8 @@ mod tests {
     #[test]
     fn test_registry_contains_all_commands() {
         let expected = vec![
-            "exit", "history", "reload", "z", "jobs", "fg", "bg", "lisp", "lisp-run", "var", "read",
+            "exit", "history", "reload", "z", "jobs", "fg", "bg", "kill", "disown", "lisp",
+            "lisp-run", "break", "continue", "return", "local", "shift", "var", "read",
         ];
         for cmd in expected {
             assert!(
@@ -107,6 +123,6 @@ mod tests {
     #[test]
     fn test_registry_list() {
         let list = BUILTIN_REGISTRY.list();
-        assert!(list.len() >= 11);
+        assert!(list.len() >= 16);
     }
 }
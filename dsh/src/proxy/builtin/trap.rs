@@ -0,0 +1,70 @@
+//! Trap command handler.
+
+use crate::shell::Shell;
+use crate::shell::trap::{TrapAction, TrapSignal, format_trap, set_trap};
+use anyhow::{Result, bail};
+use dsh_types::Context;
+use nix::sys::signal::Signal;
+
+/// Execute the `trap` builtin command.
+///
+/// `trap 'cmd' SIG...` sets a handler, `trap '' SIG` ignores the signal, `trap - SIG`
+/// (or `trap SIG`) resets it, `trap -p [SIG...]` prints handlers and `trap -l` lists
+/// the signal names.
+pub fn execute(shell: &mut Shell, ctx: &Context, argv: Vec<String>) -> Result<()> {
+    let mut args = &argv[1..];
+    let mut print = false;
+    while let Some(arg) = args.first() {
+        match arg.as_str() {
+            "-p" => print = true,
+            "-l" => return list_signals(ctx),
+            "--" => {
+                args = &args[1..];
+                break;
+            }
+            opt if opt.starts_with('-') && opt.len() > 1 => bail!("{opt}: invalid option"),
+            _ => break,
+        }
+        args = &args[1..];
+    }
+
+    if print || args.is_empty() {
+        let signals = args
+            .iter()
+            .map(|spec| TrapSignal::parse(spec))
+            .collect::<Result<Vec<_>>>()?;
+        let env = shell.environment.read();
+        for (signal, action) in &env.traps {
+            if signals.is_empty() || signals.contains(signal) {
+                ctx.write_stdout(&format_trap(*signal, action))?;
+            }
+        }
+        return Ok(());
+    }
+
+    // A lone signal resets it like `trap - SIG`
+    let (action, specs) = if args.len() == 1 || args[0] == "-" {
+        let specs = if args[0] == "-" { &args[1..] } else { args };
+        (None, specs)
+    } else {
+        (Some(args[0].clone()), &args[1..])
+    };
+
+    let mut env = shell.environment.write();
+    for spec in specs {
+        let signal = TrapSignal::parse(spec)?;
+        set_trap(&mut env, signal, action.clone().map(TrapAction::Command))?;
+    }
+    Ok(())
+}
+
+/// Prints the signal numbers and names, five per row.
+pub(crate) fn list_signals(ctx: &Context) -> Result<()> {
+    let names: Vec<String> = Signal::iterator()
+        .map(|signal| format!("{:>2}) {}", signal as i32, signal.as_str()))
+        .collect();
+    for row in names.chunks(5) {
+        ctx.write_stdout(&row.join("\t"))?;
+    }
+    Ok(())
+}
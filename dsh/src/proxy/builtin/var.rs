@@ -5,6 +5,7 @@ use anyhow::{Context as _, Result};
 use dsh_types::Context;
 use std::fs::File;
 use std::io::prelude::*;
+use std::mem::ManuallyDrop;
 use std::os::unix::io::FromRawFd;
 use tabled::{Table, Tabled};
 
@@ -38,11 +39,11 @@ pub fn execute_var(shell: &mut Shell, ctx: &Context, _argv: Vec<String>) -> Resu
 /// Reads input from stdin and assigns it to a variable.
 pub fn execute_read(shell: &mut Shell, ctx: &Context, argv: Vec<String>) -> Result<()> {
     let mut stdin = Vec::new();
-    unsafe {
-        File::from_raw_fd(ctx.infile)
-            .read_to_end(&mut stdin)
-            .context("read: failed to read input")?;
-    };
+    // The descriptor belongs to the caller (a redirect or `exec 3<file`), don't close it
+    let mut input = ManuallyDrop::new(unsafe { File::from_raw_fd(ctx.infile) });
+    input
+        .read_to_end(&mut stdin)
+        .context("read: failed to read input")?;
     let key = format!("${}", argv[1]);
     let output = match std::str::from_utf8(&stdin) {
         Ok(s) => s.trim_end_matches('\n').to_owned(),
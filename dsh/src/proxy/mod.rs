@@ -3,14 +3,19 @@
 //! This module provides the `ShellProxy` trait implementation for `Shell`,
 //! routing builtin commands to their respective handlers.
 
-mod builtin;
+pub(crate) mod builtin;
 mod external;
 
 use crate::shell::Shell;
 use anyhow::{Context as _, Result};
 use dsh_builtin::ShellProxy;
-use dsh_types::{Context, mcp::McpServerConfig};
+use dsh_types::{
+    Context,
+    mcp::McpServerConfig,
+    options::{OptionEntry, OptionValue},
+};
 use globmatch;
+use std::os::fd::{AsRawFd, RawFd};
 use std::path::{Path, PathBuf};
 use tracing::{debug, warn};
 
@@ -125,6 +130,36 @@ impl ShellProxy for Shell {
         }
     }
 
+    fn describe_commands(&mut self, ctx: &Context, argv: Vec<String>) -> Result<i32> {
+        builtin::command::describe(self, ctx, argv)
+    }
+
+    fn eval_arithmetic(&mut self, expr: &str) -> Result<i64> {
+        crate::parser::arithmetic::evaluate(expr, &mut *self.environment.write())
+    }
+
+    fn positional_params(&self) -> Vec<String> {
+        self.environment.read().positional_params.clone()
+    }
+
+    fn resolve_fd(&self, fd: RawFd) -> RawFd {
+        self.environment
+            .read()
+            .exec_fds
+            .get(&fd)
+            .map_or(fd, |file| file.as_raw_fd())
+    }
+
+    fn shell_options(&self) -> Vec<OptionEntry> {
+        self.environment.read().options()
+    }
+
+    fn set_shell_option(&mut self, name: &str, value: &str) -> Result<()> {
+        self.environment
+            .write()
+            .set_option(name, OptionValue::Text(value.to_string()))
+    }
+
     fn get_var(&mut self, key: &str) -> Option<String> {
         self.environment.read().get_var(key)
     }
@@ -752,6 +787,11 @@ impl ShellProxy for Shell {
     }
 }
 
+// Re-export for backward compatibility
+pub use builtin::jobs::parse_job_spec;
+pub use builtin::reload::format_reload_error;
+pub use builtin::z::parse_z_args;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -771,8 +811,3 @@ mod tests {
         );
     }
 }
-
-// Re-export for backward compatibility
-pub use builtin::jobs::parse_job_spec;
-pub use builtin::reload::format_reload_error;
-pub use builtin::z::parse_z_args;
@@ -2,6 +2,7 @@ use crate::command_palette::CommandPalette;
 use crate::completion::display::Candidate;
 use crate::repl::Repl;
 use crate::repl::state::ReplControlFlow;
+use crate::shell::trap;
 use crate::terminal::renderer::TerminalRenderer;
 use anyhow::Result;
 use crossterm::cursor;
@@ -9,6 +10,7 @@ use crossterm::queue;
 use crossterm::style::Print;
 use crossterm::terminal::{Clear, ClearType};
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use dsh_types::Context;
 use skim::prelude::*;
 use std::io::Write;
 use std::sync::Arc;
@@ -79,6 +81,21 @@ pub(crate) async fn check_background_jobs(repl: &mut Repl<'_>, output: bool) ->
     Ok(())
 }
 
+/// Runs the traps of signals that arrived while the prompt was waiting for input.
+pub(crate) async fn run_pending_traps(repl: &mut Repl<'_>) -> Result<()> {
+    if !trap::has_pending_traps(repl.shell) {
+        return Ok(());
+    }
+    let ctx = Context::new_safe(repl.shell.pid, repl.shell.pgid, true);
+    let mut renderer = TerminalRenderer::new();
+    renderer.write_all(b"\r\n")?;
+    renderer.flush()?;
+    trap::run_pending_traps(repl.shell, &ctx).await;
+    repl.print_prompt(&mut renderer);
+    renderer.flush()?;
+    Ok(())
+}
+
 pub(crate) async fn handle_macro_record(repl: &mut Repl<'_>) -> Result<()> {
     let history_items = if let Some(history_arc) = &repl.shell.cmd_history {
         let history = history_arc.lock();
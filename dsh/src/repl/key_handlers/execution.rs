@@ -146,7 +146,10 @@ pub(crate) async fn handle_execute(repl: &mut Repl<'_>) -> Result<()> {
 
         // Record command timing statistics
         let elapsed = start_time.elapsed();
-        if let Some(cmd_name) = command_timing::extract_command_name(&input_str) {
+        // `time` records the command it timed itself
+        if let Some(cmd_name) = command_timing::extract_command_name(&input_str)
+            && cmd_name != "time"
+        {
             let mut timing = repl.command_timing.write();
             timing.record(&cmd_name, exit_code, elapsed);
             if let Some(path) = command_timing::get_timing_file_path()
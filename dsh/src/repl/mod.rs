@@ -277,6 +277,9 @@ impl<'a> Repl<'a> {
         // Setup AI event channel
         let (ai_tx, ai_rx) = tokio::sync::mpsc::unbounded_channel();
 
+        let command_timing = command_timing::create_shared_timing();
+        shell.command_timing = Some(Arc::clone(&command_timing));
+
         Repl {
             shell,
             input: Input::new(input_config),
@@ -302,7 +305,7 @@ impl<'a> Repl<'a> {
             input_preferences,
             ai_pending_shown: false,
             ai_service,
-            command_timing: command_timing::create_shared_timing(),
+            command_timing,
             last_command_string: String::new(),
             stopped_jobs_warned: false,
             multiline_buffer: String::new(),
@@ -721,6 +724,7 @@ impl<'a> Repl<'a> {
                         warn!("Failed to save command timing: {}", e);
                     }
                     self.check_background_jobs(true).await?;
+                    key_handlers::auxiliary::run_pending_traps(self).await?;
 
                     // Reload path history every 30 seconds to sync with other processes
                     if self.history_sync_last_check.elapsed() > Duration::from_secs(30) {
@@ -1106,7 +1110,7 @@ impl<'a> Repl<'a> {
             }
             if self.should_exit || self.shell.exited.is_some() {
                 debug!("Shell exiting normally");
-                if !self.shell.wait_jobs.is_empty() {
+                if self.shell.wait_jobs.iter().any(|job| !job.nohup) {
                     // Allow one retry to exit with stopped jobs
                     if !self.stopped_jobs_warned {
                         self.shell
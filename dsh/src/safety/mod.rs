@@ -169,6 +169,30 @@ impl SafetyGuard {
         SafetyResult::Allowed
     }
 
+    /// Check the output redirections of a pipeline against `noclobber`: a `>` that would
+    /// truncate an existing regular file needs confirmation, `>>` and `>|` never do.
+    pub fn check_redirects(&self, jobs: &[Job], noclobber: bool) -> SafetyResult {
+        if !noclobber {
+            return SafetyResult::Allowed;
+        }
+        let clobbered: Vec<String> = jobs.iter().flat_map(|job| job.clobbered_files()).collect();
+        match clobbered.as_slice() {
+            [] => SafetyResult::Allowed,
+            [file] => SafetyResult::Confirm(format!(
+                "'{}' exists and noclobber is set. Overwrite it?",
+                file
+            )),
+            files => SafetyResult::Confirm(format!(
+                "{} exist and noclobber is set. Overwrite them?",
+                files
+                    .iter()
+                    .map(|file| format!("'{}'", file))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+
     /// Check a single command (legacy or simpler use cases)
     /// This is now mostly a wrapper or for simple checks.
     pub fn check_command(
@@ -576,6 +600,33 @@ impl SafetyGuard {
         Some("System service modification detected. Proceed?".to_string())
     }
 
+    /// Check a write to a file made from Lisp (`write-file`, `append-file`). The path is
+    /// expected to be absolute.
+    pub fn check_file_write(&self, path: &str, level: &SafetyLevel) -> SafetyResult {
+        const SYSTEM_DIRS: &[&str] = &[
+            "/etc/", "/usr/", "/bin/", "/sbin/", "/lib/", "/lib64/", "/boot/", "/sys/", "/proc/",
+        ];
+
+        match level {
+            SafetyLevel::Loose => SafetyResult::Allowed,
+            SafetyLevel::Strict => {
+                SafetyResult::Confirm(format!("File '{}' will be written. Proceed?", path))
+            }
+            SafetyLevel::Normal => {
+                if let Some(msg) = Self::check_sensitive_file_access(&[path.to_string()]) {
+                    return SafetyResult::Confirm(msg);
+                }
+                if SYSTEM_DIRS.iter().any(|dir| path.starts_with(dir)) {
+                    return SafetyResult::Confirm(format!(
+                        "Write to system file detected: '{}'. Proceed?",
+                        path
+                    ));
+                }
+                SafetyResult::Allowed
+            }
+        }
+    }
+
     /// Check if modifying an environment variable is safe
     pub fn check_environment_modification(
         &self,
@@ -782,6 +833,46 @@ mod tests {
         assert_eq!(guard.check_jobs(&jobs, &level, &[]), SafetyResult::Allowed);
     }
 
+    #[test]
+    fn test_noclobber_redirect_check() {
+        use crate::process::{JobProcess, Process, Redirect};
+
+        let guard = SafetyGuard::new();
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("important.txt");
+        std::fs::write(&existing, "keep").unwrap();
+        let output = |path: &std::path::Path, append, force| Redirect::Output {
+            fd: 1,
+            path: path.display().to_string(),
+            append,
+            force,
+        };
+        let job_with = |redirect: Redirect| {
+            let mut job = mock_job("echo hi");
+            job.set_process(
+                JobProcess::Command(Process::new("echo".to_string(), vec!["echo".to_string()]))
+                    .with_redirects(vec![redirect]),
+            );
+            job
+        };
+
+        let mut jobs = vec![job_with(output(&existing, false, false))];
+        assert!(matches!(
+            guard.check_redirects(&jobs, true),
+            SafetyResult::Confirm(msg) if msg.contains("important.txt")
+        ));
+        assert_eq!(guard.check_redirects(&jobs, false), SafetyResult::Allowed);
+
+        jobs[0].force_clobber();
+        assert_eq!(guard.check_redirects(&jobs, true), SafetyResult::Allowed);
+
+        let jobs = vec![
+            job_with(output(&existing, true, false)),
+            job_with(output(&dir.path().join("new.txt"), false, false)),
+        ];
+        assert_eq!(guard.check_redirects(&jobs, true), SafetyResult::Allowed);
+    }
+
     #[test]
     fn test_mcp_tool_check() {
         let guard = SafetyGuard::new();
@@ -913,6 +1004,32 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_file_write_check() {
+        let guard = SafetyGuard::new();
+
+        assert_eq!(
+            guard.check_file_write("/home/user/notes.txt", &SafetyLevel::Normal),
+            SafetyResult::Allowed
+        );
+        assert!(matches!(
+            guard.check_file_write("/home/user/.ssh/authorized_keys", &SafetyLevel::Normal),
+            SafetyResult::Confirm(_)
+        ));
+        assert!(matches!(
+            guard.check_file_write("/etc/hosts", &SafetyLevel::Normal),
+            SafetyResult::Confirm(_)
+        ));
+        assert!(matches!(
+            guard.check_file_write("/home/user/notes.txt", &SafetyLevel::Strict),
+            SafetyResult::Confirm(_)
+        ));
+        assert_eq!(
+            guard.check_file_write("/etc/hosts", &SafetyLevel::Loose),
+            SafetyResult::Allowed
+        );
+    }
+
     #[test]
     fn test_environment_modification() {
         let guard = SafetyGuard::new();
@@ -0,0 +1,292 @@
+//! Script mode: running dsh files non-interactively.
+//!
+//! `dsh script.dsh args...` (or an executable file starting with `#!/usr/bin/env dsh`)
+//! reads the file, joins continuation lines and multi-line compound commands into
+//! statements, and evaluates them one by one with `Shell::eval_str`.
+//!
+//! `source file args...` (or `. file`) does the same inside the running shell, so the
+//! variables, aliases, functions and working directory it sets are kept.
+
+use crate::errors::display_user_error;
+use crate::parser;
+use crate::shell::eval::error_status;
+use crate::shell::{ControlFlow, Shell, function};
+use anyhow::{Context as _, Result, anyhow, bail};
+use dsh_types::Context;
+use libc::STDOUT_FILENO;
+use std::path::Path;
+use tracing::debug;
+
+/// Maximum nesting of `source`, to stop a file that sources itself.
+const MAX_SOURCE_DEPTH: usize = 64;
+
+/// A statement and the line it starts on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Statement {
+    pub line: usize,
+    pub text: String,
+    /// The input ended before the statement was complete (an open quote or `if`).
+    pub incomplete: bool,
+}
+
+/// Assembles complete statements from input lines.
+///
+/// Blank lines and comment lines between statements are skipped, a trailing backslash
+/// joins the next line, and lines are collected while the input is incomplete
+/// (open quotes, unterminated `if`/`for`/... or function bodies).
+#[derive(Debug, Default)]
+pub struct StatementReader {
+    pending: String,
+    start_line: usize,
+    line_no: usize,
+    continued: bool,
+}
+
+impl StatementReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the next input line, returning a statement once one is complete.
+    pub fn push_line(&mut self, line: &str) -> Option<Statement> {
+        self.line_no += 1;
+        let line = line.trim_end_matches(['\r', '\n']);
+
+        if self.pending.is_empty() && !self.continued {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                return None;
+            }
+            self.start_line = self.line_no;
+        } else if !self.continued {
+            self.pending.push('\n');
+        }
+
+        if let Some(joined) = strip_continuation(line) {
+            self.pending.push_str(joined);
+            self.continued = true;
+            return None;
+        }
+        self.continued = false;
+        self.pending.push_str(line);
+
+        if parser::is_incomplete_input(&self.pending) {
+            return None;
+        }
+        self.take()
+    }
+
+    /// Returns whatever is left at the end of the input, complete or not.
+    pub fn finish(&mut self) -> Option<Statement> {
+        self.continued = false;
+        if self.pending.trim().is_empty() {
+            self.pending.clear();
+            return None;
+        }
+        let incomplete = parser::is_incomplete_input(&self.pending);
+        self.take().map(|statement| Statement {
+            incomplete,
+            ..statement
+        })
+    }
+
+    fn take(&mut self) -> Option<Statement> {
+        let text = std::mem::take(&mut self.pending);
+        Some(Statement {
+            line: self.start_line,
+            text: text.trim().to_string(),
+            incomplete: false,
+        })
+    }
+}
+
+/// Returns the line without its trailing backslash if it ends with an unescaped one.
+fn strip_continuation(line: &str) -> Option<&str> {
+    let backslashes = line.chars().rev().take_while(|c| *c == '\\').count();
+    (backslashes % 2 == 1).then(|| &line[..line.len() - 1])
+}
+
+/// Splits a whole script into statements, skipping a leading `#!` line.
+pub fn split_statements(source: &str) -> Vec<Statement> {
+    let mut reader = StatementReader::new();
+    let mut statements = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        if index == 0 && line.starts_with("#!") {
+            reader.line_no += 1;
+            continue;
+        }
+        statements.extend(reader.push_line(line));
+    }
+    statements.extend(reader.finish());
+    statements
+}
+
+/// Runs the script at `path` with `args` as positional parameters.
+///
+/// Returns the status of the last statement, or the status passed to `exit`.
+pub async fn run_script(
+    shell: &mut Shell,
+    ctx: &mut Context,
+    path: &Path,
+    args: Vec<String>,
+) -> Result<i32> {
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("{}: cannot read script", path.display()))?;
+
+    {
+        let mut env = shell.environment.write();
+        env.positional_params = args;
+        env.variables
+            .insert("0".to_string(), path.display().to_string());
+    }
+
+    Ok(run_statements(shell, ctx, path, &source).await)
+}
+
+/// Runs the file at `path` in the current shell, for `source` and `.`.
+///
+/// `args`, when given, replace the positional parameters until the file is done.
+/// Returns the status of the last statement.
+pub async fn source_file(
+    shell: &mut Shell,
+    ctx: &Context,
+    path: &Path,
+    args: Vec<String>,
+) -> Result<i32> {
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("{}: cannot read file", path.display()))?;
+    if shell.source_depth >= MAX_SOURCE_DEPTH {
+        bail!(
+            "maximum source nesting level exceeded ({})",
+            MAX_SOURCE_DEPTH
+        );
+    }
+
+    let saved_params = (!args.is_empty())
+        .then(|| std::mem::replace(&mut shell.environment.write().positional_params, args));
+
+    let mut ctx = ctx.clone();
+    ctx.save_history = false;
+    // Inside a pipeline or substitution every statement writes to the same fd
+    if ctx.outfile != STDOUT_FILENO && ctx.captured_out.is_none() {
+        ctx.captured_out = Some(ctx.outfile);
+    }
+    shell.source_depth += 1;
+    function::push_source_frame(shell);
+    let status = run_statements(shell, &mut ctx, path, &source).await;
+    let status = function::pop_source_frame(shell, status);
+    shell.source_depth -= 1;
+
+    if let Some(params) = saved_params {
+        shell.environment.write().positional_params = params;
+    }
+    Ok(status)
+}
+
+/// Evaluates the statements of `source`, reporting errors with the file and line.
+async fn run_statements(shell: &mut Shell, ctx: &mut Context, path: &Path, source: &str) -> i32 {
+    let mut status = 0;
+    for statement in split_statements(source) {
+        debug!(
+            "script {}:{}: {}",
+            path.display(),
+            statement.line,
+            statement.text
+        );
+        if statement.incomplete {
+            display_user_error(
+                &anyhow!(
+                    "{}:{}: syntax error: unexpected end of file",
+                    path.display(),
+                    statement.line
+                ),
+                true,
+            );
+            return 2;
+        }
+        status = match shell.eval_str(ctx, statement.text, false).await {
+            Ok(code) => code,
+            Err(err) => {
+                let code = error_status(&err);
+                display_user_error(
+                    &anyhow!("{}:{}: {}", path.display(), statement.line, err),
+                    true,
+                );
+                code
+            }
+        };
+        // `return` leaves a sourced file
+        if shell.exited.is_some() || matches!(shell.control_flow, Some(ControlFlow::Return(_))) {
+            break;
+        }
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(source: &str) -> Vec<(usize, String)> {
+        split_statements(source)
+            .into_iter()
+            .map(|s| (s.line, s.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_skips_shebang_comments_and_blank_lines() {
+        let source =
+            "#!/usr/bin/env dsh\n# setup\n\necho one\n  # indented comment\necho two # trailing\n";
+        assert_eq!(
+            texts(source),
+            vec![
+                (4, "echo one".to_string()),
+                (6, "echo two # trailing".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_joins_continuation_lines() {
+        let source = "echo one \\\n  two \\\n  three\necho 'a\\\\'";
+        assert_eq!(
+            texts(source),
+            vec![
+                (1, "echo one   two   three".to_string()),
+                (4, "echo 'a\\\\'".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collects_compound_commands() {
+        let source = "for x in a b\ndo\n  # don't stop here\n  echo $x\ndone\necho after";
+        assert_eq!(
+            texts(source),
+            vec![
+                (
+                    1,
+                    "for x in a b\ndo\n  # don't stop here\n  echo $x\ndone".to_string()
+                ),
+                (6, "echo after".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unterminated_statement_is_returned_at_end() {
+        assert_eq!(
+            texts("echo ok\nif true; then\n  echo never"),
+            vec![
+                (1, "echo ok".to_string()),
+                (2, "if true; then\n  echo never".to_string()),
+            ]
+        );
+
+        let statements = split_statements("echo ok\necho \"unterminated\n");
+        assert!(!statements[0].incomplete);
+        assert!(statements[1].incomplete);
+        assert_eq!(statements[1].line, 2);
+    }
+}
@@ -1,19 +1,30 @@
+use crate::errors::{ShellError, display_user_error};
+use crate::parser::compound::{self, CasePattern, Command, ListItem, Stage};
+use crate::parser::heredoc;
+use crate::parser::pathname::Pattern;
 use crate::parser::{self, Rule, ShellParser};
-use crate::process::{Job, ListOp, ProcessState, wait_pid_job};
+use crate::process::io::open_redirects;
+use crate::process::{Job, JobProcess, ListOp, Process, ProcessState, wait_pid_job};
 use crate::shell::{
-    Shell,
-    parse::{ParseContext, parse_commands},
+    ControlFlow, Shell, function,
+    parse::{ParseContext, expand_words, parse_commands, parse_redirects},
+    select::{self, Menu},
+    time::Timer,
+    trap::{self, TrapSignal},
 };
 use crate::terminal::title;
 use anyhow::{Context as _, Result, anyhow};
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
 use dsh_builtin::execute_chat_message;
 use dsh_types::{Context, ExitStatus};
+use futures::future::{FutureExt, LocalBoxFuture};
+use nix::fcntl::OFlag;
+use nix::sys::signal::Signal;
 use nix::sys::termios::{LocalFlags, SetArg, tcgetattr, tcsetattr};
-use nix::unistd::{ForkResult, Pid, fork, getpid, setpgid};
+use nix::unistd::{ForkResult, Pid, close, fork, getpid, pipe2, setpgid};
 use pest::Parser;
 use std::io::Write;
-use std::os::fd::{AsRawFd, BorrowedFd};
+use std::os::fd::{AsRawFd, BorrowedFd, RawFd};
 use std::sync::Arc;
 use tokio::task;
 use tracing::debug;
@@ -66,7 +77,10 @@ pub async fn eval_str(
         }
     }
 
-    if let Some(rest) = input.trim_start().strip_prefix('!') {
+    // `!message` chats with the AI, `! pipeline` negates the pipeline
+    if let Some(rest) = input.trim_start().strip_prefix('!')
+        && !rest.starts_with(char::is_whitespace)
+    {
         if let Err(e) = disable_raw_mode() {
             tracing::error!("Failed to disable raw mode: {}", e);
         } else {
@@ -114,9 +128,423 @@ pub async fn eval_str(
 
     // Smart Pipe transformation
     let input = transform_input_for_smart_pipe(input);
+    let input = heredoc::normalize_heredocs(&input);
+
+    let result = match compound::parse_program(&input)? {
+        Some(list) => {
+            let result = eval_command_list(shell, ctx, &list, force_background).await;
+            // A `return` still has to leave the function or sourced file it is in
+            let returning = matches!(shell.control_flow, Some(ControlFlow::Return(_)))
+                && function::can_return(shell);
+            if shell.loop_depth == 0 && !returning {
+                shell.control_flow = None;
+            }
+            result
+        }
+        None => eval_jobs(shell, ctx, &input, force_background).await,
+    };
+
+    // A command line that fails with an error still sets `$?` and trips `set -e`
+    let status = match &result {
+        Ok(code) => *code,
+        Err(err) => error_status(err),
+    };
+    shell.environment.write().last_status = status;
+    if result.is_err() {
+        trap::run_err_trap(shell, ctx).await;
+    }
+    if let Err(err) = &result
+        && (errexit_applies(shell) || exits_non_interactive(ctx, err))
+    {
+        shell.exited = Some(ExitStatus::ExitedWith(status));
+    }
+    result
+}
+
+/// Returns true for errors that end a non-interactive shell, like expanding an unset
+/// variable under `set -u` or with `${name:?}`.
+fn exits_non_interactive(ctx: &Context, err: &anyhow::Error) -> bool {
+    !ctx.interactive
+        && matches!(
+            ShellError::of(err),
+            Some(ShellError::UnboundVariable(_) | ShellError::ParameterUnset { .. })
+        )
+}
+
+/// Exit status of a command that failed with an error instead of exiting.
+pub(crate) fn error_status(err: &anyhow::Error) -> i32 {
+    match ShellError::of(err) {
+        Some(ShellError::CommandNotFound { .. }) => 127,
+        _ => 1,
+    }
+}
+
+/// Returns true if a failing command has to stop the shell because of `set -e`.
+fn errexit_applies(shell: &Shell) -> bool {
+    shell.condition_depth == 0 && shell.environment.read().shell_options.errexit
+}
+
+/// Records the status of a finished job as `$?` and `$PIPESTATUS`.
+fn record_status(shell: &Shell, status: i32, pipe_status: Vec<i32>) {
+    let mut env = shell.environment.write();
+    env.last_status = status;
+    env.pipe_status = pipe_status;
+}
+
+/// Evaluates a parsed program.
+///
+/// Errors from individual commands are reported and turned into a failure status, so
+/// `||`, `if` and loops can react to them like to any other failing command.
+fn eval_command_list<'a>(
+    shell: &'a mut Shell,
+    ctx: &'a mut Context,
+    list: &'a [ListItem],
+    force_background: bool,
+) -> LocalBoxFuture<'a, Result<i32>> {
+    async move {
+        let mut last_exit_code = 0_i32;
+        let mut gate_op = ListOp::None;
+        for item in list {
+            let should_run = match gate_op {
+                ListOp::None => true,
+                ListOp::And => last_exit_code == 0,
+                ListOp::Or => last_exit_code != 0,
+            };
+            gate_op = item.list_op.clone();
+            if !should_run {
+                continue;
+            }
+
+            let previous_exit_code = last_exit_code;
+            // A command followed by `&&` or `||` may fail without stopping `set -e`
+            let guarded = usize::from(item.list_op != ListOp::None);
+            shell.condition_depth += guarded;
+            let result = eval_command(shell, ctx, &item.command, force_background).await;
+            shell.condition_depth -= guarded;
+            last_exit_code = match result {
+                Ok(code) => code,
+                Err(err) => {
+                    display_user_error(&err, false);
+                    if exits_non_interactive(ctx, &err) {
+                        shell.exited = Some(ExitStatus::ExitedWith(1));
+                    }
+                    let status = error_status(&err);
+                    if guarded == 0 {
+                        shell.environment.write().last_status = status;
+                        trap::run_err_trap(shell, ctx).await;
+                    }
+                    status
+                }
+            };
+            shell.environment.write().last_status = last_exit_code;
+            if shell.control_flow == Some(ControlFlow::Return(None)) {
+                // A bare `return` keeps the status of the command before it
+                shell.control_flow = Some(ControlFlow::Return(Some(previous_exit_code)));
+            }
+            // The status of `! pipeline` does not stop `set -e` either
+            if last_exit_code != 0
+                && item.list_op == ListOp::None
+                && !matches!(item.command, Command::Not(_))
+                && shell.exited.is_none()
+                && errexit_applies(shell)
+            {
+                shell.exited = Some(ExitStatus::ExitedWith(last_exit_code));
+            }
+            if shell.control_flow.is_some() || shell.exited.is_some() {
+                break;
+            }
+        }
+        Ok(last_exit_code)
+    }
+    .boxed_local()
+}
+
+pub(crate) async fn eval_command(
+    shell: &mut Shell,
+    ctx: &mut Context,
+    command: &Command,
+    force_background: bool,
+) -> Result<i32> {
+    match command {
+        Command::Simple(line) => eval_jobs(shell, ctx, line, force_background).await,
+        Command::Pipeline { text, stages } => {
+            eval_pipeline(shell, ctx, text, stages, force_background).await
+        }
+        Command::Group(body) => eval_command_list(shell, ctx, body, force_background).await,
+        Command::Not(body) => {
+            shell.condition_depth += 1;
+            let result = eval_command(shell, ctx, body, force_background)
+                .boxed_local()
+                .await;
+            shell.condition_depth -= 1;
+            let status = match result {
+                Ok(status) => status,
+                Err(err) => {
+                    display_user_error(&err, false);
+                    error_status(&err)
+                }
+            };
+            Ok(i32::from(status == 0))
+        }
+        Command::If {
+            branches,
+            else_body,
+        } => {
+            for (condition, body) in branches {
+                let status = eval_condition(shell, ctx, condition, force_background).await?;
+                if shell.control_flow.is_some() || shell.exited.is_some() {
+                    return Ok(status);
+                }
+                if status == 0 {
+                    return eval_command_list(shell, ctx, body, force_background).await;
+                }
+            }
+            match else_body {
+                Some(body) => eval_command_list(shell, ctx, body, force_background).await,
+                None => Ok(0),
+            }
+        }
+        Command::While {
+            condition,
+            body,
+            until,
+        } => {
+            shell.loop_depth += 1;
+            let mut last_exit_code = 0;
+            let result = async {
+                loop {
+                    let status = eval_condition(shell, ctx, condition, force_background).await?;
+                    if finish_iteration(shell) || (status == 0) == *until {
+                        break;
+                    }
+                    last_exit_code = eval_command_list(shell, ctx, body, force_background).await?;
+                    if finish_iteration(shell) {
+                        break;
+                    }
+                }
+                Ok(last_exit_code)
+            }
+            .await;
+            shell.loop_depth -= 1;
+            result
+        }
+        Command::For { name, words, body } => {
+            let values = match words {
+                Some(words) => expand_words(shell, words)?,
+                None => shell.environment.read().positional_params.clone(),
+            };
+            shell.loop_depth += 1;
+            let mut last_exit_code = 0;
+            let result = async {
+                for value in values {
+                    shell
+                        .environment
+                        .write()
+                        .variables
+                        .insert(name.clone(), value);
+                    last_exit_code = eval_command_list(shell, ctx, body, force_background).await?;
+                    if finish_iteration(shell) {
+                        break;
+                    }
+                }
+                Ok(last_exit_code)
+            }
+            .await;
+            shell.loop_depth -= 1;
+            result
+        }
+        Command::Select { name, words, body } => {
+            let items = match words {
+                Some(words) => expand_words(shell, words)?,
+                None => shell.environment.read().positional_params.clone(),
+            };
+            if items.is_empty() {
+                return Ok(0);
+            }
+            let mut menu = Menu::new(ctx, items);
+            shell.loop_depth += 1;
+            let mut last_exit_code = 0;
+            let result = async {
+                loop {
+                    let prompt = shell
+                        .environment
+                        .read()
+                        .get_var("PS3")
+                        .unwrap_or_else(|| select::DEFAULT_PROMPT.to_string());
+                    let Some(choice) = menu.choose(ctx, &prompt)? else {
+                        break;
+                    };
+                    {
+                        let mut env = shell.environment.write();
+                        env.variables.insert(name.clone(), choice.item);
+                        env.variables.insert("REPLY".to_string(), choice.reply);
+                    }
+                    last_exit_code = eval_command_list(shell, ctx, body, force_background).await?;
+                    if finish_iteration(shell) {
+                        break;
+                    }
+                }
+                Ok(last_exit_code)
+            }
+            .await;
+            shell.loop_depth -= 1;
+            result
+        }
+        Command::Case { word, items } => {
+            let word = expand_words(shell, word)?.join(" ");
+            for item in items {
+                if item
+                    .patterns
+                    .iter()
+                    .any(|pattern| case_pattern_matches(shell, pattern, &word))
+                {
+                    return eval_command_list(shell, ctx, &item.body, force_background).await;
+                }
+            }
+            Ok(0)
+        }
+        Command::Coproc { name, body } => {
+            let pid = task::block_in_place(|| spawn_coproc(shell, ctx, name, body))?;
+            let mut job = Job::forked(format!("coproc {name}"), shell.pgid, pid);
+            job.job_id = shell.get_next_job_id();
+            shell.wait_jobs.push(job);
+            Ok(0)
+        }
+        Command::FunctionDef { name, body } => {
+            shell
+                .environment
+                .write()
+                .functions
+                .insert(name.clone(), Arc::new((**body).clone()));
+            Ok(0)
+        }
+    }
+}
+
+/// Runs a pipeline with compound commands among its stages. Those run in forked copies of
+/// the shell, like `( ... )`, except a lone compound command with redirections, which runs
+/// in the shell so that the variables it sets are kept.
+async fn eval_pipeline(
+    shell: &mut Shell,
+    ctx: &mut Context,
+    text: &str,
+    stages: &[Stage],
+    force_background: bool,
+) -> Result<i32> {
+    if let [
+        Stage::Compound {
+            body, redirects, ..
+        },
+    ] = stages
+    {
+        let mut job = Job::new(text.to_string(), shell.pgid);
+        let redirects = parse_redirects(shell, &mut job, redirects)?;
+        let redirect_fds = open_redirects(&redirects, &shell.environment.read())?;
+        let mut stage_ctx = ctx.clone();
+        [stage_ctx.infile, stage_ctx.outfile, stage_ctx.errfile] =
+            redirect_fds.apply_to_std([ctx.infile, ctx.outfile, ctx.errfile]);
+        if stage_ctx.outfile != ctx.outfile {
+            // Every command of the body writes to the redirected file
+            stage_ctx.captured_out = Some(stage_ctx.outfile);
+        }
+        let result = Box::pin(eval_command(shell, &mut stage_ctx, body, force_background)).await;
+        drop(redirect_fds);
+        job.reap_proc_subst_readers(false);
+        return result;
+    }
+
+    let mut job = Job::new(text.to_string(), shell.pgid);
+    for stage in stages {
+        match stage {
+            Stage::Simple(line) => {
+                for stage_job in get_jobs(shell, line)? {
+                    job.append(stage_job);
+                }
+            }
+            Stage::Compound {
+                text,
+                body,
+                redirects,
+            } => {
+                let redirects = parse_redirects(shell, &mut job, redirects)?;
+                job.set_process(
+                    JobProcess::Command(Process::compound(text.clone(), (**body).clone()))
+                        .with_redirects(redirects),
+                );
+            }
+        }
+    }
+    run_jobs(shell, ctx, vec![job], force_background).await
+}
 
-    let jobs = get_jobs(shell, &input)?;
+/// Evaluates the condition of `if`, `while` or `until`, where failures are expected.
+async fn eval_condition(
+    shell: &mut Shell,
+    ctx: &mut Context,
+    condition: &[ListItem],
+    force_background: bool,
+) -> Result<i32> {
+    shell.condition_depth += 1;
+    let result = eval_command_list(shell, ctx, condition, force_background).await;
+    shell.condition_depth -= 1;
+    result
+}
 
+/// Consumes a pending `break`/`continue` at the end of a loop iteration.
+/// Returns true if the loop has to stop.
+fn finish_iteration(shell: &mut Shell) -> bool {
+    if shell.exited.is_some() {
+        return true;
+    }
+    match shell.control_flow {
+        None => false,
+        Some(ControlFlow::Break(n)) => {
+            shell.control_flow = (n > 1).then_some(ControlFlow::Break(n - 1));
+            true
+        }
+        Some(ControlFlow::Continue(n)) => {
+            shell.control_flow = (n > 1).then_some(ControlFlow::Continue(n - 1));
+            n > 1
+        }
+        Some(ControlFlow::Return(_)) | Some(ControlFlow::Interrupt) => true,
+    }
+}
+
+fn case_pattern_matches(shell: &Shell, pattern: &CasePattern, word: &str) -> bool {
+    if pattern.quoted {
+        return pattern.text == word;
+    }
+    let text = if pattern.text.starts_with('$') {
+        shell
+            .environment
+            .read()
+            .get_var(&pattern.text)
+            .unwrap_or_default()
+    } else {
+        pattern.text.clone()
+    };
+    let extglob = shell.environment.read().shell_options.extglob;
+    Pattern::new(&text, extglob).matches(word)
+}
+
+/// Parses a plain command line into jobs and runs them, honouring `&&` and `||`.
+async fn eval_jobs(
+    shell: &mut Shell,
+    ctx: &mut Context,
+    input: &str,
+    force_background: bool,
+) -> Result<i32> {
+    let jobs = get_jobs(shell, input)?;
+    run_jobs(shell, ctx, jobs, force_background).await
+}
+
+/// Runs the jobs of a command line in turn, honouring `&&` and `||`.
+async fn run_jobs(
+    shell: &mut Shell,
+    ctx: &mut Context,
+    mut jobs: Vec<Job>,
+    force_background: bool,
+) -> Result<i32> {
     // SAFETY CHECK
     {
         use crate::repl::confirmation::ConfirmationAction;
@@ -131,10 +559,29 @@ pub async fn eval_str(
             let safety_level_guard = environment.safety_level.read();
             let allowlist_guard = environment.execute_allowlist.read();
 
-            match shell
-                .safety_guard
-                .check_jobs(&jobs, &safety_level_guard, &allowlist_guard)
-            {
+            let result =
+                shell
+                    .safety_guard
+                    .check_jobs(&jobs, &safety_level_guard, &allowlist_guard);
+            // Overwriting a file despite noclobber can only be confirmed at the prompt;
+            // elsewhere opening the redirection fails
+            let clobber = if ctx.interactive {
+                shell
+                    .safety_guard
+                    .check_redirects(&jobs, environment.noclobber())
+            } else {
+                SafetyResult::Allowed
+            };
+            let force_clobber = matches!(clobber, SafetyResult::Confirm(_));
+            let result = match (result, clobber) {
+                (SafetyResult::Confirm(reason), SafetyResult::Confirm(overwrite)) => {
+                    SafetyResult::Confirm(format!("{} {}", reason, overwrite))
+                }
+                (SafetyResult::Allowed, clobber) => clobber,
+                (result, _) => result,
+            };
+
+            match result {
                 SafetyResult::Allowed => {
                     // Proceed
                 }
@@ -147,7 +594,16 @@ pub async fn eval_str(
                     drop(safety_level_guard);
                     drop(environment);
 
-                    match crate::repl::confirmation::confirm_action(&reason) {
+                    let action = crate::repl::confirmation::confirm_action(&reason);
+                    if force_clobber
+                        && matches!(
+                            action,
+                            Ok(ConfirmationAction::Yes | ConfirmationAction::AlwaysAllow)
+                        )
+                    {
+                        jobs.iter_mut().for_each(Job::force_clobber);
+                    }
+                    match action {
                         Ok(ConfirmationAction::Yes) => {
                             // Proceed
                         }
@@ -182,6 +638,8 @@ pub async fn eval_str(
         }
     }
 
+    // Pipes and redirects of one job must not leak into the next one
+    let (base_infile, base_outfile, base_errfile) = (ctx.infile, ctx.outfile, ctx.errfile);
     let mut last_exit_code = 0_i32;
     // Operator that gates execution of the *current* job based on the previous job result.
     // This is effectively "the separator between previous and current job".
@@ -206,6 +664,10 @@ pub async fn eval_str(
             gate_op = next_gate_op;
             continue;
         }
+        // A job followed by `&&` or `||` may fail without stopping `set -e`
+        let guarded = usize::from(next_gate_op != ListOp::None);
+
+        trap::run_trap(shell, ctx, TrapSignal::Debug).await;
 
         // Execute pre-exec hooks
         if let Err(e) = shell.exec_pre_exec_hooks(&job.cmd) {
@@ -219,6 +681,10 @@ pub async fn eval_str(
             debug!("EVAL_STR: Successfully disabled raw mode");
         }
 
+        if shell.environment.read().shell_options.xtrace {
+            eprintln!("+ {}", job.cmd);
+        }
+
         if force_background {
             // all job run background
             job.foreground = false;
@@ -227,15 +693,19 @@ pub async fn eval_str(
         job.job_id = shell.get_job_id(); // set job id
 
         debug!(
-            "start job '{:?}' foreground:{:?} redirect:{:?} list_op:{:?} capture:{:?}",
-            job.cmd, job.foreground, job.redirect, job.list_op, job.capture_output,
+            "start job '{:?}' foreground:{:?} list_op:{:?} capture:{:?}",
+            job.cmd, job.foreground, job.list_op, job.capture_output,
         );
         let _title_guard = TitleGuard::new(ctx, &job);
 
         // Handle capture mode with |>
         if job.capture_output {
-            let (exit, stdout, stderr) = execute_with_capture(shell, ctx, &mut job).await?;
+            shell.condition_depth += guarded;
+            let captured = execute_with_capture(shell, ctx, &mut job).await;
+            shell.condition_depth -= guarded;
+            let (exit, stdout, stderr) = captured?;
             last_exit_code = exit;
+            record_status(shell, exit, vec![exit]);
 
             // Save to output history
             {
@@ -290,8 +760,10 @@ pub async fn eval_str(
             );
 
             // Execute command through regular job launch path and capture output.
-            let (exit_code, output, stderr_output) =
-                execute_with_capture(shell, ctx, &mut job).await?;
+            shell.condition_depth += guarded;
+            let captured = execute_with_capture(shell, ctx, &mut job).await;
+            shell.condition_depth -= guarded;
+            let (exit_code, output, stderr_output) = captured?;
             last_exit_code = exit_code;
 
             // Output stderr to terminal (struct_pipe only processes stdout)
@@ -343,6 +815,7 @@ pub async fn eval_str(
             if current_value != Value::NIL {
                 println!("{}", current_value);
             }
+            record_status(shell, last_exit_code, vec![last_exit_code]);
 
             // Execute post-exec hooks
             if let Err(e) = shell.exec_post_exec_hooks(&job.cmd, last_exit_code) {
@@ -357,7 +830,15 @@ pub async fn eval_str(
             continue;
         }
 
+        job.set_stdin(base_infile);
+        job.set_stderr(base_errfile);
+        let timer = job.timed.map(Timer::start);
+        shell.condition_depth += guarded;
         let launch_result = job.launch(ctx, shell).await;
+        shell.condition_depth -= guarded;
+        ctx.infile = base_infile;
+        ctx.outfile = base_outfile;
+        ctx.errfile = base_errfile;
         let mut stop_processing = false;
         match launch_result {
             Ok(ProcessState::Running) => {
@@ -365,6 +846,7 @@ pub async fn eval_str(
                 shell.wait_jobs.push(job);
                 // Background jobs are considered successfully started.
                 last_exit_code = 0;
+                record_status(shell, 0, vec![0]);
             }
             Ok(ProcessState::Stopped(pid, _signal)) => {
                 debug!("job '{}' stopped pid: {:?}", job.cmd, pid);
@@ -373,9 +855,28 @@ pub async fn eval_str(
                 // evaluating the rest of the command list.
                 stop_processing = true;
             }
-            Ok(ProcessState::Completed(exit, _signal)) => {
+            Ok(ProcessState::Completed(exit, signal)) => {
                 debug!("job '{}' completed exit_code: {:?}", job.cmd, exit);
-                last_exit_code = i32::from(exit);
+                let pipe_status = job.pipeline_status().await;
+                last_exit_code = match signal {
+                    Some(signal) => 128 + signal as i32,
+                    None => i32::from(exit),
+                };
+                if shell.environment.read().shell_options.pipefail
+                    && let Some(failed) = pipe_status.iter().rev().find(|status| **status != 0)
+                {
+                    // The last stage that failed decides the status of the pipeline
+                    last_exit_code = *failed;
+                }
+                record_status(shell, last_exit_code, pipe_status);
+                if let Some(timer) = timer {
+                    timer.finish(shell, ctx, &job.cmd, last_exit_code);
+                }
+                if signal == Some(Signal::SIGINT) && shell.loop_depth > 0 {
+                    // Ctrl+C stops the enclosing loops, not only the current command
+                    shell.control_flow = Some(ControlFlow::Interrupt);
+                    stop_processing = true;
+                }
 
                 // Execute post-exec hooks
                 if let Err(e) = shell.exec_post_exec_hooks(&job.cmd, exit as i32) {
@@ -403,7 +904,19 @@ pub async fn eval_str(
 
         gate_op = next_gate_op;
 
-        if stop_processing {
+        if last_exit_code != 0 && guarded == 0 && !stop_processing {
+            trap::run_err_trap(shell, ctx).await;
+        }
+        if last_exit_code != 0
+            && guarded == 0
+            && !stop_processing
+            && shell.exited.is_none()
+            && errexit_applies(shell)
+        {
+            shell.exited = Some(ExitStatus::ExitedWith(last_exit_code));
+        }
+        trap::run_pending_traps(shell, ctx).await;
+        if stop_processing || shell.control_flow.is_some() || shell.exited.is_some() {
             break;
         }
     }
@@ -521,10 +1034,7 @@ pub fn get_jobs(shell: &mut Shell, input: &str) -> Result<Vec<Job>> {
 pub fn launch_subshell(shell: &mut Shell, ctx: &mut Context, jobs: Vec<Job>) -> Result<()> {
     for mut job in jobs {
         disable_raw_mode().ok();
-        let pid = task::block_in_place(|| {
-            // Avoid nested-runtime panic by driving only this future directly.
-            futures::executor::block_on(spawn_subshell(shell, ctx, &mut job))
-        })?;
+        let pid = task::block_in_place(|| spawn_subshell(shell, ctx, &mut job))?;
         debug!("spawned subshell cmd:{} pid: {:?}", job.cmd, pid);
         let res = wait_pid_job(pid, false);
         debug!("wait subshell exit:{:?}", res);
@@ -546,7 +1056,7 @@ pub fn launch_subshell(shell: &mut Shell, ctx: &mut Context, jobs: Vec<Job>) ->
 // Ideally, subshells should be implemented by re-executing the shell binary with specific flags,
 // or by using a dedicated process spawner that avoids this pattern.
 // Proceed with caution.
-async fn spawn_subshell(shell: &mut Shell, ctx: &mut Context, job: &mut Job) -> Result<Pid> {
+fn spawn_subshell(shell: &mut Shell, ctx: &mut Context, job: &mut Job) -> Result<Pid> {
     let pid = unsafe { fork().context("failed fork")? };
 
     match pid {
@@ -569,17 +1079,145 @@ async fn spawn_subshell(shell: &mut Shell, ctx: &mut Context, job: &mut Job) ->
             ctx.pgid = Some(pid);
 
             // Execute
-            let res = job.launch(ctx, shell).await;
+            let res = child_runtime().and_then(|runtime| runtime.block_on(job.launch(ctx, shell)));
 
-            if let Ok(ProcessState::Completed(exit, _)) = res {
-                std::process::exit(i32::from(exit));
-            } else {
-                std::process::exit(-1);
+            match res {
+                Ok(ProcessState::Completed(exit, _)) => exit_child(i32::from(exit)),
+                _ => exit_child(1),
+            }
+        }
+    }
+}
+
+/// Leaves a forked child of the shell with `status`, after flushing its output once.
+///
+/// `_exit` skips the exit handlers of the shell, which the child has copies of.
+pub(crate) fn exit_child(status: i32) -> ! {
+    std::io::stdout().flush().ok();
+    std::io::stderr().flush().ok();
+    unsafe { libc::_exit(status) }
+}
+
+/// Starts the jobs of an output process substitution `>(...)` in a child that reads
+/// from `ctx.infile`, and returns its pid without waiting for it.
+///
+/// `writers` are the shell's ends of the substitution pipes; the child closes them so
+/// that it sees end of file once the command writing to it exits.
+pub fn spawn_proc_subst_reader(
+    shell: &mut Shell,
+    ctx: &mut Context,
+    jobs: Vec<Job>,
+    writers: &[RawFd],
+) -> Result<Pid> {
+    task::block_in_place(|| spawn_reader(shell, ctx, jobs, writers))
+}
+
+// Same fork-without-exec caveats as `spawn_subshell`.
+fn spawn_reader(
+    shell: &mut Shell,
+    ctx: &mut Context,
+    jobs: Vec<Job>,
+    writers: &[RawFd],
+) -> Result<Pid> {
+    let pid = unsafe { fork().context("failed fork")? };
+
+    match pid {
+        ForkResult::Parent { child } => {
+            debug!("spawned process substitution reader pid: {}", child);
+            setpgid(child, child).context("failed setpgid")?;
+            Ok(child)
+        }
+        ForkResult::Child => {
+            // SAFETY: Do NOT use tracing here. Unsafe after fork.
+            for fd in writers {
+                let _ = close(*fd);
+            }
+            let pid = getpid();
+            let _ = setpgid(pid, pid);
+            ctx.pgid = Some(pid);
+
+            let Ok(runtime) = child_runtime() else {
+                exit_child(1);
+            };
+            // Every job of `>(a; b)` reads from the same pipe
+            let infile = ctx.infile;
+            let mut status = 0;
+            for mut job in jobs {
+                job.pgid = Some(pid);
+                job.set_stdin(infile);
+                ctx.infile = infile;
+                status = match runtime.block_on(job.launch(ctx, shell)) {
+                    Ok(ProcessState::Completed(exit, _)) => i32::from(exit),
+                    _ => 1,
+                };
+            }
+            exit_child(status);
+        }
+    }
+}
+
+/// Forks a child that runs `body` with its stdin and stdout connected to the shell by
+/// pipes, and registers the shell's ends of the pipes as the coprocess `name`.
+// Same fork-without-exec caveats as `spawn_subshell`.
+fn spawn_coproc(shell: &mut Shell, ctx: &Context, name: &str, body: &Command) -> Result<Pid> {
+    let (child_stdin, input) = pipe2(OFlag::O_CLOEXEC).context("failed pipe")?;
+    let (output, child_stdout) = pipe2(OFlag::O_CLOEXEC).context("failed pipe")?;
+
+    match unsafe { fork().context("failed fork")? } {
+        ForkResult::Parent { child } => {
+            debug!("spawned coproc {} pid: {}", name, child);
+            setpgid(child, child).context("failed setpgid")?;
+            shell
+                .environment
+                .write()
+                .add_coproc(name, child.as_raw(), output, input);
+            Ok(child)
+        }
+        ForkResult::Child => {
+            // SAFETY: Do NOT use tracing here. Unsafe after fork.
+            // The coprocess would never see the end of its input if it kept the write end
+            drop(input);
+            drop(output);
+            if unsafe { libc::dup2(child_stdin.as_raw_fd(), 0) } < 0
+                || unsafe { libc::dup2(child_stdout.as_raw_fd(), 1) } < 0
+            {
+                exit_child(1);
             }
+            drop(child_stdin);
+            drop(child_stdout);
+            let pid = getpid();
+            let _ = setpgid(pid, pid);
+
+            let mut ctx = ctx.clone();
+            ctx.infile = 0;
+            ctx.outfile = 1;
+            ctx.interactive = false;
+            ctx.pgid = Some(pid);
+            let Ok(runtime) = child_runtime() else {
+                exit_child(1);
+            };
+            let status = runtime
+                .block_on(eval_command(shell, &mut ctx, body, false))
+                .unwrap_or(1);
+            exit_child(status);
         }
     }
 }
 
+/// Runtime for the jobs of a forked child.
+///
+/// The worker threads of the shell's runtime do not exist after `fork`, so timers and
+/// blocking tasks started on it would never complete. Like the shell's own runtime it is
+/// multi-threaded, as forking again for a nested `$(...)` or a background builtin goes
+/// through `block_in_place`.
+pub(crate) fn child_runtime() -> Result<tokio::runtime::Runtime> {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .context("failed to build runtime")
+}
+
 fn transform_input_for_smart_pipe(input: String) -> String {
     let trimmed = input.trim_start();
     // Check if it starts with | but not |> (capture) or || (OR operator)
@@ -695,4 +1333,24 @@ mod tests {
             "__dsh_print_last_stdout \t| sed 's/a/b/g'"
         );
     }
+
+    #[test]
+    fn test_error_status_follows_error_kind() {
+        let not_found = anyhow::Error::from(ShellError::CommandNotFound {
+            name: "nope".to_string(),
+            hint: String::new(),
+        });
+        assert_eq!(error_status(&not_found), 127);
+        assert_eq!(error_status(&not_found.context("script.dsh:3")), 127);
+        assert_eq!(error_status(&anyhow!("unknown command: nope")), 1);
+
+        let mut ctx = Context::new_safe(getpid(), getpid(), false);
+        ctx.interactive = false;
+        let unbound = anyhow::Error::from(ShellError::UnboundVariable("x".to_string()));
+        assert!(exits_non_interactive(&ctx, &unbound));
+        assert!(!exits_non_interactive(
+            &ctx,
+            &anyhow!("x: unbound variable")
+        ));
+    }
 }
@@ -0,0 +1,132 @@
+//! Shell functions defined with `name() { ... }`.
+//!
+//! Each call pushes a frame holding the caller's positional parameters and the
+//! previous values of variables declared `local`, restored when the call returns.
+//! `source` pushes a frame as well, so that `return` leaves the sourced file.
+
+use crate::shell::{ControlFlow, Shell, eval};
+use anyhow::{Result, bail};
+use dsh_types::Context;
+use libc::STDOUT_FILENO;
+use std::collections::HashMap;
+
+/// Maximum nesting depth of function calls, to stop runaway recursion before the
+/// shell thread runs out of stack.
+const MAX_CALL_DEPTH: usize = 1000;
+
+#[derive(Debug, Default)]
+pub struct CallFrame {
+    /// The frame of a file run by `source`, which keeps no state of its own.
+    sourcing: bool,
+    saved_params: Vec<String>,
+    /// Previous values of variables declared `local` in this frame.
+    saved_locals: HashMap<String, Option<String>>,
+}
+
+/// Runs the function named by `argv[0]` with the remaining arguments as positional parameters.
+pub async fn call_function(shell: &mut Shell, ctx: &Context, argv: Vec<String>) -> Result<i32> {
+    let Some((name, args)) = argv.split_first() else {
+        bail!("missing function name");
+    };
+    let Some(body) = shell.environment.read().functions.get(name).cloned() else {
+        bail!("function not found");
+    };
+    if shell.call_frames.len() >= MAX_CALL_DEPTH {
+        bail!(
+            "maximum function nesting level exceeded ({})",
+            MAX_CALL_DEPTH
+        );
+    }
+
+    let saved_params = std::mem::replace(
+        &mut shell.environment.write().positional_params,
+        args.to_vec(),
+    );
+    shell.call_frames.push(CallFrame {
+        saved_params,
+        ..CallFrame::default()
+    });
+    // Loops of the caller are not visible to `break`/`continue` inside the function
+    let loop_depth = std::mem::take(&mut shell.loop_depth);
+
+    let mut ctx = ctx.clone();
+    // Inside a pipeline or substitution every command of the body writes to the same fd
+    if ctx.outfile != STDOUT_FILENO && ctx.captured_out.is_none() {
+        ctx.captured_out = Some(ctx.outfile);
+    }
+    let result = eval::eval_command(shell, &mut ctx, &body, false).await;
+
+    shell.loop_depth = loop_depth;
+    if let Some(frame) = shell.call_frames.pop() {
+        let mut env = shell.environment.write();
+        env.positional_params = frame.saved_params;
+        for (key, value) in frame.saved_locals {
+            match value {
+                Some(value) => env.variables.insert(key, value),
+                None => env.variables.remove(&key),
+            };
+        }
+    }
+
+    let status = result?;
+    match shell.control_flow {
+        Some(ControlFlow::Return(code)) => {
+            shell.control_flow = None;
+            Ok(code.unwrap_or(status))
+        }
+        Some(ControlFlow::Break(_)) | Some(ControlFlow::Continue(_)) => {
+            shell.control_flow = None;
+            Ok(status)
+        }
+        Some(ControlFlow::Interrupt) | None => Ok(status),
+    }
+}
+
+/// Pushes the frame of a file run by `source`, which `return` unwinds to.
+pub fn push_source_frame(shell: &mut Shell) {
+    shell.call_frames.push(CallFrame {
+        sourcing: true,
+        ..CallFrame::default()
+    });
+}
+
+/// Pops the frame pushed by [`push_source_frame`] and returns the status of `source`,
+/// taken from a `return` that left the file.
+pub fn pop_source_frame(shell: &mut Shell, status: i32) -> i32 {
+    shell.call_frames.pop();
+    match shell.control_flow {
+        Some(ControlFlow::Return(code)) => {
+            shell.control_flow = None;
+            code.unwrap_or(status)
+        }
+        _ => status,
+    }
+}
+
+/// Declares `name` as local to the current function frame and assigns `value` if given.
+pub fn declare_local(shell: &mut Shell, name: &str, value: Option<String>) -> Result<()> {
+    let Some(frame) = shell
+        .call_frames
+        .iter_mut()
+        .rev()
+        .find(|frame| !frame.sourcing)
+    else {
+        bail!("can only be used in a function");
+    };
+    let mut env = shell.environment.write();
+    frame
+        .saved_locals
+        .entry(name.to_string())
+        .or_insert_with(|| env.variables.get(name).cloned());
+    match value {
+        Some(value) => env.variables.insert(name.to_string(), value),
+        None => env.variables.remove(name),
+    };
+    Ok(())
+}
+
+/// Returns true while a shell function or a sourced file is running, which `return`
+/// can leave.
+pub fn can_return(shell: &Shell) -> bool {
+    !shell.call_frames.is_empty()
+}
@@ -199,10 +199,8 @@ pub async fn check_job_state(shell: &mut Shell) -> Result<Vec<Job>> {
 }
 
 pub fn kill_wait_jobs(shell: &mut Shell) -> Result<()> {
-    let mut i = 0;
-    while i < shell.wait_jobs.len() {
-        shell.wait_jobs[i].kill()?;
-        i += 1;
+    for job in shell.wait_jobs.iter_mut().filter(|job| !job.nohup) {
+        job.kill()?;
     }
     Ok(())
 }
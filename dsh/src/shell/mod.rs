@@ -1,9 +1,15 @@
 pub mod eval;
+pub mod function;
 pub mod hooks;
 pub mod job;
 pub mod parse;
+pub mod resolve;
+pub mod select;
 pub mod terminal;
+pub mod time;
+pub mod trap;
 
+use crate::command_timing::SharedCommandTiming;
 use crate::environment::Environment;
 use crate::history::{FrecencyHistory, HistoryMetadata};
 use crate::lisp;
@@ -24,6 +30,19 @@ use tracing::{debug, warn};
 pub const APP_NAME: &str = "dsh";
 pub const SHELL_TERMINAL: c_int = STDIN_FILENO;
 
+/// Pending change of control flow raised while evaluating compound commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlFlow {
+    /// `break N`: leave N enclosing loops.
+    Break(usize),
+    /// `continue N`: resume the Nth enclosing loop.
+    Continue(usize),
+    /// `return [N]`: leave the current function; `None` keeps the last status.
+    Return(Option<i32>),
+    /// A foreground job was interrupted by Ctrl+C; unwind all loops.
+    Interrupt,
+}
+
 pub struct Shell {
     pub environment: Arc<RwLock<Environment>>,
     pub exited: Option<ExitStatus>,
@@ -38,6 +57,18 @@ pub struct Shell {
     pub safety_guard: Arc<crate::safety::SafetyGuard>,
     pub github_status: Option<Arc<RwLock<crate::github::GitHubStatus>>>,
     pub session_id: String,
+    pub(crate) control_flow: Option<ControlFlow>,
+    pub(crate) loop_depth: usize,
+    /// Nesting of conditions (`if`/`while` tests, commands followed by `&&`/`||`)
+    /// in which a failure does not trigger `set -e`.
+    pub(crate) condition_depth: usize,
+    /// Nesting of files run by `source`.
+    pub(crate) source_depth: usize,
+    /// Set while a trap runs, so that its commands do not fire traps themselves.
+    pub(crate) in_trap: bool,
+    pub(crate) call_frames: Vec<function::CallFrame>,
+    /// Timing statistics of the REPL, which `time` records into as well.
+    pub command_timing: Option<SharedCommandTiming>,
 }
 
 impl std::fmt::Debug for Shell {
@@ -78,6 +109,13 @@ impl Shell {
             safety_guard,
             github_status: None,
             session_id: xid::new().to_string(),
+            control_flow: None,
+            loop_depth: 0,
+            condition_depth: 0,
+            source_depth: 0,
+            in_trap: false,
+            call_frames: Vec::new(),
+            command_timing: None,
         }
     }
 
@@ -1,15 +1,19 @@
-use crate::dirs;
-use crate::parser::{self, Rule};
-use crate::process::{self, Job, JobProcess, Redirect, SubshellType};
-use crate::shell::Shell;
-use anyhow::{Context as _, Result, bail};
+use crate::errors::ShellError;
+use crate::parser::{self, Rule, ShellParser, heredoc};
+use crate::process::{self, Job, JobProcess, Redirect, ShellCall, SubshellType};
+use crate::shell::resolve::{Lookup, Resolution, resolve};
+use crate::shell::time::TimeFormat;
+use crate::shell::{Shell, eval};
+use anyhow::{Context as _, Result, anyhow, bail};
 use dsh_types::Context;
-use nix::sys::termios::tcgetattr;
-use nix::unistd::pipe;
+use nix::sys::termios::{Termios, tcgetattr};
+use nix::unistd::{Pid, pipe};
+use pest::Parser;
 use pest::iterators::Pair;
 use std::fs::File;
 use std::io::Read;
-use std::os::fd::{AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, RawFd};
+use std::os::fd::{AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
+use std::sync::Arc;
 use tracing::{debug, warn};
 
 #[derive(Debug)]
@@ -32,6 +36,9 @@ pub struct ParseContext {
     pub foreground: bool,
     pub subshell: bool,
     pub proc_subst: bool,
+    /// `> >(cmd)` and `< <(cmd)`: the index of the redirect and the substitution that
+    /// provides its path.
+    pub redirect_substs: Vec<(usize, ParsedJob)>,
 }
 
 impl ParseContext {
@@ -40,6 +47,7 @@ impl ParseContext {
             foreground,
             subshell: false,
             proc_subst: false,
+            redirect_substs: Vec::new(),
         }
     }
 }
@@ -47,7 +55,7 @@ impl ParseContext {
 pub fn parse_argv(
     shell: &mut Shell,
     ctx: &mut ParseContext,
-    current_job: &mut Job,
+    redirects: &mut Vec<Redirect>,
     pair: Pair<Rule>,
 ) -> Result<Vec<(String, Option<ParsedJob>)>> {
     let mut argv: Vec<(String, Option<ParsedJob>)> = vec![];
@@ -56,6 +64,13 @@ pub fn parse_argv(
         match inner_pair.as_rule() {
             Rule::argv0 => {
                 for inner_pair in inner_pair.into_inner() {
+                    if inner_pair.as_rule() == Rule::arith_command {
+                        // `(( expr ))` runs as `let "expr"`
+                        let text = inner_pair.as_str();
+                        argv.push(("let".to_string(), None));
+                        argv.push((text[2..text.len() - 2].to_string(), None));
+                        continue;
+                    }
                     // span
                     for inner_pair in inner_pair.into_inner() {
                         match inner_pair.as_rule() {
@@ -115,53 +130,25 @@ pub fn parse_argv(
                 }
             }
             Rule::args => {
+                // End of the previous span, to join spans written without a space between
+                let mut prev_end = None;
                 for inner_pair in inner_pair.into_inner() {
                     if let Rule::redirect = inner_pair.as_rule() {
-                        // set redirect
-                        let mut redirect_rule = None;
-                        for pair in inner_pair.into_inner() {
-                            if let Rule::stdout_redirect_direction
-                            | Rule::stderr_redirect_direction
-                            | Rule::stdouterr_redirect_direction
-                            | Rule::stdin_redirect_direction = pair.as_rule()
-                            {
-                                if let Some(rule) = pair.into_inner().next() {
-                                    redirect_rule = Some(rule.as_rule());
-                                }
-                            } else if let Rule::span = pair.as_rule() {
-                                let dest = pair.as_str();
-
-                                let redirect = match redirect_rule {
-                                    Some(Rule::stdout_redirect_direction_out) => {
-                                        Some(Redirect::StdoutOutput(dest.to_string()))
-                                    }
-                                    Some(Rule::stdout_redirect_direction_append) => {
-                                        Some(Redirect::StdoutAppend(dest.to_string()))
-                                    }
-
-                                    Some(Rule::stderr_redirect_direction_out) => {
-                                        Some(Redirect::StderrOutput(dest.to_string()))
-                                    }
-                                    Some(Rule::stderr_redirect_direction_append) => {
-                                        Some(Redirect::StderrAppend(dest.to_string()))
-                                    }
-
-                                    Some(Rule::stdouterr_redirect_direction_out) => {
-                                        Some(Redirect::StdouterrOutput(dest.to_string()))
-                                    }
-                                    Some(Rule::stdouterr_redirect_direction_append) => {
-                                        Some(Redirect::StdouterrAppend(dest.to_string()))
-                                    }
-                                    Some(Rule::stdin_redirect_direction_in) => {
-                                        Some(Redirect::Input(dest.to_string()))
-                                    }
-                                    _ => None,
-                                };
-                                current_job.redirect = redirect;
-                            }
+                        let parsed = redirects.len();
+                        let subst = parse_redirect(inner_pair, redirects);
+                        for redirect in &mut redirects[parsed..] {
+                            substitute_heredoc_commands(shell, redirect)?;
                         }
+                        if let Some(subst) = subst {
+                            let (_, parsed) = parse_proc_subst(shell, ctx.foreground, subst)?;
+                            ctx.redirect_substs.push((redirects.len() - 1, parsed));
+                        }
+                        prev_end = None;
                         continue;
                     }
+                    let span = inner_pair.as_span();
+                    let adjacent = prev_end == Some(span.start());
+                    prev_end = Some(span.end());
 
                     for inner_pair in inner_pair.into_inner() {
                         match inner_pair.as_rule() {
@@ -182,23 +169,9 @@ pub fn parse_argv(
                             }
                             Rule::proc_subst => {
                                 debug!("find proc_subs args");
-                                for inner_pair in inner_pair.into_inner() {
-                                    if inner_pair.as_rule() == Rule::proc_subst_direction {
-                                        continue;
-                                    }
-                                    // commands
-                                    let cmd_str = inner_pair.as_str().to_string();
-                                    let mut ctx = ParseContext::new(ctx.foreground);
-                                    ctx.proc_subst = true;
-                                    let res = parse_commands(shell, &mut ctx, inner_pair)?;
-                                    argv.push((
-                                        cmd_str,
-                                        Some(ParsedJob::new(
-                                            SubshellType::ProcessSubstitution,
-                                            res,
-                                        )),
-                                    ));
-                                }
+                                let (cmd_str, parsed) =
+                                    parse_proc_subst(shell, ctx.foreground, inner_pair)?;
+                                argv.push((cmd_str, Some(parsed)));
                             }
                             Rule::command_subst => {
                                 debug!("find command_subst args");
@@ -218,7 +191,10 @@ pub fn parse_argv(
                             }
                             _ => {
                                 if let Some(arg) = parser::get_string(inner_pair) {
-                                    argv.push((arg, None));
+                                    match argv.last_mut() {
+                                        Some((last, None)) if adjacent => last.push_str(&arg),
+                                        _ => argv.push((arg, None)),
+                                    }
                                 }
                             }
                         }
@@ -226,7 +202,7 @@ pub fn parse_argv(
                 }
             }
             Rule::simple_command => {
-                let mut res = parse_argv(shell, ctx, current_job, inner_pair)?;
+                let mut res = parse_argv(shell, ctx, redirects, inner_pair)?;
                 argv.append(&mut res);
             }
             _ => {
@@ -237,6 +213,80 @@ pub fn parse_argv(
     Ok(argv)
 }
 
+/// Starts the process substitutions that are targets of `redirects` and replaces those
+/// targets with their paths.
+fn resolve_redirect_substs(
+    shell: &mut Shell,
+    substs: Vec<(usize, ParsedJob)>,
+    redirects: &mut [Redirect],
+    readers: &mut Vec<(Pid, OwnedFd)>,
+) -> Result<()> {
+    for (index, parsed) in substs {
+        let path = resolve_argv(shell, vec![(String::new(), Some(parsed))], readers)?;
+        if let Some(Redirect::Output { path: target, .. } | Redirect::Input { path: target, .. }) =
+            redirects.get_mut(index)
+        {
+            *target = path.concat();
+        }
+    }
+    Ok(())
+}
+
+/// Parses the redirections written after a compound command, such as `< in > >(tee log)`.
+/// The readers of its output process substitutions are added to `job`.
+pub fn parse_redirects(shell: &mut Shell, job: &mut Job, input: &str) -> Result<Vec<Redirect>> {
+    let mut redirects = Vec::new();
+    if input.is_empty() {
+        return Ok(redirects);
+    }
+    // The grammar only knows redirections as arguments, so they follow a placeholder word
+    let line = format!(": {input}");
+    let (expanded, pairs) = parser::parse_with_expansion(&line, Arc::clone(&shell.environment))?;
+    let pairs = match pairs {
+        Some(pairs) => pairs,
+        None => ShellParser::parse(Rule::commands, &expanded).map_err(|e| anyhow!(e))?,
+    };
+    let Some(pair) = pairs
+        .flatten()
+        .find(|pair| pair.as_rule() == Rule::simple_command)
+    else {
+        bail!("invalid redirection: {}", input);
+    };
+
+    let mut ctx = ParseContext::new(true);
+    parse_argv(shell, &mut ctx, &mut redirects, pair)?;
+    let mut readers = Vec::new();
+    let substs = std::mem::take(&mut ctx.redirect_substs);
+    resolve_redirect_substs(shell, substs, &mut redirects, &mut readers)?;
+    job.add_proc_subst_readers(readers);
+    Ok(redirects)
+}
+
+/// Parses the commands of `<(...)` or `>(...)`.
+fn parse_proc_subst(
+    shell: &mut Shell,
+    foreground: bool,
+    pair: Pair<Rule>,
+) -> Result<(String, ParsedJob)> {
+    let mut subshell_type = SubshellType::ProcessSubstitution;
+    let mut cmd_str = String::new();
+    let mut jobs = Vec::new();
+    for inner_pair in pair.into_inner() {
+        if inner_pair.as_rule() == Rule::proc_subst_direction {
+            if inner_pair.as_str() == ">(" {
+                subshell_type = SubshellType::OutputProcessSubstitution;
+            }
+            continue;
+        }
+        // commands
+        cmd_str = inner_pair.as_str().to_string();
+        let mut ctx = ParseContext::new(foreground);
+        ctx.proc_subst = true;
+        jobs = parse_commands(shell, &mut ctx, inner_pair)?;
+    }
+    Ok((cmd_str, ParsedJob::new(subshell_type, jobs)))
+}
+
 pub fn parse_commands(
     shell: &mut Shell,
     ctx: &mut ParseContext,
@@ -281,82 +331,36 @@ pub fn parse_command(
     pair: Pair<Rule>,
 ) -> Result<()> {
     debug!("start parse command: {}", pair.as_str());
-    let parsed_argv = parse_argv(shell, ctx, current_job, pair)?;
+    let mut redirects = Vec::new();
+    let parsed_argv = parse_argv(shell, ctx, &mut redirects, pair)?;
+    let redirect_substs = std::mem::take(&mut ctx.redirect_substs);
     if parsed_argv.is_empty() {
         return Ok(());
     }
 
-    let mut argv: Vec<String> = Vec::new();
-
-    for (cmd_str, jobs) in parsed_argv {
-        if let Some(ParsedJob {
-            subshell_type,
-            jobs,
-        }) = jobs
-        {
-            debug!("parsed job '{:?}' jobs:{:?}", cmd_str, jobs);
-            if jobs.is_empty() {
-                continue;
-            }
-            debug!("run subshell: {}", cmd_str);
-            let tmode = match tcgetattr(unsafe { BorrowedFd::borrow_raw(0) }) {
-                Ok(mode) => Some(mode),
-                Err(err) => {
-                    debug!("tcgetattr fallback for command substitution: {}", err);
-                    Context::new_safe(shell.pid, shell.pgid, false).shell_tmode
-                }
-            };
-
-            match subshell_type {
-                SubshellType::Subshell => {
-                    let mut ctx = Context::new(shell.pid, shell.pgid, tmode.clone(), false);
-                    ctx.foreground = true;
-                    // make pipe
-                    let (pout, pin) = pipe().context("failed pipe")?;
-                    ctx.outfile = pin.as_raw_fd();
-                    shell.launch_subshell(&mut ctx, jobs)?;
-                    drop(pin); // Close write end
-                    let output = read_fd(pout.into_raw_fd())?;
-                    output.lines().for_each(|x| argv.push(x.to_owned()));
-                }
-                SubshellType::CommandSubstitution => {
-                    let mut ctx = Context::new(shell.pid, shell.pgid, tmode.clone(), false);
-                    ctx.foreground = true;
-                    let (pout, pin) = pipe().context("failed pipe")?;
-                    ctx.outfile = pin.as_raw_fd();
-                    shell.launch_subshell(&mut ctx, jobs)?;
-                    drop(pin); // Close write end
-                    let output = read_fd(pout.into_raw_fd())?;
-                    for part in output.split_whitespace() {
-                        if !part.is_empty() {
-                            argv.push(part.to_owned());
-                        }
-                    }
-                }
-                SubshellType::ProcessSubstitution => {
-                    let mut ctx = Context::new(shell.pid, shell.pgid, tmode.clone(), false);
-                    ctx.foreground = true;
-                    // make pipe
-                    let (pout, pin) = pipe().context("failed pipe")?;
-                    ctx.outfile = pin.as_raw_fd();
-                    shell.launch_subshell(&mut ctx, jobs)?;
-                    drop(pin); // Close write end
-                    // Leak pout to keep it open for process substitution
-                    let file_name = format!("/dev/fd/{}", pout.into_raw_fd());
-                    argv.push(file_name);
-                }
-                SubshellType::None => {}
-            }
-        } else {
-            argv.push(cmd_str);
-        }
-    }
+    let mut readers = Vec::new();
+    let mut argv = resolve_argv(shell, parsed_argv, &mut readers)?;
+    resolve_redirect_substs(shell, redirect_substs, &mut redirects, &mut readers)?;
+    current_job.add_proc_subst_readers(readers);
 
     if argv.is_empty() {
         // no main command
         return Ok(());
     }
 
+    // `time [-p] PIPELINE` reports how long the whole job took
+    if argv[0] == "time" {
+        let format = match argv.get(1).map(String::as_str) {
+            Some("-p") => TimeFormat::Posix,
+            _ => TimeFormat::Default,
+        };
+        let keyword_len = if format == TimeFormat::Posix { 2 } else { 1 };
+        if argv.len() > keyword_len {
+            argv.drain(..keyword_len);
+            current_job.timed = Some(format);
+        }
+    }
+
     // Handle 'nopty' prefix
     if argv[0] == "nopty" {
         if argv.len() > 1 {
@@ -368,30 +372,77 @@ pub fn parse_command(
         }
     }
 
+    // `command NAME` skips functions and `builtin NAME` only runs builtins; with options
+    // they are the builtins describing NAME
+    let lookup = match argv[0].as_str() {
+        "command" if argv.len() > 2 && argv[1] == "-p" => {
+            argv.drain(..2);
+            Lookup::SkipFunctions
+        }
+        "command" if argv.len() > 1 && !argv[1].starts_with('-') => {
+            argv.remove(0);
+            Lookup::SkipFunctions
+        }
+        "builtin" if argv.len() > 1 => {
+            argv.remove(0);
+            Lookup::BuiltinOnly
+        }
+        // `exec CMD` replaces the shell with CMD; `exec` alone keeps its redirections
+        "exec" if argv.len() > 1 => {
+            argv.remove(0);
+            Lookup::ExecutableOnly
+        }
+        _ => Lookup::Default,
+    };
+
     let cmd = argv[0].as_str();
-    if let Some(cmd_fn) = dsh_builtin::get_command(cmd) {
-        let builtin = process::BuiltinProcess::new(cmd.to_string(), cmd_fn, argv);
-        current_job.set_process(JobProcess::Builtin(builtin));
-    } else if shell.lisp_engine.borrow().is_export(cmd) {
-        let cmd_fn = dsh_builtin::lisp::run;
-        let builtin = process::BuiltinProcess::new(cmd.to_string(), cmd_fn, argv);
-        current_job.set_process(JobProcess::Builtin(builtin));
-    } else {
-        let cmd_lookup = shell.environment.read().lookup(cmd);
-        if let Some(cmd) = cmd_lookup {
-            let process = process::Process::new(cmd, argv);
-            current_job.set_process(JobProcess::Command(process));
+    match resolve(shell, cmd, lookup) {
+        Some(Resolution::Function) => {
+            let builtin =
+                process::BuiltinProcess::shell_call(cmd.to_string(), ShellCall::Function, argv);
+            current_job.set_process(JobProcess::Builtin(builtin).with_redirects(redirects));
+        }
+        Some(Resolution::Builtin) => {
+            if let Some(cmd_fn) = dsh_builtin::get_command(cmd) {
+                let keep_redirects = cmd == "exec";
+                let shell_call = ShellCall::for_builtin(cmd);
+                let mut builtin = process::BuiltinProcess::new(cmd.to_string(), cmd_fn, argv);
+                builtin.keep_redirects = keep_redirects;
+                builtin.shell_call = shell_call;
+                current_job.set_process(JobProcess::Builtin(builtin).with_redirects(redirects));
+            }
+        }
+        Some(Resolution::Lisp) => {
+            let cmd_fn = dsh_builtin::lisp::run;
+            let builtin = process::BuiltinProcess::new(cmd.to_string(), cmd_fn, argv);
+            current_job.set_process(JobProcess::Builtin(builtin).with_redirects(redirects));
+        }
+        Some(Resolution::File(cmd)) => {
+            let mut process = process::Process::new(cmd, argv);
+            if lookup == Lookup::ExecutableOnly {
+                // The command takes over the shell's terminal, not a PTY of its own
+                process.replace_shell = true;
+                current_job.disable_pty = true;
+            }
+            current_job.set_process(JobProcess::Command(process).with_redirects(redirects));
             current_job.foreground = ctx.foreground;
-        } else if dirs::is_dir(cmd) {
+        }
+        Some(Resolution::Directory) => {
             if let Some(cmd_fn) = dsh_builtin::get_command("cd") {
                 let builtin = process::BuiltinProcess::new(
                     cmd.to_string(),
                     cmd_fn,
                     vec!["cd".to_string(), cmd.to_string()],
                 );
-                current_job.set_process(JobProcess::Builtin(builtin));
+                current_job.set_process(JobProcess::Builtin(builtin).with_redirects(redirects));
             }
-        } else {
+        }
+        Some(Resolution::Alias(_) | Resolution::Abbreviation(_)) => {
+            unreachable!("resolve does not return aliases")
+        }
+        None if lookup == Lookup::BuiltinOnly => bail!("builtin: {}: not a shell builtin", cmd),
+        None if lookup == Lookup::ExecutableOnly => bail!("exec: {}: not found", cmd),
+        None => {
             // Execute command-not-found hooks before showing error
             // Hooks can perform side effects like suggesting package installation
             shell.exec_command_not_found_hooks(cmd);
@@ -427,16 +478,182 @@ pub fn parse_command(
                 format!("\rProject tasks: {commands}\r\n")
             };
 
-            if !suggestion_msg.is_empty() || !task_msg.is_empty() {
-                bail!("unknown command: {}\n{}{}", cmd, suggestion_msg, task_msg);
+            return Err(ShellError::CommandNotFound {
+                name: cmd.to_string(),
+                hint: format!("{suggestion_msg}{task_msg}"),
+            }
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Runs the subshells and substitutions found by `parse_argv` and returns the final argv.
+///
+/// Output process substitutions keep running: their pids and the shell's ends of their
+/// pipes are added to `readers` for the job that writes to them.
+fn resolve_argv(
+    shell: &mut Shell,
+    parsed_argv: Vec<(String, Option<ParsedJob>)>,
+    readers: &mut Vec<(Pid, OwnedFd)>,
+) -> Result<Vec<String>> {
+    let mut argv: Vec<String> = Vec::new();
+
+    for (cmd_str, jobs) in parsed_argv {
+        if let Some(ParsedJob {
+            subshell_type,
+            jobs,
+        }) = jobs
+        {
+            debug!("parsed job '{:?}' jobs:{:?}", cmd_str, jobs);
+            if jobs.is_empty() {
+                continue;
             }
+            debug!("run subshell: {}", cmd_str);
+            let tmode = subshell_tmode(shell);
 
-            bail!("unknown command: {}", cmd);
+            match subshell_type {
+                SubshellType::Subshell => {
+                    let mut ctx = Context::new(shell.pid, shell.pgid, tmode.clone(), false);
+                    ctx.foreground = true;
+                    // make pipe
+                    let (pout, pin) = pipe().context("failed pipe")?;
+                    ctx.outfile = pin.as_raw_fd();
+                    shell.launch_subshell(&mut ctx, jobs)?;
+                    drop(pin); // Close write end
+                    let output = read_fd(pout.into_raw_fd())?;
+                    output.lines().for_each(|x| argv.push(x.to_owned()));
+                }
+                SubshellType::CommandSubstitution => {
+                    let output = capture_subshell(shell, tmode.clone(), jobs)?;
+                    for part in output.split_whitespace() {
+                        if !part.is_empty() {
+                            argv.push(part.to_owned());
+                        }
+                    }
+                }
+                SubshellType::ProcessSubstitution => {
+                    let mut ctx = Context::new(shell.pid, shell.pgid, tmode.clone(), false);
+                    ctx.foreground = true;
+                    // make pipe
+                    let (pout, pin) = pipe().context("failed pipe")?;
+                    ctx.outfile = pin.as_raw_fd();
+                    shell.launch_subshell(&mut ctx, jobs)?;
+                    drop(pin); // Close write end
+                    // Leak pout to keep it open for process substitution
+                    let file_name = format!("/dev/fd/{}", pout.into_raw_fd());
+                    argv.push(file_name);
+                }
+                SubshellType::OutputProcessSubstitution => {
+                    let mut ctx = Context::new(shell.pid, shell.pgid, tmode.clone(), false);
+                    ctx.foreground = true;
+                    // The reader runs alongside the command, it must not take the terminal
+                    ctx.interactive = false;
+                    let (pout, pin) = pipe().context("failed pipe")?;
+                    ctx.infile = pout.as_raw_fd();
+                    let mut writers: Vec<RawFd> =
+                        readers.iter().map(|(_, fd)| fd.as_raw_fd()).collect();
+                    writers.push(pin.as_raw_fd());
+                    let pid = eval::spawn_proc_subst_reader(shell, &mut ctx, jobs, &writers)?;
+                    drop(pout); // Close read end
+                    argv.push(format!("/dev/fd/{}", pin.as_raw_fd()));
+                    readers.push((pid, pin));
+                }
+                SubshellType::None => {}
+            }
+        } else {
+            argv.push(cmd_str);
+        }
+    }
+    Ok(argv)
+}
+
+/// The terminal modes the jobs of a subshell start with.
+fn subshell_tmode(shell: &Shell) -> Option<Termios> {
+    match tcgetattr(unsafe { BorrowedFd::borrow_raw(0) }) {
+        Ok(mode) => Some(mode),
+        Err(err) => {
+            debug!("tcgetattr fallback for command substitution: {}", err);
+            Context::new_safe(shell.pid, shell.pgid, false).shell_tmode
         }
     }
+}
+
+/// Runs the jobs of a command substitution and returns their output without the
+/// trailing newlines.
+fn capture_subshell(shell: &mut Shell, tmode: Option<Termios>, jobs: Vec<Job>) -> Result<String> {
+    let mut ctx = Context::new(shell.pid, shell.pgid, tmode, false);
+    ctx.foreground = true;
+    let (pout, pin) = pipe().context("failed pipe")?;
+    ctx.outfile = pin.as_raw_fd();
+    shell.launch_subshell(&mut ctx, jobs)?;
+    drop(pin); // Close write end
+    read_fd(pout.into_raw_fd())
+}
+
+/// Runs the command substitutions in the body of an unquoted here-document, in the
+/// same way as those of command arguments.
+fn substitute_heredoc_commands(shell: &mut Shell, redirect: &mut Redirect) -> Result<()> {
+    let Redirect::HereDoc { body, expand: true } = redirect else {
+        return Ok(());
+    };
+    *body = heredoc::substitute_commands(body, |command| {
+        let (expanded, pairs) =
+            parser::parse_with_expansion(command, Arc::clone(&shell.environment))?;
+        let pairs = match pairs {
+            Some(pairs) => pairs,
+            None => ShellParser::parse(Rule::commands, &expanded).map_err(|e| anyhow!(e))?,
+        };
+        let mut ctx = ParseContext::new(true);
+        ctx.subshell = true;
+        let mut jobs = Vec::new();
+        for pair in pairs {
+            jobs.extend(parse_commands(shell, &mut ctx, pair)?);
+        }
+        let tmode = subshell_tmode(shell);
+        capture_subshell(shell, tmode, jobs)
+    })?;
     Ok(())
 }
 
+/// Expands a list of words the way command arguments are expanded
+/// (variables, tilde, globs and command substitutions).
+pub fn expand_words(shell: &mut Shell, words: &str) -> Result<Vec<String>> {
+    if words.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    // Parse the words as arguments of a placeholder command so that the regular
+    // expansion and substitution logic applies to them.
+    let input = format!(": {}", words);
+    let (expanded, pairs) = parser::parse_with_expansion(&input, Arc::clone(&shell.environment))?;
+    let pairs = match pairs {
+        Some(pairs) => pairs,
+        None => ShellParser::parse(Rule::commands, &expanded).map_err(|e| anyhow!(e))?,
+    };
+
+    let mut ctx = ParseContext::new(true);
+    let mut redirects = Vec::new();
+    let mut argv = Vec::new();
+    let mut readers = Vec::new();
+    for command in pairs.flat_map(|pair| pair.into_inner()) {
+        for pair in command.into_inner() {
+            if pair.as_rule() == Rule::simple_command {
+                let parsed_argv = parse_argv(shell, &mut ctx, &mut redirects, pair)?;
+                argv.extend(resolve_argv(shell, parsed_argv, &mut readers)?);
+            }
+        }
+    }
+    // Without a command to write to them, output process substitutions only get EOF
+    for (pid, writer) in readers {
+        drop(writer);
+        process::wait_pid_job(pid, false);
+    }
+    if !argv.is_empty() {
+        argv.remove(0);
+    }
+    Ok(argv)
+}
+
 fn parse_jobs(
     shell: &mut Shell,
     ctx: &mut ParseContext,
@@ -568,3 +785,88 @@ fn read_fd(fd: RawFd) -> Result<String> {
         .to_owned();
     Ok(output)
 }
+
+/// Adds the redirections of a `redirect` pair to `redirects`.
+///
+/// Returns the `proc_subst` of a target like `>(cmd)`, whose path replaces the target
+/// once the substitution is running.
+fn parse_redirect<'i>(
+    pair: Pair<'i, Rule>,
+    redirects: &mut Vec<Redirect>,
+) -> Option<Pair<'i, Rule>> {
+    let mut direction: Option<Pair<Rule>> = None;
+    let mut subst = None;
+    for pair in pair.into_inner() {
+        match pair.as_rule() {
+            Rule::heredoc => redirects.push(heredoc_redirect(pair)),
+            Rule::dup_redirect_target => {
+                let Some(direction) = direction.take() else {
+                    continue;
+                };
+                let (fd, op) = split_io_number(direction.as_str());
+                let default_fd = if op.starts_with('<') { 0 } else { 1 };
+                redirects.extend(Redirect::dup(fd.unwrap_or(default_fd), pair.as_str()));
+            }
+            Rule::span => {
+                let Some(direction) = direction.take() else {
+                    continue;
+                };
+                subst = pair
+                    .clone()
+                    .into_inner()
+                    .next()
+                    .filter(|inner| inner.as_rule() == Rule::proc_subst);
+                let target = parser::get_string(pair).unwrap_or_default();
+                let (fd, op) = split_io_number(direction.as_str());
+                match op {
+                    "<<<" => redirects.push(Redirect::HereString(target)),
+                    "<" => redirects.push(Redirect::Input {
+                        fd: fd.unwrap_or(0),
+                        path: target,
+                    }),
+                    "&>" | "&>>" => {
+                        redirects.push(Redirect::Output {
+                            fd: 1,
+                            path: target,
+                            append: op == "&>>",
+                            force: false,
+                        });
+                        redirects.push(Redirect::Dup { fd: 2, source: 1 });
+                    }
+                    _ => redirects.push(Redirect::Output {
+                        fd: fd.unwrap_or(1),
+                        path: target,
+                        append: op.ends_with(">>"),
+                        force: op.ends_with(">|"),
+                    }),
+                }
+            }
+            _ => direction = Some(pair),
+        }
+    }
+    subst
+}
+
+/// Splits a redirection operator like `2>>` into its descriptor number and operator.
+fn split_io_number(direction: &str) -> (Option<RawFd>, &str) {
+    let digits = direction.len()
+        - direction
+            .trim_start_matches(|c: char| c.is_ascii_digit())
+            .len();
+    let (number, op) = direction.split_at(digits);
+    (number.parse().ok(), op)
+}
+
+/// Builds the stdin redirect for a `heredoc` pair.
+fn heredoc_redirect(pair: Pair<Rule>) -> Redirect {
+    let mut body = String::new();
+    let mut expand = true;
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::heredoc_delimiter => expand = !inner.as_str().starts_with('\''),
+            Rule::heredoc_body => body = inner.as_str().to_string(),
+            _ => {}
+        }
+    }
+    Redirect::HereDoc { body, expand }
+}
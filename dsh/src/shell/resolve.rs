@@ -0,0 +1,150 @@
+//! How a command name resolves, for running it and for `type`, `which` and `command -v`.
+//!
+//! Aliases and abbreviations are expanded before a command runs. The name that is left
+//! is looked up in this order: shell functions, builtins, Lisp functions exported as
+//! commands, executables in `PATH` and finally directories, which `cd` into.
+
+use crate::dirs;
+use crate::shell::Shell;
+
+/// One way a command name resolves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    /// An alias and its expansion.
+    Alias(String),
+    /// An abbreviation and its expansion.
+    Abbreviation(String),
+    /// A shell function.
+    Function,
+    /// A builtin command.
+    Builtin,
+    /// A Lisp function exported as a command.
+    Lisp,
+    /// An executable, with its path.
+    File(String),
+    /// A directory, run as `cd NAME`.
+    Directory,
+}
+
+impl Resolution {
+    /// Kind as printed by `type -t`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Alias(_) => "alias",
+            Self::Abbreviation(_) => "abbreviation",
+            Self::Function => "function",
+            Self::Builtin => "builtin",
+            Self::Lisp => "lisp",
+            Self::File(_) => "file",
+            Self::Directory => "directory",
+        }
+    }
+}
+
+/// Which resolutions are considered when running a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lookup {
+    /// The regular lookup.
+    Default,
+    /// `command NAME`: shell and Lisp functions are skipped.
+    SkipFunctions,
+    /// `builtin NAME`: builtins only.
+    BuiltinOnly,
+    /// `exec NAME`: executables only.
+    ExecutableOnly,
+}
+
+/// Resolves the command a name runs, after aliases have been expanded.
+pub fn resolve(shell: &Shell, name: &str, lookup: Lookup) -> Option<Resolution> {
+    if lookup == Lookup::ExecutableOnly {
+        return shell.environment.read().lookup(name).map(Resolution::File);
+    }
+    let functions = lookup == Lookup::Default;
+    if functions && shell.environment.read().functions.contains_key(name) {
+        return Some(Resolution::Function);
+    }
+    if dsh_builtin::get_command(name).is_some() {
+        return Some(Resolution::Builtin);
+    }
+    if lookup == Lookup::BuiltinOnly {
+        return None;
+    }
+    if functions && shell.lisp_engine.borrow().is_export(name) {
+        return Some(Resolution::Lisp);
+    }
+    if let Some(path) = shell.environment.read().lookup(name) {
+        return Some(Resolution::File(path));
+    }
+    if dirs::is_dir(name) {
+        return Some(Resolution::Directory);
+    }
+    None
+}
+
+/// Every way a name resolves, in the order they apply, for `type -a`.
+pub fn resolve_all(shell: &Shell, name: &str) -> Vec<Resolution> {
+    let mut found = Vec::new();
+    {
+        let env = shell.environment.read();
+        if let Some(alias) = env.alias.get(name) {
+            found.push(Resolution::Alias(alias.clone()));
+        }
+        if let Some(abbr) = env.abbreviations.get(name) {
+            found.push(Resolution::Abbreviation(abbr.clone()));
+        }
+        if env.functions.contains_key(name) {
+            found.push(Resolution::Function);
+        }
+    }
+    if dsh_builtin::get_command(name).is_some() {
+        found.push(Resolution::Builtin);
+    }
+    if shell.lisp_engine.borrow().is_export(name) {
+        found.push(Resolution::Lisp);
+    }
+    let paths = shell.environment.read().lookup_all(name);
+    found.extend(paths.into_iter().map(Resolution::File));
+    if dirs::is_dir(name) {
+        found.push(Resolution::Directory);
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::Environment;
+
+    #[test]
+    fn test_resolve_order() {
+        let shell = Shell::new(Environment::new());
+        shell
+            .environment
+            .write()
+            .alias
+            .insert("cd".to_string(), "cd -P".to_string());
+
+        assert_eq!(
+            resolve(&shell, "cd", Lookup::Default),
+            Some(Resolution::Builtin)
+        );
+        let all = resolve_all(&shell, "cd");
+        assert_eq!(all[0], Resolution::Alias("cd -P".to_string()));
+        assert_eq!(all[1], Resolution::Builtin);
+
+        assert_eq!(resolve(&shell, "sh", Lookup::BuiltinOnly), None);
+        assert_eq!(resolve(&shell, "cd", Lookup::ExecutableOnly), None);
+        assert!(matches!(
+            resolve(&shell, "sh", Lookup::SkipFunctions),
+            Some(Resolution::File(_))
+        ));
+        assert_eq!(
+            resolve(&shell, "/", Lookup::Default),
+            Some(Resolution::Directory)
+        );
+        assert_eq!(
+            resolve(&shell, "dsh-no-such-command", Lookup::Default),
+            None
+        );
+    }
+}
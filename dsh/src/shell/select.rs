@@ -0,0 +1,184 @@
+//! The menu of the `select` loop.
+//!
+//! In an interactive shell the words are offered in the skim picker. Otherwise a numbered
+//! menu is printed to stderr and the answer is read from stdin, as in POSIX shells:
+//! `REPLY` gets the line read and the loop variable the word with that number.
+
+use anyhow::{Context as _, Result};
+use dsh_types::Context;
+use nix::unistd::isatty;
+use skim::prelude::*;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::mem::ManuallyDrop;
+use std::os::fd::{BorrowedFd, FromRawFd};
+
+/// Prompt of the menu when `PS3` is not set.
+pub(crate) const DEFAULT_PROMPT: &str = "#? ";
+
+/// What the user answered to the menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Choice {
+    /// The line read, stored in `REPLY`.
+    pub reply: String,
+    /// The word picked, empty when the reply is not one of the numbers.
+    pub item: String,
+}
+
+/// A `select` menu over a list of words.
+#[derive(Debug)]
+pub(crate) struct Menu {
+    items: Vec<String>,
+    picker: bool,
+    show_list: bool,
+}
+
+impl Menu {
+    pub(crate) fn new(ctx: &Context, items: Vec<String>) -> Self {
+        let picker = ctx.interactive
+            && isatty(unsafe { BorrowedFd::borrow_raw(ctx.infile) }).unwrap_or(false);
+        Self {
+            items,
+            picker,
+            show_list: true,
+        }
+    }
+
+    /// Asks for the next choice. Returns `None` when the picker is aborted or stdin ends.
+    pub(crate) fn choose(&mut self, ctx: &Context, prompt: &str) -> Result<Option<Choice>> {
+        if self.picker {
+            return self.pick(prompt);
+        }
+        loop {
+            if self.show_list {
+                ctx.write_stderr(&self.list()).ok();
+                self.show_list = false;
+            }
+            write_prompt(ctx, prompt)?;
+            let Some(line) = read_line(ctx)? else {
+                // Leave the terminal on a fresh line, as when `read` hits EOF
+                ctx.write_stderr("").ok();
+                return Ok(None);
+            };
+            let reply = line.trim().to_string();
+            if reply.is_empty() {
+                // An empty answer shows the menu again
+                self.show_list = true;
+                continue;
+            }
+            let item = self.item(&reply).unwrap_or_default();
+            return Ok(Some(Choice { reply, item }));
+        }
+    }
+
+    fn list(&self) -> String {
+        let width = self.items.len().to_string().len();
+        self.items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| format!("{:>width$}) {item}", i + 1))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The word numbered `reply`, counting from 1.
+    fn item(&self, reply: &str) -> Option<String> {
+        let index = reply.parse::<usize>().ok()?.checked_sub(1)?;
+        self.items.get(index).cloned()
+    }
+
+    fn pick(&self, prompt: &str) -> Result<Option<Choice>> {
+        let options = SkimOptionsBuilder::default()
+            .prompt(prompt.to_string())
+            .bind(vec!["Enter:accept".to_string(), "Esc:abort".to_string()])
+            .build()
+            .context("select: failed to build skim options")?;
+        let (tx, rx): (SkimItemSender, SkimItemReceiver) = unbounded();
+        for (i, item) in self.items.iter().enumerate() {
+            tx.send(vec![Arc::new(MenuItem {
+                number: i + 1,
+                text: item.clone(),
+            })])
+            .ok();
+        }
+        drop(tx);
+
+        let Some(output) = crate::utils::skim::run_skim_with(options, Some(rx)) else {
+            return Ok(None);
+        };
+        if output.is_abort {
+            return Ok(None);
+        }
+        Ok(output.selected_items.first().and_then(|selected| {
+            let reply = selected.output().to_string();
+            self.item(&reply).map(|item| Choice { reply, item })
+        }))
+    }
+}
+
+/// A word shown in the picker; its output is its number, which goes to `REPLY`.
+struct MenuItem {
+    number: usize,
+    text: String,
+}
+
+impl SkimItem for MenuItem {
+    fn text(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.text)
+    }
+
+    fn output(&self) -> Cow<'_, str> {
+        Cow::Owned(self.number.to_string())
+    }
+}
+
+fn write_prompt(ctx: &Context, prompt: &str) -> Result<()> {
+    // The descriptors belong to the caller, don't close them
+    let mut err = ManuallyDrop::new(unsafe { File::from_raw_fd(ctx.errfile) });
+    err.write_all(prompt.as_bytes())?;
+    err.flush()?;
+    Ok(())
+}
+
+/// Reads one line from stdin a byte at a time, so that later commands get the rest.
+fn read_line(ctx: &Context) -> Result<Option<String>> {
+    let mut input = ManuallyDrop::new(unsafe { File::from_raw_fd(ctx.infile) });
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    loop {
+        match input.read(&mut byte) {
+            Ok(0) if line.is_empty() => return Ok(None),
+            Ok(0) => break,
+            Ok(_) if byte[0] == b'\n' => break,
+            Ok(_) => line.push(byte[0]),
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err).context("select: failed to read input"),
+        }
+    }
+    Ok(Some(String::from_utf8_lossy(&line).into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn menu(items: &[&str]) -> Menu {
+        Menu {
+            items: items.iter().map(|s| s.to_string()).collect(),
+            picker: false,
+            show_list: true,
+        }
+    }
+
+    #[test]
+    fn test_menu_list_and_item() {
+        let menu = menu(&["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"]);
+        let list = menu.list();
+        assert!(list.starts_with(" 1) a\n 2) b"));
+        assert!(list.ends_with("10) j"));
+        assert_eq!(menu.item("2"), Some("b".to_string()));
+        assert_eq!(menu.item("0"), None);
+        assert_eq!(menu.item("11"), None);
+        assert_eq!(menu.item("b"), None);
+    }
+}
@@ -0,0 +1,152 @@
+//! The `time` keyword: real, user and system time of a whole pipeline.
+//!
+//! User and system time add up the shell and the children it has waited for, so every
+//! process of the pipeline is counted, builtins included.
+
+use crate::command_timing::{self, CommandTiming};
+use crate::shell::Shell;
+use dsh_types::Context;
+use nix::sys::resource::{UsageWho, getrusage};
+use nix::sys::time::TimeVal;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// How `time` prints its report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeFormat {
+    /// `real 0m1.002s` lines, as in bash.
+    Default,
+    /// `time -p`: `real 1.00` lines, as in POSIX.
+    Posix,
+}
+
+fn to_duration(time: TimeVal) -> Duration {
+    Duration::from_secs(time.tv_sec().max(0) as u64)
+        + Duration::from_micros(time.tv_usec().max(0) as u64)
+}
+
+/// User and system time used so far by the shell and its waited-for children.
+fn cpu_times() -> (Duration, Duration) {
+    [UsageWho::RUSAGE_SELF, UsageWho::RUSAGE_CHILDREN]
+        .into_iter()
+        .filter_map(|who| getrusage(who).ok())
+        .fold((Duration::ZERO, Duration::ZERO), |(user, sys), usage| {
+            (
+                user + to_duration(usage.user_time()),
+                sys + to_duration(usage.system_time()),
+            )
+        })
+}
+
+/// A job being timed.
+#[derive(Debug)]
+pub(crate) struct Timer {
+    format: TimeFormat,
+    started: Instant,
+    cpu: (Duration, Duration),
+}
+
+impl Timer {
+    pub(crate) fn start(format: TimeFormat) -> Self {
+        Self {
+            format,
+            started: Instant::now(),
+            cpu: cpu_times(),
+        }
+    }
+
+    /// Prints the report to stderr and records the timed command in `CommandTiming`.
+    pub(crate) fn finish(self, shell: &Shell, ctx: &Context, cmd: &str, status: i32) {
+        let real = self.started.elapsed();
+        let (user, sys) = cpu_times();
+        let report = format_report(
+            self.format,
+            real,
+            user.saturating_sub(self.cpu.0),
+            sys.saturating_sub(self.cpu.1),
+        );
+        ctx.write_stderr(&report).ok();
+        if let Some(name) = timed_command(cmd) {
+            record(shell, &name, status, real);
+        }
+    }
+}
+
+fn format_report(format: TimeFormat, real: Duration, user: Duration, sys: Duration) -> String {
+    let lines = [("real", real), ("user", user), ("sys", sys)].map(|(label, time)| match format {
+        TimeFormat::Default => {
+            let millis = time.as_millis();
+            format!(
+                "{label}\t{}m{}.{:03}s",
+                millis / 60_000,
+                millis / 1000 % 60,
+                millis % 1000
+            )
+        }
+        TimeFormat::Posix => format!("{label} {:.2}", time.as_secs_f64()),
+    });
+    // bash separates the report from the command's output with an empty line
+    match format {
+        TimeFormat::Default => format!("\n{}", lines.join("\n")),
+        TimeFormat::Posix => lines.join("\n"),
+    }
+}
+
+/// Name of the timed command in `job_cmd`, the job text that starts with the keyword.
+fn timed_command(job_cmd: &str) -> Option<String> {
+    let rest = job_cmd.trim_start();
+    let rest = rest.strip_prefix("time").unwrap_or(rest).trim_start();
+    let rest = rest.strip_prefix("-p ").unwrap_or(rest);
+    command_timing::extract_command_name(rest)
+}
+
+/// Records a timed command, in the REPL's statistics when there is one and otherwise
+/// directly in the timing file.
+fn record(shell: &Shell, name: &str, status: i32, real: Duration) {
+    let Some(path) = command_timing::get_timing_file_path() else {
+        return;
+    };
+    let saved = match &shell.command_timing {
+        Some(timing) => {
+            let mut timing = timing.write();
+            timing.record(name, status, real);
+            timing.save_to_file_if_due(&path).map(|_| ())
+        }
+        None => {
+            let mut timing = CommandTiming::load_from_file(&path).unwrap_or_default();
+            timing.record(name, status, real);
+            timing.save_to_file(&path)
+        }
+    };
+    if let Err(e) = saved {
+        debug!("Failed to save command timing: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_report() {
+        let real = Duration::from_millis(61_234);
+        let user = Duration::from_millis(7);
+        assert_eq!(
+            format_report(TimeFormat::Default, real, user, Duration::ZERO),
+            "\nreal\t1m1.234s\nuser\t0m0.007s\nsys\t0m0.000s"
+        );
+        assert_eq!(
+            format_report(TimeFormat::Posix, real, user, Duration::ZERO),
+            "real 61.23\nuser 0.01\nsys 0.00"
+        );
+    }
+
+    #[test]
+    fn test_timed_command() {
+        assert_eq!(timed_command("time sleep 1"), Some("sleep".to_string()));
+        assert_eq!(
+            timed_command("time -p /bin/ls | wc -l"),
+            Some("ls".to_string())
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Traps: shell code or Lisp functions run when the shell receives a signal.
+//!
+//! Besides real signals there are three pseudo-signals: `EXIT` runs when the shell
+//! exits, `ERR` after a command fails where `set -e` would stop the shell, and `DEBUG`
+//! before every simple command. A signal handler only marks its signal as pending, the
+//! trap itself runs through the normal evaluator at the next safe point: after a job
+//! finishes, or on the idle tick of the REPL.
+
+use crate::environment::{Environment, UnsafeSend};
+use crate::errors::display_user_error;
+use crate::lisp::Value;
+use crate::process::signal;
+use crate::shell::Shell;
+use anyhow::{Result, bail};
+use dsh_types::Context;
+use futures::future::{FutureExt, LocalBoxFuture};
+use nix::sys::signal::Signal;
+
+/// What a trap is set on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum TrapSignal {
+    Exit,
+    Signal(Signal),
+    Err,
+    Debug,
+}
+
+impl TrapSignal {
+    /// Parses `EXIT`, `ERR`, `DEBUG`, a signal name with or without `SIG`, or a signal
+    /// number (`0` is `EXIT`).
+    pub fn parse(spec: &str) -> Result<Self> {
+        let upper = spec.to_ascii_uppercase();
+        match upper.strip_prefix("SIG").unwrap_or(&upper) {
+            "EXIT" => return Ok(Self::Exit),
+            "ERR" => return Ok(Self::Err),
+            "DEBUG" => return Ok(Self::Debug),
+            _ => {}
+        }
+        match parse_signal(spec)? {
+            None => Ok(Self::Exit),
+            Some(Signal::SIGKILL | Signal::SIGSTOP) => bail!("{spec}: cannot be trapped"),
+            Some(signal) => Ok(Self::Signal(signal)),
+        }
+    }
+
+    /// Name as printed by `trap -p`, without the `SIG` prefix.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Exit => "EXIT",
+            Self::Signal(signal) => signal.as_str().trim_start_matches("SIG"),
+            Self::Err => "ERR",
+            Self::Debug => "DEBUG",
+        }
+    }
+}
+
+/// Parses a signal name with or without `SIG`, or a signal number, as `trap` and `kill`
+/// take them. `0` is `None`: no signal, only a check that the target exists.
+pub fn parse_signal(spec: &str) -> Result<Option<Signal>> {
+    let upper = spec.to_ascii_uppercase();
+    let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+    let signal = match name.parse::<i32>() {
+        Ok(0) => return Ok(None),
+        Ok(number) => Signal::try_from(number).ok(),
+        Err(_) => format!("SIG{name}").parse::<Signal>().ok(),
+    };
+    match signal {
+        Some(signal) => Ok(Some(signal)),
+        None => bail!("{spec}: invalid signal specification"),
+    }
+}
+
+/// Handler of a trap.
+#[derive(Debug, Clone)]
+pub enum TrapAction {
+    /// Shell code; an empty command ignores the signal.
+    Command(String),
+    /// A Lisp function registered from config code.
+    Lisp(UnsafeSend<Value>),
+}
+
+/// Sets (`Some`) or resets (`None`) the trap on `signal` and updates the disposition
+/// of real signals to match.
+pub fn set_trap(
+    env: &mut Environment,
+    signal: TrapSignal,
+    action: Option<TrapAction>,
+) -> Result<()> {
+    if let TrapSignal::Signal(sig) = signal {
+        match &action {
+            None => signal::restore_signal_handler(sig)?,
+            Some(TrapAction::Command(command)) if command.is_empty() => signal::ignore_signal(sig)?,
+            Some(_) => signal::install_trap_handler(sig)?,
+        }
+    }
+    match action {
+        Some(action) => env.traps.insert(signal, action),
+        None => env.traps.remove(&signal),
+    };
+    Ok(())
+}
+
+/// Formats a trap the way `trap -p` prints it, so that it can be read back.
+pub fn format_trap(signal: TrapSignal, action: &TrapAction) -> String {
+    match action {
+        TrapAction::Command(command) => format!(
+            "trap -- '{}' {}",
+            command.replace('\'', "'\\''"),
+            signal.name()
+        ),
+        TrapAction::Lisp(func) => format!("trap -- '{}' {}", func.0, signal.name()),
+    }
+}
+
+/// Runs the trap on `signal`, if one is set, keeping `$?` and `$PIPESTATUS`.
+///
+/// Traps do not fire while another trap runs.
+pub(crate) fn run_trap<'a>(
+    shell: &'a mut Shell,
+    ctx: &'a Context,
+    signal: TrapSignal,
+) -> LocalBoxFuture<'a, ()> {
+    async move {
+        if shell.in_trap {
+            return;
+        }
+        let Some(action) = shell.environment.read().traps.get(&signal).cloned() else {
+            return;
+        };
+        run_action(shell, ctx, signal, action).await;
+    }
+    .boxed_local()
+}
+
+async fn run_action(shell: &mut Shell, ctx: &Context, signal: TrapSignal, action: TrapAction) {
+    let (status, pipe_status) = {
+        let env = shell.environment.read();
+        (env.last_status, env.pipe_status.clone())
+    };
+    let in_trap = std::mem::replace(&mut shell.in_trap, true);
+    match action {
+        TrapAction::Command(command) if command.is_empty() => {}
+        TrapAction::Command(command) => {
+            let mut trap_ctx = ctx.clone();
+            trap_ctx.save_history = false;
+            if let Err(err) = shell.eval_str(&mut trap_ctx, command, false).await {
+                display_user_error(&err, false);
+            }
+        }
+        TrapAction::Lisp(func) => {
+            let result = shell.lisp_engine.borrow().apply_func(func.0, Vec::new());
+            if let Err(err) = result {
+                eprintln!("dsh: trap {}: {err}", signal.name());
+            }
+        }
+    }
+    shell.in_trap = in_trap;
+    let mut env = shell.environment.write();
+    env.last_status = status;
+    env.pipe_status = pipe_status;
+}
+
+/// Runs the `ERR` trap for a command that failed outside of a condition.
+pub(crate) async fn run_err_trap(shell: &mut Shell, ctx: &Context) {
+    if shell.condition_depth == 0 {
+        run_trap(shell, ctx, TrapSignal::Err).await;
+    }
+}
+
+/// Returns true if a signal with a trap arrived since the last safe point.
+pub(crate) fn has_pending_traps(shell: &Shell) -> bool {
+    pending_trap_signal(shell).is_some()
+}
+
+/// Returns the first signal with a trap that arrived since the last safe point.
+pub(crate) fn pending_trap_signal(shell: &Shell) -> Option<Signal> {
+    let env = shell.environment.read();
+    signal::pending_signals()
+        .into_iter()
+        .find(|sig| env.traps.contains_key(&TrapSignal::Signal(*sig)))
+}
+
+/// Runs the traps of the signals that arrived since the last safe point.
+pub(crate) async fn run_pending_traps(shell: &mut Shell, ctx: &Context) {
+    if shell.in_trap {
+        return;
+    }
+    for sig in signal::take_pending_signals() {
+        run_trap(shell, ctx, TrapSignal::Signal(sig)).await;
+    }
+}
+
+/// Runs the `EXIT` trap once, when the shell is about to exit.
+pub async fn run_exit_trap(shell: &mut Shell, ctx: &Context) {
+    let Some(action) = shell.environment.write().traps.remove(&TrapSignal::Exit) else {
+        return;
+    };
+    run_action(shell, ctx, TrapSignal::Exit, action).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_trap_signal() {
+        assert_eq!(TrapSignal::parse("EXIT").unwrap(), TrapSignal::Exit);
+        assert_eq!(TrapSignal::parse("0").unwrap(), TrapSignal::Exit);
+        assert_eq!(TrapSignal::parse("err").unwrap(), TrapSignal::Err);
+        assert_eq!(
+            TrapSignal::parse("INT").unwrap(),
+            TrapSignal::Signal(Signal::SIGINT)
+        );
+        assert_eq!(
+            TrapSignal::parse("SIGTERM").unwrap(),
+            TrapSignal::Signal(Signal::SIGTERM)
+        );
+        assert_eq!(
+            TrapSignal::parse("1").unwrap(),
+            TrapSignal::Signal(Signal::SIGHUP)
+        );
+        assert!(TrapSignal::parse("KILL").is_err());
+        assert!(TrapSignal::parse("NOPE").is_err());
+        assert_eq!(TrapSignal::Signal(Signal::SIGUSR1).name(), "USR1");
+    }
+
+    #[test]
+    fn test_format_trap() {
+        assert_eq!(
+            format_trap(TrapSignal::Exit, &TrapAction::Command("echo 'bye'".into())),
+            "trap -- 'echo '\\''bye'\\''' EXIT"
+        );
+        assert_eq!(
+            format_trap(
+                TrapSignal::Signal(Signal::SIGINT),
+                &TrapAction::Command(String::new())
+            ),
+            "trap -- '' INT"
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use std::process::Command;
+
+fn run_dsh_command(command: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .arg("-c")
+        .arg(command)
+        .output()
+        .expect("Failed to run dsh")
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn arithmetic_expansion_assigns_variables() {
+    let output =
+        run_dsh_command("set i 5\necho $((i += 2)) $i \"$(( 0x10 + 2#101 ))\" x$((2**10))");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert!(stdout.contains("7 7 21 x1024"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn arithmetic_command_sets_status() {
+    let output = run_dsh_command(
+        "(( 3 > 2 )) && echo greater\n(( 0 )) || echo zero\nset n 3\nwhile (( n-- ))\ndo\n  echo tick\ndone",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("greater"), "stdout:\n{stdout}");
+    assert!(stdout.contains("zero"), "stdout:\n{stdout}");
+    assert_eq!(stdout.matches("tick").count(), 3, "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn let_builtin_evaluates_each_argument() {
+    let output = run_dsh_command("let a=4 b=a*2\necho $a $b\nlet 0 || echo let-false");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("4 8"), "stdout:\n{stdout}");
+    assert!(stdout.contains("let-false"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn division_by_zero_is_an_error() {
+    let output = run_dsh_command("echo $((1 / 0))");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(
+        stderr.contains("division by 0") || stdout.contains("division by 0"),
+        "output: {output:?}"
+    );
+}
@@ -0,0 +1,54 @@
+use std::process::Command;
+
+fn run_dsh_command(command: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .args(["-c", command])
+        .output()
+        .expect("failed to execute dsh")
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn type_lists_every_resolution_in_lookup_order() {
+    let output = run_dsh_command(
+        "alias cd='cd -P'; pwd() { echo mine; }; type -a cd; type pwd; type -t sh; type nope; echo STATUS=$?",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    let alias = stdout.find("cd is aliased to `cd -P'").expect(&stdout);
+    let builtin = stdout.find("cd is a shell builtin").expect(&stdout);
+    assert!(alias < builtin, "stdout: {stdout}");
+    assert!(stdout.contains("pwd is a function"), "stdout: {stdout}");
+    assert!(stdout.contains("file"), "stdout: {stdout}");
+    assert!(stdout.contains("STATUS=1"), "stdout: {stdout}");
+    assert!(stderr.contains("type: nope: not found"), "stderr: {stderr}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn command_and_builtin_bypass_functions() {
+    let output = run_dsh_command(
+        "echo() { /bin/echo wrapped; }; echo hi; command echo plain; command -v cd; builtin ls; /bin/echo STATUS=$?",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stdout.contains("wrapped"), "stdout: {stdout}");
+    assert!(stdout.contains("plain"), "stdout: {stdout}");
+    assert!(
+        stdout.contains("\ncd\n") || stdout.starts_with("cd\n"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stderr.contains("builtin: ls: not a shell builtin"),
+        "stderr: {stderr}"
+    );
+    assert!(stdout.contains("STATUS=1"), "stdout: {stdout}");
+}
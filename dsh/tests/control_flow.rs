@@ -0,0 +1,221 @@
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn run_dsh_command(command: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .arg("-c")
+        .arg(command)
+        .output()
+        .expect("Failed to run dsh")
+}
+
+fn run_dsh_stdin(input: &str) -> std::process::Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn dsh");
+
+    {
+        let stdin = child.stdin.as_mut().expect("Failed to open stdin");
+        writeln!(stdin, "{input}").unwrap();
+        writeln!(stdin, "exit").unwrap();
+    }
+
+    child.wait_with_output().expect("Failed to read output")
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn if_elif_else_selects_branch() {
+    let output = run_dsh_command(
+        "if false; then echo BRANCH_IF; elif true; then echo BRANCH_ELIF; else echo BRANCH_ELSE; fi",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("BRANCH_ELIF"), "stdout:\n{stdout}");
+    assert!(!stdout.contains("BRANCH_IF"), "stdout:\n{stdout}");
+    assert!(!stdout.contains("BRANCH_ELSE"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn for_loop_with_break_and_continue() {
+    let output = run_dsh_command(
+        "for i in one two three four; do if [ $i = two ]; then continue; fi; if [ $i = four ]; then break; fi; echo ITEM $i; done",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("ITEM one"), "stdout:\n{stdout}");
+    assert!(stdout.contains("ITEM three"), "stdout:\n{stdout}");
+    assert!(!stdout.contains("ITEM two"), "stdout:\n{stdout}");
+    assert!(!stdout.contains("ITEM four"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn while_and_until_loops_check_condition() {
+    let output = run_dsh_command(
+        "while true; do echo WHILE_BODY; break; done; until true; do echo UNTIL_BODY; done; echo AFTER_LOOPS",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(stdout.matches("WHILE_BODY").count(), 1, "stdout:\n{stdout}");
+    assert!(!stdout.contains("UNTIL_BODY"), "stdout:\n{stdout}");
+    assert!(stdout.contains("AFTER_LOOPS"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn case_matches_glob_patterns() {
+    let output = run_dsh_command(
+        "case notes.txt in *.md) echo CASE_MD;; *.txt|*.log) echo CASE_TEXT;; *) echo CASE_DEFAULT;; esac",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("CASE_TEXT"), "stdout:\n{stdout}");
+    assert!(!stdout.contains("CASE_MD"), "stdout:\n{stdout}");
+    assert!(!stdout.contains("CASE_DEFAULT"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn multi_line_loop_from_stdin() {
+    let output = run_dsh_stdin("for x in a b\ndo\n  echo LINE $x # comment\ndone\necho DONE_MARK");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("LINE a"), "stdout:\n{stdout}");
+    assert!(stdout.contains("LINE b"), "stdout:\n{stdout}");
+    assert!(!stdout.contains("comment"), "stdout:\n{stdout}");
+    assert!(stdout.contains("DONE_MARK"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn builtin_status_drives_list_operators() {
+    let output = run_dsh_command("cd . && echo CD_OK; cd /nonexistent-dsh-dir || echo CD_FAILED");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("CD_OK"), "stdout:\n{stdout}");
+    assert!(stdout.contains("CD_FAILED"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn bang_negates_the_status_of_a_pipeline() {
+    let output = run_dsh_command(
+        "if ! false; then echo NEGATED; fi\n! true; echo STATUS=$?\nset -e\n! echo a | grep -q b && echo NO_MATCH\n! true\necho AFTER",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert!(stdout.contains("NEGATED"), "stdout:\n{stdout}");
+    assert!(stdout.contains("STATUS=1"), "stdout:\n{stdout}");
+    assert!(stdout.contains("NO_MATCH"), "stdout:\n{stdout}");
+    assert!(stdout.contains("AFTER"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn unterminated_compound_command_is_a_syntax_error() {
+    let output = run_dsh_command("if true; then echo NEVER");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(!stdout.contains("NEVER"), "stdout:\n{stdout}");
+    assert!(stderr.contains("syntax error"), "stderr:\n{stderr}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn compound_commands_are_pipeline_stages() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let input = dir.path().join("lines");
+    fs::write(&input, "beta\nalpha\n").expect("write input");
+
+    let script = format!(
+        "for i in 1 2; do echo FOR_$i; done | cat\nwhile read l; do echo got $l; done < {} | sort\nprintf 'x\\ny\\n' | cat | while read l; do echo L$l; done\nif true; then echo upper; fi | tr a-z A-Z",
+        input.display()
+    );
+    let output = run_dsh_command(&script);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert!(stdout.contains("FOR_1\nFOR_2\n"), "stdout:\n{stdout}");
+    assert!(
+        stdout.contains("got alpha\ngot beta\n"),
+        "stdout:\n{stdout}"
+    );
+    assert!(stdout.contains("Lx\nLy\n"), "stdout:\n{stdout}");
+    assert!(stdout.contains("UPPER"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn redirected_compound_command_runs_in_the_shell() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let input = dir.path().join("lines");
+    let out = dir.path().join("out");
+    fs::write(&input, "a\nb\nc\n").expect("write input");
+
+    let script = format!(
+        "set n 0\nwhile read l; do set n $((n + 1)); done < {}\necho count=$n\n{{ echo g1; echo g2 >&2; }} > {} 2>&1",
+        input.display(),
+        out.display()
+    );
+    let output = run_dsh_command(&script);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert!(stdout.contains("count=3"), "stdout:\n{stdout}");
+    let written = fs::read_to_string(&out).expect("read redirected output");
+    assert_eq!(written, "g1\ng2\n");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn compound_stage_status_counts_for_pipefail() {
+    let output = run_dsh_command(
+        "set -o pipefail\nfor i in 1; do false; done | cat\necho status=$?\nprintf 'a\\n' | cat | { read x; sleep 0.2; echo late-$x; }",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("status=1"), "stdout:\n{stdout}");
+    assert!(stdout.contains("late-a"), "stdout:\n{stdout}");
+}
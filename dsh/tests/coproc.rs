@@ -0,0 +1,74 @@
+use std::fs;
+use std::process::Command;
+
+fn run_dsh(command: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .args(["-c", command])
+        .output()
+        .expect("failed to execute dsh")
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn coproc_talks_through_its_descriptors() {
+    let script = "coproc CALC { while read -u 0 expr; do echo \"= $(( expr ))\"; done; }\necho \"2+3\" >&${CALC[1]}\nread -u ${CALC[0]} answer\necho \"answer $answer\"\necho \"6*7\" >&${CALC[1]}\nread -u ${CALC[0]}\necho \"reply $REPLY\"\njobs";
+    let output = run_dsh(script);
+
+    assert!(output.status.success(), "command failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("answer = 5"), "stdout: {stdout}");
+    assert!(stdout.contains("reply = 42"), "stdout: {stdout}");
+    assert!(stdout.contains("coproc CALC"), "stdout: {stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn coproc_without_a_name_is_coproc() {
+    let script = "coproc cat -n\ntest -n \"$COPROC_PID\" && echo has-pid\necho hello >&${COPROC[1]}\nread -u${COPROC[0]} line\necho \"[$line]\"";
+    let output = run_dsh(script);
+
+    assert!(output.status.success(), "command failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("has-pid"), "stdout: {stdout}");
+    assert!(stdout.contains("[     1\thello]"), "stdout: {stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn read_u_fails_at_end_of_input() {
+    let output = run_dsh("exec 3</dev/null\nread -u 3 line\necho \"status $?\"");
+
+    assert!(output.status.success(), "command failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("status 1"), "stdout: {stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn coproc_runs_functions_sourced_files_and_wait() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let script = dir.path().join("print.dsh");
+    fs::write(&script, "echo SOURCED\n").expect("write script");
+
+    let script = format!(
+        "f() {{ echo FN; }}\ncoproc {{ f; source {}; sleep 0.1 & wait; echo WAITED; }}\nread -u ${{COPROC[0]}} a\nread -u ${{COPROC[0]}} b\nread -u ${{COPROC[0]}} c\necho \"[$a $b $c]\"",
+        script.display()
+    );
+    let output = run_dsh(&script);
+
+    assert!(output.status.success(), "command failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[FN SOURCED WAITED]"), "stdout: {stdout}");
+}
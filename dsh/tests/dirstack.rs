@@ -0,0 +1,91 @@
+use std::process::Command;
+
+fn run_dsh_command(command: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .arg("-c")
+        .arg(command)
+        .output()
+        .expect("Failed to run dsh")
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn pushd_and_popd_walk_the_stack() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let root = dir.path().canonicalize().expect("canonical temp dir");
+    let (a, b) = (root.join("a"), root.join("b"));
+    std::fs::create_dir(&a).expect("create a");
+    std::fs::create_dir(&b).expect("create b");
+
+    let output = run_dsh_command(&format!(
+        "cd {}\npushd a\npushd {}\ndirs -v\npopd\npwd",
+        root.display(),
+        b.display()
+    ));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert!(
+        stdout.contains(&format!(" 0  {}\n", b.display())),
+        "stdout:\n{stdout}"
+    );
+    assert!(
+        stdout.contains(&format!(" 1  {}\n", a.display())),
+        "stdout:\n{stdout}"
+    );
+    assert!(
+        stdout.contains(&format!(" 2  {}\n", root.display())),
+        "stdout:\n{stdout}"
+    );
+    assert!(
+        stdout.contains(&format!("{}\n", a.display())),
+        "stdout:\n{stdout}"
+    );
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn rotation_and_tilde_entries() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let root = dir.path().canonicalize().expect("canonical temp dir");
+    let a = root.join("a");
+    std::fs::create_dir(&a).expect("create a");
+
+    let output = run_dsh_command(&format!(
+        "cd {}; pushd a; echo ~1/x; pushd +1; echo top ~0",
+        root.display()
+    ));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert!(
+        stdout.contains(&format!("{}/x", root.display())),
+        "stdout:\n{stdout}"
+    );
+    assert!(
+        stdout.contains(&format!("top {}", root.display())),
+        "stdout:\n{stdout}"
+    );
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn popd_on_empty_stack_fails() {
+    let output = run_dsh_command("popd");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success(), "output: {output:?}");
+    assert!(
+        stderr.contains("popd: directory stack empty"),
+        "stderr:\n{stderr}"
+    );
+}
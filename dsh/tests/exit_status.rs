@@ -0,0 +1,118 @@
+use std::process::Command;
+
+fn run_dsh_command(command: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .arg("-c")
+        .arg(command)
+        .output()
+        .expect("Failed to run dsh")
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn status_variables_follow_each_command() {
+    let output = run_dsh_command(
+        "false; echo status=$?\ntrue | false | true\necho pipe=$PIPESTATUS second=${PIPESTATUS[1]} last=$?",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert!(stdout.contains("status=1"), "stdout:\n{stdout}");
+    assert!(
+        stdout.contains("pipe=0 1 0 second=1 last=0"),
+        "stdout:\n{stdout}"
+    );
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn pipefail_reports_failing_stage() {
+    let output = run_dsh_command("set -o pipefail\nfalse | true\necho pipefail=$?");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("pipefail=1"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn errexit_stops_at_unchecked_failure() {
+    let output = run_dsh_command(
+        "set -e\nfalse || echo handled\nif false; then echo no; fi\nfalse && echo no\necho checked\nfalse\necho unreachable",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(output.status.code(), Some(1), "output: {output:?}");
+    assert!(stdout.contains("handled"), "stdout:\n{stdout}");
+    assert!(stdout.contains("checked"), "stdout:\n{stdout}");
+    assert!(!stdout.contains("unreachable"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn nounset_rejects_unset_variables() {
+    let output =
+        run_dsh_command("set -u\necho ${missing:-fallback}\necho $missing\necho unreachable");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success(), "output: {output:?}");
+    assert!(stdout.contains("fallback"), "stdout:\n{stdout}");
+    assert!(!stdout.contains("unreachable"), "stdout:\n{stdout}");
+    assert!(
+        stderr.contains("missing: unbound variable"),
+        "stderr:\n{stderr}"
+    );
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn xtrace_prints_commands() {
+    let output = run_dsh_command("set -x\necho traced\nset +x\necho quiet");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stderr.contains("+ echo traced"), "stderr:\n{stderr}");
+    assert!(!stderr.contains("+ echo quiet"), "stderr:\n{stderr}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn pipefail_waits_for_slow_stages() {
+    let output = run_dsh_command(
+        "set -o pipefail\nsh -c 'sleep 0.3; exit 3' | true\necho pipefail=$? pipe=$PIPESTATUS",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("pipefail=3 pipe=3 0"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn pipestatus_expands_as_an_array() {
+    let output =
+        run_dsh_command("true | false | true\necho all=${PIPESTATUS[@]} count=${#PIPESTATUS[@]}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert!(stdout.contains("all=0 1 0 count=3"), "stdout:\n{stdout}");
+}
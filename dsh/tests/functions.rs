@@ -0,0 +1,205 @@
+use std::process::Command;
+
+fn run_dsh_command(command: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .arg("-c")
+        .arg(command)
+        .output()
+        .expect("Failed to run dsh")
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn function_receives_positional_params() {
+    let output =
+        run_dsh_command("greet() { echo HELLO $1 COUNT $#; echo ALL $@; }; greet alice bob");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("HELLO alice COUNT 2"), "stdout:\n{stdout}");
+    assert!(stdout.contains("ALL alice bob"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn function_keyword_and_multi_line_definition() {
+    let output = run_dsh_command("function hello {\n  echo KEYWORD_FN $1\n}\nhello world");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("KEYWORD_FN world"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn return_status_drives_list_operators() {
+    let output = run_dsh_command(
+        "fails() { return 3; echo NEVER; }; ok() { true; }; fails || echo RETURNED_FAILURE; ok && echo RETURNED_OK",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("RETURNED_FAILURE"), "stdout:\n{stdout}");
+    assert!(stdout.contains("RETURNED_OK"), "stdout:\n{stdout}");
+    assert!(!stdout.contains("NEVER"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn shift_and_recursion() {
+    let output = run_dsh_command(
+        "count() { if [ $# = 0 ]; then return 0; fi; echo LEVEL $#; shift; count $@; }; count a b c",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("LEVEL 3"), "stdout:\n{stdout}");
+    assert!(stdout.contains("LEVEL 2"), "stdout:\n{stdout}");
+    assert!(stdout.contains("LEVEL 1"), "stdout:\n{stdout}");
+    assert!(!stdout.contains("LEVEL 0"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn deep_recursion_reaches_the_nesting_limit() {
+    let output = run_dsh_command(
+        "down() { if [ $1 -gt 0 ]; then down $(( $1 - 1 )); else echo BOTTOM; fi; }; down 999",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert!(stdout.contains("BOTTOM"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn runaway_recursion_is_an_error() {
+    let output = run_dsh_command("forever() { forever; }; forever; echo AFTER $?");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(output.status.code().is_some(), "output: {output:?}");
+    assert!(
+        stderr.contains("maximum function nesting level exceeded (1000)"),
+        "stderr:\n{stderr}"
+    );
+    assert!(stdout.contains("AFTER 1"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn local_variables_are_restored() {
+    let output = run_dsh_command(
+        "set name global\nf() { local name=inner; echo INSIDE $name; }\nf\necho AFTER $name",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("INSIDE inner"), "stdout:\n{stdout}");
+    assert!(stdout.contains("AFTER global"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn function_output_can_be_piped() {
+    let output =
+        run_dsh_command("lines() { echo FIRST_LINE; echo SECOND_LINE; }; lines | grep SECOND");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("SECOND_LINE"), "stdout:\n{stdout}");
+    assert!(!stdout.contains("FIRST_LINE"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn return_outside_function_is_an_error() {
+    let output = run_dsh_command("return 1");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(
+        stderr.contains("can only `return' from a function"),
+        "stderr:\n{stderr}"
+    );
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn function_output_can_be_substituted() {
+    let output = run_dsh_command("greet() { echo HELLO_$1; }; echo BEFORE $(greet sub) AFTER");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert!(
+        stdout.contains("BEFORE HELLO_sub AFTER"),
+        "stdout:\n{stdout}"
+    );
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn nested_command_substitution_calls_functions() {
+    let output =
+        run_dsh_command("inner() { echo INNER; }; outer() { echo $(inner); }; echo [$(outer)]");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert!(stdout.contains("[ INNER ]"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn background_function_finishes_as_a_job() {
+    let output = run_dsh_command(
+        "f() { sleep 0.1; echo BG_F; }; f & wait; echo WAITED=$?; f & wait %1; echo DONE",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert_eq!(stdout.matches("BG_F").count(), 2, "stdout:\n{stdout}");
+    assert!(stdout.contains("WAITED=0"), "stdout:\n{stdout}");
+    assert!(stdout.contains("DONE"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn finished_background_function_is_done_in_jobs() {
+    let output = run_dsh_command("f() { true; }; f & sleep 0.3; jobs");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("done"), "stdout:\n{stdout}");
+    assert!(!stdout.contains("running"), "stdout:\n{stdout}");
+}
@@ -0,0 +1,101 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn run_dsh_in(dir: &Path, command: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .args(["-c", command])
+        .current_dir(dir)
+        .output()
+        .expect("failed to execute dsh")
+}
+
+fn project() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    fs::create_dir(dir.path().join("src")).unwrap();
+    for file in ["main.rs", ".hidden.rs", "notes.txt", "src/lib.rs"] {
+        fs::write(dir.path().join(file), "").unwrap();
+    }
+    dir
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn nullglob_and_failglob_change_unmatched_patterns() {
+    let dir = project();
+
+    let output = run_dsh_in(dir.path(), "echo [*.tmp]");
+    assert!(output.status.success(), "command failed: {:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("[*.tmp]"));
+
+    let output = run_dsh_in(
+        dir.path(),
+        "shopt -s nullglob\necho [*.tmp]\necho x *.tmp y",
+    );
+    assert!(output.status.success(), "command failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("x y"), "stdout: {stdout}");
+
+    let output = run_dsh_in(dir.path(), "shopt -s failglob\necho *.tmp");
+    assert!(!output.status.success(), "command succeeded: {:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("no match: *.tmp"), "stderr: {stderr}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn dotglob_globstar_and_extglob_change_matches() {
+    let dir = project();
+
+    let output = run_dsh_in(dir.path(), "echo *.rs");
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "main.rs");
+
+    let output = run_dsh_in(dir.path(), "set -o dotglob\necho *.rs");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        ".hidden.rs main.rs"
+    );
+
+    let output = run_dsh_in(dir.path(), "echo **/*.rs");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "main.rs src/lib.rs"
+    );
+
+    let output = run_dsh_in(dir.path(), "shopt -u globstar\necho **/*.rs");
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "src/lib.rs");
+
+    let output = run_dsh_in(dir.path(), "shopt -s extglob\necho !(*.rs)");
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "notes.txt src"
+    );
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn shopt_reports_options() {
+    let dir = project();
+
+    let output = run_dsh_in(dir.path(), "shopt -s dotglob\nshopt dotglob nullglob");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("dotglob         on"), "stdout: {stdout}");
+    assert!(stdout.contains("nullglob        off"), "stdout: {stdout}");
+    assert!(!output.status.success(), "nullglob is off: {:?}", output);
+
+    let output = run_dsh_in(dir.path(), "shopt -q globstar");
+    assert!(output.status.success(), "globstar is on: {:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).trim().is_empty());
+
+    let output = run_dsh_in(dir.path(), "shopt -s nosuchoption");
+    assert!(!output.status.success(), "command succeeded: {:?}", output);
+}
@@ -0,0 +1,98 @@
+use std::fs;
+use std::process::Command;
+
+fn run_dsh_command(command: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .arg("-c")
+        .arg(command)
+        .output()
+        .expect("Failed to run dsh")
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn heredoc_expands_variables() {
+    let output = run_dsh_command(
+        "set greeting HELLO\ncat <<EOF\n$greeting ${greeting}_DOC \\$greeting\nEOF",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert!(
+        stdout.contains("HELLO HELLO_DOC $greeting"),
+        "stdout:\n{stdout}"
+    );
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn heredoc_expands_status_and_command_substitution() {
+    let output = run_dsh_command("false; cat <<EOF\nstatus $? $(echo sub) `echo tick`\nEOF");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("status 1 sub tick"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn quoted_delimiter_keeps_body_literal() {
+    let output = run_dsh_command("set greeting HELLO\ncat <<'EOF'\nLITERAL $greeting\nEOF");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("LITERAL $greeting"), "stdout:\n{stdout}");
+    assert!(!stdout.contains("HELLO"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn dash_heredoc_strips_tabs_and_feeds_pipeline() {
+    let output = run_dsh_command("cat <<-END | tr a-z A-Z\n\t\ttabbed line\n\tEND\necho AFTER_DOC");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("TABBED LINE\n"), "stdout:\n{stdout}");
+    assert!(!stdout.contains("\tTABBED"), "stdout:\n{stdout}");
+    assert!(stdout.contains("AFTER_DOC"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn heredoc_with_output_redirect_writes_file() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let path = dir.path().join("doc.txt");
+
+    let output = run_dsh_command(&format!(
+        "cat > {} <<EOF\nfirst\nsecond\nEOF",
+        path.display()
+    ));
+    assert!(output.status.success(), "output: {output:?}");
+
+    let written = fs::read_to_string(&path).expect("read redirected output");
+    assert_eq!(written, "first\nsecond\n");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn herestring_feeds_word_with_newline() {
+    let output = run_dsh_command("tr a-z A-Z <<< 'here string'");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("HERE STRING\n"), "stdout:\n{stdout}");
+}
@@ -0,0 +1,114 @@
+use std::process::Command;
+use std::time::Duration;
+
+fn run_dsh_command(command: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .arg("-c")
+        .arg(command)
+        .output()
+        .expect("Failed to run dsh")
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn wait_returns_the_exit_status_of_the_job() {
+    let output = run_dsh_command(
+        "sh -c 'sleep 0.2; exit 3' &\nsh -c 'exit 5' &\nwait -n\necho NEXT=$?\nwait %1\necho FIRST=$?\nwait %7\necho UNKNOWN=$?",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("NEXT=5"), "stdout:\n{stdout}");
+    assert!(stdout.contains("FIRST=3"), "stdout:\n{stdout}");
+    assert!(stdout.contains("UNKNOWN=127"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn kill_signals_a_job_by_spec() {
+    let output = run_dsh_command(
+        "sleep 5 &\nkill %1\nwait %1\necho KILLED=$?\nkill -l 143\nkill %3\necho MISSING=$?",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stdout.contains("KILLED=143"), "stdout:\n{stdout}");
+    assert!(stdout.contains("TERM"), "stdout:\n{stdout}");
+    assert!(stdout.contains("MISSING=1"), "stdout:\n{stdout}");
+    assert!(
+        stderr.contains("kill: %3: no such job"),
+        "stderr:\n{stderr}"
+    );
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn disown_h_keeps_a_job_running_after_exit() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let killed = dir.path().join("killed");
+    let kept = dir.path().join("kept");
+    let output = run_dsh_command(&format!(
+        "sh -c 'sleep 0.3; touch {}' &\nsh -c 'sleep 0.3; touch {}' &\ndisown -h %2\njobs",
+        killed.display(),
+        kept.display()
+    ));
+    assert!(output.status.success(), "output: {output:?}");
+
+    std::thread::sleep(Duration::from_millis(800));
+    assert!(kept.exists(), "disowned job should outlive the shell");
+    assert!(
+        !killed.exists(),
+        "other jobs are killed when the shell exits"
+    );
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn wait_runs_inside_command_substitution() {
+    let output = run_dsh_command(
+        "sleep 0.1 &\necho BEFORE $(wait) AFTER\nwaiter() { wait; echo WAITED; }\nsleep 0.1 &\necho $(waiter)",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert!(stdout.contains("BEFORE AFTER"), "stdout:\n{stdout}");
+    assert!(stdout.contains("WAITED"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn kill_and_trap_accept_the_same_signal_names() {
+    let output = run_dsh_command(
+        "trap 'echo CAUGHT' sigusr1\nkill -SIGUSR1 $$\nkill -s usr1 $$\nkill -l SIGUSR1\nkill -l usr1",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(stdout.matches("CAUGHT").count(), 2, "stdout:\n{stdout}");
+    assert_eq!(stdout.matches("10").count(), 2, "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn wait_returns_the_status_of_a_function_job() {
+    let output = run_dsh_command("f() { return 3; }; f & wait %1; echo STATUS=$?");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("STATUS=3"), "stdout:\n{stdout}");
+}
@@ -0,0 +1,52 @@
+use std::fs;
+use std::process::Command;
+
+fn run_dsh(command: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .args(["-c", command])
+        .output()
+        .expect("failed to execute dsh")
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn noclobber_refuses_to_overwrite_existing_files() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let file = dir.path().join("important.txt");
+    fs::write(&file, "keep\n").unwrap();
+
+    let output = run_dsh(&format!("set -o noclobber; echo lost > {}", file.display()));
+
+    assert!(!output.status.success(), "command succeeded: {:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("cannot overwrite existing file"),
+        "stderr: {stderr}"
+    );
+    assert_eq!(fs::read_to_string(&file).unwrap(), "keep\n");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn noclobber_allows_new_files_appends_and_force() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let file = dir.path().join("log.txt");
+
+    let cmd = format!(
+        "set -C; echo one > {file}; echo two >> {file}; /bin/echo three >> {file}; echo four > /dev/null",
+        file = file.display()
+    );
+    let output = run_dsh(&cmd);
+    assert!(output.status.success(), "command failed: {:?}", output);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "one\ntwo\nthree\n");
+
+    let output = run_dsh(&format!("set -C; echo forced >| {}", file.display()));
+    assert!(output.status.success(), "command failed: {:?}", output);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "forced\n");
+}
@@ -0,0 +1,77 @@
+use std::process::Command;
+
+fn run_dsh_command(command: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .arg("-c")
+        .arg(command)
+        .output()
+        .expect("Failed to run dsh")
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn braced_operators_expand_in_words_and_double_quotes() {
+    let output = run_dsh_command(
+        "set file report.final.txt\necho \"[${file%.*}] [${file##*.}] [${#file}]\" ${file/final/draft}\necho L$file ${missing:-fallback}",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert!(
+        stdout.contains("[report.final] [txt] [16] report.draft.txt"),
+        "stdout:\n{stdout}"
+    );
+    assert!(
+        stdout.contains("Lreport.final.txt fallback"),
+        "stdout:\n{stdout}"
+    );
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn error_operator_stops_the_command() {
+    let output = run_dsh_command("echo ${REQUIRED_SETTING:?must be set}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success(), "output: {output:?}");
+    assert!(
+        stderr.contains("REQUIRED_SETTING: must be set"),
+        "stderr:\n{stderr}"
+    );
+    assert!(!stdout.contains("must be set"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn error_operator_exits_non_interactive_shell() {
+    let output = run_dsh_command("echo ${UNSET_SETTING:?boom}; echo after");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success(), "output: {output:?}");
+    assert!(stderr.contains("UNSET_SETTING: boom"), "stderr:\n{stderr}");
+    assert!(!stdout.contains("after"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn output_history_supports_parameter_operators() {
+    let output = run_dsh_command("echo first-value\necho ${OUT%-value} $OUT[1]");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert!(stdout.contains("first first-value\n"), "stdout:\n{stdout}");
+}
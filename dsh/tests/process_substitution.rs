@@ -0,0 +1,101 @@
+use std::fs;
+use std::process::Command;
+
+fn run_dsh(command: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .args(["-c", command])
+        .output()
+        .expect("failed to execute dsh")
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn input_process_substitution_reads_command_output() {
+    let output = run_dsh("/bin/cat <(echo first) <(echo second)");
+
+    assert!(output.status.success(), "command failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("first"), "stdout: {stdout}");
+    assert!(stdout.contains("second"), "stdout: {stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn output_process_substitution_fans_out_before_the_next_command() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let copy = dir.path().join("copy.txt");
+    let count = dir.path().join("count.txt");
+
+    // The readers are slower than tee, the next command must still see their output
+    let cmd = format!(
+        "printf 'a\\nb\\nc\\n' | tee >(sleep 0.2; cat > {}) >(wc -l > {}) > /dev/null; /bin/cat {} {}",
+        copy.display(),
+        count.display(),
+        copy.display(),
+        count.display()
+    );
+    let output = run_dsh(&cmd);
+
+    assert!(output.status.success(), "command failed: {:?}", output);
+    assert_eq!(fs::read_to_string(&copy).unwrap(), "a\nb\nc\n");
+    assert_eq!(fs::read_to_string(&count).unwrap().trim(), "3");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("a\nb\nc\n3"), "stdout: {stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn shell_code_runs_inside_process_substitutions() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let script = dir.path().join("print.dsh");
+    fs::write(&script, "echo SOURCED_$1\n").expect("write script");
+
+    let cmd = format!(
+        "f() {{ echo FN_$1; }}\n/bin/cat <(f in) <(source {0} in) <(sleep 0.1 & wait; echo WAITED_in)\necho data > >(f out; source {0} out; sleep 0.1 & wait; echo WAITED_out)",
+        script.display()
+    );
+    let output = run_dsh(&cmd);
+
+    assert!(output.status.success(), "command failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for expected in [
+        "FN_in",
+        "SOURCED_in",
+        "WAITED_in",
+        "FN_out",
+        "SOURCED_out",
+        "WAITED_out",
+    ] {
+        assert!(stdout.contains(expected), "stdout: {stdout}");
+    }
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn process_substitution_as_redirect_target() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let upper = dir.path().join("upper.txt");
+
+    let cmd = format!(
+        "echo one two > >(tr a-z A-Z > {0}); /bin/cat {0}; /bin/cat < <(echo from-input)",
+        upper.display()
+    );
+    let output = run_dsh(&cmd);
+
+    assert!(output.status.success(), "command failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("ONE TWO"), "stdout: {stdout}");
+    assert!(stdout.contains("from-input"), "stdout: {stdout}");
+}
@@ -72,3 +72,96 @@ fn output_redirect_still_writes_file() {
     assert_eq!(written, "sample");
     fs::remove_file(path).ok();
 }
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn stderr_dup_follows_redirection_order() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let both = dir.path().join("both.txt");
+    let only_out = dir.path().join("out.txt");
+
+    let cmd = format!(
+        "sh -c 'echo out; echo err >&2' > {} 2>&1; sh -c 'echo out; echo err >&2' 2>&1 > {}",
+        both.display(),
+        only_out.display()
+    );
+    let output = run_dsh(&cmd);
+    assert!(output.status.success(), "command failed: {:?}", output);
+
+    assert_eq!(fs::read_to_string(&both).unwrap(), "out\nerr\n");
+    assert_eq!(fs::read_to_string(&only_out).unwrap(), "out\n");
+    // `2>&1` came first, so stderr still goes where stdout originally went
+    assert!(String::from_utf8_lossy(&output.stdout).contains("err"));
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn stderr_dup_before_output_redirect_of_function_and_group() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let function_out = dir.path().join("function.txt");
+    let group_out = dir.path().join("group.txt");
+
+    let cmd = format!(
+        "f() {{ echo fout; echo ferr >&2; }}; f 2>&1 > {}; {{ echo gout; echo gerr >&2; }} 2>&1 > {}",
+        function_out.display(),
+        group_out.display()
+    );
+    let output = run_dsh(&cmd);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "command failed: {:?}", output);
+
+    assert_eq!(fs::read_to_string(&function_out).unwrap(), "fout\n");
+    assert_eq!(fs::read_to_string(&group_out).unwrap(), "gout\n");
+    assert!(stdout.contains("ferr"), "stdout:\n{stdout}");
+    assert!(stdout.contains("gerr"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn stderr_dup_into_pipe() {
+    let output = run_dsh("sh -c 'echo problem >&2' 2>&1 | tr a-z A-Z");
+    assert!(output.status.success(), "command failed: {:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("PROBLEM"));
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn builtin_writes_to_stderr_with_dup() {
+    let output = run_dsh("echo warning >&2");
+    assert!(output.status.success(), "command failed: {:?}", output);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("warning"));
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("warning"));
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn numbered_fd_redirect_and_close() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let log = dir.path().join("fd3.txt");
+
+    let output = run_dsh(&format!("sh -c 'echo three >&3' 3> {}", log.display()));
+    assert!(output.status.success(), "command failed: {:?}", output);
+    assert_eq!(fs::read_to_string(&log).unwrap(), "three\n");
+
+    let output = run_dsh("cat <&-");
+    assert!(!output.status.success(), "command unexpectedly succeeded");
+
+    let output = run_dsh("echo hi >&7");
+    assert!(!output.status.success(), "command unexpectedly succeeded");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("7: bad file descriptor"));
+}
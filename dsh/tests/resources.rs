@@ -0,0 +1,84 @@
+use std::fs;
+use std::process::Command;
+
+fn run_dsh(command: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .args(["-c", command])
+        .output()
+        .expect("failed to execute dsh")
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn ulimit_and_umask_are_inherited_by_children() {
+    let output = run_dsh("ulimit -n 128; umask 027; /bin/sh -c 'ulimit -n; umask'");
+
+    assert!(output.status.success(), "command failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("128"), "stdout: {stdout}");
+    assert!(stdout.contains("0027"), "stdout: {stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn exec_redirections_stay_open_for_later_commands() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let log = dir.path().join("log.txt");
+
+    let cmd = format!(
+        "exec 3>{}; echo one >&3; /bin/echo two >&3; /bin/sh -c 'echo three >&3'; exec 3>&-",
+        log.display()
+    );
+    let output = run_dsh(&cmd);
+
+    assert!(output.status.success(), "command failed: {:?}", output);
+    assert_eq!(fs::read_to_string(&log).unwrap(), "one\ntwo\nthree\n");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn exec_replaces_the_shell() {
+    let output = run_dsh("exec /bin/echo replaced; /bin/echo not reached");
+
+    assert!(output.status.success(), "command failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("replaced"), "stdout: {stdout}");
+    assert!(!stdout.contains("not reached"), "stdout: {stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn time_reports_and_records_the_pipeline() {
+    let data = tempfile::tempdir().expect("create temp dir");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .args(["-c", "time -p /bin/sleep 0.1 | /bin/cat"])
+        .env("XDG_DATA_HOME", data.path())
+        .output()
+        .expect("failed to execute dsh");
+
+    assert!(output.status.success(), "command failed: {:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let real: f64 = stderr
+        .lines()
+        .find_map(|line| line.strip_prefix("real "))
+        .and_then(|secs| secs.parse().ok())
+        .unwrap_or_else(|| panic!("no real time in stderr: {stderr}"));
+    assert!(real >= 0.1, "stderr: {stderr}");
+    assert!(stderr.contains("user "), "stderr: {stderr}");
+    assert!(stderr.contains("sys "), "stderr: {stderr}");
+    let timing = fs::read_to_string(data.path().join("dsh/timing.json")).unwrap();
+    assert!(timing.contains("\"sleep\""), "timing: {timing}");
+}
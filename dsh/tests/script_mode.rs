@@ -0,0 +1,114 @@
+use std::io::Write;
+use std::process::Command;
+
+fn write_script(source: &str) -> tempfile::NamedTempFile {
+    let mut file = tempfile::Builder::new()
+        .suffix(".dsh")
+        .tempfile()
+        .expect("Failed to create script");
+    file.write_all(source.as_bytes()).unwrap();
+    file
+}
+
+fn run_dsh_script(script: &tempfile::NamedTempFile, args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .arg(script.path())
+        .args(args)
+        .output()
+        .expect("Failed to run dsh")
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn script_receives_arguments_as_positional_params() {
+    let script = write_script(
+        "#!/usr/bin/env dsh\n# it's a comment\necho ARGC $#\nfor arg in $@; do\n  echo ARG $arg\ndone\n",
+    );
+    let output = run_dsh_script(&script, &["first", "--second"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "status: {:?}", output.status);
+    assert!(stdout.contains("ARGC 2"), "stdout:\n{stdout}");
+    assert!(stdout.contains("ARG first"), "stdout:\n{stdout}");
+    assert!(stdout.contains("ARG --second"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn script_joins_continuation_lines() {
+    let script = write_script("echo JOINED \\\n  LINE\n");
+    let output = run_dsh_script(&script, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("JOINED LINE"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn script_exits_with_last_status() {
+    let script = write_script("echo BEFORE\nfalse\n");
+    let output = run_dsh_script(&script, &[]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let script = write_script("echo BEFORE\nexit 4\necho NEVER\n");
+    let output = run_dsh_script(&script, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(!stdout.contains("NEVER"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn script_syntax_error_reports_line() {
+    let script = write_script("echo OK\n\nif true; then\n  echo NEVER\n");
+    let output = run_dsh_script(&script, &[]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains(":3: syntax error"), "stderr:\n{stderr}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn script_unterminated_quote_is_syntax_error() {
+    let script = write_script("echo OK\necho \"unterminated\n");
+    let output = run_dsh_script(&script, &[]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let location = format!("{}:2: syntax error", script.path().display());
+
+    assert!(!output.status.success(), "status: {:?}", output.status);
+    assert!(stderr.contains(&location), "stderr:\n{stderr}");
+    assert_eq!(stdout, "OK\n");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn missing_script_fails() {
+    let output = Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .arg("/nonexistent/dsh-script.dsh")
+        .output()
+        .expect("Failed to run dsh");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert_eq!(output.status.code(), Some(127));
+    assert!(stderr.contains("cannot read script"), "stderr:\n{stderr}");
+}
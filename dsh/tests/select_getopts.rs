@@ -0,0 +1,120 @@
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn run_dsh_with_input(args: &[&str], input: &str) -> std::process::Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to execute dsh");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().expect("failed to wait for dsh")
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn select_reads_numbered_choices_from_stdin() {
+    let script = "set PS3 \"Pick: \"\nselect fruit in apple \"green melon\" pear\ndo\n  echo \"got [$fruit] $REPLY\"\n  if test \"$REPLY\" = 3; then break; fi\ndone\necho done";
+    let output = run_dsh_with_input(&["-c", script], "2\n\n7\n3\n");
+
+    assert!(output.status.success(), "command failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let got: Vec<&str> = stdout.lines().filter(|l| l.starts_with("got")).collect();
+    assert_eq!(
+        got,
+        vec!["got [green melon] 2", "got [] 7", "got [pear] 3"],
+        "stdout: {stdout}"
+    );
+    assert!(stdout.contains("done"), "stdout: {stdout}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // The menu is shown again after an empty answer
+    assert_eq!(
+        stderr.matches("2) green melon").count(),
+        2,
+        "stderr: {stderr}"
+    );
+    assert!(stderr.contains("Pick: "), "stderr: {stderr}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn select_ends_at_end_of_input() {
+    let output = run_dsh_with_input(
+        &[
+            "-c",
+            "select x in a b\ndo\n  echo picked $x\ndone\necho after",
+        ],
+        "1\n",
+    );
+
+    assert!(output.status.success(), "command failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("picked a"), "stdout: {stdout}");
+    assert!(stdout.contains("after"), "stdout: {stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn getopts_parses_script_arguments() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let script = dir.path().join("opts.dsh");
+    fs::write(
+        &script,
+        "while getopts \"vo:\" opt; do\n  case $opt in\n    v) echo verbose;;\n    o) echo \"output $OPTARG\";;\n    *) echo invalid;;\n  esac\ndone\nshift $(( OPTIND - 1 ))\necho \"rest $@\"\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .arg(&script)
+        .args(["-vo", "out.txt", "-x", "--", "-a", "file"])
+        .output()
+        .expect("failed to execute dsh");
+
+    assert!(output.status.success(), "command failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    assert_eq!(
+        lines,
+        vec!["verbose", "output out.txt", "invalid", "rest -a file"],
+        "stdout: {stdout}"
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("illegal option -- x"), "stderr: {stderr}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn getopts_silent_mode_reports_errors_through_the_variable() {
+    let script = "getopts \":ab:\" o -b\necho \"$? $o $OPTARG $OPTIND\"\ngetopts \":ab:\" o -b\necho \"$? $o $OPTIND\"";
+    let output = run_dsh_with_input(&["-c", script], "");
+
+    assert!(output.status.success(), "command failed: {:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("0 : b 2"), "stdout: {stdout}");
+    assert!(stdout.contains("1 ? 2"), "stdout: {stdout}");
+    assert!(String::from_utf8_lossy(&output.stderr).trim().is_empty());
+}
@@ -0,0 +1,70 @@
+use std::process::Command;
+
+fn run_dsh(command: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .args(["-c", command])
+        .output()
+        .expect("failed to execute dsh")
+}
+
+fn stdout_lines(output: &std::process::Output) -> Vec<String> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn set_o_lists_and_changes_registered_options() {
+    let output = run_dsh("set -o auto-notify-threshold=42\nset -o safety-level=loose\nset -o");
+    assert!(output.status.success(), "command failed: {:?}", output);
+    let lines = stdout_lines(&output);
+    for expected in [
+        "auto-notify-threshold 42",
+        "safety-level    loose",
+        "noclobber       off",
+        "globstar        on",
+    ] {
+        assert!(lines.iter().any(|l| l == expected), "{expected}: {lines:?}");
+    }
+
+    let output = run_dsh("set -o safety-level=reckless");
+    assert!(!output.status.success(), "command succeeded: {:?}", output);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("safety-level"), "stderr: {stderr}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn set_plus_o_and_shopt_p_print_restoring_commands() {
+    let output = run_dsh("set -o pipefail\nset +o auto-pair\nset +o");
+    assert!(output.status.success(), "command failed: {:?}", output);
+    let lines = stdout_lines(&output);
+    for expected in [
+        "set -o pipefail",
+        "set +o auto-pair",
+        "set +o errexit",
+        "set -o suggestion-mode=ghost",
+    ] {
+        assert!(lines.iter().any(|l| l == expected), "{expected}: {lines:?}");
+    }
+
+    let output = run_dsh("shopt -s dotglob\nshopt -p dotglob nullglob safety-level");
+    let lines = stdout_lines(&output);
+    assert_eq!(
+        lines,
+        vec![
+            "shopt -s dotglob",
+            "shopt -u nullglob",
+            "set -o safety-level=normal"
+        ]
+    );
+}
@@ -0,0 +1,134 @@
+use std::fs;
+use std::process::Command;
+
+fn run_dsh_command(command: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .arg("-c")
+        .arg(command)
+        .output()
+        .expect("Failed to run dsh")
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn sourced_definitions_persist() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let lib = dir.path().join("lib.dsh");
+    fs::write(
+        &lib,
+        format!(
+            "# helpers\nset greeting hello\nalias shout='echo LOUD'\ngreet() {{\n  echo \"$greeting $1\"\n}}\necho \"sourced with $# $1\"\ncd {}\n",
+            dir.path().display()
+        ),
+    )
+    .expect("write library");
+
+    let output = run_dsh_command(&format!(
+        "source {} first second\ngreet world\nshout\npwd\necho params=$#",
+        lib.display()
+    ));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert!(stdout.contains("sourced with 2 first"), "stdout:\n{stdout}");
+    assert!(stdout.contains("hello world"), "stdout:\n{stdout}");
+    assert!(stdout.contains("LOUD"), "stdout:\n{stdout}");
+    assert!(
+        stdout.contains(&dir.path().display().to_string()),
+        "stdout:\n{stdout}"
+    );
+    assert!(stdout.contains("params=0"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn dot_reports_errors_with_file_and_line() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let script = dir.path().join("broken.dsh");
+    fs::write(&script, "echo start\n\nno_such_command_xyz\necho end\n").expect("write script");
+
+    let output = run_dsh_command(&format!(". {}\necho status=$?", script.display()));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stdout.contains("start"), "stdout:\n{stdout}");
+    assert!(stdout.contains("end"), "stdout:\n{stdout}");
+    assert!(stdout.contains("status=0"), "stdout:\n{stdout}");
+    assert!(
+        stderr.contains(&format!(
+            "{}:3: no_such_command_xyz: command not found",
+            script.display()
+        )),
+        "stderr:\n{stderr}"
+    );
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn source_missing_file_fails() {
+    let output = run_dsh_command("source /nonexistent/dsh/file.dsh || echo failed=$?");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("failed=1"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn source_inside_command_substitution() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let script = dir.path().join("print.dsh");
+    fs::write(&script, "echo SOURCED_$1\n").expect("write script");
+
+    let output = run_dsh_command(&format!(
+        "echo BEFORE $(source {0} one) $(. {0} two) AFTER",
+        script.display()
+    ));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    assert!(
+        stdout.contains("BEFORE SOURCED_one SOURCED_two AFTER"),
+        "stdout:\n{stdout}"
+    );
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn return_leaves_a_sourced_file() {
+    let dir = tempfile::tempdir().expect("create temp dir");
+    let script = dir.path().join("r.dsh");
+    fs::write(
+        &script,
+        "inner() { return 7; }\ninner\necho inner=$?\nif true; then\n  return 3\nfi\necho NOTREACHED\n",
+    )
+    .expect("write script");
+
+    let output = run_dsh_command(&format!(
+        "source {}\necho status=$?\nf() {{ . {}; echo in_f=$?; }}\nf",
+        script.display(),
+        script.display()
+    ));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(stdout.contains("inner=7"), "stdout:\n{stdout}");
+    assert!(stdout.contains("status=3"), "stdout:\n{stdout}");
+    assert!(stdout.contains("in_f=3"), "stdout:\n{stdout}");
+    assert!(!stdout.contains("NOTREACHED"), "stdout:\n{stdout}");
+    assert!(!stderr.contains("can only"), "stderr:\n{stderr}");
+}
@@ -0,0 +1,76 @@
+use std::process::Command;
+
+fn run_dsh_command(command: &str) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_dsh"))
+        .arg("-c")
+        .arg(command)
+        .output()
+        .expect("Failed to run dsh")
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn exit_trap_runs_once_with_exit_status() {
+    let output = run_dsh_command("trap 'echo BYE $?' EXIT\nset -e\nfalse\necho NOT_REACHED");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(output.status.code(), Some(1), "output: {output:?}");
+    assert_eq!(stdout.matches("BYE 1").count(), 1, "stdout:\n{stdout}");
+    assert!(!stdout.contains("NOT_REACHED"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn err_trap_skips_conditions_and_can_be_reset() {
+    let output = run_dsh_command(
+        "trap 'echo ERR_TRAP' ERR\nfalse || true\nif false; then :; fi\nfalse\ntrap -p ERR\ntrap - ERR\nfalse\necho DONE",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(stdout.matches("ERR_TRAP").count(), 2, "stdout:\n{stdout}");
+    assert!(
+        stdout.contains("trap -- 'echo ERR_TRAP' ERR"),
+        "stdout:\n{stdout}"
+    );
+    assert!(stdout.contains("DONE"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn signal_trap_runs_after_the_signal_arrives() {
+    let output = run_dsh_command(
+        "trap 'echo GOT_USR1' USR1\nkill -USR1 $$\necho AFTER\ntrap '' USR1\nkill -USR1 $$\necho STILL_ALIVE",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(output.status.success(), "output: {output:?}");
+    let got = stdout.find("GOT_USR1").expect("trap output");
+    let after = stdout.find("AFTER").expect("AFTER output");
+    assert!(got < after, "stdout:\n{stdout}");
+    assert_eq!(stdout.matches("GOT_USR1").count(), 1, "stdout:\n{stdout}");
+    assert!(stdout.contains("STILL_ALIVE"), "stdout:\n{stdout}");
+}
+
+#[test]
+#[cfg_attr(
+    target_os = "macos",
+    ignore = "macOS sandbox can deny child dsh tracing log initialization"
+)]
+fn lisp_trap_registers_a_lambda() {
+    let output =
+        run_dsh_command("lisp '(trap \"EXIT\" (lambda () (print \"LISP_EXIT\")))'\necho MAIN");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let main = stdout.find("MAIN").expect("MAIN output");
+    let exit = stdout.find("LISP_EXIT").expect("Lisp trap output");
+    assert!(main < exit, "stdout:\n{stdout}");
+}